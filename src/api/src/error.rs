@@ -152,6 +152,14 @@ impl Error {
         }))
     }
 
+    #[inline]
+    pub fn quota_exceeded(database_id: u64, resource: impl Into<String>) -> Self {
+        Self::with_detail_value(error_detail_union::Value::QuotaExceeded(QuotaExceeded {
+            database_id,
+            resource: resource.into(),
+        }))
+    }
+
     #[inline]
     pub fn status(code: i32, msg: impl Into<String>) -> Self {
         Error {
@@ -11,22 +11,50 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use crate::server::v1::{shard_desc::*, *};
+use xxhash_rust::xxh64::xxh64;
+
+use crate::{
+    server::v1::{shard_desc::*, *},
+    v1::HashAlgorithm,
+};
 
 pub fn in_range(start: &[u8], end: &[u8], key: &[u8]) -> bool {
     start <= key && (key < end || end.is_empty())
 }
 
+/// The hash algorithm used if it isn't specified when creating a collection.
+pub const DEFAULT_HASH_ALGORITHM: HashAlgorithm = HashAlgorithm::Xxhash64;
+
+/// Return the slot of a key, with the hash algorithm of the collection. `None` is returned if the
+/// hash algorithm is unknown or there is no any slot.
 #[inline]
-pub fn key_slot(key: &[u8], slots: u32) -> u32 {
-    // TODO: it's temp hash impl..
-    crc32fast::hash(key) % slots
+pub fn key_slot(key: &[u8], slots: u32, algorithm: i32) -> Option<u32> {
+    if slots == 0 {
+        return None;
+    }
+    let slot = match HashAlgorithm::from_i32(algorithm)? {
+        HashAlgorithm::Crc32 => crc32fast::hash(key) % slots,
+        HashAlgorithm::Xxhash64 => (xxh64(key, 0) % slots as u64) as u32,
+        HashAlgorithm::JumpConsistent => jump_consistent_hash(xxh64(key, 0), slots),
+    };
+    Some(slot)
+}
+
+/// The jump consistent hash algorithm, see https://arxiv.org/abs/1406.2294 for details.
+fn jump_consistent_hash(mut key: u64, buckets: u32) -> u32 {
+    let (mut b, mut j) = (-1i64, 0i64);
+    while j < buckets as i64 {
+        b = j;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    b as u32
 }
 
 /// Return whether a key belongs to the corresponding shard.
 pub fn belong_to(shard: &ShardDesc, key: &[u8]) -> bool {
     match shard.partition.as_ref().unwrap() {
-        Partition::Hash(hash) => hash.slot_id == key_slot(key, hash.slots),
+        Partition::Hash(hash) => Some(hash.slot_id) == key_slot(key, hash.slots, hash.algorithm),
         Partition::Range(RangePartition { start, end }) => in_range(start, end, key),
     }
 }
@@ -56,3 +84,90 @@ pub fn slot(shard: &ShardDesc) -> Option<u32> {
         Partition::Range(_) => None,
     }
 }
+
+/// Return whether two different shards share the same key range in a group engine. The keys of a
+/// hash shard are prefixed by the collection and slot only, so the shards of the same slot of the
+/// current and target slots of a resharding collection can't be served by the same group.
+pub fn is_key_conflict(lhs: &ShardDesc, rhs: &ShardDesc) -> bool {
+    lhs.id != rhs.id
+        && lhs.collection_id == rhs.collection_id
+        && matches!((slot(lhs), slot(rhs)), (Some(l), Some(r)) if l == r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_slot_with_algorithms() {
+        for algorithm in [
+            HashAlgorithm::Crc32,
+            HashAlgorithm::Xxhash64,
+            HashAlgorithm::JumpConsistent,
+        ] {
+            for key in [b"".as_slice(), b"a", b"key", b"\xff\xff\xff"] {
+                let slot = key_slot(key, 7, algorithm as i32).unwrap();
+                assert!(slot < 7);
+                assert_eq!(key_slot(key, 7, algorithm as i32), Some(slot));
+                assert_eq!(key_slot(key, 1, algorithm as i32), Some(0));
+            }
+        }
+
+        assert_eq!(
+            key_slot(b"key", 8, HashAlgorithm::Crc32 as i32),
+            Some(crc32fast::hash(b"key") % 8)
+        );
+        assert_eq!(
+            key_slot(b"key", 8, HashAlgorithm::Xxhash64 as i32),
+            Some((xxh64(b"key", 0) % 8) as u32)
+        );
+    }
+
+    #[test]
+    fn key_slot_with_invalid_arguments() {
+        assert_eq!(key_slot(b"key", 8, -1), None);
+        assert_eq!(key_slot(b"key", 8, 1024), None);
+        assert_eq!(key_slot(b"key", 0, HashAlgorithm::Xxhash64 as i32), None);
+    }
+
+    #[test]
+    fn jump_consistent_hash_moves_few_keys() {
+        let keys = (0..10000u64)
+            .map(|i| xxh64(&i.to_le_bytes(), 0))
+            .collect::<Vec<_>>();
+        for buckets in 1..32u32 {
+            let mut moved = 0;
+            for &key in &keys {
+                let before = jump_consistent_hash(key, buckets);
+                let after = jump_consistent_hash(key, buckets + 1);
+                assert!(before < buckets);
+                // A key either stays or moves to the new bucket.
+                if before != after {
+                    assert_eq!(after, buckets);
+                    moved += 1;
+                }
+            }
+            // About 1/(buckets+1) of keys are moved.
+            let expect = keys.len() as u32 / (buckets + 1);
+            assert!(
+                moved > expect / 2 && moved < expect * 2,
+                "buckets {buckets} moved {moved}"
+            );
+        }
+    }
+
+    #[test]
+    fn belong_to_unknown_algorithm() {
+        let shard = ShardDesc {
+            id: 1,
+            collection_id: 1,
+            partition: Some(Partition::Hash(HashPartition {
+                slot_id: 0,
+                slots: 1,
+                algorithm: -1,
+                target_slots: 0,
+            })),
+        };
+        assert!(!belong_to(&shard, b"key"));
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use engula_api::server::v1::{NodeStatus, RaftRole, ReplicaRole};
+use engula_server::diagnosis::Metadata;
+use hyper::{body, header, Client, Uri};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// The max number of redirections to follow, the root leader only redirects once.
+const MAX_REDIRECTIONS: usize = 3;
+
+/// A client of the admin HTTP endpoints served on the same address as the node service.
+pub struct AdminClient {
+    addrs: Vec<String>,
+}
+
+impl AdminClient {
+    pub fn new(addrs: Vec<String>) -> Self {
+        AdminClient { addrs }
+    }
+
+    pub async fn metadata(&self) -> Result<Metadata> {
+        let body = self.get("/admin/metadata").await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Return the ongoing and history background jobs of root.
+    pub async fn jobs(&self) -> Result<serde_json::Value> {
+        let body = self.get("/admin/job").await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Cancel an ongoing background job, it is rolled back by root asynchronously.
+    pub async fn cancel_job(&self, job_id: u64) -> Result<()> {
+        self.get(&format!("/admin/job/cancel?job_id={job_id}"))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn cordon(&self, node_id: u64) -> Result<()> {
+        self.get(&format!("/admin/cordon?node_id={node_id}"))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn uncordon(&self, node_id: u64) -> Result<()> {
+        self.get(&format!("/admin/uncordon?node_id={node_id}"))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn label_node(&self, node_id: u64, labels: &str) -> Result<()> {
+        self.get(&format!(
+            "/admin/label_node?node_id={node_id}&labels={labels}"
+        ))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn drain(&self, node_id: u64) -> Result<()> {
+        self.get(&format!("/admin/drain?node_id={node_id}")).await?;
+        Ok(())
+    }
+
+    pub async fn decommission(&self, node_id: u64) -> Result<()> {
+        self.get(&format!("/admin/decommission?node_id={node_id}"))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_node(&self, node_id: u64) -> Result<()> {
+        self.get(&format!("/admin/remove_node?node_id={node_id}"))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn transfer_leader(&self, group_id: u64, replica_id: u64) -> Result<()> {
+        self.get(&format!(
+            "/admin/transfer_leader?group_id={group_id}&replica_id={replica_id}"
+        ))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn move_shard(&self, shard_id: u64, dest_group_id: u64) -> Result<()> {
+        self.get(&format!(
+            "/admin/move_shard?shard_id={shard_id}&dest_group_id={dest_group_id}"
+        ))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn node_status(&self, node_id: u64) -> Result<String> {
+        let body = self
+            .get(&format!("/admin/node_status?node_id={node_id}"))
+            .await?;
+        let value: serde_json::Value = serde_json::from_str(&body)?;
+        Ok(value["node_status"].as_str().unwrap_or_default().to_owned())
+    }
+
+    /// Issue the request to each address until one of them succeeds. The requests which require
+    /// root leader, such as cordon, will fail on other nodes.
+    async fn get(&self, path_and_query: &str) -> Result<String> {
+        let mut last_err = None;
+        for addr in &self.addrs {
+            match get(&format!("http://{addr}{path_and_query}")).await {
+                Ok(body) => return Ok(body),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| "no address is available".into()))
+    }
+}
+
+async fn get(uri: &str) -> Result<String> {
+    let client = Client::new();
+    let mut uri: Uri = uri.parse()?;
+    for _ in 0..MAX_REDIRECTIONS {
+        let resp = client.get(uri.clone()).await?;
+        let status = resp.status();
+        if status.is_redirection() {
+            let location = resp
+                .headers()
+                .get(header::LOCATION)
+                .ok_or("the location of redirection is missing")?;
+            uri = location.to_str()?.parse()?;
+            continue;
+        }
+
+        let body = body::to_bytes(resp.into_body()).await?;
+        let body = String::from_utf8_lossy(&body).into_owned();
+        if !status.is_success() {
+            return Err(format!("{uri}: {status} {body}").into());
+        }
+        return Ok(body);
+    }
+    Err(format!("{uri}: too many redirections").into())
+}
+
+pub fn node_status(status: i32) -> String {
+    NodeStatus::from_i32(status)
+        .map(|s| format!("{s:?}").to_uppercase())
+        .unwrap_or_else(|| status.to_string())
+}
+
+pub fn raft_role(role: i32) -> String {
+    RaftRole::from_i32(role)
+        .map(|r| format!("{r:?}").to_uppercase())
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+pub fn replica_role(role: i32) -> String {
+    ReplicaRole::from_i32(role)
+        .map(|r| format!("{r:?}").to_uppercase())
+        .unwrap_or_else(|| "-".to_owned())
+}
@@ -0,0 +1,384 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! A Jepsen style linearizability check. Workers issue concurrent puts and gets against a small
+//! set of keys and record the invocation and completion of each operation, then the recorded
+//! history is verified against a register model, per key, with the WGL algorithm.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use engula_client::Collection;
+use rand::prelude::*;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use super::AppConfig;
+
+/// The return time of an operation whose outcome is unknown, such an operation might take effect
+/// at any point after it was invoked.
+const UNKNOWN: u64 = u64::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Write a unique value to the register.
+    Write(u64),
+    /// Read the register, `None` means the key does not exist.
+    Read(Option<u64>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub worker: usize,
+    pub key: u64,
+    pub kind: Kind,
+    /// The nanoseconds elapsed since the start of history when the operation is invoked.
+    pub call: u64,
+    /// The nanoseconds elapsed since the start of history when the operation returns, or
+    /// [`UNKNOWN`] if the outcome of the operation is unknown.
+    pub ret: u64,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "worker {} {:?} invoked at {}ns ",
+            self.worker, self.kind, self.call
+        )?;
+        if self.ret == UNKNOWN {
+            write!(f, "with unknown outcome")
+        } else {
+            write!(f, "returned at {}ns", self.ret)
+        }
+    }
+}
+
+/// A concurrent operation history shared by all workers.
+#[derive(Clone)]
+pub struct History {
+    start: Instant,
+    ops: Arc<Mutex<Vec<Operation>>>,
+}
+
+#[derive(Debug)]
+pub struct Violation {
+    pub key: u64,
+    /// All operations of the key, ordered by invocation time.
+    pub ops: Vec<Operation>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            start: Instant::now(),
+            ops: Arc::default(),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+
+    fn record(&self, op: Operation) {
+        self.ops.lock().unwrap().push(op);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.lock().unwrap().len()
+    }
+
+    pub fn operations(&self) -> Vec<Operation> {
+        self.ops.lock().unwrap().clone()
+    }
+}
+
+/// Issue `num_op` random operations and record them into the history. The value of each write is
+/// unique across workers, so that a read can be attributed to exactly one write.
+pub async fn worker_main(
+    id: usize,
+    co: Collection,
+    cfg: AppConfig,
+    seed: u64,
+    num_op: usize,
+    run_id: u64,
+    history: History,
+) {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    for seq in 0..num_op as u64 {
+        let key = rng.gen_range(0..cfg.check.keys);
+        let user_key = format!("{}check_{run_id}_{key}", cfg.key.prefix).into_bytes();
+        let call = history.now();
+        let (kind, ret) = if rng.gen_bool(cfg.data.write) {
+            let value = ((id as u64) << 32) | seq;
+            match co.put(user_key, value.to_be_bytes().to_vec()).await {
+                Ok(()) => (Kind::Write(value), history.now()),
+                Err(err) => {
+                    // The write might be applied even if the client see an error, eg timeout.
+                    debug!("worker {id} put key {key}: {err:?}");
+                    (Kind::Write(value), UNKNOWN)
+                }
+            }
+        } else {
+            match co.get(user_key).await {
+                Ok(value) => (Kind::Read(value.map(decode_value)), history.now()),
+                Err(err) => {
+                    // A failed read has no side effect, so it is omitted from the history.
+                    debug!("worker {id} get key {key}: {err:?}");
+                    continue;
+                }
+            }
+        };
+        history.record(Operation {
+            worker: id,
+            key,
+            kind,
+            call,
+            ret,
+        });
+    }
+}
+
+fn decode_value(value: Vec<u8>) -> u64 {
+    match <[u8; 8]>::try_from(value.as_slice()) {
+        Ok(bytes) => u64::from_be_bytes(bytes),
+        Err(_) => {
+            warn!("read an unexpected value {value:?}");
+            UNKNOWN
+        }
+    }
+}
+
+/// Check whether the history is linearizable, keys are independent registers so they are checked
+/// separately.
+pub fn check(ops: Vec<Operation>) -> Result<(), Violation> {
+    let mut keys: HashMap<u64, Vec<Operation>> = HashMap::new();
+    for op in ops {
+        keys.entry(op.key).or_default().push(op);
+    }
+    let mut keys = keys.into_iter().collect::<Vec<_>>();
+    keys.sort_unstable_by_key(|(key, _)| *key);
+    for (key, mut ops) in keys {
+        ops.sort_by_key(|op| op.call);
+        if !check_register(&ops) {
+            return Err(Violation { key, ops });
+        }
+    }
+    Ok(())
+}
+
+fn step(state: Option<u64>, kind: Kind) -> Option<Option<u64>> {
+    match kind {
+        Kind::Write(value) => Some(Some(value)),
+        Kind::Read(value) if value == state => Some(state),
+        Kind::Read(_) => None,
+    }
+}
+
+/// A possible state of the register, and the invoked operations which are not linearized yet.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Config {
+    state: Option<u64>,
+    pending: Vec<usize>,
+}
+
+enum Event {
+    Call(usize),
+    Return(usize),
+}
+
+/// Check register operations in the way of the just-in-time linearization of knossos: replay the
+/// events in time order and track all possible configurations. An operation is linearized lazily,
+/// only when it returns, along with any pending operations that must precede it. So the search
+/// space is bounded by the concurrency instead of the length of history.
+fn check_register(ops: &[Operation]) -> bool {
+    // The reads with unknown outcome have no side effect, and the writes with unknown outcome
+    // which are never observed can be linearized at the end of history, so both are omitted to
+    // keep the configurations small.
+    let observed = ops
+        .iter()
+        .filter_map(|op| match op.kind {
+            Kind::Read(value) => value,
+            Kind::Write(_) => None,
+        })
+        .collect::<HashSet<_>>();
+    let mut events = Vec::with_capacity(ops.len() * 2);
+    for (i, op) in ops.iter().enumerate() {
+        if op.ret == UNKNOWN {
+            if !matches!(op.kind, Kind::Write(v) if observed.contains(&v)) {
+                continue;
+            }
+        } else {
+            events.push((op.ret, 1, Event::Return(i)));
+        }
+        events.push((op.call, 0, Event::Call(i)));
+    }
+    // The calls are ordered before returns with the same time, which treats them as concurrent.
+    events.sort_by_key(|(time, order, _)| (*time, *order));
+
+    let mut configs = HashSet::from([Config {
+        state: None,
+        pending: vec![],
+    }]);
+    for (_, _, event) in events {
+        configs = match event {
+            Event::Call(i) => configs
+                .into_iter()
+                .map(|mut config| {
+                    config.pending.push(i);
+                    config
+                })
+                .collect(),
+            Event::Return(i) => linearize(ops, configs, i),
+        };
+        if configs.is_empty() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Return all configurations in which the operation `target` is linearized, by linearizing any
+/// sequence of pending operations ending with `target`.
+fn linearize(ops: &[Operation], configs: HashSet<Config>, target: usize) -> HashSet<Config> {
+    let mut linearized = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut stack = configs.into_iter().collect::<Vec<_>>();
+    while let Some(config) = stack.pop() {
+        if !config.pending.contains(&target) {
+            linearized.insert(config);
+            continue;
+        }
+        for (idx, &i) in config.pending.iter().enumerate() {
+            let Some(state) = step(config.state, ops[i].kind) else {
+                continue;
+            };
+            let mut pending = config.pending.clone();
+            pending.remove(idx);
+            let next = Config { state, pending };
+            if visited.insert(next.clone()) {
+                stack.push(next);
+            }
+        }
+    }
+    linearized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(call: u64, ret: u64, kind: Kind) -> Operation {
+        Operation {
+            worker: 0,
+            key: 0,
+            kind,
+            call,
+            ret,
+        }
+    }
+
+    #[test]
+    fn sequential_history() {
+        let ops = vec![
+            op(0, 1, Kind::Read(None)),
+            op(2, 3, Kind::Write(1)),
+            op(4, 5, Kind::Read(Some(1))),
+            op(6, 7, Kind::Write(2)),
+            op(8, 9, Kind::Read(Some(2))),
+        ];
+        assert!(check(ops).is_ok());
+    }
+
+    #[test]
+    fn concurrent_history() {
+        // The two writes overlap, so both orders are allowed.
+        let ops = vec![
+            op(0, 10, Kind::Write(1)),
+            op(1, 11, Kind::Write(2)),
+            op(12, 13, Kind::Read(Some(1))),
+            op(14, 15, Kind::Read(Some(1))),
+        ];
+        assert!(check(ops).is_ok());
+
+        // The read overlaps with the write, so both the old and new values are allowed.
+        let ops = vec![
+            op(0, 1, Kind::Write(1)),
+            op(2, 10, Kind::Write(2)),
+            op(3, 4, Kind::Read(Some(2))),
+            op(5, 6, Kind::Read(Some(2))),
+        ];
+        assert!(check(ops).is_ok());
+    }
+
+    #[test]
+    fn stale_read() {
+        let ops = vec![
+            op(0, 1, Kind::Write(1)),
+            op(2, 3, Kind::Write(2)),
+            op(4, 5, Kind::Read(Some(1))),
+        ];
+        let violation = check(ops).unwrap_err();
+        assert_eq!(violation.key, 0);
+        assert_eq!(violation.ops.len(), 3);
+
+        // Once a new value is observed, the later reads must not observe the old one.
+        let ops = vec![
+            op(0, 1, Kind::Write(1)),
+            op(2, 20, Kind::Write(2)),
+            op(3, 4, Kind::Read(Some(2))),
+            op(5, 6, Kind::Read(Some(1))),
+        ];
+        assert!(check(ops).is_err());
+    }
+
+    #[test]
+    fn unknown_write() {
+        // A write with unknown outcome might take effect later, or never.
+        let ops = vec![
+            op(0, 1, Kind::Write(1)),
+            op(2, UNKNOWN, Kind::Write(2)),
+            op(3, 4, Kind::Read(Some(1))),
+            op(10, 11, Kind::Read(Some(2))),
+        ];
+        assert!(check(ops).is_ok());
+
+        let ops = vec![
+            op(0, 1, Kind::Write(1)),
+            op(2, UNKNOWN, Kind::Write(2)),
+            op(3, 4, Kind::Read(Some(1))),
+        ];
+        assert!(check(ops).is_ok());
+
+        // But it can't take effect before it is invoked.
+        let ops = vec![
+            op(0, 1, Kind::Write(1)),
+            op(2, 3, Kind::Read(Some(2))),
+            op(4, UNKNOWN, Kind::Write(2)),
+        ];
+        assert!(check(ops).is_err());
+    }
+
+    #[test]
+    fn independent_keys() {
+        let mut ops = vec![op(0, 1, Kind::Write(1)), op(2, 3, Kind::Read(Some(1)))];
+        let mut other = op(0, 3, Kind::Read(Some(1)));
+        other.key = 1;
+        ops.push(other);
+        let violation = check(ops).unwrap_err();
+        assert_eq!(violation.key, 1);
+    }
+}
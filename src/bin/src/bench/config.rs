@@ -34,6 +34,7 @@ pub struct AppConfig {
     pub data: DataConfig,
     pub key: KeyConfig,
     pub worker: WorkerConfig,
+    pub check: CheckConfig,
 }
 
 impl Default for AppConfig {
@@ -51,6 +52,7 @@ impl Default for AppConfig {
             data: DataConfig::default(),
             key: KeyConfig::default(),
             worker: WorkerConfig::default(),
+            check: CheckConfig::default(),
         }
     }
 }
@@ -106,3 +108,25 @@ impl Default for WorkerConfig {
         }
     }
 }
+
+/// The config of linearizability check, see `engula bench --check`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckConfig {
+    /// The number of keys, the fewer keys the more contention.
+    pub keys: u64,
+    /// The interval between nemesis actions, nemesis is disabled if it is `None`.
+    pub nemesis_interval: Option<Duration>,
+    pub transfer_leader: bool,
+    pub move_shard: bool,
+}
+
+impl Default for CheckConfig {
+    fn default() -> Self {
+        CheckConfig {
+            keys: 16,
+            nemesis_interval: Some(Duration::from_secs(1)),
+            transfer_leader: true,
+            move_shard: true,
+        }
+    }
+}
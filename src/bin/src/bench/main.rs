@@ -18,9 +18,9 @@ use engula_client::{AppError, ClientOptions, Collection, Database, EngulaClient,
 use engula_server::runtime::{sync::WaitGroup, Shutdown, ShutdownNotifier};
 use rand::{rngs::OsRng, RngCore};
 use tokio::{runtime::Runtime, select, time::MissedTickBehavior};
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
-use super::{config::*, report, report::ReportContext, worker::*};
+use super::{check, config::*, nemesis::nemesis_main, report, report::ReportContext, worker::*};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
@@ -36,10 +36,16 @@ pub struct Command {
     /// Sets a custom config file
     #[clap(long, value_name = "FILE")]
     conf: Option<String>,
+
+    /// Check the linearizability of operations while moving leaders and shards, instead of
+    /// measuring the performance
+    #[clap(long)]
+    check: bool,
 }
 
 impl Command {
     pub fn run(self) {
+        let check = self.check;
         let cfg = load_config(self).unwrap();
 
         info!("config {:#?}", cfg);
@@ -53,6 +59,11 @@ impl Command {
         let co = runtime
             .block_on(async { open_collection(&cfg).await })
             .expect("open collection");
+        if check {
+            run_check(runtime, cfg, co);
+            return;
+        }
+
         let notifier = ShutdownNotifier::default();
         let ctx = Context {
             wait_group: WaitGroup::new(),
@@ -116,6 +127,56 @@ fn spawn_worker(ctx: &Context, cfg: AppConfig, i: usize, seed: u64, num_op: usiz
     });
 }
 
+fn run_check(runtime: Runtime, cfg: AppConfig, co: Collection) {
+    let base_seed = cfg.seed.unwrap_or_else(|| OsRng.next_u64());
+    // The keys are isolated between runs, so that the initial value of each key is absent.
+    let run_id = OsRng.next_u64();
+    info!(
+        "spawn {} check workers with base seed {base_seed}, run id {run_id}",
+        cfg.worker.num_worker
+    );
+
+    let history = check::History::new();
+    let nemesis = runtime.spawn(nemesis_main(cfg.clone(), base_seed));
+    let num_op = cfg.operation / cfg.worker.num_worker;
+    let handles = (0..cfg.worker.num_worker)
+        .map(|i| {
+            let seed = base_seed + i as u64;
+            let worker = check::worker_main(
+                i,
+                co.clone(),
+                cfg.clone(),
+                seed,
+                num_op,
+                run_id,
+                history.clone(),
+            );
+            runtime.spawn(worker)
+        })
+        .collect::<Vec<_>>();
+    runtime.block_on(async move {
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    });
+    nemesis.abort();
+
+    info!("check the history of {} operations ...", history.len());
+    match check::check(history.operations()) {
+        Ok(()) => info!("the history is linearizable"),
+        Err(violation) => {
+            error!(
+                "the history of key {} is not linearizable, operations:",
+                violation.key
+            );
+            for op in &violation.ops {
+                error!("{op}");
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
 async fn create_or_open_database(client: &EngulaClient, database: &str) -> Result<Database> {
     match client.create_database(database.to_owned()).await {
         Ok(db) => Ok(db),
@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod check;
 mod config;
 mod main;
 mod metrics;
+mod nemesis;
 mod report;
 mod worker;
 
@@ -0,0 +1,136 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Inject faults into the cluster while the linearizability check is running, by moving the
+//! leaders and shards via the admin endpoints. Node restarts are not driven here since nodes
+//! are not managed by the bench, but they can be injected externally during the check.
+
+use engula_api::server::v1::{RaftRole, ReplicaRole};
+use engula_server::diagnosis::Metadata;
+use rand::prelude::*;
+use tracing::{info, warn};
+
+use super::AppConfig;
+use crate::admin::AdminClient;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug)]
+enum Action {
+    TransferLeader { group_id: u64, replica_id: u64 },
+    MoveShard { shard_id: u64, dest_group_id: u64 },
+}
+
+pub async fn nemesis_main(cfg: AppConfig, seed: u64) {
+    let Some(interval) = cfg.check.nemesis_interval else {
+        return;
+    };
+    let admin = AdminClient::new(cfg.addrs.clone());
+    let mut rng = SmallRng::seed_from_u64(seed);
+    loop {
+        tokio::time::sleep(interval).await;
+        match next_action(&admin, &cfg, &mut rng).await {
+            Ok(Some(action)) => {
+                info!("nemesis {action:?}");
+                if let Err(err) = execute(&admin, &action).await {
+                    warn!("nemesis {action:?}: {err}");
+                }
+            }
+            Ok(None) => {}
+            Err(err) => warn!("nemesis fetch metadata: {err}"),
+        }
+    }
+}
+
+async fn execute(admin: &AdminClient, action: &Action) -> Result<()> {
+    match *action {
+        Action::TransferLeader {
+            group_id,
+            replica_id,
+        } => admin.transfer_leader(group_id, replica_id).await,
+        Action::MoveShard {
+            shard_id,
+            dest_group_id,
+        } => admin.move_shard(shard_id, dest_group_id).await,
+    }
+}
+
+async fn next_action(
+    admin: &AdminClient,
+    cfg: &AppConfig,
+    rng: &mut SmallRng,
+) -> Result<Option<Action>> {
+    let metadata = admin.metadata().await?;
+    let mut actions = Vec::with_capacity(2);
+    if cfg.check.transfer_leader {
+        actions.extend(transfer_leader(&metadata, cfg, rng));
+    }
+    if cfg.check.move_shard {
+        actions.extend(move_shard(&metadata, cfg, rng));
+    }
+    Ok(actions.into_iter().choose(rng))
+}
+
+fn collection_id(metadata: &Metadata, cfg: &AppConfig) -> Option<u64> {
+    metadata
+        .databases
+        .iter()
+        .find(|db| db.name == cfg.database)?
+        .collections
+        .iter()
+        .find(|co| co.name == cfg.collection)
+        .map(|co| co.id)
+}
+
+/// Transfer the leadership of a group serving the collection to a random voter.
+fn transfer_leader(metadata: &Metadata, cfg: &AppConfig, rng: &mut SmallRng) -> Option<Action> {
+    let collection_id = collection_id(metadata, cfg)?;
+    let group = metadata
+        .groups
+        .iter()
+        .filter(|g| g.shards.iter().any(|s| s.collection == collection_id))
+        .choose(rng)?;
+    let replica = group
+        .replicas
+        .iter()
+        .filter(|r| {
+            r.raft_role != RaftRole::Leader as i32 && r.replica_role == ReplicaRole::Voter as i32
+        })
+        .choose(rng)?;
+    Some(Action::TransferLeader {
+        group_id: group.id,
+        replica_id: replica.id,
+    })
+}
+
+/// Move a random shard of the collection to another group which also serves the collection, so
+/// that the shards are never moved into the root group.
+fn move_shard(metadata: &Metadata, cfg: &AppConfig, rng: &mut SmallRng) -> Option<Action> {
+    let collection_id = collection_id(metadata, cfg)?;
+    let groups = metadata
+        .groups
+        .iter()
+        .filter(|g| g.shards.iter().any(|s| s.collection == collection_id))
+        .collect::<Vec<_>>();
+    let src = groups.choose(rng)?;
+    let dest = groups.iter().filter(|g| g.id != src.id).choose(rng)?;
+    let shard = src
+        .shards
+        .iter()
+        .filter(|s| s.collection == collection_id)
+        .choose(rng)?;
+    Some(Action::MoveShard {
+        shard_id: shard.id,
+        dest_group_id: dest.id,
+    })
+}
@@ -0,0 +1,498 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use engula_api::server::v1::RaftRole;
+use serde_json::{json, Value};
+
+use crate::{
+    admin::{node_status, raft_role, replica_role, AdminClient},
+    table::{json_str, Table},
+};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[clap(about = "Manage engula cluster")]
+pub struct Command {
+    /// Sets the address of the target cluster to operate
+    #[clap(long, default_value = "0.0.0.0:21805")]
+    addrs: Vec<String>,
+
+    /// Sets the format of output
+    #[clap(long, value_enum, default_value = "table")]
+    output: Output,
+
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum SubCommand {
+    /// Manage the nodes of cluster
+    #[clap(subcommand)]
+    Node(NodeCommand),
+    /// Inspect groups and transfer group leaders
+    #[clap(subcommand)]
+    Group(GroupCommand),
+    /// Inspect the replicas of groups
+    #[clap(subcommand)]
+    Replica(ReplicaCommand),
+    /// Inspect and move shards
+    #[clap(subcommand)]
+    Shard(ShardCommand),
+    /// Inspect and cancel the background jobs of root
+    #[clap(subcommand)]
+    Job(JobCommand),
+}
+
+#[derive(Subcommand)]
+enum NodeCommand {
+    /// List the nodes of cluster
+    List,
+    /// Show the status of a node
+    Status { node_id: u64 },
+    /// Stop placing new replicas to a node
+    Cordon { node_id: u64 },
+    /// Allow placing new replicas to a node again
+    Uncordon { node_id: u64 },
+    /// Replace the labels of a node, the nodes labelled for read replicas only hold read learners
+    Label {
+        node_id: u64,
+
+        /// Sets the comma separated labels, an empty value clears all labels
+        #[clap(long, default_value = "")]
+        labels: String,
+    },
+    /// Move the leaders out of a cordoned node
+    Drain { node_id: u64 },
+    /// Move all replicas out of a node, and wait until it is decommissioned
+    Decommission {
+        node_id: u64,
+
+        /// Sets the seconds to wait for the node to be decommissioned
+        #[clap(long, default_value = "600")]
+        timeout: u64,
+    },
+    /// Remove a drained node which holds no replicas from cluster
+    Remove { node_id: u64 },
+}
+
+#[derive(Subcommand)]
+enum GroupCommand {
+    /// List the groups of cluster
+    List,
+    /// Show the replicas and shards of a group
+    Show { group_id: u64 },
+    /// Transfer the leadership of a group to the target replica
+    TransferLeader { group_id: u64, replica_id: u64 },
+}
+
+#[derive(Subcommand)]
+enum ReplicaCommand {
+    /// List the replicas of cluster
+    List {
+        /// Only list the replicas of this group
+        #[clap(long)]
+        group: Option<u64>,
+
+        /// Only list the replicas on this node
+        #[clap(long)]
+        node: Option<u64>,
+
+        /// Only list the leader replicas
+        #[clap(long)]
+        leader: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ShardCommand {
+    /// List the shards of cluster
+    List {
+        /// Only list the shards of this group
+        #[clap(long)]
+        group: Option<u64>,
+    },
+    /// Move a shard to the target group
+    Move { shard_id: u64, dest_group_id: u64 },
+}
+
+#[derive(Subcommand)]
+enum JobCommand {
+    /// List the ongoing background jobs
+    List {
+        /// List the finished jobs instead
+        #[clap(long)]
+        history: bool,
+    },
+    /// Show a background job
+    Show { job_id: u64 },
+    /// Cancel an ongoing background job, only the resharding jobs before cutover are supported
+    Cancel { job_id: u64 },
+}
+
+impl Command {
+    pub fn run(self) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let ctx = Context {
+            admin: AdminClient::new(self.addrs),
+            output: self.output,
+        };
+        if let Err(err) = runtime.block_on(ctx.execute(self.subcmd)) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+struct Context {
+    admin: AdminClient,
+    output: Output,
+}
+
+impl Context {
+    async fn execute(&self, cmd: SubCommand) -> Result<()> {
+        match cmd {
+            SubCommand::Node(cmd) => self.execute_node_cmd(cmd).await,
+            SubCommand::Group(cmd) => self.execute_group_cmd(cmd).await,
+            SubCommand::Replica(cmd) => self.execute_replica_cmd(cmd).await,
+            SubCommand::Shard(cmd) => self.execute_shard_cmd(cmd).await,
+            SubCommand::Job(cmd) => self.execute_job_cmd(cmd).await,
+        }
+    }
+
+    async fn execute_node_cmd(&self, cmd: NodeCommand) -> Result<()> {
+        let node_id = match cmd {
+            NodeCommand::List => {
+                let metadata = self.admin.metadata().await?;
+                let rows = metadata
+                    .nodes
+                    .iter()
+                    .map(|n| {
+                        vec![
+                            json!(n.id),
+                            json!(n.addr),
+                            json!(node_status(n.status)),
+                            json!(n.replicas.len()),
+                            json!(n.leaders.len()),
+                        ]
+                    })
+                    .collect();
+                return self.print(&["id", "addr", "status", "replicas", "leaders"], rows);
+            }
+            NodeCommand::Status { node_id } => node_id,
+            NodeCommand::Cordon { node_id } => {
+                self.admin.cordon(node_id).await?;
+                node_id
+            }
+            NodeCommand::Uncordon { node_id } => {
+                self.admin.uncordon(node_id).await?;
+                node_id
+            }
+            NodeCommand::Label { node_id, labels } => {
+                self.admin.label_node(node_id, &labels).await?;
+                node_id
+            }
+            NodeCommand::Drain { node_id } => {
+                self.admin.drain(node_id).await?;
+                node_id
+            }
+            NodeCommand::Decommission { node_id, timeout } => {
+                self.decommission(node_id, Duration::from_secs(timeout))
+                    .await?;
+                node_id
+            }
+            NodeCommand::Remove { node_id } => {
+                self.admin.remove_node(node_id).await?;
+                return self.print(
+                    &["node", "status"],
+                    vec![vec![json!(node_id), json!("REMOVED")]],
+                );
+            }
+        };
+        let status = self.admin.node_status(node_id).await?;
+        self.print(
+            &["node", "status"],
+            vec![vec![json!(node_id), json!(status)]],
+        )
+    }
+
+    async fn execute_group_cmd(&self, cmd: GroupCommand) -> Result<()> {
+        match cmd {
+            GroupCommand::List => {
+                let metadata = self.admin.metadata().await?;
+                let rows = metadata
+                    .groups
+                    .iter()
+                    .map(|g| {
+                        let leader = g
+                            .replicas
+                            .iter()
+                            .find(|r| r.raft_role == RaftRole::Leader as i32);
+                        vec![
+                            json!(g.id),
+                            json!(g.epoch),
+                            json!(leader.map(|r| r.id)),
+                            json!(leader.map(|r| r.node)),
+                            json!(g.replicas.len()),
+                            json!(g.shards.len()),
+                        ]
+                    })
+                    .collect();
+                self.print(
+                    &["id", "epoch", "leader", "leader_node", "replicas", "shards"],
+                    rows,
+                )
+            }
+            GroupCommand::Show { group_id } => {
+                let metadata = self.admin.metadata().await?;
+                let group = metadata
+                    .groups
+                    .iter()
+                    .find(|g| g.id == group_id)
+                    .ok_or_else(|| format!("group {group_id} not found"))?;
+                match self.output {
+                    Output::Json => println!("{}", serde_json::to_string_pretty(group)?),
+                    Output::Table => {
+                        let mut replicas =
+                            Table::new(["replica", "node", "raft_role", "replica_role", "term"]);
+                        for r in &group.replicas {
+                            replicas.add_row([
+                                r.id.to_string(),
+                                r.node.to_string(),
+                                raft_role(r.raft_role),
+                                replica_role(r.replica_role),
+                                r.term.to_string(),
+                            ]);
+                        }
+                        let mut shards = Table::new(["shard", "collection", "partition"]);
+                        for s in &group.shards {
+                            shards.add_row([
+                                s.id.to_string(),
+                                s.collection.to_string(),
+                                s.partition.clone(),
+                            ]);
+                        }
+                        println!("group {} (epoch {})\n", group.id, group.epoch);
+                        println!("{replicas}");
+                        print!("{shards}");
+                    }
+                }
+                Ok(())
+            }
+            GroupCommand::TransferLeader {
+                group_id,
+                replica_id,
+            } => {
+                self.admin.transfer_leader(group_id, replica_id).await?;
+                self.print(
+                    &["group", "target_replica", "state"],
+                    vec![vec![json!(group_id), json!(replica_id), json!("SUBMITTED")]],
+                )
+            }
+        }
+    }
+
+    async fn execute_replica_cmd(&self, cmd: ReplicaCommand) -> Result<()> {
+        let ReplicaCommand::List {
+            group,
+            node,
+            leader,
+        } = cmd;
+        let metadata = self.admin.metadata().await?;
+        let rows = metadata
+            .groups
+            .iter()
+            .filter(|g| group.map(|id| id == g.id).unwrap_or(true))
+            .flat_map(|g| g.replicas.iter().map(move |r| (g.id, r)))
+            .filter(|(_, r)| node.map(|id| id == r.node).unwrap_or(true))
+            .filter(|(_, r)| !leader || r.raft_role == RaftRole::Leader as i32)
+            .map(|(group_id, r)| {
+                vec![
+                    json!(group_id),
+                    json!(r.id),
+                    json!(r.node),
+                    json!(raft_role(r.raft_role)),
+                    json!(replica_role(r.replica_role)),
+                    json!(r.term),
+                ]
+            })
+            .collect();
+        self.print(
+            &["group", "id", "node", "raft_role", "replica_role", "term"],
+            rows,
+        )
+    }
+
+    async fn execute_shard_cmd(&self, cmd: ShardCommand) -> Result<()> {
+        match cmd {
+            ShardCommand::List { group } => {
+                let metadata = self.admin.metadata().await?;
+                let rows = metadata
+                    .groups
+                    .iter()
+                    .filter(|g| group.map(|id| id == g.id).unwrap_or(true))
+                    .flat_map(|g| g.shards.iter().map(move |s| (g.id, s)))
+                    .map(|(group_id, s)| {
+                        vec![
+                            json!(group_id),
+                            json!(s.id),
+                            json!(s.collection),
+                            json!(s.partition),
+                        ]
+                    })
+                    .collect();
+                self.print(&["group", "id", "collection", "partition"], rows)
+            }
+            ShardCommand::Move {
+                shard_id,
+                dest_group_id,
+            } => {
+                self.admin.move_shard(shard_id, dest_group_id).await?;
+                self.print(
+                    &["shard", "dest_group", "state"],
+                    vec![vec![
+                        json!(shard_id),
+                        json!(dest_group_id),
+                        json!("SUBMITTED"),
+                    ]],
+                )
+            }
+        }
+    }
+
+    async fn execute_job_cmd(&self, cmd: JobCommand) -> Result<()> {
+        if let JobCommand::Cancel { job_id } = cmd {
+            self.admin.cancel_job(job_id).await?;
+            return self.print(
+                &["job", "state"],
+                vec![vec![json!(job_id), json!("CANCELING")]],
+            );
+        }
+
+        let jobs = self.admin.jobs().await?;
+        let (jobs, show) = match cmd {
+            JobCommand::List { history } => {
+                let state = if history { "history" } else { "ongoing" };
+                (jobs[state].as_array().cloned().unwrap_or_default(), None)
+            }
+            JobCommand::Show { job_id } => {
+                let jobs = ["ongoing", "history"]
+                    .iter()
+                    .flat_map(|state| jobs[state].as_array().cloned().unwrap_or_default())
+                    .filter(|job| job["id"].as_u64() == Some(job_id))
+                    .collect::<Vec<_>>();
+                if jobs.is_empty() {
+                    return Err(format!("job {job_id} not found").into());
+                }
+                (jobs, Some(job_id))
+            }
+            JobCommand::Cancel { .. } => unreachable!(),
+        };
+
+        match self.output {
+            Output::Json if show.is_some() => {
+                println!("{}", serde_json::to_string_pretty(&jobs[0])?);
+            }
+            Output::Json => println!("{}", serde_json::to_string_pretty(&jobs)?),
+            Output::Table => {
+                let mut table = Table::new(["id", "type", "status", "details"]);
+                for job in &jobs {
+                    let details = job
+                        .as_object()
+                        .map(|fields| {
+                            fields
+                                .iter()
+                                .filter(|(k, _)| !matches!(k.as_str(), "id" | "type" | "status"))
+                                .map(|(k, v)| format!("{k}={v}"))
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        })
+                        .unwrap_or_default();
+                    table.add_row([
+                        json_str(&job["id"]),
+                        json_str(&job["type"]),
+                        json_str(&job["status"]),
+                        details,
+                    ]);
+                }
+                print!("{table}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Decommission the node, then wait until all replicas are moved out. Decommissioning the
+    /// root leader is rejected until the root leadership is moved out, so it is retried until
+    /// timeout.
+    async fn decommission(&self, node_id: u64, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let status = self.admin.node_status(node_id).await?;
+            match status.as_str() {
+                "DECOMMISSIONING" => {}
+                "DECOMMISSIONED" => return Ok(()),
+                _ => {
+                    if let Err(err) = self.admin.decommission(node_id).await {
+                        eprintln!("decommission node {node_id}: {err}, retry later");
+                    }
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(
+                    format!("wait node {node_id} decommissioned timeout, it is {status}").into(),
+                );
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    fn print(&self, header: &[&str], rows: Vec<Vec<Value>>) -> Result<()> {
+        match self.output {
+            Output::Json => {
+                let objects = rows
+                    .into_iter()
+                    .map(|row| {
+                        header
+                            .iter()
+                            .map(|h| h.to_string())
+                            .zip(row)
+                            .collect::<serde_json::Map<_, _>>()
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", serde_json::to_string_pretty(&objects)?);
+            }
+            Output::Table => {
+                let mut table = Table::new(header);
+                for row in &rows {
+                    table.add_row(row.iter().map(json_str));
+                }
+                print!("{table}");
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use engula_server::{
+    debug::{GroupEntry, Inspector},
+    raftgroup::ApplyEntry,
+    runtime::ExecutorOwner,
+    serverpb::v1::ReplicaLocalState,
+    DbConfig, Error, Result,
+};
+
+use crate::table::Table;
+
+#[derive(Parser)]
+#[clap(about = "Inspect and repair the data directory of a stopped node")]
+pub struct Command {
+    /// Sets the path of the data directory of the node
+    #[clap(long, value_name = "DIR")]
+    db: PathBuf,
+
+    /// Sets the config file of the node, the data directory is opened with its db options
+    #[clap(long, value_name = "FILE")]
+    conf: Option<String>,
+
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Subcommand)]
+enum SubCommand {
+    /// List the replicas of the node and their local states
+    Replicas,
+    /// Show the apply state, descriptor and migration state of the local replica of a group
+    Group { group_id: u64 },
+    /// Print the raft states and log entries of a replica
+    RaftLog {
+        replica_id: u64,
+
+        /// Print the entries since this index, default is the first index
+        #[clap(long)]
+        start: Option<u64>,
+
+        /// Sets the max number of entries to print
+        #[clap(long, default_value = "100")]
+        limit: usize,
+    },
+    /// Print the decoded keys of the local replica of a group
+    Keys {
+        group_id: u64,
+
+        /// Sets the max number of keys to print
+        #[clap(long, default_value = "100")]
+        limit: usize,
+    },
+    /// List the snapshots of the node
+    Snapshots,
+    /// Make the local replica the only voter of a group which has lost the majority of replicas.
+    ///
+    /// The entries not yet applied by the local replica are discarded, and the writes committed
+    /// by the lost replicas might be lost. Only run it on one surviving replica of the group.
+    UnsafeRecover {
+        /// The id of the group to recover
+        #[clap(long)]
+        group: u64,
+
+        /// Recover the local replica even if it is not a voter, eg a learner or a witness
+        #[clap(long)]
+        force: bool,
+    },
+}
+
+impl Command {
+    pub fn run(self) -> Result<()> {
+        let owner = ExecutorOwner::new(1);
+        let executor = owner.executor();
+        let db = self.db;
+        let db_cfg = load_db_config(self.conf.as_deref())?;
+        executor.block_on(async move {
+            match self.subcmd {
+                SubCommand::UnsafeRecover { group, force } => {
+                    unsafe_recover(db, &db_cfg, group, force).await
+                }
+                cmd => {
+                    let inspector = Inspector::open(&db, &db_cfg)?;
+                    inspect(&inspector, cmd).await
+                }
+            }
+        })
+    }
+}
+
+/// Load the db options of the node from the config file, the data directory must be opened with
+/// the same options as the node, eg the column families and the table format.
+fn load_db_config(conf: Option<&str>) -> Result<DbConfig> {
+    use config::{Config, ConfigError, File};
+
+    let Some(conf) = conf else {
+        return Ok(DbConfig::default());
+    };
+    let c = Config::builder()
+        .add_source(File::with_name(conf))
+        .build()
+        .map_err(|e| Error::InvalidArgument(format!("Config: {e}")))?;
+    match c.get::<DbConfig>("db") {
+        Ok(db_cfg) => Ok(db_cfg),
+        Err(ConfigError::NotFound(_)) => Ok(DbConfig::default()),
+        Err(e) => Err(Error::InvalidArgument(format!("Config: {e}"))),
+    }
+}
+
+async fn inspect(inspector: &Inspector, cmd: SubCommand) -> Result<()> {
+    match cmd {
+        SubCommand::Replicas => list_replicas(inspector).await,
+        SubCommand::Group { group_id } => show_group(inspector, group_id).await,
+        SubCommand::RaftLog {
+            replica_id,
+            start,
+            limit,
+        } => print_raft_log(inspector, replica_id, start, limit),
+        SubCommand::Keys { group_id, limit } => print_keys(inspector, group_id, limit).await,
+        SubCommand::Snapshots => list_snapshots(inspector),
+        SubCommand::UnsafeRecover { .. } => unreachable!(),
+    }
+}
+
+async fn list_replicas(inspector: &Inspector) -> Result<()> {
+    match inspector.node_ident().await? {
+        Some(ident) => println!("node {}", ident.node_id),
+        None => println!("node is not bootstrapped"),
+    }
+    if let Some(root_desc) = inspector.root_desc().await? {
+        let root_nodes = root_desc
+            .root_nodes
+            .iter()
+            .map(|n| format!("{}({})", n.id, n.addr))
+            .collect::<Vec<_>>();
+        println!(
+            "root epoch {} nodes {}",
+            root_desc.epoch,
+            root_nodes.join(", ")
+        );
+    }
+
+    let mut table = Table::new(["group", "replica", "state"]);
+    for (group_id, replica_id, state) in inspector.replica_states().await? {
+        table.add_row([
+            group_id.to_string(),
+            replica_id.to_string(),
+            local_state(state).to_owned(),
+        ]);
+    }
+    print!("{table}");
+    Ok(())
+}
+
+async fn show_group(inspector: &Inspector, group_id: u64) -> Result<()> {
+    let states = inspector.group_states(group_id).await?;
+    println!("replica: {}", states.replica_id);
+    println!(
+        "apply state: index {} term {}",
+        states.apply_state.index, states.apply_state.term
+    );
+    println!("descriptor: {:#?}", states.descriptor);
+    match states.migration_state {
+        Some(state) => println!("migration state: {state:#?}"),
+        None => println!("migration state: none"),
+    }
+    Ok(())
+}
+
+fn print_raft_log(
+    inspector: &Inspector,
+    replica_id: u64,
+    start: Option<u64>,
+    limit: usize,
+) -> Result<()> {
+    let dump = inspector.raft_log(replica_id, start, limit)?;
+    match dump.hard_state {
+        Some(hs) => println!(
+            "hard state: term {} vote {} commit {}",
+            hs.term, hs.vote, hs.commit
+        ),
+        None => println!("hard state: none"),
+    }
+    match dump.local_state.and_then(|s| s.last_truncated) {
+        Some(truncated) => println!(
+            "last truncated: index {} term {}",
+            truncated.index, truncated.term
+        ),
+        None => println!("last truncated: none"),
+    }
+    println!("log range: [{}, {}]", dump.first_index, dump.last_index);
+
+    let mut table = Table::new(["index", "term", "type", "payload"]);
+    for (entry_id, entry) in dump.entries {
+        let (kind, payload) = match entry {
+            ApplyEntry::Empty => ("empty", String::default()),
+            ApplyEntry::ConfigChange { change_replicas } => {
+                ("config_change", format!("{:?}", change_replicas.changes))
+            }
+            ApplyEntry::Proposal { eval_result } => {
+                let mut payload = vec![];
+                if let Some(batch) = eval_result.batch {
+                    payload.push(format!(
+                        "batch {} bytes {} delete ranges",
+                        batch.data.len(),
+                        batch.delete_ranges.len()
+                    ));
+                }
+                if let Some(op) = eval_result.op {
+                    payload.push(format!("op {op:?}"));
+                }
+                ("proposal", payload.join(", "))
+            }
+        };
+        table.add_row([
+            entry_id.index.to_string(),
+            entry_id.term.to_string(),
+            kind.to_owned(),
+            payload,
+        ]);
+    }
+    print!("{table}");
+    Ok(())
+}
+
+async fn print_keys(inspector: &Inspector, group_id: u64, limit: usize) -> Result<()> {
+    let mut table = Table::new(["collection", "slot", "key", "version", "value"]);
+    for entry in inspector.group_entries(group_id, limit).await? {
+        let row = match entry {
+            GroupEntry::Local { name, value } => [
+                "local".to_owned(),
+                String::default(),
+                name,
+                String::default(),
+                format!("{} bytes", value.len()),
+            ],
+            GroupEntry::Mvcc {
+                collection_id,
+                slot,
+                user_key,
+                version,
+                value,
+            } => [
+                collection_id.to_string(),
+                slot.map(|s| s.to_string()).unwrap_or_default(),
+                user_key.escape_ascii().to_string(),
+                version.to_string(),
+                value
+                    .map(|v| v.escape_ascii().to_string())
+                    .unwrap_or_else(|| "<tombstone>".to_owned()),
+            ],
+            GroupEntry::Unknown { key, value } => [
+                "unknown".to_owned(),
+                String::default(),
+                key.escape_ascii().to_string(),
+                String::default(),
+                format!("{} bytes", value.len()),
+            ],
+        };
+        table.add_row(row);
+    }
+    print!("{table}");
+    Ok(())
+}
+
+fn list_snapshots(inspector: &Inspector) -> Result<()> {
+    let mut table = Table::new(["replica", "index", "term", "epoch", "files", "dir"]);
+    for (replica_id, dir, meta) in inspector.snapshots()? {
+        let (index, term, epoch, files) = match meta {
+            Some(meta) => {
+                let apply_state = meta.apply_state.unwrap_or_default();
+                let epoch = meta.group_desc.map(|d| d.epoch).unwrap_or_default();
+                (
+                    apply_state.index.to_string(),
+                    apply_state.term.to_string(),
+                    epoch.to_string(),
+                    meta.files.len().to_string(),
+                )
+            }
+            None => {
+                let invalid = "<invalid>".to_owned();
+                (invalid.clone(), invalid.clone(), invalid.clone(), invalid)
+            }
+        };
+        table.add_row([
+            replica_id.to_string(),
+            index,
+            term,
+            epoch,
+            files,
+            dir.display().to_string(),
+        ]);
+    }
+    print!("{table}");
+    Ok(())
+}
+
+async fn unsafe_recover(db: PathBuf, db_cfg: &DbConfig, group_id: u64, force: bool) -> Result<()> {
+    let desc = engula_server::debug::unsafe_recover(&db, db_cfg, group_id, force).await?;
+    println!(
+        "group {} is recovered with epoch {}, replica {} on node {} is the only voter now",
+        desc.id, desc.epoch, desc.replicas[0].id, desc.replicas[0].node_id
+    );
+    Ok(())
+}
+
+fn local_state(state: ReplicaLocalState) -> &'static str {
+    match state {
+        ReplicaLocalState::Initial => "initial",
+        ReplicaLocalState::Pending => "pending",
+        ReplicaLocalState::Normal => "normal",
+        ReplicaLocalState::Terminated => "terminated",
+        ReplicaLocalState::Tombstone => "tombstone",
+    }
+}
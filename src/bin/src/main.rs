@@ -13,8 +13,12 @@
 // limitations under the License.
 #![feature(once_cell)]
 
+mod admin;
 mod bench;
+mod ctl;
+mod debug;
 mod shell;
+mod table;
 
 use clap::{Parser, Subcommand};
 use engula_server::{Error, Result};
@@ -39,6 +43,8 @@ enum SubCommand {
     Start(StartCommand),
     Bench(bench::BenchCommand),
     Shell(shell::ShellCommand),
+    Ctl(ctl::CtlCommand),
+    Debug(debug::DebugCommand),
 }
 
 impl SubCommand {
@@ -53,6 +59,11 @@ impl SubCommand {
                 cmd.run();
                 Ok(())
             }
+            SubCommand::Ctl(cmd) => {
+                cmd.run();
+                Ok(())
+            }
+            SubCommand::Debug(cmd) => cmd.run(),
         }
     }
 }
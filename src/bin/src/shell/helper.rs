@@ -0,0 +1,113 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Context,
+    Helper,
+};
+
+const COMMANDS: &[&str] = &[
+    "help",
+    "config",
+    "get",
+    "put",
+    "delete",
+    "scan",
+    "prefix",
+    "databases",
+    "create-db",
+    "drop-db",
+    "collections",
+    "create-coll",
+    "drop-coll",
+    "truncate-coll",
+    "nodes",
+    "groups",
+    "replicas",
+    "leaders",
+    "shards",
+    "jobs",
+    "cordon",
+    "uncordon",
+    "drain",
+    "status",
+];
+
+const KEYWORDS: &[&str] = &["db", "coll", "limit", "hash", "range"];
+
+/// Completes the commands at the start of line, and the keywords or the names of known
+/// databases and collections for the rest.
+#[derive(Clone, Default)]
+pub struct ShellHelper {
+    names: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl ShellHelper {
+    pub fn add_names<I: IntoIterator<Item = String>>(&self, names: I) {
+        self.names.lock().unwrap().extend(names);
+    }
+
+    pub fn remove_name(&self, name: &str) {
+        self.names.lock().unwrap().remove(name);
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line
+            .rfind(|c: char| c.is_ascii_whitespace())
+            .map(|idx| idx + 1)
+            .unwrap_or_default();
+        let word = &line[start..];
+        let candidates = if line[..start].trim().is_empty() {
+            COMMANDS
+                .iter()
+                .filter(|cmd| cmd.starts_with(word))
+                .map(ToString::to_string)
+                .collect()
+        } else {
+            let names = self.names.lock().unwrap();
+            KEYWORDS
+                .iter()
+                .map(ToString::to_string)
+                .chain(names.iter().cloned())
+                .filter(|name| name.starts_with(word))
+                .collect()
+        };
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
@@ -15,10 +15,20 @@
 use std::{collections::HashMap, io::Write, time::Duration};
 
 use clap::Parser;
+use engula_api::{
+    server::v1::RaftRole,
+    v1::{collection_desc, CollectionDesc},
+};
 use engula_client::{AppError, ClientOptions, Collection, Database, EngulaClient, Partition};
 use lazy_static::lazy_static;
 use rustyline::{error::ReadlineError, Editor};
 
+use super::helper::ShellHelper;
+use crate::{
+    admin::{node_status, raft_role, replica_role, AdminClient},
+    table::{json_str, Table},
+};
+
 type ParseResult<T = Request> = std::result::Result<T, String>;
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
@@ -51,6 +61,29 @@ pub enum Token {
     Config,
     Db,
     Coll,
+    Scan,
+    Prefix,
+    Limit,
+    Reverse,
+    Hash,
+    Range,
+    Databases,
+    CreateDb,
+    DropDb,
+    Collections,
+    CreateColl,
+    DropColl,
+    TruncateColl,
+    Nodes,
+    Groups,
+    Replicas,
+    Leaders,
+    Shards,
+    Jobs,
+    Cordon,
+    Uncordon,
+    Drain,
+    Status,
 }
 
 enum Request {
@@ -76,10 +109,67 @@ enum Request {
         key: String,
         value: String,
     },
+    Scan {
+        start: Vec<u8>,
+        end: Vec<u8>,
+        prefix: Vec<u8>,
+        limit: usize,
+        reverse: bool,
+        db: String,
+        coll: String,
+    },
+    Databases,
+    CreateDb {
+        db: String,
+    },
+    DropDb {
+        db: String,
+    },
+    Collections {
+        db: String,
+    },
+    CreateColl {
+        partition: Partition,
+        db: String,
+        coll: String,
+    },
+    DropColl {
+        db: String,
+        coll: String,
+    },
+    TruncateColl {
+        db: String,
+        coll: String,
+    },
+    Nodes,
+    Groups,
+    Replicas {
+        group: Option<u64>,
+    },
+    Leaders,
+    Shards {
+        db: Option<String>,
+        coll: Option<String>,
+    },
+    Jobs,
+    Cordon {
+        node: u64,
+    },
+    Uncordon {
+        node: u64,
+    },
+    Drain {
+        node: u64,
+    },
+    Status {
+        node: u64,
+    },
 }
 
 struct Session {
     client: EngulaClient,
+    admin: AdminClient,
+    helper: ShellHelper,
     config: HashMap<String, String>,
     databases: HashMap<String, Database>,
     collections: HashMap<String, Collection>,
@@ -89,6 +179,9 @@ const CONFIG_DB: &str = "database";
 const CONFIG_COLL: &str = "collection";
 const CONFIG_CREATE_IF_MISSING: &str = "create-if-missing";
 
+const DEFAULT_SCAN_LIMIT: usize = 100;
+const DEFAULT_NUM_SLOTS: u32 = 64;
+
 impl Session {
     async fn parse_and_execute(&mut self, input: &[u8]) -> Result<()> {
         match self.parse_request(input)? {
@@ -128,9 +221,246 @@ impl Session {
                 coll.delete(key).await?;
                 Ok(())
             }
+            Request::Scan {
+                start,
+                end,
+                prefix,
+                limit,
+                reverse,
+                db,
+                coll,
+            } => {
+                let db = self.open_database(&db).await?;
+                let coll = self.open_collection(&db, &coll).await?;
+                let entries = if reverse {
+                    coll.reverse_scan(start, end, limit).await?
+                } else if prefix.is_empty() {
+                    coll.scan(start, end, limit).await?
+                } else {
+                    coll.prefix_scan(prefix, limit).await?
+                };
+                let mut table = Table::new(["key", "value"]);
+                for (key, value) in &entries {
+                    table.add_row([String::from_utf8_lossy(key), String::from_utf8_lossy(value)]);
+                }
+                print_table(&table)?;
+                o(&format!("({} entries)\n", entries.len()))?;
+                Ok(())
+            }
+            Request::Databases => {
+                let databases = self.client.list_database().await?;
+                let mut table = Table::new(["id", "name"]);
+                for db in &databases {
+                    table.add_row([db.desc().id.to_string(), db.name()]);
+                }
+                self.helper.add_names(databases.iter().map(Database::name));
+                print_table(&table)
+            }
+            Request::CreateDb { db } => {
+                let database = self.client.create_database(db.clone()).await?;
+                self.databases.insert(db.clone(), database);
+                self.helper.add_names([db]);
+                Ok(())
+            }
+            Request::DropDb { db } => {
+                self.client.delete_database(db.clone()).await?;
+                let prefix = format!("{db}-");
+                self.databases.remove(&db);
+                self.collections
+                    .retain(|name, _| !name.starts_with(&prefix));
+                self.helper.remove_name(&db);
+                Ok(())
+            }
+            Request::Collections { db } => {
+                let db = self.open_database(&db).await?;
+                let collections = db.list_collection().await?;
+                let mut table =
+                    Table::new(["id", "name", "partition", "approx_keys", "approx_size"]);
+                for co in &collections {
+                    let desc = co.desc();
+                    let stats = db.collection_stats(desc.name.clone()).await?;
+                    table.add_row([
+                        desc.id.to_string(),
+                        desc.name.clone(),
+                        partition_of(&desc),
+                        stats.approximate_keys.to_string(),
+                        stats.approximate_size.to_string(),
+                    ]);
+                }
+                self.helper
+                    .add_names(collections.iter().map(|co| co.desc().name));
+                print_table(&table)
+            }
+            Request::CreateColl {
+                partition,
+                db,
+                coll,
+            } => {
+                let db = self.open_database(&db).await?;
+                let co = db.create_collection(coll.clone(), Some(partition)).await?;
+                self.collections
+                    .insert(format!("{}-{}", db.name(), coll), co);
+                self.helper.add_names([coll]);
+                Ok(())
+            }
+            Request::DropColl { db, coll } => {
+                let db = self.open_database(&db).await?;
+                db.delete_collection(coll.clone()).await?;
+                self.collections.remove(&format!("{}-{}", db.name(), coll));
+                Ok(())
+            }
+            Request::TruncateColl { db, coll } => {
+                let db = self.open_database(&db).await?;
+                let coll = self.open_collection(&db, &coll).await?;
+                coll.truncate().await?;
+                Ok(())
+            }
+            Request::Nodes => {
+                let metadata = self.admin.metadata().await?;
+                let mut table = Table::new(["id", "addr", "status", "replicas", "leaders"]);
+                for node in &metadata.nodes {
+                    table.add_row([
+                        node.id.to_string(),
+                        node.addr.clone(),
+                        node_status(node.status),
+                        node.replicas.len().to_string(),
+                        node.leaders.len().to_string(),
+                    ]);
+                }
+                print_table(&table)
+            }
+            Request::Groups => {
+                let metadata = self.admin.metadata().await?;
+                let mut table = Table::new(["id", "epoch", "leader", "replicas", "shards"]);
+                for group in &metadata.groups {
+                    let leader = group
+                        .replicas
+                        .iter()
+                        .find(|r| r.raft_role == RaftRole::Leader as i32)
+                        .map(|r| r.id.to_string())
+                        .unwrap_or_else(|| "-".to_owned());
+                    table.add_row([
+                        group.id.to_string(),
+                        group.epoch.to_string(),
+                        leader,
+                        group.replicas.len().to_string(),
+                        group.shards.len().to_string(),
+                    ]);
+                }
+                print_table(&table)
+            }
+            Request::Replicas { group } => {
+                let metadata = self.admin.metadata().await?;
+                let mut table =
+                    Table::new(["group", "id", "node", "raft_role", "replica_role", "term"]);
+                for g in &metadata.groups {
+                    if group.map(|id| id != g.id).unwrap_or_default() {
+                        continue;
+                    }
+                    for r in &g.replicas {
+                        table.add_row([
+                            g.id.to_string(),
+                            r.id.to_string(),
+                            r.node.to_string(),
+                            raft_role(r.raft_role),
+                            replica_role(r.replica_role),
+                            r.term.to_string(),
+                        ]);
+                    }
+                }
+                print_table(&table)
+            }
+            Request::Leaders => {
+                let metadata = self.admin.metadata().await?;
+                let mut table = Table::new(["node", "addr", "group", "replica"]);
+                for node in &metadata.nodes {
+                    for leader in &node.leaders {
+                        table.add_row([
+                            node.id.to_string(),
+                            node.addr.clone(),
+                            leader.group.to_string(),
+                            leader.id.to_string(),
+                        ]);
+                    }
+                }
+                print_table(&table)
+            }
+            Request::Shards { db, coll } => {
+                let metadata = self.admin.metadata().await?;
+                let collections = metadata
+                    .databases
+                    .iter()
+                    .filter(|d| db.as_ref().map(|name| name == &d.name).unwrap_or(true))
+                    .flat_map(|d| d.collections.iter().map(move |c| (c.id, (d, c))))
+                    .filter(|(_, (_, c))| coll.as_ref().map(|name| name == &c.name).unwrap_or(true))
+                    .collect::<HashMap<_, _>>();
+                let mut table =
+                    Table::new(["group", "shard", "database", "collection", "partition"]);
+                for group in &metadata.groups {
+                    for shard in &group.shards {
+                        if let Some((d, c)) = collections.get(&shard.collection) {
+                            table.add_row([
+                                group.id.to_string(),
+                                shard.id.to_string(),
+                                d.name.clone(),
+                                c.name.clone(),
+                                shard.partition.clone(),
+                            ]);
+                        }
+                    }
+                }
+                print_table(&table)
+            }
+            Request::Jobs => {
+                let jobs = self.admin.jobs().await?;
+                let mut table = Table::new(["state", "type", "status", "details"]);
+                for state in ["ongoing", "history"] {
+                    let Some(list) = jobs[state].as_array() else {
+                        continue;
+                    };
+                    for job in list {
+                        let Some(fields) = job.as_object() else {
+                            continue;
+                        };
+                        let details = fields
+                            .iter()
+                            .filter(|(k, _)| *k != "type" && *k != "status")
+                            .map(|(k, v)| format!("{k}={v}"))
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        table.add_row([
+                            state.to_owned(),
+                            json_str(&job["type"]),
+                            json_str(&job["status"]),
+                            details,
+                        ]);
+                    }
+                }
+                print_table(&table)
+            }
+            Request::Cordon { node } => {
+                self.admin.cordon(node).await?;
+                self.print_node_status(node).await
+            }
+            Request::Uncordon { node } => {
+                self.admin.uncordon(node).await?;
+                self.print_node_status(node).await
+            }
+            Request::Drain { node } => {
+                self.admin.drain(node).await?;
+                self.print_node_status(node).await
+            }
+            Request::Status { node } => self.print_node_status(node).await,
         }
     }
 
+    async fn print_node_status(&self, node: u64) -> Result<()> {
+        let status = self.admin.node_status(node).await?;
+        let mut table = Table::new(["node", "status"]);
+        table.add_row([node.to_string(), status]);
+        print_table(&table)
+    }
+
     fn parse_request(&self, input: &[u8]) -> ParseResult {
         let input = skip_space(input);
         match next_token(input) {
@@ -138,6 +468,78 @@ impl Session {
             Some((input, Token::Put)) => self.parse_put_request(input),
             Some((input, Token::Delete)) => self.parse_delete_request(input),
             Some((input, Token::Config)) => self.parse_config_request(input),
+            Some((input, Token::Scan)) => self.parse_scan_request(input),
+            Some((input, Token::Prefix)) => self.parse_prefix_request(input),
+            Some((input, Token::Databases)) => must_eof(input).map(|_| Request::Databases),
+            Some((input, Token::CreateDb)) => {
+                let (input, db) = parse_name(input, "database")?;
+                must_eof(input)?;
+                Ok(Request::CreateDb { db })
+            }
+            Some((input, Token::DropDb)) => {
+                let (input, db) = parse_name(input, "database")?;
+                must_eof(input)?;
+                Ok(Request::DropDb { db })
+            }
+            Some((input, Token::Collections)) => {
+                let (input, db) = self.parse_or_get_config(input, Token::Db, CONFIG_DB)?;
+                must_eof(input)?;
+                Ok(Request::Collections { db })
+            }
+            Some((input, Token::CreateColl)) => self.parse_create_coll_request(input),
+            Some((input, Token::DropColl)) => {
+                let (input, coll) = parse_name(input, "collection")?;
+                let (input, db) = self.parse_or_get_config(input, Token::Db, CONFIG_DB)?;
+                must_eof(input)?;
+                Ok(Request::DropColl { db, coll })
+            }
+            Some((input, Token::TruncateColl)) => {
+                let (input, coll) = parse_name(input, "collection")?;
+                let (input, db) = self.parse_or_get_config(input, Token::Db, CONFIG_DB)?;
+                must_eof(input)?;
+                Ok(Request::TruncateColl { db, coll })
+            }
+            Some((input, Token::Nodes)) => must_eof(input).map(|_| Request::Nodes),
+            Some((input, Token::Groups)) => must_eof(input).map(|_| Request::Groups),
+            Some((input, Token::Replicas)) => {
+                let input = skip_space(input);
+                let (input, group) = if is_eof(input) {
+                    (input, None)
+                } else {
+                    let (input, group) = parse_number(input, "group id")?;
+                    (input, Some(group))
+                };
+                must_eof(input)?;
+                Ok(Request::Replicas { group })
+            }
+            Some((input, Token::Leaders)) => must_eof(input).map(|_| Request::Leaders),
+            Some((input, Token::Shards)) => {
+                let (input, db) = self.parse_optional_item(input, Token::Db, "database")?;
+                let (input, coll) = self.parse_optional_item(input, Token::Coll, "collection")?;
+                must_eof(input)?;
+                Ok(Request::Shards { db, coll })
+            }
+            Some((input, Token::Jobs)) => must_eof(input).map(|_| Request::Jobs),
+            Some((input, Token::Cordon)) => {
+                let (input, node) = parse_number(input, "node id")?;
+                must_eof(input)?;
+                Ok(Request::Cordon { node })
+            }
+            Some((input, Token::Uncordon)) => {
+                let (input, node) = parse_number(input, "node id")?;
+                must_eof(input)?;
+                Ok(Request::Uncordon { node })
+            }
+            Some((input, Token::Drain)) => {
+                let (input, node) = parse_number(input, "node id")?;
+                must_eof(input)?;
+                Ok(Request::Drain { node })
+            }
+            Some((input, Token::Status)) => {
+                let (input, node) = parse_number(input, "node id")?;
+                must_eof(input)?;
+                Ok(Request::Status { node })
+            }
             Some((_, Token::Help)) => Ok(Request::Usage),
             _ => {
                 if is_eof(input) {
@@ -223,6 +625,87 @@ impl Session {
         Ok(Request::Config { key, value })
     }
 
+    fn parse_scan_request(&self, input: &[u8]) -> ParseResult {
+        let input = skip_space(input);
+        let Some((input, start)) = read_entry(input) else {
+            return Err("expect start key, but nothing are found".to_owned());
+        };
+
+        let input = skip_space(input);
+        let Some((input, end)) = read_entry(input) else {
+            return Err("expect end key, but nothing are found".to_owned());
+        };
+
+        let (input, limit) = parse_limit(input)?;
+        let input = skip_space(input);
+        let (input, reverse) = match expect_token(input, Token::Reverse) {
+            Some(input) => (input, true),
+            None => (input, false),
+        };
+        let (input, db) = self.parse_or_get_config(input, Token::Db, CONFIG_DB)?;
+        let (input, coll) = self.parse_or_get_config(input, Token::Coll, CONFIG_COLL)?;
+
+        must_eof(input)?;
+
+        Ok(Request::Scan {
+            start: parse_bound(start),
+            end: parse_bound(end),
+            prefix: vec![],
+            limit,
+            reverse,
+            db,
+            coll,
+        })
+    }
+
+    fn parse_prefix_request(&self, input: &[u8]) -> ParseResult {
+        let input = skip_space(input);
+        let Some((input, prefix)) = read_entry(input) else {
+            return Err("expect prefix, but nothing are found".to_owned());
+        };
+
+        let (input, limit) = parse_limit(input)?;
+        let (input, db) = self.parse_or_get_config(input, Token::Db, CONFIG_DB)?;
+        let (input, coll) = self.parse_or_get_config(input, Token::Coll, CONFIG_COLL)?;
+
+        must_eof(input)?;
+
+        Ok(Request::Scan {
+            start: vec![],
+            end: vec![],
+            prefix,
+            limit,
+            reverse: false,
+            db,
+            coll,
+        })
+    }
+
+    fn parse_create_coll_request(&self, input: &[u8]) -> ParseResult {
+        let (input, coll) = parse_name(input, "collection")?;
+
+        let input = skip_space(input);
+        let (input, partition) = if let Some(input) = expect_token(input, Token::Range) {
+            (input, Partition::Range)
+        } else if let Some(input) = expect_token(input, Token::Hash) {
+            let (input, slots) = parse_number(input, "slots")?;
+            (input, Partition::Hash { slots })
+        } else {
+            let slots = DEFAULT_NUM_SLOTS;
+            (input, Partition::Hash { slots })
+        };
+
+        let (input, db) = self.parse_or_get_config(input, Token::Db, CONFIG_DB)?;
+
+        must_eof(input)?;
+
+        Ok(Request::CreateColl {
+            partition,
+            db,
+            coll,
+        })
+    }
+
     fn parse_or_get_config<'a>(
         &self,
         input: &'a [u8],
@@ -257,6 +740,18 @@ impl Session {
         Ok(None)
     }
 
+    fn parse_optional_item<'a>(
+        &self,
+        input: &'a [u8],
+        tok: Token,
+        name: &str,
+    ) -> ParseResult<(&'a [u8], Option<String>)> {
+        match self.parse_config_item(input, tok, name)? {
+            Some((input, item)) => Ok((input, Some(item))),
+            None => Ok((input, None)),
+        }
+    }
+
     async fn create_or_open_database(&mut self, database: &str) -> Result<Database> {
         match self.client.create_database(database.to_owned()).await {
             Ok(db) => {
@@ -329,7 +824,7 @@ impl Session {
         } else {
             let co = match db.open_collection(coll.to_owned()).await {
                 Ok(co) => {
-                    self.collections.insert(coll.to_owned(), co.clone());
+                    self.collections.insert(name, co.clone());
                     co
                 }
                 Err(AppError::NotFound(_)) if create_if_missing => {
@@ -346,7 +841,8 @@ impl Session {
 
 async fn editor_main(addrs: Vec<String>) {
     let mut session = new_session(addrs).await.expect("new session");
-    let mut editor = Editor::<()>::new().expect("Editor::new");
+    let mut editor = Editor::<ShellHelper>::new().expect("Editor::new");
+    editor.set_helper(Some(session.helper.clone()));
     loop {
         let readline = editor.readline(">> ");
         match readline {
@@ -389,17 +885,56 @@ fn usage() -> Result<()> {
     o("\t get key [db <db-name>] [coll <co-name>]\n")?;
     o("\t put key value [db <db-name>] [coll <co-name>]\n")?;
     o("\t delete key [db <db-name>] [coll <co-name>]\n")?;
+    o("\t scan start end [limit <n>] [reverse] [db <db-name>] [coll <co-name>] \t '-' means unbounded, limit 0 means no limit\n")?;
+    o("\t prefix prefix [limit <n>] [db <db-name>] [coll <co-name>]\n")?;
+    o("\t databases\n")?;
+    o("\t create-db name\n")?;
+    o("\t drop-db name\n")?;
+    o("\t collections [db <db-name>] \t with the approximate key count and size of each collection\n")?;
+    o("\t create-coll name [hash <slots> | range] [db <db-name>]\n")?;
+    o("\t drop-coll name [db <db-name>]\n")?;
+    o("\t truncate-coll name [db <db-name>] \t delete all entries of the collection\n")?;
+    o("\t nodes\n")?;
+    o("\t groups\n")?;
+    o("\t replicas [group-id]\n")?;
+    o("\t leaders\n")?;
+    o("\t shards [db <db-name>] [coll <co-name>]\n")?;
+    o("\t jobs\n")?;
+    o("\t cordon node-id\n")?;
+    o("\t uncordon node-id\n")?;
+    o("\t drain node-id\n")?;
+    o("\t status node-id\n")?;
+    Ok(())
+}
+
+fn print_table(table: &Table) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(table.to_string().as_bytes())?;
+    stdout.flush()?;
     Ok(())
 }
 
+fn partition_of(desc: &CollectionDesc) -> String {
+    match desc.partition.as_ref() {
+        Some(collection_desc::Partition::Hash(hash)) if hash.target_slots != 0 => {
+            format!("hash({} -> {})", hash.slots, hash.target_slots)
+        }
+        Some(collection_desc::Partition::Hash(hash)) => format!("hash({})", hash.slots),
+        Some(collection_desc::Partition::Range(_)) => "range".to_owned(),
+        None => "-".to_owned(),
+    }
+}
+
 async fn new_session(addrs: Vec<String>) -> Result<Session> {
     let opts = ClientOptions {
         connect_timeout: Some(Duration::from_millis(200)),
         timeout: Some(Duration::from_millis(500)),
     };
-    let client = EngulaClient::new(opts, addrs).await?;
+    let client = EngulaClient::new(opts, addrs.clone()).await?;
     Ok(Session {
         client,
+        admin: AdminClient::new(addrs),
+        helper: ShellHelper::default(),
         config: HashMap::default(),
         databases: HashMap::default(),
         collections: HashMap::default(),
@@ -436,6 +971,45 @@ fn expect_token(input: &[u8], tok: Token) -> Option<&[u8]> {
     None
 }
 
+fn parse_limit(input: &[u8]) -> ParseResult<(&[u8], usize)> {
+    let input = skip_space(input);
+    if let Some(input) = expect_token(input, Token::Limit) {
+        parse_number(input, "limit")
+    } else {
+        Ok((input, DEFAULT_SCAN_LIMIT))
+    }
+}
+
+/// A single `-` stands for the unbounded key of a scan.
+fn parse_bound(key: Vec<u8>) -> Vec<u8> {
+    if key == b"-" {
+        vec![]
+    } else {
+        key
+    }
+}
+
+fn parse_name<'a>(input: &'a [u8], name: &str) -> ParseResult<(&'a [u8], String)> {
+    let input = skip_space(input);
+    let Some((input, value)) = read_entry(input) else {
+        return Err(format!("expect {name}, but nothing are found"));
+    };
+    let value =
+        String::from_utf8(value).map_err(|_| format!("the {name} is invalid UTF-8 sequence"))?;
+    Ok((input, value))
+}
+
+fn parse_number<'a, T: std::str::FromStr>(
+    input: &'a [u8],
+    name: &str,
+) -> ParseResult<(&'a [u8], T)> {
+    let (input, value) = parse_name(input, name)?;
+    let value = value
+        .parse()
+        .map_err(|_| format!("the {name} is not a valid number"))?;
+    Ok((input, value))
+}
+
 fn must_eof(input: &[u8]) -> ParseResult<()> {
     let input = skip_space(input);
     if !is_eof(input) {
@@ -497,6 +1071,29 @@ lazy_static! {
         m.insert(Vec::from(&b"config"[..]), Token::Config);
         m.insert(Vec::from(&b"db"[..]), Token::Db);
         m.insert(Vec::from(&b"coll"[..]), Token::Coll);
+        m.insert(Vec::from(&b"scan"[..]), Token::Scan);
+        m.insert(Vec::from(&b"prefix"[..]), Token::Prefix);
+        m.insert(Vec::from(&b"limit"[..]), Token::Limit);
+        m.insert(Vec::from(&b"reverse"[..]), Token::Reverse);
+        m.insert(Vec::from(&b"hash"[..]), Token::Hash);
+        m.insert(Vec::from(&b"range"[..]), Token::Range);
+        m.insert(Vec::from(&b"databases"[..]), Token::Databases);
+        m.insert(Vec::from(&b"create-db"[..]), Token::CreateDb);
+        m.insert(Vec::from(&b"drop-db"[..]), Token::DropDb);
+        m.insert(Vec::from(&b"collections"[..]), Token::Collections);
+        m.insert(Vec::from(&b"create-coll"[..]), Token::CreateColl);
+        m.insert(Vec::from(&b"drop-coll"[..]), Token::DropColl);
+        m.insert(Vec::from(&b"truncate-coll"[..]), Token::TruncateColl);
+        m.insert(Vec::from(&b"nodes"[..]), Token::Nodes);
+        m.insert(Vec::from(&b"groups"[..]), Token::Groups);
+        m.insert(Vec::from(&b"replicas"[..]), Token::Replicas);
+        m.insert(Vec::from(&b"leaders"[..]), Token::Leaders);
+        m.insert(Vec::from(&b"shards"[..]), Token::Shards);
+        m.insert(Vec::from(&b"jobs"[..]), Token::Jobs);
+        m.insert(Vec::from(&b"cordon"[..]), Token::Cordon);
+        m.insert(Vec::from(&b"uncordon"[..]), Token::Uncordon);
+        m.insert(Vec::from(&b"drain"[..]), Token::Drain);
+        m.insert(Vec::from(&b"status"[..]), Token::Status);
         m
     };
 }
@@ -0,0 +1,94 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Display, Formatter, Result};
+
+/// A plain text table, columns are aligned to the widest cell.
+#[derive(Default)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<I, S>(header: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        Table {
+            header: header.into_iter().map(|s| s.to_string()).collect(),
+            rows: Vec::default(),
+        }
+    }
+
+    pub fn add_row<I, S>(&mut self, row: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.rows
+            .push(row.into_iter().map(|s| s.to_string()).collect());
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths = self.header.iter().map(|h| h.len()).collect::<Vec<_>>();
+        for row in &self.rows {
+            for (idx, cell) in row.iter().enumerate() {
+                if idx < widths.len() {
+                    widths[idx] = widths[idx].max(cell.len());
+                } else {
+                    widths.push(cell.len());
+                }
+            }
+        }
+        widths
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        fn write_row(f: &mut Formatter<'_>, widths: &[usize], row: &[String]) -> Result {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, &width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())
+        }
+
+        let widths = self.widths();
+        write_row(f, &widths, &self.header)?;
+        let separators = widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>();
+        write_row(f, &widths, &separators)?;
+        for row in &self.rows {
+            write_row(f, &widths, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Render a JSON value as a table cell, strings are rendered without quotes.
+pub fn json_str(value: &serde_json::Value) -> String {
+    value
+        .as_str()
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| value.to_string())
+}
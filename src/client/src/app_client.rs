@@ -16,6 +16,7 @@ use std::{sync::Arc, time::Duration};
 
 use engula_api::{
     server::v1::{group_request_union::Request, group_response_union::Response, *},
+    shard,
     v1::{create_collection_request::*, *},
 };
 
@@ -158,7 +159,10 @@ impl From<Partition> for create_collection_request::Partition {
     fn from(p: Partition) -> Self {
         match p {
             Partition::Hash { slots } => {
-                create_collection_request::Partition::Hash(HashPartition { slots })
+                create_collection_request::Partition::Hash(HashPartition {
+                    slots,
+                    algorithm: None,
+                })
             }
             Partition::Range => create_collection_request::Partition::Range(RangePartition {}),
         }
@@ -168,7 +172,7 @@ impl From<Partition> for create_collection_request::Partition {
 impl From<create_collection_request::Partition> for Partition {
     fn from(p: create_collection_request::Partition) -> Self {
         match p {
-            create_collection_request::Partition::Hash(HashPartition { slots }) => {
+            create_collection_request::Partition::Hash(HashPartition { slots, .. }) => {
                 Partition::Hash { slots }
             }
             create_collection_request::Partition::Range(RangePartition {}) => Partition::Range,
@@ -226,6 +230,73 @@ impl Database {
         }
     }
 
+    /// Change the number of slots of a hash collection. The data is copied to the new shards in
+    /// background, and the collection is switched to the new shards once the copying is finished.
+    pub async fn reshard_collection(&self, name: String, slots: u32) -> AppResult<Collection> {
+        let client = self.client.clone();
+        let db_desc = self.desc.clone();
+        let root_client = client.inner.root_client.clone();
+        let resp = root_client
+            .admin(AdminRequestBuilder::reshard_collection(
+                db_desc,
+                name.clone(),
+                slots,
+            ))
+            .await?;
+        match AdminResponseExtractor::update_collection(resp) {
+            None => Err(AppError::NotFound(format!("collection {name}"))),
+            Some(co_desc) => Ok(Collection {
+                rpc_timeout: self.rpc_timeout,
+                co_desc,
+                client: client.clone(),
+            }),
+        }
+    }
+
+    /// Keep the specified number of read learners for each group serving the collection. The read
+    /// learners are placed on the nodes labelled for read replicas, and serve follower reads, see
+    /// `Collection::follower_get` for details.
+    pub async fn set_read_replicas(&self, name: String, replicas: u32) -> AppResult<Collection> {
+        let client = self.client.clone();
+        let db_desc = self.desc.clone();
+        let root_client = client.inner.root_client.clone();
+        let resp = root_client
+            .admin(AdminRequestBuilder::set_read_replicas(
+                db_desc,
+                name.clone(),
+                replicas,
+            ))
+            .await?;
+        match AdminResponseExtractor::update_collection(resp) {
+            None => Err(AppError::NotFound(format!("collection {name}"))),
+            Some(co_desc) => Ok(Collection {
+                rpc_timeout: self.rpc_timeout,
+                co_desc,
+                client: client.clone(),
+            }),
+        }
+    }
+
+    /// Replace the quota of this database, zero means unlimited. The requests exceeding the
+    /// quota are rejected with `AppError::QuotaExceeded`.
+    pub async fn set_quota(&self, quota: DatabaseQuota) -> AppResult<Database> {
+        let root_client = self.client.inner.root_client.clone();
+        let resp = root_client
+            .admin(AdminRequestBuilder::set_database_quota(
+                self.desc.name.clone(),
+                quota,
+            ))
+            .await?;
+        match AdminResponseExtractor::update_database(resp) {
+            None => Err(AppError::NotFound(format!("database {}", self.desc.name))),
+            Some(desc) => Ok(Database {
+                client: self.client.clone(),
+                desc,
+                rpc_timeout: self.rpc_timeout,
+            }),
+        }
+    }
+
     pub async fn list_collection(&self) -> AppResult<Vec<Collection>> {
         let client = self.client.clone();
         let root_client = client.inner.root_client.clone();
@@ -259,6 +330,22 @@ impl Database {
         }
     }
 
+    /// Return the approximate statistics of the collection. They are collected periodically, so
+    /// the recent writes might not be included.
+    pub async fn collection_stats(&self, name: String) -> AppResult<CollectionStats> {
+        let root_client = self.client.inner.root_client.clone();
+        let resp = root_client
+            .admin(AdminRequestBuilder::get_collection(
+                self.desc.clone(),
+                name.clone(),
+            ))
+            .await?;
+        match AdminResponseExtractor::get_collection_stats(resp) {
+            None => Err(AppError::NotFound(format!("collection {name}"))),
+            Some(stats) => Ok(stats),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn name(&self) -> String {
         self.desc.name.to_owned()
@@ -277,6 +364,13 @@ pub struct Collection {
     rpc_timeout: Option<Duration>,
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+struct ScanOpt {
+    reverse: bool,
+    /// Scan from the read learners, see `Collection::follower_get`.
+    follower_read: bool,
+}
+
 impl Collection {
     pub fn new(
         client: Client,
@@ -325,13 +419,27 @@ impl Collection {
     }
 
     pub async fn get(&self, key: Vec<u8>) -> AppResult<Option<Vec<u8>>> {
+        self.get_with_opt(key, false).await
+    }
+
+    /// Get the value of the key from a read learner of the serving group, it falls back to the
+    /// leader if no read learner is available. The value is as fresh as the one read from the
+    /// leader, since the read learner waits until the read index of the leader is applied.
+    pub async fn follower_get(&self, key: Vec<u8>) -> AppResult<Option<Vec<u8>>> {
+        self.get_with_opt(key, true).await
+    }
+
+    async fn get_with_opt(&self, key: Vec<u8>, follower_read: bool) -> AppResult<Option<Vec<u8>>> {
         CLIENT_DATABASE_BYTES_TOTAL.rx.inc_by(key.len() as u64);
         CLIENT_DATABASE_REQUEST_TOTAL.get.inc();
         record_latency!(&CLIENT_DATABASE_REQUEST_DURATION_SECONDS.get);
         let mut retry_state = RetryState::new(self.rpc_timeout);
 
         loop {
-            match self.get_inner(&key, retry_state.timeout()).await {
+            match self
+                .get_inner(&key, follower_read, retry_state.timeout())
+                .await
+            {
                 Ok(value) => {
                     CLIENT_DATABASE_BYTES_TOTAL
                         .tx
@@ -345,9 +453,266 @@ impl Collection {
         }
     }
 
+    /// Scan the key-value pairs in range `[start_key, end_key)`, an empty `end_key` means the
+    /// end of the collection. At most `limit` entries are returned if it isn't zero.
+    ///
+    /// The entries of a hash partitioned collection are only ordered in the same slot.
+    pub async fn scan(
+        &self,
+        start_key: Vec<u8>,
+        end_key: Vec<u8>,
+        limit: usize,
+    ) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.scan_with_opt(
+            start_key,
+            end_key,
+            vec![],
+            vec![],
+            limit,
+            ScanOpt::default(),
+        )
+        .await
+    }
+
+    /// Scan the key-value pairs in range `[start_key, end_key)` from the read learners, see
+    /// [`Collection::scan`] and [`Collection::follower_get`] for details.
+    pub async fn follower_scan(
+        &self,
+        start_key: Vec<u8>,
+        end_key: Vec<u8>,
+        limit: usize,
+    ) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let opt = ScanOpt {
+            follower_read: true,
+            ..Default::default()
+        };
+        self.scan_with_opt(start_key, end_key, vec![], vec![], limit, opt)
+            .await
+    }
+
+    /// Scan the key-value pairs in range `[start_key, end_key)` in descending key order, see
+    /// [`Collection::scan`] for details.
+    pub async fn reverse_scan(
+        &self,
+        start_key: Vec<u8>,
+        end_key: Vec<u8>,
+        limit: usize,
+    ) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let opt = ScanOpt {
+            reverse: true,
+            ..Default::default()
+        };
+        self.scan_with_opt(start_key, end_key, vec![], vec![], limit, opt)
+            .await
+    }
+
+    /// Scan the key-value pairs with the specified prefix, see [`Collection::scan`] for details.
+    pub async fn prefix_scan(
+        &self,
+        prefix: Vec<u8>,
+        limit: usize,
+    ) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.scan_with_opt(vec![], vec![], prefix, vec![], limit, ScanOpt::default())
+            .await
+    }
+
+    /// Scan the key-value pairs with the specified prefix, resuming after the key `after`
+    /// returned by a previous scan. An empty `after` means starting from the beginning, see
+    /// [`Collection::prefix_scan`] for details.
+    pub async fn prefix_scan_after(
+        &self,
+        prefix: Vec<u8>,
+        after: Vec<u8>,
+        limit: usize,
+    ) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.scan_with_opt(vec![], vec![], prefix, after, limit, ScanOpt::default())
+            .await
+    }
+
+    /// Delete the key-value pairs in range `[start_key, end_key)`, an empty `end_key` means the
+    /// end of the collection.
+    ///
+    /// A hash partitioned collection only supports deleting the whole collection, see
+    /// [`Collection::truncate`].
+    pub async fn delete_range(&self, start_key: Vec<u8>, end_key: Vec<u8>) -> AppResult<()> {
+        if !end_key.is_empty() && start_key >= end_key {
+            return Err(AppError::InvalidArgument(
+                "start key must be less than end key".into(),
+            ));
+        }
+
+        let mut retry_state = RetryState::new(self.rpc_timeout);
+        loop {
+            match self
+                .delete_range_inner(&start_key, &end_key, retry_state.timeout())
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    retry_state.retry(err).await?;
+                }
+            }
+        }
+    }
+
+    /// Delete all key-value pairs of the collection.
+    pub async fn truncate(&self) -> AppResult<()> {
+        self.delete_range(vec![], vec![]).await
+    }
+
+    /// Delete all key-value pairs of a slot of the hash partitioned collection, the shard serving
+    /// the slot is deleted by a range deletion.
+    pub async fn delete_slot(&self, slot_id: u32) -> AppResult<()> {
+        let mut retry_state = RetryState::new(self.rpc_timeout);
+        loop {
+            match self.delete_slot_inner(slot_id, retry_state.timeout()).await {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    retry_state.retry(err).await?;
+                }
+            }
+        }
+    }
+
+    async fn scan_with_opt(
+        &self,
+        start_key: Vec<u8>,
+        end_key: Vec<u8>,
+        prefix: Vec<u8>,
+        after: Vec<u8>,
+        limit: usize,
+        opt: ScanOpt,
+    ) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut retry_state = RetryState::new(self.rpc_timeout);
+
+        loop {
+            match self
+                .scan_inner(
+                    &start_key,
+                    &end_key,
+                    &prefix,
+                    &after,
+                    limit,
+                    opt,
+                    retry_state.timeout(),
+                )
+                .await
+            {
+                Ok(entries) => {
+                    CLIENT_DATABASE_BYTES_TOTAL.tx.inc_by(
+                        entries
+                            .iter()
+                            .map(|(k, v)| k.len() + v.len())
+                            .sum::<usize>() as u64,
+                    );
+                    return Ok(entries);
+                }
+                Err(err) => {
+                    retry_state.retry(err).await?;
+                }
+            }
+        }
+    }
+
+    async fn scan_inner(
+        &self,
+        start_key: &[u8],
+        end_key: &[u8],
+        prefix: &[u8],
+        after: &[u8],
+        limit: usize,
+        opt: ScanOpt,
+        timeout: Option<Duration>,
+    ) -> crate::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let reverse = opt.reverse;
+        let desc = self.latest_desc();
+        let router = self.client.inner.router.clone();
+        let mut shards = router.collection_shards(&desc)?;
+        if reverse {
+            shards.reverse();
+        }
+
+        // Resume from the shard serving the key `after`, the keys of the former shards have been
+        // returned by the previous scans.
+        let mut resume = None;
+        if !after.is_empty() {
+            debug_assert!(!reverse);
+            let (_, shard) = router.find_shard(desc, after)?;
+            if let Some(index) = shards.iter().position(|(_, s)| s.id == shard.id) {
+                shards.drain(..index);
+            }
+            let mut next_key = after.to_owned();
+            next_key.push(0);
+            resume = Some((shard.id, std::cmp::max(start_key.to_owned(), next_key)));
+        }
+
+        let lower_bound = std::cmp::max(start_key, prefix);
+        let mut entries = Vec::new();
+        for (group, shard) in shards {
+            if let Some(shard_desc::Partition::Range(range)) = shard.partition.as_ref() {
+                let after_end = !end_key.is_empty() && range.start.as_slice() >= end_key;
+                let before_start = !range.end.is_empty() && range.end.as_slice() <= lower_bound;
+                match (after_end, before_start, reverse) {
+                    (true, _, false) | (_, true, true) => break,
+                    (true, _, true) | (_, true, false) => continue,
+                    _ => {}
+                }
+            }
+
+            let mut client = GroupClient::new(
+                group,
+                self.client.inner.router.clone(),
+                self.client.inner.conn_manager.clone(),
+            );
+            if opt.follower_read {
+                client.prefer_read_learner();
+            }
+            // The user keys of a hash shard are only ordered in the same slot, so the resumed
+            // start key is not applied to the later shards.
+            let shard_start_key = match &resume {
+                Some((shard_id, key)) if *shard_id == shard.id => key.as_slice(),
+                Some((_, key))
+                    if !matches!(shard.partition, Some(shard_desc::Partition::Hash(_))) =>
+                {
+                    key.as_slice()
+                }
+                _ => start_key,
+            };
+            let req = Request::Scan(ShardScanRequest {
+                shard_id: shard.id,
+                start_key: shard_start_key.to_owned(),
+                end_key: end_key.to_owned(),
+                prefix: prefix.to_owned(),
+                limit: if limit == 0 {
+                    0
+                } else {
+                    (limit - entries.len()) as u64
+                },
+                reverse,
+            });
+            if let Some(duration) = timeout {
+                client.set_timeout(duration);
+            }
+            match client.request(&req).await? {
+                Response::Scan(ShardScanResponse { data }) => {
+                    entries.extend(data.into_iter().map(|d| (d.key, d.value)));
+                }
+                _ => {
+                    return Err(crate::Error::Internal(wrap(
+                        "invalid response type, Scan is required",
+                    )))
+                }
+            }
+            if limit != 0 && entries.len() >= limit {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
     async fn delete_inner(&self, key: &[u8], timeout: Option<Duration>) -> crate::Result<()> {
         let router = self.client.inner.router.clone();
-        let (group, shard) = router.find_shard(self.co_desc.clone(), key)?;
+        let (group, shard) = router.find_shard(self.latest_desc(), key)?;
         let mut client = GroupClient::new(
             group,
             self.client.inner.router.clone(),
@@ -366,6 +731,99 @@ impl Collection {
         Ok(())
     }
 
+    async fn delete_range_inner(
+        &self,
+        start_key: &[u8],
+        end_key: &[u8],
+        timeout: Option<Duration>,
+    ) -> crate::Result<()> {
+        let desc = self.latest_desc();
+        if let Some(collection_desc::Partition::Hash(hash)) = desc.partition.as_ref() {
+            if !start_key.is_empty() || !end_key.is_empty() {
+                return Err(crate::Error::InvalidArgument(
+                    "hash partitioned collection only supports truncating".into(),
+                ));
+            }
+            if hash.target_slots != 0 {
+                return Err(crate::Error::InvalidArgument(
+                    "collection is resharding".into(),
+                ));
+            }
+        }
+
+        let router = self.client.inner.router.clone();
+        let shards = router.collection_shards(&desc)?;
+        for (group, shard) in shards {
+            if let Some(shard_desc::Partition::Range(range)) = shard.partition.as_ref() {
+                if !end_key.is_empty() && range.start.as_slice() >= end_key {
+                    break;
+                }
+                if !range.end.is_empty() && range.end.as_slice() <= start_key {
+                    continue;
+                }
+            }
+
+            let mut client = GroupClient::new(
+                group,
+                self.client.inner.router.clone(),
+                self.client.inner.conn_manager.clone(),
+            );
+            let req = Request::DeleteRange(ShardDeleteRangeRequest {
+                shard_id: shard.id,
+                start_key: start_key.to_owned(),
+                end_key: end_key.to_owned(),
+            });
+            if let Some(duration) = timeout {
+                client.set_timeout(duration);
+            }
+            client.request(&req).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_slot_inner(
+        &self,
+        slot_id: u32,
+        timeout: Option<Duration>,
+    ) -> crate::Result<()> {
+        let desc = self.latest_desc();
+        match desc.partition.as_ref() {
+            Some(collection_desc::Partition::Hash(hash)) if hash.target_slots == 0 => {}
+            Some(collection_desc::Partition::Hash(_)) => {
+                return Err(crate::Error::InvalidArgument(
+                    "collection is resharding".into(),
+                ));
+            }
+            _ => {
+                return Err(crate::Error::InvalidArgument(
+                    "range partitioned collection has no slots".into(),
+                ));
+            }
+        }
+
+        let router = self.client.inner.router.clone();
+        let (group, shard) = router
+            .collection_shards(&desc)?
+            .into_iter()
+            .find(|(_, s)| shard::slot(s) == Some(slot_id))
+            .ok_or_else(|| crate::Error::NotFound(format!("shard (slot={slot_id})")))?;
+        let mut client = GroupClient::new(
+            group,
+            self.client.inner.router.clone(),
+            self.client.inner.conn_manager.clone(),
+        );
+        let req = Request::DeleteRange(ShardDeleteRangeRequest {
+            shard_id: shard.id,
+            start_key: vec![],
+            end_key: vec![],
+        });
+        if let Some(duration) = timeout {
+            client.set_timeout(duration);
+        }
+        client.request(&req).await?;
+        Ok(())
+    }
+
     async fn put_inner(
         &self,
         key: &[u8],
@@ -373,7 +831,7 @@ impl Collection {
         timeout: Option<Duration>,
     ) -> crate::Result<()> {
         let router = self.client.inner.router.clone();
-        let (group, shard) = router.find_shard(self.co_desc.clone(), key)?;
+        let (group, shard) = router.find_shard(self.latest_desc(), key)?;
         let mut client = GroupClient::new(
             group,
             self.client.inner.router.clone(),
@@ -396,15 +854,19 @@ impl Collection {
     async fn get_inner(
         &self,
         key: &[u8],
+        follower_read: bool,
         timeout: Option<Duration>,
     ) -> crate::Result<Option<Vec<u8>>> {
         let router = self.client.inner.router.clone();
-        let (group, shard) = router.find_shard(self.co_desc.clone(), key)?;
+        let (group, shard) = router.find_shard(self.latest_desc(), key)?;
         let mut client = GroupClient::new(
             group,
             self.client.inner.router.clone(),
             self.client.inner.conn_manager.clone(),
         );
+        if follower_read {
+            client.prefer_read_learner();
+        }
         let req = Request::Get(ShardGetRequest {
             shard_id: shard.id,
             get: Some(GetRequest {
@@ -422,6 +884,15 @@ impl Collection {
         }
     }
 
+    /// The collection desc might be changed by resharding, prefer the one watched by router.
+    fn latest_desc(&self) -> CollectionDesc {
+        self.client
+            .inner
+            .router
+            .find_collection(self.co_desc.id)
+            .unwrap_or_else(|| self.co_desc.clone())
+    }
+
     #[allow(dead_code)]
     fn name(&self) -> String {
         self.co_desc.name.to_owned()
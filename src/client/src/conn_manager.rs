@@ -13,18 +13,42 @@
 // limitations under the License.
 use std::{
     collections::HashMap,
+    pin::Pin,
     sync::{Arc, Mutex},
+    task::{Context, Poll},
     time::Duration,
 };
 
+use derivative::Derivative;
 use engula_api::server::v1::root_client::RootClient;
-use tonic::transport::{Channel, Endpoint};
+use futures::future::BoxFuture;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tonic::{
+    codegen::Service,
+    transport::{Channel, Endpoint, Uri},
+};
 
 use crate::{Error, NodeClient, Result};
 
-#[derive(Clone, Debug)]
+/// A bidirectional byte stream returned by [`Connector`].
+pub trait Io: AsyncRead + AsyncWrite + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + 'static> Io for T {}
+
+pub type BoxIo = Pin<Box<dyn Io>>;
+
+/// Establish connections to the target address, instead of TCP. It is used to run clients over
+/// an in-memory network, eg. in simulation tests.
+pub trait Connector: Send + Sync + 'static {
+    fn connect(&self, addr: String) -> BoxFuture<'static, std::io::Result<BoxIo>>;
+}
+
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
 pub struct ConnManager {
     connect_timeout: Option<Duration>,
+    #[derivative(Debug = "ignore")]
+    connector: Option<Arc<dyn Connector>>,
     core: Arc<Mutex<Core>>,
 }
 
@@ -50,6 +74,13 @@ impl ConnManager {
         mgr
     }
 
+    /// Create a [`ConnManager`] which establishes connections by the specified [`Connector`].
+    pub fn with_connector(connector: Arc<dyn Connector>) -> Self {
+        let mut mgr = ConnManager::new();
+        mgr.connector = Some(connector);
+        mgr
+    }
+
     // TODO(walter) add tags
     pub fn get(&self, addr: String) -> Result<Channel> {
         let mut core = self.core.lock().unwrap();
@@ -58,14 +89,13 @@ impl ConnManager {
             return Ok(info.channel.clone());
         }
 
-        let channel = match Endpoint::new(format!("http://{}", addr)) {
-            Ok(endpoint) => {
-                if let Some(connect_timeout) = self.connect_timeout {
-                    endpoint.connect_timeout(connect_timeout).connect_lazy()
-                } else {
-                    endpoint.connect_lazy()
+        let channel = match self.endpoint(&addr) {
+            Ok(endpoint) => match &self.connector {
+                Some(connector) => {
+                    endpoint.connect_with_connector_lazy(ConnectorService(connector.clone()))
                 }
-            }
+                None => endpoint.connect_lazy(),
+            },
             Err(e) => return Err(Error::Internal(Box::new(e))),
         };
         let info = ChannelInfo {
@@ -76,6 +106,19 @@ impl ConnManager {
         Ok(channel)
     }
 
+    /// Establish a dedicated connection to the address, which is not shared with other clients.
+    pub async fn connect(&self, addr: &str) -> Result<Channel, tonic::transport::Error> {
+        let endpoint = self.endpoint(addr)?;
+        match &self.connector {
+            Some(connector) => {
+                endpoint
+                    .connect_with_connector(ConnectorService(connector.clone()))
+                    .await
+            }
+            None => endpoint.connect().await,
+        }
+    }
+
     #[inline]
     pub fn get_node_client(&self, addr: String) -> Result<NodeClient> {
         let channel = self.get(addr)?;
@@ -87,6 +130,14 @@ impl ConnManager {
         let channel = self.get(addr)?;
         Ok(RootClient::new(channel))
     }
+
+    fn endpoint(&self, addr: &str) -> Result<Endpoint, tonic::transport::Error> {
+        let endpoint = Endpoint::new(format!("http://{}", addr))?;
+        Ok(match self.connect_timeout {
+            Some(connect_timeout) => endpoint.connect_timeout(connect_timeout),
+            None => endpoint,
+        })
+    }
 }
 
 impl Default for ConnManager {
@@ -105,10 +156,32 @@ impl Default for ConnManager {
         ConnManager {
             core,
             connect_timeout: None,
+            connector: None,
         }
     }
 }
 
+#[derive(Clone)]
+struct ConnectorService(Arc<dyn Connector>);
+
+impl Service<Uri> for ConnectorService {
+    type Response = BoxIo;
+    type Error = std::io::Error;
+    type Future = BoxFuture<'static, std::io::Result<BoxIo>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let addr = uri
+            .authority()
+            .map(|authority| authority.to_string())
+            .unwrap_or_default();
+        self.0.connect(addr)
+    }
+}
+
 async fn recycle_conn_main(core: Arc<Mutex<Core>>) {
     let mut interval = tokio::time::interval(Duration::from_secs(60));
     loop {
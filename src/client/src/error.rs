@@ -33,6 +33,9 @@ pub enum AppError {
     #[error("deadline exceeded {0}")]
     DeadlineExceeded(String),
 
+    #[error("database {0} exceeds the quota of {1}")]
+    QuotaExceeded(/* database_id */ u64, /* resource */ String),
+
     #[error("network: {0}")]
     Network(tonic::Status),
 
@@ -57,6 +60,9 @@ pub enum Error {
     #[error("{0} is exhausted")]
     ResourceExhausted(String),
 
+    #[error("database {0} exceeds the quota of {1}")]
+    QuotaExceeded(/* database_id */ u64, /* resource */ String),
+
     #[error("group epoch not match")]
     EpochNotMatch(GroupDesc),
 
@@ -102,7 +108,7 @@ impl From<tonic::Status> for Error {
                 Error::DeadlineExceeded(status.message().into())
             }
             Code::AlreadyExists => Error::AlreadyExists(status.message().into()),
-            Code::ResourceExhausted => Error::ResourceExhausted(status.message().into()),
+            Code::ResourceExhausted => from_resource_exhausted(status),
             Code::NotFound => Error::NotFound(status.message().into()),
             Code::Internal => Error::Internal(status.message().into()),
             Code::Unknown => from_source_or_details(status),
@@ -131,6 +137,7 @@ impl From<engula_api::server::v1::Error> for Error {
                 Error::NotRootLeader(v.root.unwrap_or_default(), v.term, v.leader)
             }
             Some(Value::NotMatch(v)) => Error::EpochNotMatch(v.descriptor.unwrap_or_default()),
+            Some(Value::QuotaExceeded(v)) => Error::QuotaExceeded(v.database_id, v.resource),
             Some(Value::StatusCode(v)) => Status::new(v.into(), msg).into(),
             _ => Status::internal(format!("unknown error detail, msg: {msg}")).into(),
         }
@@ -144,6 +151,7 @@ impl From<Error> for AppError {
             Error::DeadlineExceeded(v) => AppError::DeadlineExceeded(v),
             Error::NotFound(v) => AppError::NotFound(v),
             Error::AlreadyExists(v) => AppError::AlreadyExists(v),
+            Error::QuotaExceeded(db, resource) => AppError::QuotaExceeded(db, resource),
             Error::Internal(v) => AppError::Internal(v),
 
             Error::Transport(status) => AppError::Network(status),
@@ -162,13 +170,22 @@ impl From<Error> for AppError {
 
 impl From<AppError> for tonic::Status {
     fn from(err: AppError) -> Self {
-        use tonic::Status;
+        use engula_api::server::v1;
+        use prost::Message;
+        use tonic::{Code, Status};
 
         match err {
             AppError::NotFound(msg) => Status::not_found(msg),
             AppError::AlreadyExists(msg) => Status::already_exists(msg),
             AppError::InvalidArgument(msg) => Status::invalid_argument(msg),
             AppError::DeadlineExceeded(msg) => Status::deadline_exceeded(msg),
+            AppError::QuotaExceeded(db, resource) => Status::with_details(
+                Code::ResourceExhausted,
+                format!("database {db} exceeds the quota of {resource}"),
+                v1::Error::quota_exceeded(db, resource)
+                    .encode_to_vec()
+                    .into(),
+            ),
             AppError::Network(status) => status, // as proxy
             AppError::Internal(err) => Status::internal(err.to_string()),
         }
@@ -268,6 +285,20 @@ pub fn from_source_or_details(status: tonic::Status) -> Error {
     from_source(status)
 }
 
+/// The details of `RESOURCE_EXHAUSTED` carry the exceeded quota, see `Error::QuotaExceeded`.
+fn from_resource_exhausted(status: tonic::Status) -> Error {
+    use engula_api::server::v1;
+    use prost::Message;
+
+    if !status.details().is_empty() {
+        if let Ok(err) = v1::Error::decode(status.details()) {
+            return err.into();
+        }
+    }
+
+    Error::ResourceExhausted(status.message().into())
+}
+
 pub fn from_source(status: tonic::Status) -> Error {
     if retryable_rpc_err(&status) {
         Error::Connect(status)
@@ -80,6 +80,9 @@ pub struct GroupClient {
     access_node_id: Option<u64>,
     next_access_index: usize,
 
+    /// Access the read learners before the leader, see `GroupClient::prefer_read_learner`.
+    prefer_read_learner: bool,
+
     /// Node id to node client.
     node_clients: HashMap<u64, NodeClient>,
 }
@@ -96,6 +99,7 @@ impl GroupClient {
             access_node_id: None,
             replicas: Vec::default(),
             next_access_index: 0,
+            prefer_read_learner: false,
 
             router,
             conn_manager,
@@ -116,6 +120,13 @@ impl GroupClient {
         self.timeout = Some(timeout);
     }
 
+    /// Issue the requests to the read learners of the group first, it is used by the follower
+    /// reads. The leader is still accessed if none of the read learners is available.
+    pub fn prefer_read_learner(&mut self) {
+        self.prefer_read_learner = true;
+        move_read_learners_to_front(&mut self.replicas);
+    }
+
     async fn invoke<F, O, V>(&mut self, op: F) -> Result<V>
     where
         F: Fn(InvokeContext, NodeClient) -> O,
@@ -205,6 +216,9 @@ impl GroupClient {
             );
             move_node_to_first_element(&mut self.replicas, node_id);
         }
+        if self.prefer_read_learner {
+            move_read_learners_to_front(&mut self.replicas);
+        }
     }
 
     /// Return the next node id, skip the leader node.
@@ -449,6 +463,51 @@ impl GroupClient {
         self.invoke(op).await
     }
 
+    pub async fn update_shard(&mut self, desc: &ShardDesc) -> Result<()> {
+        let op = |ctx: InvokeContext, client: NodeClient| {
+            let desc = desc.to_owned();
+            let req = RequestBatchBuilder::new(ctx.node_id)
+                .update_shard(ctx.group_id, ctx.epoch, desc)
+                .build();
+            async move {
+                let resp = client
+                    .batch_group_requests(req)
+                    .await
+                    .and_then(Self::batch_response)
+                    .and_then(Self::group_response)?;
+                match resp {
+                    Response::CreateShard(_) => Ok(()),
+                    _ => Err(Status::internal(
+                        "invalid response type, CreateShard is required",
+                    )),
+                }
+            }
+        };
+        self.invoke(op).await
+    }
+
+    pub async fn remove_shard(&mut self, shard_id: u64) -> Result<()> {
+        let op = |ctx: InvokeContext, client: NodeClient| {
+            let req = RequestBatchBuilder::new(ctx.node_id)
+                .remove_shard(ctx.group_id, ctx.epoch, shard_id)
+                .build();
+            async move {
+                let resp = client
+                    .batch_group_requests(req)
+                    .await
+                    .and_then(Self::batch_response)
+                    .and_then(Self::group_response)?;
+                match resp {
+                    Response::RemoveShard(_) => Ok(()),
+                    _ => Err(Status::internal(
+                        "invalid response type, RemoveShard is required",
+                    )),
+                }
+            }
+        };
+        self.invoke(op).await
+    }
+
     pub async fn transfer_leader(&mut self, dest_replica: u64) -> Result<()> {
         let op = |ctx: InvokeContext, client: NodeClient| {
             let dest_replica = dest_replica.to_owned();
@@ -566,6 +625,50 @@ impl GroupClient {
         self.invoke(op).await
     }
 
+    pub async fn add_witness(&mut self, replica: u64, node: u64) -> Result<()> {
+        let op = |ctx: InvokeContext, client: NodeClient| {
+            let req = RequestBatchBuilder::new(ctx.node_id)
+                .add_witness(ctx.group_id, ctx.epoch, replica, node)
+                .build();
+            async move {
+                let resp = client
+                    .batch_group_requests(req)
+                    .await
+                    .and_then(Self::batch_response)
+                    .and_then(Self::group_response)?;
+                match resp {
+                    Response::ChangeReplicas(_) => Ok(()),
+                    _ => Err(Status::internal(
+                        "invalid response type, ChangeReplicas is required",
+                    )),
+                }
+            }
+        };
+        self.invoke(op).await
+    }
+
+    pub async fn add_read_learner(&mut self, replica: u64, node: u64) -> Result<()> {
+        let op = |ctx: InvokeContext, client: NodeClient| {
+            let req = RequestBatchBuilder::new(ctx.node_id)
+                .add_read_learner(ctx.group_id, ctx.epoch, replica, node)
+                .build();
+            async move {
+                let resp = client
+                    .batch_group_requests(req)
+                    .await
+                    .and_then(Self::batch_response)
+                    .and_then(Self::group_response)?;
+                match resp {
+                    Response::ChangeReplicas(_) => Ok(()),
+                    _ => Err(Status::internal(
+                        "invalid response type, ChangeReplicas is required",
+                    )),
+                }
+            }
+        };
+        self.invoke(op).await
+    }
+
     pub async fn accept_shard(
         &mut self,
         src_group: u64,
@@ -646,13 +749,42 @@ impl GroupClient {
     }
 
     pub async fn retryable_pull(
+        self,
+        shard_id: u64,
+        last_key: Vec<u8>,
+    ) -> Result<RetryableShardChunkStreaming> {
+        self.retryable_pull_with_opt(shard_id, last_key, false)
+            .await
+    }
+
+    /// Pull the data of a serving shard, it is used to copy data of a resharding collection.
+    pub async fn retryable_pull_for_resharding(
+        self,
+        shard_id: u64,
+        last_key: Vec<u8>,
+    ) -> Result<RetryableShardChunkStreaming> {
+        self.retryable_pull_with_opt(shard_id, last_key, true).await
+    }
+
+    pub async fn ingest(&mut self, shard_id: u64, data: Vec<ShardData>) -> Result<()> {
+        let req = Request::Ingest(ShardIngestRequest { shard_id, data });
+        match self.request(&req).await? {
+            Response::Ingest(_) => Ok(()),
+            _ => Err(Error::Internal(
+                "invalid response type, `Ingest` is required".into(),
+            )),
+        }
+    }
+
+    async fn retryable_pull_with_opt(
         mut self,
         shard_id: u64,
         last_key: Vec<u8>,
+        resharding: bool,
     ) -> Result<RetryableShardChunkStreaming> {
-        let streaming = self.pull(shard_id, &last_key).await?;
+        let streaming = self.pull(shard_id, &last_key, resharding).await?;
         let retryable_streaming =
-            RetryableShardChunkStreaming::new(shard_id, last_key, self, streaming);
+            RetryableShardChunkStreaming::new(shard_id, last_key, resharding, self, streaming);
         Ok(retryable_streaming)
     }
 
@@ -660,6 +792,7 @@ impl GroupClient {
         &mut self,
         shard_id: u64,
         last_key: &[u8],
+        resharding: bool,
     ) -> Result<tonic::Streaming<ShardChunk>> {
         let group_id = self.group_id;
         let op = |_: InvokeContext, client: NodeClient| {
@@ -667,6 +800,7 @@ impl GroupClient {
                 group_id,
                 shard_id,
                 last_key: last_key.to_owned(),
+                resharding,
             };
             async move { client.pull(request).await }
         };
@@ -682,10 +816,11 @@ impl RetryableShardChunkStreaming {
     pub fn new(
         shard_id: u64,
         last_key: Vec<u8>,
+        resharding: bool,
         client: GroupClient,
         streaming: tonic::Streaming<ShardChunk>,
     ) -> Self {
-        let inner = retryable_chunk_stream(shard_id, last_key, client, streaming);
+        let inner = retryable_chunk_stream(shard_id, last_key, resharding, client, streaming);
         Self {
             inner: Box::pin(inner),
         }
@@ -695,6 +830,7 @@ impl RetryableShardChunkStreaming {
 fn retryable_chunk_stream(
     shard_id: u64,
     mut last_key: Vec<u8>,
+    resharding: bool,
     mut client: GroupClient,
     mut streaming: tonic::Streaming<ShardChunk>,
 ) -> impl futures::Stream<Item = Result<ShardChunk>> {
@@ -717,7 +853,7 @@ fn retryable_chunk_stream(
 
                     // retry, by recreate new stream.
                     GROUP_CLIENT_RETRY_TOTAL.inc();
-                    match client.pull(shard_id, &last_key).await {
+                    match client.pull(shard_id, &last_key, resharding).await {
                         Ok(new_stream) => streaming = new_stream,
                         Err(e) => {
                             warn!("shard {shard_id} fetch shard recreate steam meet error: {e:?}");
@@ -746,7 +882,10 @@ impl futures::Stream for RetryableShardChunkStreaming {
 
 #[inline]
 fn is_read_only_request(request: &Request) -> bool {
-    matches!(request, Request::Get(_) | Request::PrefixList(_))
+    matches!(
+        request,
+        Request::Get(_) | Request::PrefixList(_) | Request::Scan(_)
+    )
 }
 
 fn is_executable(descriptor: &GroupDesc, request: &Request) -> bool {
@@ -761,6 +900,12 @@ fn is_executable(descriptor: &GroupDesc, request: &Request) -> bool {
             is_target_shard_exists(descriptor, req.shard_id, &req.delete.as_ref().unwrap().key)
         }
         Request::PrefixList(req) => is_target_shard_exists(descriptor, req.shard_id, &req.prefix),
+        Request::Scan(req) => descriptor.shards.iter().any(|s| s.id == req.shard_id),
+        Request::DeleteRange(req) => descriptor.shards.iter().any(|s| s.id == req.shard_id),
+        Request::Ingest(req) => req
+            .data
+            .iter()
+            .all(|data| is_target_shard_exists(descriptor, req.shard_id, &data.key)),
         _ => false,
     }
 }
@@ -785,6 +930,12 @@ fn move_node_to_first_element(replicas: &mut [ReplicaDesc], node_id: u64) {
     }
 }
 
+/// Move the read learners to the front, the relative order of other replicas is kept, so the
+/// leader is accessed first if no read learner is available.
+fn move_read_learners_to_front(replicas: &mut [ReplicaDesc]) {
+    replicas.sort_by_key(|r| r.role != ReplicaRole::ReadLearner as i32);
+}
+
 fn move_replica_to_first_element(replicas: &mut Vec<ReplicaDesc>, replica: ReplicaDesc) {
     let idx = if let Some(idx) = replicas.iter().position(|r| r.node_id == replica.node_id) {
         idx
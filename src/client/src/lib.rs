@@ -28,7 +28,7 @@ mod router;
 mod shard_client;
 
 pub use app_client::{Client as EngulaClient, ClientOptions, Collection, Database, Partition};
-pub use conn_manager::ConnManager;
+pub use conn_manager::{BoxIo, ConnManager, Connector, Io};
 pub use discovery::{ServiceDiscovery, StaticServiceDiscovery};
 pub use error::{AppError, AppResult, Error, Result};
 pub use group_client::{GroupClient, RetryableShardChunkStreaming};
@@ -28,8 +28,12 @@ make_static_metric! {
             batch_write,
             accept_shard,
             create_shard,
+            remove_shard,
             move_replicas,
             change_replicas,
+            ingest,
+            scan,
+            delete_range,
         }
     }
     pub struct GroupRequestDuration: Histogram {
@@ -42,8 +46,12 @@ make_static_metric! {
             batch_write,
             accept_shard,
             create_shard,
+            remove_shard,
             move_replicas,
             change_replicas,
+            ingest,
+            scan,
+            delete_range,
         }
     }
 }
@@ -109,6 +117,10 @@ pub fn take_group_request_metrics(
             GROUP_CLIENT_GROUP_REQUEST_TOTAL.create_shard.inc();
             Some(&GROUP_CLIENT_GROUP_REQUEST_DURATION_SECONDS.create_shard)
         }
+        Request::RemoveShard(_) => {
+            GROUP_CLIENT_GROUP_REQUEST_TOTAL.remove_shard.inc();
+            Some(&GROUP_CLIENT_GROUP_REQUEST_DURATION_SECONDS.remove_shard)
+        }
         Request::ChangeReplicas(_) => {
             GROUP_CLIENT_GROUP_REQUEST_TOTAL.change_replicas.inc();
             Some(&GROUP_CLIENT_GROUP_REQUEST_DURATION_SECONDS.change_replicas)
@@ -121,6 +133,18 @@ pub fn take_group_request_metrics(
             GROUP_CLIENT_GROUP_REQUEST_TOTAL.move_replicas.inc();
             Some(&GROUP_CLIENT_GROUP_REQUEST_DURATION_SECONDS.move_replicas)
         }
+        Request::Ingest(_) => {
+            GROUP_CLIENT_GROUP_REQUEST_TOTAL.ingest.inc();
+            Some(&GROUP_CLIENT_GROUP_REQUEST_DURATION_SECONDS.ingest)
+        }
+        Request::Scan(_) => {
+            GROUP_CLIENT_GROUP_REQUEST_TOTAL.scan.inc();
+            Some(&GROUP_CLIENT_GROUP_REQUEST_DURATION_SECONDS.scan)
+        }
+        Request::DeleteRange(_) => {
+            GROUP_CLIENT_GROUP_REQUEST_TOTAL.delete_range.inc();
+            Some(&GROUP_CLIENT_GROUP_REQUEST_DURATION_SECONDS.delete_range)
+        }
     }
 }
 
@@ -73,6 +73,17 @@ impl Client {
         Ok(())
     }
 
+    pub async fn collect_checksum(
+        &self,
+        group_id: u64,
+        check_id: u64,
+    ) -> Result<CollectChecksumResponse, tonic::Status> {
+        let mut client = self.client.clone();
+        let req = CollectChecksumRequest { group_id, check_id };
+        let res = client.collect_checksum(req).await?;
+        Ok(res.into_inner())
+    }
+
     pub async fn batch_group_requests(
         &self,
         req: impl IntoRequest<BatchRequest>,
@@ -184,6 +195,23 @@ impl RequestBatchBuilder {
                 request: Some(group_request_union::Request::CreateShard(
                     CreateShardRequest {
                         shard: Some(shard_desc),
+                        update: false,
+                    },
+                )),
+            }),
+        });
+        self
+    }
+
+    pub fn update_shard(mut self, group_id: u64, epoch: u64, shard_desc: ShardDesc) -> Self {
+        self.requests.push(GroupRequest {
+            group_id,
+            epoch,
+            request: Some(GroupRequestUnion {
+                request: Some(group_request_union::Request::CreateShard(
+                    CreateShardRequest {
+                        shard: Some(shard_desc),
+                        update: true,
                     },
                 )),
             }),
@@ -191,6 +219,19 @@ impl RequestBatchBuilder {
         self
     }
 
+    pub fn remove_shard(mut self, group_id: u64, epoch: u64, shard_id: u64) -> Self {
+        self.requests.push(GroupRequest {
+            group_id,
+            epoch,
+            request: Some(GroupRequestUnion {
+                request: Some(group_request_union::Request::RemoveShard(
+                    RemoveShardRequest { shard_id },
+                )),
+            }),
+        });
+        self
+    }
+
     pub fn add_replica(mut self, group_id: u64, epoch: u64, replica_id: u64, node_id: u64) -> Self {
         let change_replicas = ChangeReplicasRequest {
             change_replicas: Some(ChangeReplicas {
@@ -237,6 +278,58 @@ impl RequestBatchBuilder {
         self
     }
 
+    pub fn add_witness(mut self, group_id: u64, epoch: u64, replica_id: u64, node_id: u64) -> Self {
+        let change_replicas = ChangeReplicasRequest {
+            change_replicas: Some(ChangeReplicas {
+                changes: vec![ChangeReplica {
+                    change_type: ChangeReplicaType::AddWitness.into(),
+                    replica_id,
+                    node_id,
+                }],
+            }),
+        };
+
+        self.requests.push(GroupRequest {
+            group_id,
+            epoch,
+            request: Some(GroupRequestUnion {
+                request: Some(group_request_union::Request::ChangeReplicas(
+                    change_replicas,
+                )),
+            }),
+        });
+        self
+    }
+
+    pub fn add_read_learner(
+        mut self,
+        group_id: u64,
+        epoch: u64,
+        replica_id: u64,
+        node_id: u64,
+    ) -> Self {
+        let change_replicas = ChangeReplicasRequest {
+            change_replicas: Some(ChangeReplicas {
+                changes: vec![ChangeReplica {
+                    change_type: ChangeReplicaType::AddReadLearner.into(),
+                    replica_id,
+                    node_id,
+                }],
+            }),
+        };
+
+        self.requests.push(GroupRequest {
+            group_id,
+            epoch,
+            request: Some(GroupRequestUnion {
+                request: Some(group_request_union::Request::ChangeReplicas(
+                    change_replicas,
+                )),
+            }),
+        });
+        self
+    }
+
     pub fn remove_replica(mut self, group_id: u64, epoch: u64, replica_id: u64) -> Self {
         let change_replicas = ChangeReplicasRequest {
             change_replicas: Some(ChangeReplicas {
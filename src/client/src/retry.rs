@@ -65,6 +65,7 @@ impl RetryState {
             Error::InvalidArgument(_)
             | Error::DeadlineExceeded(_)
             | Error::ResourceExhausted(_)
+            | Error::QuotaExceeded(..)
             | Error::AlreadyExists(_)
             | Error::Rpc(_)
             | Error::Transport(_)
@@ -319,6 +319,21 @@ impl AdminRequestBuilder {
         }
     }
 
+    pub fn set_database_quota(name: String, quota: DatabaseQuota) -> AdminRequest {
+        AdminRequest {
+            request: Some(AdminRequestUnion {
+                request: Some(admin_request_union::Request::UpdateDatabase(
+                    UpdateDatabaseRequest {
+                        name,
+                        action: Some(update_database_request::Action::SetQuota(
+                            update_database_request::SetQuota { quota: Some(quota) },
+                        )),
+                    },
+                )),
+            }),
+        }
+    }
+
     pub fn list_database() -> AdminRequest {
         AdminRequest {
             request: Some(AdminRequestUnion {
@@ -370,6 +385,42 @@ impl AdminRequestBuilder {
         }
     }
 
+    pub fn reshard_collection(database: DatabaseDesc, co_name: String, slots: u32) -> AdminRequest {
+        AdminRequest {
+            request: Some(AdminRequestUnion {
+                request: Some(admin_request_union::Request::UpdateCollection(
+                    UpdateCollectionRequest {
+                        name: co_name,
+                        database: Some(database),
+                        action: Some(update_collection_request::Action::Reshard(
+                            update_collection_request::Reshard { slots },
+                        )),
+                    },
+                )),
+            }),
+        }
+    }
+
+    pub fn set_read_replicas(
+        database: DatabaseDesc,
+        co_name: String,
+        replicas: u32,
+    ) -> AdminRequest {
+        AdminRequest {
+            request: Some(AdminRequestUnion {
+                request: Some(admin_request_union::Request::UpdateCollection(
+                    UpdateCollectionRequest {
+                        name: co_name,
+                        database: Some(database),
+                        action: Some(update_collection_request::Action::ReadReplicas(
+                            update_collection_request::ReadReplicas { replicas },
+                        )),
+                    },
+                )),
+            }),
+        }
+    }
+
     pub fn list_collection(database: DatabaseDesc) -> AdminRequest {
         AdminRequest {
             request: Some(AdminRequestUnion {
@@ -463,6 +514,28 @@ impl AdminResponseExtractor {
         }
     }
 
+    pub fn update_database(resp: AdminResponse) -> Option<DatabaseDesc> {
+        if let Some(AdminResponseUnion {
+            response: Some(admin_response_union::Response::UpdateDatabase(response)),
+        }) = resp.response
+        {
+            response.database
+        } else {
+            None
+        }
+    }
+
+    pub fn update_collection(resp: AdminResponse) -> Option<CollectionDesc> {
+        if let Some(AdminResponseUnion {
+            response: Some(admin_response_union::Response::UpdateCollection(response)),
+        }) = resp.response
+        {
+            response.collection
+        } else {
+            None
+        }
+    }
+
     pub fn list_collection(resp: AdminResponse) -> Vec<CollectionDesc> {
         if let Some(AdminResponseUnion {
             response: Some(admin_response_union::Response::ListCollections(response)),
@@ -484,6 +557,17 @@ impl AdminResponseExtractor {
             None
         }
     }
+
+    pub fn get_collection_stats(resp: AdminResponse) -> Option<CollectionStats> {
+        if let Some(AdminResponseUnion {
+            response: Some(admin_response_union::Response::GetCollection(response)),
+        }) = resp.response
+        {
+            response.collection.and(response.stats)
+        } else {
+            None
+        }
+    }
 }
 
 fn extract_root_descriptor(status: &tonic::Status) -> Option<(RootDesc, u64, Option<ReplicaDesc>)> {
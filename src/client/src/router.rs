@@ -23,6 +23,7 @@ use engula_api::{
         watch_response::{delete_event::Event as DeleteEvent, update_event::Event as UpdateEvent},
         *,
     },
+    shard,
     v1::*,
 };
 use futures::StreamExt;
@@ -73,41 +74,9 @@ impl Router {
         desc: CollectionDesc,
         key: &[u8],
     ) -> Result<(RouterGroupState, ShardDesc), crate::Error> {
-        if let Some(collection_desc::Partition::Hash(collection_desc::HashPartition { slots })) =
-            desc.partition
-        {
-            // TODO: it's temp hash impl..
-            let crc = crc32fast::hash(key);
-            let slot = crc % slots;
-
+        if let Some(collection_desc::Partition::Hash(hash)) = desc.partition {
             let state = self.state.lock().unwrap();
-
-            let shards = state
-                .co_shards_lookup
-                .get(&desc.id)
-                .ok_or_else(|| crate::Error::NotFound(format!("shard (key={:?})", key)))?;
-
-            if slots != shards.len() as u32 {
-                return Err(crate::Error::NotFound("expired shard info".into()));
-            }
-
-            let shard = shards
-                .iter()
-                .find(|s| {
-                    if let shard_desc::Partition::Hash(p) = s.partition.as_ref().unwrap() {
-                        if p.slot_id == slot {
-                            return true;
-                        }
-                    }
-                    false
-                })
-                .unwrap();
-
-            let group_state = state
-                .find_group_by_shard(shard.id)
-                .ok_or_else(|| crate::Error::NotFound(format!("shard (key={key:?}) group")))?;
-
-            return Ok((group_state, shard.clone()));
+            return state.find_hash_shard(desc.id, hash.slots, hash.algorithm, key);
         }
 
         let state = self.state.lock().unwrap();
@@ -135,6 +104,68 @@ impl Router {
         Err(crate::Error::NotFound(format!("shard (key={:?})", key)))
     }
 
+    /// Find the shard of the target slots if the shard is resharding, the writes of the shard
+    /// should also be applied to it until the resharding is finished.
+    pub fn find_resharding_shard(
+        &self,
+        shard: &ShardDesc,
+        key: &[u8],
+    ) -> Result<Option<(RouterGroupState, ShardDesc)>, crate::Error> {
+        match shard.partition.as_ref() {
+            Some(shard_desc::Partition::Hash(hash)) if hash.target_slots != 0 => {
+                let state = self.state.lock().unwrap();
+                let target = state.find_hash_shard(
+                    shard.collection_id,
+                    hash.target_slots,
+                    hash.algorithm,
+                    key,
+                )?;
+                Ok(Some(target))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// List the serving shards of a collection, in the order of key range or hash slot. The
+    /// shards of the target slots of a resharding collection are excluded.
+    pub fn collection_shards(
+        &self,
+        desc: &CollectionDesc,
+    ) -> Result<Vec<(RouterGroupState, ShardDesc)>, crate::Error> {
+        let state = self.state.lock().unwrap();
+        let shards = state
+            .co_shards_lookup
+            .get(&desc.id)
+            .ok_or_else(|| crate::Error::NotFound(format!("shards (collection={})", desc.id)))?;
+
+        let mut shards = match desc.partition.as_ref() {
+            Some(collection_desc::Partition::Hash(hash)) => shards
+                .iter()
+                .filter(|s| match s.partition.as_ref() {
+                    Some(shard_desc::Partition::Hash(p)) => p.slots == hash.slots,
+                    _ => false,
+                })
+                .cloned()
+                .collect::<Vec<_>>(),
+            _ => shards.clone(),
+        };
+        shards.sort_by_key(|s| (shard::slot(s), shard::start_key(s)));
+
+        let mut states = Vec::with_capacity(shards.len());
+        for shard in shards {
+            let group_state = state
+                .find_group_by_shard(shard.id)
+                .ok_or_else(|| crate::Error::NotFound(format!("group (shard={})", shard.id)))?;
+            states.push((group_state, shard));
+        }
+        Ok(states)
+    }
+
+    pub fn find_collection(&self, id: u64) -> Option<CollectionDesc> {
+        let state = self.state.lock().unwrap();
+        state.co_id_lookup.get(&id).cloned()
+    }
+
     pub fn find_group_by_shard(&self, shard: u64) -> Result<RouterGroupState, crate::Error> {
         let state = self.state.lock().unwrap();
         state
@@ -160,6 +191,44 @@ impl Router {
 }
 
 impl State {
+    fn find_hash_shard(
+        &self,
+        co_id: u64,
+        slots: u32,
+        algorithm: i32,
+        key: &[u8],
+    ) -> Result<(RouterGroupState, ShardDesc), crate::Error> {
+        let slot = shard::key_slot(key, slots, algorithm).ok_or_else(|| {
+            crate::Error::InvalidArgument(format!(
+                "invalid hash partition (slots={slots}, algorithm={algorithm})"
+            ))
+        })?;
+
+        let shards = self
+            .co_shards_lookup
+            .get(&co_id)
+            .ok_or_else(|| crate::Error::NotFound(format!("shard (key={:?})", key)))?;
+
+        // The shards of both current and target slots exist during resharding.
+        let shard = shards
+            .iter()
+            .find(|s| {
+                if let Some(shard_desc::Partition::Hash(p)) = s.partition.as_ref() {
+                    if p.slots == slots && p.slot_id == slot {
+                        return true;
+                    }
+                }
+                false
+            })
+            .ok_or_else(|| crate::Error::NotFound("expired shard info".into()))?;
+
+        let group_state = self
+            .find_group_by_shard(shard.id)
+            .ok_or_else(|| crate::Error::NotFound(format!("shard (key={key:?}) group")))?;
+
+        Ok((group_state, shard.clone()))
+    }
+
     fn find_group_by_shard(&self, shard_id: u64) -> Option<RouterGroupState> {
         let (group_id, epoch) = self.shard_group_lookup.get(&shard_id).cloned()?;
         let group_state = self.group_id_lookup.get(&group_id).cloned()?;
@@ -367,6 +436,7 @@ mod tests {
             partition: Some(Partition::Hash(HashPartition {
                 slot_id: 1,
                 slots: 1,
+                ..Default::default()
             })),
         }
     }
@@ -454,4 +524,40 @@ mod tests {
             assert!(matches!(find, Some(RouterGroupState { id, .. }) if id == 2));
         }
     }
+
+    #[test]
+    fn find_hash_shard_during_resharding() {
+        fn hash_shard(id: u64, slot_id: u32, slots: u32) -> ShardDesc {
+            ShardDesc {
+                id,
+                collection_id: 1,
+                partition: Some(Partition::Hash(HashPartition {
+                    slot_id,
+                    slots,
+                    ..Default::default()
+                })),
+            }
+        }
+
+        let mut state = State::default();
+        let mut desc = descriptor(1, 1);
+        desc.shards.push(hash_shard(1, 0, 1));
+        state.apply_group_descriptor(desc);
+        let mut desc = descriptor(2, 1);
+        desc.shards.push(hash_shard(2, 0, 2));
+        desc.shards.push(hash_shard(3, 1, 2));
+        state.apply_group_descriptor(desc);
+
+        let key = b"key";
+        let (group, shard) = state.find_hash_shard(1, 1, 0, key).unwrap();
+        assert_eq!(group.id, 1);
+        assert_eq!(shard.id, 1);
+
+        let slot = engula_api::shard::key_slot(key, 2, 0).unwrap();
+        let (group, shard) = state.find_hash_shard(1, 2, 0, key).unwrap();
+        assert_eq!(group.id, 2);
+        assert_eq!(shard.id, 2 + slot as u64);
+
+        assert!(state.find_hash_shard(1, 4, 0, key).is_err());
+    }
 }
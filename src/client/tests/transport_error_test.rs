@@ -125,6 +125,14 @@ impl node_server::Node for MockedServer {
     ) -> Result<tonic::Response<engula_api::server::v1::ForwardResponse>, tonic::Status> {
         todo!()
     }
+
+    async fn collect_checksum(
+        &self,
+        request: tonic::Request<engula_api::server::v1::CollectChecksumRequest>,
+    ) -> Result<tonic::Response<engula_api::server::v1::CollectChecksumResponse>, tonic::Status>
+    {
+        todo!()
+    }
 }
 
 #[tokio::test]
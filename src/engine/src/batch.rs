@@ -0,0 +1,97 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// An operation recorded in a [`WriteBatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteOp {
+    Put {
+        key_space: String,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Delete {
+        key_space: String,
+        key: Vec<u8>,
+    },
+    /// Delete all keys in range `[start, end)`.
+    DeleteRange {
+        key_space: String,
+        start: Vec<u8>,
+        end: Vec<u8>,
+    },
+}
+
+/// A set of writes which are applied atomically by [`crate::Engine::write`].
+#[derive(Debug, Default, Clone)]
+pub struct WriteBatch {
+    ops: Vec<WriteOp>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct WriteOptions {
+    /// Sync the write ahead log before the write returns.
+    pub sync: bool,
+    /// Skip the write ahead log, the write may be lost after a crash.
+    pub disable_wal: bool,
+}
+
+impl WriteBatch {
+    pub fn put(&mut self, key_space: &str, key: &[u8], value: &[u8]) {
+        self.ops.push(WriteOp::Put {
+            key_space: key_space.to_owned(),
+            key: key.to_owned(),
+            value: value.to_owned(),
+        });
+    }
+
+    pub fn delete(&mut self, key_space: &str, key: &[u8]) {
+        self.ops.push(WriteOp::Delete {
+            key_space: key_space.to_owned(),
+            key: key.to_owned(),
+        });
+    }
+
+    pub fn delete_range(&mut self, key_space: &str, start: &[u8], end: &[u8]) {
+        self.ops.push(WriteOp::DeleteRange {
+            key_space: key_space.to_owned(),
+            start: start.to_owned(),
+            end: end.to_owned(),
+        });
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.ops.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    #[inline]
+    pub fn ops(&self) -> &[WriteOp] {
+        &self.ops
+    }
+}
+
+impl WriteOp {
+    pub fn key_space(&self) -> &str {
+        match self {
+            WriteOp::Put { key_space, .. }
+            | WriteOp::Delete { key_space, .. }
+            | WriteOp::DeleteRange { key_space, .. } => key_space,
+        }
+    }
+}
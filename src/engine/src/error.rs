@@ -0,0 +1,34 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("key space {0} not found")]
+    KeySpaceNotFound(String),
+
+    #[error("key space {0} already exists")]
+    KeySpaceExists(String),
+
+    #[error("corruption {0}")]
+    Corruption(String),
+
+    #[error("io {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "rocksdb")]
+    #[error("rocksdb {0}")]
+    RocksDb(#[from] rocksdb::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
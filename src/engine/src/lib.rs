@@ -12,11 +12,261 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! A storage engine abstraction of Engula.
+//!
+//! An [`Engine`] manages a set of named key spaces, each of which is an ordered map from key to
+//! value. Writes are grouped by [`WriteBatch`] and applied atomically, reads are served by the
+//! engine directly or by a consistent [`Snapshot`]. Key spaces could also be exported to and
+//! ingested from external files, which is used to transfer data between engines.
+//!
+//! Two implementations are provided:
+//! - [`RocksEngine`], which maps key spaces to RocksDB column families. It is enabled by the
+//!   `rocksdb` feature.
+//! - [`MemEngine`], a pure Rust in-memory engine, which is useful for testing.
+//!
+//! The server isn't built on this abstraction yet, it still accesses RocksDB through its own
+//! `RawDb` and `GroupEngine`. Moving the server onto [`Engine`] is out of the scope of this crate,
+//! until then both engines are only exercised by the tests here, and the tests of [`MemEngine`]
+//! also run without the `rocksdb` feature.
+
+mod batch;
+mod error;
+mod memory;
+#[cfg(feature = "rocksdb")]
+mod rocks;
+
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "rocksdb")]
+pub use crate::rocks::{RocksEngine, RocksIterator, RocksSnapshot};
+pub use crate::{
+    batch::{WriteBatch, WriteOp, WriteOptions},
+    error::{Error, Result},
+    memory::{MemEngine, MemIterator, MemSnapshot},
+};
+
+pub type KvPair = (Box<[u8]>, Box<[u8]>);
+
+#[derive(Debug, Default, Clone)]
+pub struct IterOptions {
+    /// The inclusive lower bound of keys.
+    pub lower_bound: Option<Vec<u8>>,
+    /// The exclusive upper bound of keys.
+    pub upper_bound: Option<Vec<u8>>,
+    /// Iterate keys in descending order.
+    pub reverse: bool,
+}
+
+pub trait Engine: Send + Sync {
+    type Snapshot<'a>: Snapshot
+    where
+        Self: 'a;
+
+    fn create_key_space(&self, name: &str) -> Result<()>;
+
+    fn drop_key_space(&self, name: &str) -> Result<()>;
+
+    /// Returns the names of all key spaces, in ascending order.
+    fn key_spaces(&self) -> Result<Vec<String>>;
+
+    fn has_key_space(&self, name: &str) -> Result<bool> {
+        Ok(self.key_spaces()?.iter().any(|n| n == name))
+    }
+
+    fn get(&self, key_space: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Apply the batch atomically. Nothing is written if any key space of the batch doesn't exist.
+    fn write(&self, batch: WriteBatch, opts: &WriteOptions) -> Result<()>;
+
+    fn snapshot(&self) -> Self::Snapshot<'_>;
+
+    /// Persist the memory data of the key space.
+    fn flush(&self, key_space: &str) -> Result<()>;
+
+    /// Export a consistent view of the key space into files under `dir`. A new file is created
+    /// once the size of the current one exceeds `max_file_size`. The file format is engine
+    /// specific, it could only be ingested by the same kind of engine.
+    fn checkpoint(&self, key_space: &str, dir: &Path, max_file_size: u64) -> Result<Vec<PathBuf>>;
+
+    /// Ingest files produced by [`Engine::checkpoint`] into the key space, existing keys are
+    /// overwritten.
+    fn ingest_external_files(&self, key_space: &str, files: &[PathBuf]) -> Result<()>;
+}
+
+pub trait Snapshot {
+    type Iter<'a>: Iterator<Item = Result<KvPair>>
+    where
+        Self: 'a;
+
+    fn get(&self, key_space: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    fn iter(&self, key_space: &str, opts: IterOptions) -> Result<Self::Iter<'_>>;
+}
+
 #[cfg(test)]
 mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn collect<S: Snapshot>(snapshot: &S, key_space: &str, opts: IterOptions) -> Vec<Vec<u8>> {
+        snapshot
+            .iter(key_space, opts)
+            .unwrap()
+            .map(|item| item.unwrap().0.to_vec())
+            .collect()
+    }
+
+    fn key_space_ops<E: Engine>(engine: &E) {
+        engine.create_key_space("a").unwrap();
+        engine.create_key_space("b").unwrap();
+        assert!(matches!(
+            engine.create_key_space("a"),
+            Err(Error::KeySpaceExists(_))
+        ));
+        assert_eq!(engine.key_spaces().unwrap(), vec!["a", "b"]);
+
+        engine.drop_key_space("a").unwrap();
+        assert!(!engine.has_key_space("a").unwrap());
+        assert!(matches!(
+            engine.drop_key_space("a"),
+            Err(Error::KeySpaceNotFound(_))
+        ));
+        assert!(matches!(
+            engine.get("a", b"key"),
+            Err(Error::KeySpaceNotFound(_))
+        ));
+    }
+
+    fn write_and_read<E: Engine>(engine: &E) {
+        engine.create_key_space("ks").unwrap();
+        let mut wb = WriteBatch::default();
+        for i in 0..10u8 {
+            wb.put("ks", &[i], &[i]);
+        }
+        engine.write(wb, &WriteOptions::default()).unwrap();
+        assert_eq!(engine.get("ks", &[1]).unwrap(), Some(vec![1]));
+
+        let mut wb = WriteBatch::default();
+        wb.delete("ks", &[1]);
+        wb.delete_range("ks", &[5], &[8]);
+        engine.write(wb, &WriteOptions::default()).unwrap();
+        assert_eq!(engine.get("ks", &[1]).unwrap(), None);
+
+        let snapshot = engine.snapshot();
+        assert_eq!(
+            collect(&snapshot, "ks", IterOptions::default()),
+            vec![vec![0], vec![2], vec![3], vec![4], vec![8], vec![9]]
+        );
+        let opts = IterOptions {
+            lower_bound: Some(vec![2]),
+            upper_bound: Some(vec![9]),
+            reverse: true,
+        };
+        assert_eq!(
+            collect(&snapshot, "ks", opts),
+            vec![vec![8], vec![4], vec![3], vec![2]]
+        );
+
+        // A batch with unknown key space is rejected entirely.
+        let mut wb = WriteBatch::default();
+        wb.put("ks", &[100], &[100]);
+        wb.put("unknown", &[100], &[100]);
+        assert!(engine.write(wb, &WriteOptions::default()).is_err());
+        assert_eq!(engine.get("ks", &[100]).unwrap(), None);
+    }
+
+    fn snapshot_isolation<E: Engine>(engine: &E) {
+        engine.create_key_space("ks").unwrap();
+        let mut wb = WriteBatch::default();
+        wb.put("ks", b"k1", b"v1");
+        engine.write(wb, &WriteOptions::default()).unwrap();
+
+        let snapshot = engine.snapshot();
+        let mut wb = WriteBatch::default();
+        wb.put("ks", b"k1", b"v2");
+        wb.put("ks", b"k2", b"v2");
+        engine.write(wb, &WriteOptions::default()).unwrap();
+
+        assert_eq!(snapshot.get("ks", b"k1").unwrap(), Some(b"v1".to_vec()));
+        assert_eq!(snapshot.get("ks", b"k2").unwrap(), None);
+        assert_eq!(engine.get("ks", b"k1").unwrap(), Some(b"v2".to_vec()));
+    }
+
+    fn checkpoint_and_ingest<E: Engine>(source: &E, target: &E, dir: &Path) {
+        source.create_key_space("ks").unwrap();
+        target.create_key_space("ks").unwrap();
+        let mut wb = WriteBatch::default();
+        for i in 0..1000u32 {
+            wb.put("ks", &i.to_be_bytes(), &[0u8; 64]);
+        }
+        source.write(wb, &WriteOptions::default()).unwrap();
+
+        let files = source.checkpoint("ks", dir, 4096).unwrap();
+        assert!(!files.is_empty());
+        target.ingest_external_files("ks", &files).unwrap();
+
+        let snapshot = target.snapshot();
+        let keys = collect(&snapshot, "ks", IterOptions::default());
+        assert_eq!(keys.len(), 1000);
+        assert_eq!(keys[999], 999u32.to_be_bytes().to_vec());
+    }
+
+    #[test]
+    fn memory_key_space_ops() {
+        key_space_ops(&MemEngine::new());
+    }
+
+    #[test]
+    fn memory_write_and_read() {
+        write_and_read(&MemEngine::new());
+    }
+
+    #[test]
+    fn memory_snapshot_isolation() {
+        snapshot_isolation(&MemEngine::new());
+    }
+
+    #[test]
+    fn memory_checkpoint_and_ingest() {
+        let dir = TempDir::new("memory-checkpoint").unwrap();
+        checkpoint_and_ingest(&MemEngine::new(), &MemEngine::new(), dir.path());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    fn open_rocks_engine(dir: &TempDir, name: &str) -> RocksEngine {
+        RocksEngine::open(dir.path().join(name), rocksdb::Options::default()).unwrap()
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn rocks_key_space_ops() {
+        let dir = TempDir::new("rocks-key-space").unwrap();
+        key_space_ops(&open_rocks_engine(&dir, "db"));
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn rocks_write_and_read() {
+        let dir = TempDir::new("rocks-write-read").unwrap();
+        write_and_read(&open_rocks_engine(&dir, "db"));
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn rocks_snapshot_isolation() {
+        let dir = TempDir::new("rocks-snapshot").unwrap();
+        snapshot_isolation(&open_rocks_engine(&dir, "db"));
+    }
+
+    #[cfg(feature = "rocksdb")]
     #[test]
-    fn it_works() {
-        let result = 2 + 2;
-        assert_eq!(result, 4);
+    fn rocks_checkpoint_and_ingest() {
+        let dir = TempDir::new("rocks-checkpoint").unwrap();
+        let checkpoint_dir = dir.path().join("checkpoint");
+        std::fs::create_dir_all(&checkpoint_dir).unwrap();
+        let source = open_rocks_engine(&dir, "source");
+        let target = open_rocks_engine(&dir, "target");
+        checkpoint_and_ingest(&source, &target, &checkpoint_dir);
     }
 }
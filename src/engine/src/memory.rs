@@ -0,0 +1,249 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{btree_map, BTreeMap, HashMap},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use crate::*;
+
+type KeySpace = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// A pure in-memory engine, all data is lost once the engine is dropped.
+///
+/// Key spaces are shared with snapshots in copy-on-write fashion, so a write to a key space which
+/// is referenced by an alive snapshot clones the key space first.
+#[derive(Clone, Default)]
+pub struct MemEngine {
+    key_spaces: Arc<RwLock<HashMap<String, Arc<KeySpace>>>>,
+}
+
+pub struct MemSnapshot {
+    key_spaces: HashMap<String, Arc<KeySpace>>,
+}
+
+pub struct MemIterator<'a> {
+    range: btree_map::Range<'a, Vec<u8>, Vec<u8>>,
+    reverse: bool,
+}
+
+impl MemEngine {
+    pub fn new() -> Self {
+        MemEngine::default()
+    }
+}
+
+impl Engine for MemEngine {
+    type Snapshot<'a> = MemSnapshot;
+
+    fn create_key_space(&self, name: &str) -> Result<()> {
+        let mut key_spaces = self.key_spaces.write().unwrap();
+        if key_spaces.contains_key(name) {
+            return Err(Error::KeySpaceExists(name.to_owned()));
+        }
+        key_spaces.insert(name.to_owned(), Arc::default());
+        Ok(())
+    }
+
+    fn drop_key_space(&self, name: &str) -> Result<()> {
+        let mut key_spaces = self.key_spaces.write().unwrap();
+        key_spaces
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| Error::KeySpaceNotFound(name.to_owned()))
+    }
+
+    fn key_spaces(&self) -> Result<Vec<String>> {
+        let key_spaces = self.key_spaces.read().unwrap();
+        let mut names = key_spaces.keys().cloned().collect::<Vec<_>>();
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    fn get(&self, key_space: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let key_spaces = self.key_spaces.read().unwrap();
+        let key_space = key_spaces
+            .get(key_space)
+            .ok_or_else(|| Error::KeySpaceNotFound(key_space.to_owned()))?;
+        Ok(key_space.get(key).cloned())
+    }
+
+    fn write(&self, batch: WriteBatch, _opts: &WriteOptions) -> Result<()> {
+        let mut key_spaces = self.key_spaces.write().unwrap();
+        // Validate the batch before applying any operation, so that a batch is applied atomically.
+        for op in batch.ops() {
+            if !key_spaces.contains_key(op.key_space()) {
+                return Err(Error::KeySpaceNotFound(op.key_space().to_owned()));
+            }
+        }
+
+        for op in batch.ops() {
+            let key_space = Arc::make_mut(key_spaces.get_mut(op.key_space()).unwrap());
+            match op {
+                WriteOp::Put { key, value, .. } => {
+                    key_space.insert(key.clone(), value.clone());
+                }
+                WriteOp::Delete { key, .. } => {
+                    key_space.remove(key);
+                }
+                WriteOp::DeleteRange { start, end, .. } => {
+                    if start >= end {
+                        continue;
+                    }
+                    let keys = key_space
+                        .range::<[u8], _>((
+                            Bound::Included(start.as_slice()),
+                            Bound::Excluded(end.as_slice()),
+                        ))
+                        .map(|(k, _)| k.clone())
+                        .collect::<Vec<_>>();
+                    for key in keys {
+                        key_space.remove(&key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Self::Snapshot<'_> {
+        let key_spaces = self.key_spaces.read().unwrap();
+        MemSnapshot {
+            key_spaces: key_spaces.clone(),
+        }
+    }
+
+    fn flush(&self, key_space: &str) -> Result<()> {
+        if !self.key_spaces.read().unwrap().contains_key(key_space) {
+            return Err(Error::KeySpaceNotFound(key_space.to_owned()));
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&self, key_space: &str, dir: &Path, max_file_size: u64) -> Result<Vec<PathBuf>> {
+        let snapshot = self.snapshot();
+        let mut iter = snapshot.iter(key_space, IterOptions::default())?.peekable();
+        let mut files = vec![];
+        while iter.peek().is_some() {
+            let file = dir.join(format!("{}.kv", files.len()));
+            let mut writer = BufWriter::new(std::fs::File::create(&file)?);
+            let mut file_size = 0;
+            for item in iter.by_ref() {
+                let (key, value) = item?;
+                write_record(&mut writer, &key)?;
+                write_record(&mut writer, &value)?;
+                file_size += (8 + key.len() + value.len()) as u64;
+                if file_size >= max_file_size {
+                    break;
+                }
+            }
+            writer
+                .into_inner()
+                .map_err(|e| e.into_error())?
+                .sync_all()?;
+            files.push(file);
+        }
+        Ok(files)
+    }
+
+    fn ingest_external_files(&self, key_space: &str, files: &[PathBuf]) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for file in files {
+            let mut reader = BufReader::new(std::fs::File::open(file)?);
+            while let Some(key) = read_record(&mut reader)? {
+                let value = read_record(&mut reader)?.ok_or_else(|| {
+                    Error::Corruption(format!("{} value is missing", file.display()))
+                })?;
+                batch.put(key_space, &key, &value);
+            }
+        }
+        self.write(batch, &WriteOptions::default())
+    }
+}
+
+impl Snapshot for MemSnapshot {
+    type Iter<'a> = MemIterator<'a>;
+
+    fn get(&self, key_space: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.key_space(key_space)?.get(key).cloned())
+    }
+
+    fn iter(&self, key_space: &str, opts: IterOptions) -> Result<Self::Iter<'_>> {
+        let key_space = self.key_space(key_space)?;
+        let lower = match opts.lower_bound.as_deref() {
+            Some(key) => Bound::Included(key),
+            None => Bound::Unbounded,
+        };
+        let upper = match opts.upper_bound.as_deref() {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+        let range = match (lower, upper) {
+            // `BTreeMap::range` panics if the start is greater than the end.
+            (Bound::Included(start), Bound::Excluded(end)) if start >= end => {
+                key_space.range::<[u8], _>((Bound::Included(start), Bound::Excluded(start)))
+            }
+            bounds => key_space.range::<[u8], _>(bounds),
+        };
+        Ok(MemIterator {
+            range,
+            reverse: opts.reverse,
+        })
+    }
+}
+
+impl MemSnapshot {
+    fn key_space(&self, key_space: &str) -> Result<&KeySpace> {
+        self.key_spaces
+            .get(key_space)
+            .map(AsRef::as_ref)
+            .ok_or_else(|| Error::KeySpaceNotFound(key_space.to_owned()))
+    }
+}
+
+impl<'a> Iterator for MemIterator<'a> {
+    type Item = Result<KvPair>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = if self.reverse {
+            self.range.next_back()
+        } else {
+            self.range.next()
+        };
+        item.map(|(key, value)| Ok((key.as_slice().into(), value.as_slice().into())))
+    }
+}
+
+fn write_record<W: Write>(w: &mut W, data: &[u8]) -> Result<()> {
+    w.write_all(&(data.len() as u32).to_le_bytes())?;
+    w.write_all(data)?;
+    Ok(())
+}
+
+fn read_record<R: Read>(r: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match r.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let mut data = vec![0u8; u32::from_le_bytes(len) as usize];
+    r.read_exact(&mut data)
+        .map_err(|_| Error::Corruption("record is truncated".to_owned()))?;
+    Ok(Some(data))
+}
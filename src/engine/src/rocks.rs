@@ -0,0 +1,212 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rocksdb::{BoundColumnFamily, DBIteratorWithThreadMode, SnapshotWithThreadMode, DB};
+
+use crate::*;
+
+/// An engine backed by RocksDB, each key space is mapped to a column family.
+pub struct RocksEngine {
+    db: DB,
+    options: rocksdb::Options,
+}
+
+pub struct RocksSnapshot<'a> {
+    engine: &'a RocksEngine,
+    snapshot: SnapshotWithThreadMode<'a, DB>,
+}
+
+pub struct RocksIterator<'a> {
+    iter: DBIteratorWithThreadMode<'a, DB>,
+}
+
+impl RocksEngine {
+    /// Open or create a RocksDB instance at `path`, all existing column families are opened.
+    pub fn open<P: AsRef<Path>>(path: P, mut options: rocksdb::Options) -> Result<Self> {
+        std::fs::create_dir_all(&path)?;
+        options.create_if_missing(true);
+
+        match DB::list_cf(&options, &path) {
+            Ok(cfs) => {
+                let db = DB::open_cf_with_opts(
+                    &options,
+                    path,
+                    cfs.into_iter().map(|name| (name, options.clone())),
+                )?;
+                Ok(RocksEngine { db, options })
+            }
+            Err(e) if e.as_ref().ends_with("CURRENT: No such file or directory") => {
+                let db = DB::open(&options, &path)?;
+                Ok(RocksEngine { db, options })
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the underlying RocksDB instance.
+    #[inline]
+    pub fn raw(&self) -> &DB {
+        &self.db
+    }
+
+    fn cf_handle(&self, name: &str) -> Result<Arc<BoundColumnFamily<'_>>> {
+        self.db
+            .cf_handle(name)
+            .ok_or_else(|| Error::KeySpaceNotFound(name.to_owned()))
+    }
+}
+
+impl Engine for RocksEngine {
+    type Snapshot<'a> = RocksSnapshot<'a>;
+
+    fn create_key_space(&self, name: &str) -> Result<()> {
+        if self.db.cf_handle(name).is_some() {
+            return Err(Error::KeySpaceExists(name.to_owned()));
+        }
+        self.db.create_cf(name, &self.options)?;
+        Ok(())
+    }
+
+    fn drop_key_space(&self, name: &str) -> Result<()> {
+        if self.db.cf_handle(name).is_none() {
+            return Err(Error::KeySpaceNotFound(name.to_owned()));
+        }
+        self.db.drop_cf(name)?;
+        Ok(())
+    }
+
+    fn key_spaces(&self) -> Result<Vec<String>> {
+        let mut names = DB::list_cf(&self.options, self.db.path())?
+            .into_iter()
+            .filter(|name| name != rocksdb::DEFAULT_COLUMN_FAMILY_NAME)
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    fn get(&self, key_space: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf = self.cf_handle(key_space)?;
+        Ok(self.db.get_cf(&cf, key)?)
+    }
+
+    fn write(&self, batch: WriteBatch, opts: &WriteOptions) -> Result<()> {
+        let mut wb = rocksdb::WriteBatch::default();
+        for op in batch.ops() {
+            let cf = self.cf_handle(op.key_space())?;
+            match op {
+                WriteOp::Put { key, value, .. } => wb.put_cf(&cf, key, value),
+                WriteOp::Delete { key, .. } => wb.delete_cf(&cf, key),
+                WriteOp::DeleteRange { start, end, .. } => wb.delete_range_cf(&cf, start, end),
+            }
+        }
+
+        let mut write_opts = rocksdb::WriteOptions::default();
+        write_opts.set_sync(opts.sync);
+        write_opts.disable_wal(opts.disable_wal);
+        self.db.write_opt(wb, &write_opts)?;
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Self::Snapshot<'_> {
+        RocksSnapshot {
+            engine: self,
+            snapshot: self.db.snapshot(),
+        }
+    }
+
+    fn flush(&self, key_space: &str) -> Result<()> {
+        let cf = self.cf_handle(key_space)?;
+        self.db.flush_cf(&cf)?;
+        Ok(())
+    }
+
+    fn checkpoint(&self, key_space: &str, dir: &Path, max_file_size: u64) -> Result<Vec<PathBuf>> {
+        use rocksdb::SstFileWriter;
+
+        let snapshot = self.snapshot();
+        let mut iter = snapshot.iter(key_space, IterOptions::default())?.peekable();
+        let opts = rocksdb::Options::default();
+        let mut files = vec![];
+        // Empty sst files are rejected by ingestion, so a file is only created if there exists
+        // some data.
+        while iter.peek().is_some() {
+            let file = dir.join(format!("{}.sst", files.len()));
+            let mut writer = SstFileWriter::create(&opts);
+            writer.open(&file)?;
+            for item in iter.by_ref() {
+                let (key, value) = item?;
+                writer.put(key, value)?;
+                if writer.file_size() >= max_file_size {
+                    break;
+                }
+            }
+            writer.finish()?;
+            files.push(file);
+        }
+        Ok(files)
+    }
+
+    fn ingest_external_files(&self, key_space: &str, files: &[PathBuf]) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let cf = self.cf_handle(key_space)?;
+        let opts = rocksdb::IngestExternalFileOptions::default();
+        self.db
+            .ingest_external_file_cf_opts(&cf, &opts, files.to_vec())?;
+        Ok(())
+    }
+}
+
+impl<'a> Snapshot for RocksSnapshot<'a> {
+    type Iter<'b> = RocksIterator<'b> where Self: 'b;
+
+    fn get(&self, key_space: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf = self.engine.cf_handle(key_space)?;
+        Ok(self.snapshot.get_cf(&cf, key)?)
+    }
+
+    fn iter(&self, key_space: &str, opts: IterOptions) -> Result<Self::Iter<'_>> {
+        use rocksdb::{IteratorMode, ReadOptions};
+
+        let cf = self.engine.cf_handle(key_space)?;
+        let mut read_opts = ReadOptions::default();
+        if let Some(lower_bound) = opts.lower_bound {
+            read_opts.set_iterate_lower_bound(lower_bound);
+        }
+        if let Some(upper_bound) = opts.upper_bound {
+            read_opts.set_iterate_upper_bound(upper_bound);
+        }
+        let mode = if opts.reverse {
+            IteratorMode::End
+        } else {
+            IteratorMode::Start
+        };
+        let iter = self.snapshot.iterator_cf_opt(&cf, read_opts, mode);
+        Ok(RocksIterator { iter })
+    }
+}
+
+impl<'a> Iterator for RocksIterator<'a> {
+    type Item = Result<KvPair>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|item| item.map_err(Into::into))
+    }
+}
@@ -15,7 +15,7 @@
 use std::{sync::Arc, time::Duration, vec};
 
 use engula_api::server::v1::{node_server::NodeServer, root_server::RootServer, *};
-use engula_client::RootClient;
+use engula_client::{EngulaClient, RootClient};
 use tracing::{debug, info, warn};
 
 use crate::{
@@ -25,76 +25,114 @@ use crate::{
     root::{Root, Schema},
     runtime::{Executor, Shutdown},
     serverpb::v1::{raft_server::RaftServer, NodeIdent},
-    service::ProxyServer,
-    transport::TransportManager,
+    service::{ProxyServer, RespServer},
+    transport::{Network, TransportManager},
     Config, Error, Result, Server,
 };
 
 /// The main entrance of engula server.
 pub fn run(config: Config, executor: Executor, shutdown: Shutdown) -> Result<()> {
-    executor.block_on(async {
-        let engines = Engines::open(&config.root_dir, &config.db)?;
-
-        let root_list = if config.init {
-            vec![config.addr.clone()]
-        } else {
-            config.join_list.clone()
-        };
-        let transport_manager = TransportManager::new(root_list, engines.state()).await;
-        let address_resolver = transport_manager.address_resolver();
-        let node = Node::new(config.clone(), engines, transport_manager.clone()).await?;
-
-        let ident =
-            bootstrap_or_join_cluster(&config, &node, transport_manager.root_client()).await?;
-        node.bootstrap(&ident).await?;
-        let root = Root::new(transport_manager.clone(), &ident, config.clone());
-        let initial_node_descs = root.bootstrap(&node).await?;
-        address_resolver.set_initial_nodes(initial_node_descs);
-
-        info!("node {} starts serving requests", ident.node_id);
-
-        let server = Server {
-            node: Arc::new(node),
-            root,
-            address_resolver,
-        };
-
-        let proxy_server = if config.enable_proxy_service {
-            Some(ProxyServer::new(&transport_manager))
-        } else {
-            None
-        };
-        bootstrap_services(&config.addr, server, proxy_server, shutdown).await
-    })
+    executor.block_on(serve(config, Network::Tcp, shutdown))
+}
+
+/// Bootstrap or join the cluster, then serve requests from the network until shutdown.
+pub(crate) async fn serve(config: Config, network: Network, shutdown: Shutdown) -> Result<()> {
+    let engines = Engines::open(&config.root_dir, &config.db)?;
+
+    let root_list = if config.init {
+        vec![config.addr.clone()]
+    } else {
+        config.join_list.clone()
+    };
+    let transport_manager =
+        TransportManager::with_conn_manager(root_list, engines.state(), network.conn_manager())
+            .await;
+    let address_resolver = transport_manager.address_resolver();
+    let node = Node::new(config.clone(), engines, transport_manager.clone()).await?;
+
+    let ident = bootstrap_or_join_cluster(&config, &node, transport_manager.root_client()).await?;
+    node.bootstrap(&ident).await?;
+    let root = Root::new(transport_manager.clone(), &ident, config.clone());
+    let initial_node_descs = root.bootstrap(&node).await?;
+    address_resolver.set_initial_nodes(initial_node_descs);
+
+    info!("node {} starts serving requests", ident.node_id);
+
+    let server = Server {
+        node: Arc::new(node),
+        root,
+        address_resolver,
+    };
+
+    // The proxy client is shared by the data API of admin service.
+    let proxy_server = ProxyServer::new(&transport_manager);
+    let data_client = proxy_server.client.clone();
+    let proxy_server = config.enable_proxy_service.then_some(proxy_server);
+    let resp_server = config
+        .resp
+        .clone()
+        .map(|cfg| RespServer::new(&transport_manager, cfg));
+    bootstrap_services(
+        &config.addr,
+        network,
+        server,
+        data_client,
+        proxy_server,
+        resp_server,
+        shutdown,
+    )
+    .await
 }
 
 /// Listen and serve incoming rpc requests.
 async fn bootstrap_services(
     addr: &str,
+    network: Network,
     server: Server,
+    data_client: EngulaClient,
     proxy_server: Option<ProxyServer>,
+    resp_server: Option<RespServer>,
     shutdown: Shutdown,
 ) -> Result<()> {
     use engula_api::v1::engula_server::EngulaServer;
+    use futures::future::BoxFuture;
     use tokio::net::TcpListener;
     use tonic::transport::Server;
 
     use crate::{runtime::TcpIncoming, service::admin::make_admin_service};
 
-    let listener = TcpListener::bind(addr).await?;
-    let incoming = TcpIncoming::from_listener(listener, true);
-
-    let server = Server::builder()
+    let router = Server::builder()
         .accept_http1(true) // Support http1 for admin service.
         .add_service(NodeServer::new(server.clone()))
         .add_service(RaftServer::new(server.clone()))
         .add_service(RootServer::new(server.clone()))
-        .add_service(make_admin_service(server.clone()))
-        .add_optional_service(proxy_server.map(EngulaServer::new))
-        .serve_with_incoming(incoming);
+        .add_service(make_admin_service(server.clone(), data_client))
+        .add_optional_service(proxy_server.map(EngulaServer::new));
+    let server: BoxFuture<'static, Result<(), tonic::transport::Error>> = match network {
+        Network::Tcp => {
+            let listener = TcpListener::bind(addr).await?;
+            let incoming = TcpIncoming::from_listener(listener, true);
+            Box::pin(router.serve_with_incoming(incoming))
+        }
+        #[cfg(feature = "sim")]
+        Network::Sim(endpoint) => Box::pin(router.serve_with_incoming(endpoint.bind())),
+    };
+
+    let resp_listener = match resp_server.as_ref() {
+        Some(resp_server) => Some(TcpListener::bind(resp_server.addr()).await?),
+        None => None,
+    };
+    let resp_server = async move {
+        match resp_server.zip(resp_listener) {
+            Some((resp_server, listener)) => resp_server.serve(listener).await,
+            None => futures::future::pending().await,
+        }
+    };
 
     crate::runtime::select! {
+        biased;
         res = server => { res? }
+        res = resp_server => { res? }
         _ = shutdown => {}
     };
 
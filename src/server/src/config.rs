@@ -32,6 +32,14 @@ pub struct Config {
 
     pub enable_proxy_service: bool,
 
+    /// Serve a Redis protocol (RESP) compatible listener if it is specified.
+    #[serde(default)]
+    pub resp: Option<RespConfig>,
+
+    /// Replicate the user writes to a standby cluster asynchronously if it is specified.
+    #[serde(default)]
+    pub replication: Option<ReplicationConfig>,
+
     pub join_list: Vec<String>,
 
     #[serde(default)]
@@ -50,6 +58,48 @@ pub struct Config {
     pub db: DbConfig,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RespConfig {
+    /// The address of RESP listener.
+    pub addr: String,
+
+    /// The database that the commands operate on.
+    pub database: String,
+
+    /// The collection that the commands operate on.
+    pub collection: String,
+
+    /// The timeout of the requests issued by a command, the command replies an error once it is
+    /// exceeded.
+    ///
+    /// Default: 5000.
+    #[serde(default = "RespConfig::default_rpc_timeout_ms")]
+    pub rpc_timeout_ms: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplicationConfig {
+    /// The addresses of the root servers of the standby cluster, which has the same databases
+    /// and collections as this cluster.
+    pub standby_addrs: Vec<String>,
+
+    /// The interval of retrying to ship the writes to the standby cluster after a failure.
+    ///
+    /// Default: 1000.
+    pub retry_interval_ms: u64,
+
+    /// The timeout of shipping a write to the standby cluster.
+    ///
+    /// Default: 5000.
+    pub rpc_timeout_ms: u64,
+
+    /// The limit number of writes of a group waiting to be shipped, the user writes of the group
+    /// are throttled once it is exceeded.
+    ///
+    /// Default: 100000.
+    pub max_pending_writes: usize,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct NodeConfig {
     /// The limit bytes of each shard chunk during migration.
@@ -83,10 +133,54 @@ pub struct ReplicaConfig {
     /// Default: 64MB.
     pub snap_file_size: u64,
 
+    /// The interval of checking whether the replicas of a group hold identical data, the check
+    /// is disabled if it is zero.
+    ///
+    /// Default: 86400.
+    pub consistency_check_interval_sec: u64,
+
+    /// The limit bytes per second of sending snapshots of a node, and the same limit is applied
+    /// to receiving snapshots separately. There is no limit if it is zero.
+    ///
+    /// NOTE: the limit is shared by all snapshots of a node, which bounds the speed of moving
+    /// replicas and recovering lagging followers. It should be raised on fast networks, eg.
+    /// 100MB/s takes about 10 minutes to transfer a 64GB shard.
+    ///
+    /// Default: 100MB.
+    pub snap_max_bytes_per_sec: u64,
+
+    /// The limit number of snapshots a node sends concurrently, the exceeded requests are
+    /// rejected and retried by the receivers later.
+    ///
+    /// Default: 4.
+    pub snap_max_concurrent_send: usize,
+
+    /// The limit number of snapshots a node receives concurrently, the exceeded downloads wait
+    /// for the running ones.
+    ///
+    /// Default: 4.
+    pub snap_max_concurrent_recv: usize,
+
+    /// The compression of snapshot chunks during transmission, one of `none`, `lz4` and `zstd`.
+    /// It only takes effect if the receiver advertises that it accepts the compression, the
+    /// chunks sent to the others are not compressed.
+    ///
+    /// Default: none.
+    pub snap_compression: SnapCompression,
+
     #[serde(skip)]
     pub testing_knobs: ReplicaTestingKnobs,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapCompression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct EngineConfig {
     /// Log slow io requests if it exceeds the specified threshold.
@@ -142,6 +236,9 @@ pub struct DbConfig {
 #[derive(Clone, Debug, Default)]
 pub struct RaftTestingKnobs {
     pub force_new_peer_receiving_snapshot: bool,
+    /// Derive the election timeouts from the seed instead of randomizing them, so that elections
+    /// are replayed under simulation.
+    pub election_seed: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -187,6 +284,53 @@ pub struct RaftConfig {
     /// Default: false
     pub enable_log_recycle: bool,
 
+    /// Coalesce the messages of all groups sent to the same node into batches, and transmit them
+    /// on a single stream. The messages to the nodes which don't support the `SendMessages` rpc
+    /// are sent by `SendMessage` instead, so it could be enabled during rolling upgrades.
+    ///
+    /// Default: false
+    pub enable_msg_batch: bool,
+
+    /// Limit the total bytes of messages in a batch, a message larger than it is sent alone.
+    ///
+    /// Default: 1MB
+    pub max_msg_batch_size: u64,
+
+    /// The intervals of waiting for more messages before sending a batch, in micros. 0 means
+    /// only the messages already queued are coalesced.
+    ///
+    /// Default: 0
+    pub msg_batch_window_us: u64,
+
+    /// The compression of message batches, one of `none`, `lz4` and `zstd`.
+    ///
+    /// Default: none
+    pub msg_compression: SnapCompression,
+
+    /// Only compress the message batches which exceed the threshold, in bytes.
+    ///
+    /// Default: 64KB
+    pub msg_compression_threshold: u64,
+
+    /// Stop ticking and heartbeating the idle groups, whose followers have caught up the leader.
+    /// A hibernated group is woken by the first proposal or message. The followers rely on the
+    /// node liveness of root and the hibernation heartbeats to detect the failure of leader. The
+    /// root group never hibernates.
+    ///
+    /// Default: false
+    pub enable_hibernation: bool,
+
+    /// The number of idle ticks before a leader hibernates the group.
+    ///
+    /// Default: 20
+    pub hibernate_idle_ticks: usize,
+
+    /// A hibernated leader sends a hibernation heartbeat every `hibernate_idle_ticks`, a
+    /// hibernated follower wakes up after missing the number of hibernation heartbeats.
+    ///
+    /// Default: 3
+    pub hibernate_missed_heartbeats: usize,
+
     #[serde(skip)]
     pub testing_knobs: RaftTestingKnobs,
 }
@@ -194,6 +338,12 @@ pub struct RaftConfig {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RootConfig {
     pub replicas_per_group: usize,
+    /// The number of witnesses of the `replicas_per_group` replicas of a new group. A witness
+    /// votes and replicates raft logs, but holds no data. It is limited to the minority of the
+    /// group, so that the most data replicas are always kept.
+    ///
+    /// Default: 0
+    pub witnesses_per_group: usize,
     pub enable_group_balance: bool,
     pub enable_replica_balance: bool,
     pub enable_shard_balance: bool,
@@ -202,6 +352,11 @@ pub struct RootConfig {
     pub heartbeat_timeout_sec: u64,
     pub schedule_interval_sec: u64,
     pub max_create_group_retry_before_rollback: u64,
+    /// The nodes with this label are dedicated to the read learners of collections that require
+    /// read replicas, no voters or witnesses are allocated to them.
+    ///
+    /// Default: "analytics"
+    pub read_replica_label: String,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -211,6 +366,51 @@ pub struct ExecutorConfig {
     pub max_blocking_threads: Option<usize>,
 }
 
+impl Default for RespConfig {
+    fn default() -> Self {
+        RespConfig {
+            addr: String::default(),
+            database: String::default(),
+            collection: String::default(),
+            rpc_timeout_ms: Self::default_rpc_timeout_ms(),
+        }
+    }
+}
+
+impl RespConfig {
+    fn default_rpc_timeout_ms() -> u64 {
+        5000
+    }
+
+    #[inline]
+    pub fn rpc_timeout(&self) -> Duration {
+        Duration::from_millis(self.rpc_timeout_ms)
+    }
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        ReplicationConfig {
+            standby_addrs: vec![],
+            retry_interval_ms: 1000,
+            rpc_timeout_ms: 5000,
+            max_pending_writes: 100000,
+        }
+    }
+}
+
+impl ReplicationConfig {
+    #[inline]
+    pub fn retry_interval(&self) -> Duration {
+        Duration::from_millis(self.retry_interval_ms)
+    }
+
+    #[inline]
+    pub fn rpc_timeout(&self) -> Duration {
+        Duration::from_millis(self.rpc_timeout_ms)
+    }
+}
+
 impl Default for NodeConfig {
     fn default() -> Self {
         NodeConfig {
@@ -226,6 +426,11 @@ impl Default for ReplicaConfig {
     fn default() -> Self {
         ReplicaConfig {
             snap_file_size: 64 * 1024 * 1024 * 1024,
+            consistency_check_interval_sec: 24 * 60 * 60,
+            snap_max_bytes_per_sec: 100 * 1024 * 1024,
+            snap_max_concurrent_send: 4,
+            snap_max_concurrent_recv: 4,
+            snap_compression: SnapCompression::None,
             testing_knobs: ReplicaTestingKnobs::default(),
         }
     }
@@ -341,6 +546,16 @@ impl Default for DbConfig {
 
 impl RaftConfig {
     pub(crate) fn to_raft_config(&self, replica_id: u64, applied: u64) -> raft::Config {
+        // raft-rs randomizes the election timeout in `[min_election_tick, max_election_tick)`, a
+        // range of one tick pins it to a value chosen by the seed.
+        let (min_election_tick, max_election_tick) = match self.testing_knobs.election_seed {
+            Some(seed) => {
+                let tick =
+                    self.election_tick + (mix64(seed ^ replica_id) as usize % self.election_tick);
+                (tick, tick + 1)
+            }
+            None => (0, 0),
+        };
         raft::Config {
             id: replica_id,
             election_tick: self.election_tick,
@@ -353,11 +568,20 @@ impl RaftConfig {
             max_inflight_msgs: self.max_inflight_msgs,
             max_committed_size_per_ready: self.max_io_batch_size,
             read_only_option: raft::ReadOnlyOption::Safe,
+            min_election_tick,
+            max_election_tick,
             ..Default::default()
         }
     }
 }
 
+/// The finalizer of splitmix64, which spreads the bits of the input.
+fn mix64(mut v: u64) -> u64 {
+    v = (v ^ (v >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    v = (v ^ (v >> 27)).wrapping_mul(0x94d049bb133111eb);
+    v ^ (v >> 31)
+}
+
 impl Default for RaftConfig {
     fn default() -> Self {
         RaftConfig {
@@ -369,6 +593,14 @@ impl Default for RaftConfig {
             max_inflight_msgs: 10 * 1000,
             engine_slow_io_threshold_ms: None,
             enable_log_recycle: false,
+            enable_msg_batch: false,
+            max_msg_batch_size: 1 << 20,
+            msg_batch_window_us: 0,
+            msg_compression: SnapCompression::None,
+            msg_compression_threshold: 64 << 10,
+            enable_hibernation: false,
+            hibernate_idle_ticks: 20,
+            hibernate_missed_heartbeats: 3,
             testing_knobs: RaftTestingKnobs::default(),
         }
     }
@@ -384,6 +616,7 @@ impl Default for RootConfig {
     fn default() -> Self {
         Self {
             replicas_per_group: REPLICA_PER_GROUP,
+            witnesses_per_group: 0,
             enable_group_balance: true,
             enable_replica_balance: true,
             enable_shard_balance: true,
@@ -392,6 +625,7 @@ impl Default for RootConfig {
             heartbeat_timeout_sec: 4,
             schedule_interval_sec: 3,
             max_create_group_retry_before_rollback: 10,
+            read_replica_label: "analytics".to_owned(),
         }
     }
 }
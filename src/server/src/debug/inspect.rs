@@ -0,0 +1,196 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use engula_api::{
+    server::v1::{GroupDesc, RootDesc},
+    shard,
+};
+
+use crate::{
+    constants::LOCAL_COLLECTION_ID,
+    engine::{keys, Engines, GroupEngine, MvccEntry},
+    raftgroup::{dump_raft_log, RaftLogDump, SnapManager},
+    serverpb::v1::{ApplyState, MigrationState, NodeIdent, ReplicaLocalState, SnapshotMeta},
+    DbConfig, EngineConfig, Error, Result,
+};
+
+/// A read only view of the data directory of a stopped node.
+pub struct Inspector {
+    engines: Engines,
+}
+
+/// The states of a replica which are saved in the group engine.
+#[derive(Debug)]
+pub struct GroupStates {
+    pub replica_id: u64,
+    pub apply_state: ApplyState,
+    pub descriptor: GroupDesc,
+    pub migration_state: Option<MigrationState>,
+}
+
+/// A decoded key value pair of the group engine.
+#[derive(Debug)]
+pub enum GroupEntry {
+    /// The local states of the replica, eg `APPLY_STATE`.
+    Local { name: String, value: Vec<u8> },
+    Mvcc {
+        collection_id: u64,
+        slot: Option<u32>,
+        user_key: Vec<u8>,
+        version: u64,
+        /// `None` if this entry is a tombstone.
+        value: Option<Vec<u8>>,
+    },
+    /// The key belongs to a collection which is not known by the group, so it can't be decoded.
+    Unknown { key: Vec<u8>, value: Vec<u8> },
+}
+
+impl Inspector {
+    pub fn open(root_dir: &Path, db_cfg: &DbConfig) -> Result<Self> {
+        let engines = Engines::open_for_read_only(root_dir, db_cfg)?;
+        Ok(Inspector { engines })
+    }
+
+    pub async fn node_ident(&self) -> Result<Option<NodeIdent>> {
+        self.engines.state().read_ident().await
+    }
+
+    pub async fn root_desc(&self) -> Result<Option<RootDesc>> {
+        self.engines.state().load_root_desc().await
+    }
+
+    /// Return the `(group_id, replica_id, local_state)` of all replicas in this node.
+    pub async fn replica_states(&self) -> Result<Vec<(u64, u64, ReplicaLocalState)>> {
+        self.engines.state().replica_states().await
+    }
+
+    /// Return the states saved in the group engine of the local replica of the group.
+    pub async fn group_states(&self, group_id: u64) -> Result<GroupStates> {
+        let (replica_id, group_engine) = self.open_group_engine(group_id).await?;
+        Ok(GroupStates {
+            replica_id,
+            apply_state: group_engine.flushed_apply_state()?,
+            descriptor: group_engine.descriptor(),
+            migration_state: group_engine.migration_state(),
+        })
+    }
+
+    /// Return the decoded key value pairs of the group engine of the local replica of the group.
+    pub async fn group_entries(&self, group_id: u64, limit: usize) -> Result<Vec<GroupEntry>> {
+        let (_, group_engine) = self.open_group_engine(group_id).await?;
+        let mut with_slots = HashMap::new();
+        let migrating_shard = group_engine
+            .migration_state()
+            .map(|m| m.get_shard_desc().clone());
+        for shard in group_engine
+            .descriptor()
+            .shards
+            .iter()
+            .chain(migrating_shard.iter())
+        {
+            with_slots.insert(shard.collection_id, shard::slot(shard).is_some());
+        }
+
+        let mut entries = vec![];
+        for item in group_engine.raw_iter()?.take(limit) {
+            let (key, value) = item?;
+            entries.push(decode_group_entry(&with_slots, key, value));
+        }
+        Ok(entries)
+    }
+
+    /// Return the raft states and at most `limit` log entries since `start` of the replica.
+    pub fn raft_log(
+        &self,
+        replica_id: u64,
+        start: Option<u64>,
+        limit: usize,
+    ) -> Result<RaftLogDump> {
+        dump_raft_log(&self.engines.log(), replica_id, start, limit)
+    }
+
+    /// Return the `(replica_id, snapshot_dir, meta)` of all snapshots in this node.
+    pub fn snapshots(&self) -> Result<Vec<(u64, PathBuf, Option<SnapshotMeta>)>> {
+        SnapManager::list_snapshots(self.engines.snap_dir())
+    }
+
+    async fn open_group_engine(&self, group_id: u64) -> Result<(u64, GroupEngine)> {
+        // A tombstone replica might be left if the group has been moved out and in again.
+        let replica_id = self
+            .replica_states()
+            .await?
+            .into_iter()
+            .filter(|(id, _, _)| *id == group_id)
+            .min_by_key(|(_, _, state)| *state == ReplicaLocalState::Tombstone)
+            .map(|(_, replica_id, _)| replica_id)
+            .ok_or(Error::GroupNotFound(group_id))?;
+        let group_engine = GroupEngine::open(
+            &EngineConfig::default(),
+            self.engines.db(),
+            group_id,
+            replica_id,
+        )
+        .await?
+        .ok_or_else(|| {
+            Error::InvalidData(format!(
+                "group {group_id} replica {replica_id} the group engine is not found"
+            ))
+        })?;
+        Ok((replica_id, group_engine))
+    }
+}
+
+/// Decode a key value pair of the group engine, the malformed one is returned as
+/// `GroupEntry::Unknown` since the data to inspect might be corrupted.
+fn decode_group_entry(
+    with_slots: &HashMap<u64, bool>,
+    key: Box<[u8]>,
+    value: Box<[u8]>,
+) -> GroupEntry {
+    const L: usize = core::mem::size_of::<u64>();
+    let unknown = |key: Box<[u8]>, value: Box<[u8]>| GroupEntry::Unknown {
+        key: key.to_vec(),
+        value: value.to_vec(),
+    };
+    if key.len() < L {
+        return unknown(key, value);
+    }
+
+    let collection_id = keys::collection_id(&key);
+    if collection_id == LOCAL_COLLECTION_ID {
+        return GroupEntry::Local {
+            name: String::from_utf8_lossy(&key[L..]).into_owned(),
+            value: value.to_vec(),
+        };
+    }
+    let Some(&with_slot) = with_slots.get(&collection_id) else {
+        return unknown(key, value);
+    };
+    if !MvccEntry::is_well_formed(with_slot, &key, &value) {
+        return unknown(key, value);
+    }
+    let entry = MvccEntry::new(with_slot, key, value);
+    GroupEntry::Mvcc {
+        collection_id,
+        slot: entry.slot(),
+        user_key: entry.user_key().to_owned(),
+        version: entry.version(),
+        value: entry.value().map(ToOwned::to_owned),
+    }
+}
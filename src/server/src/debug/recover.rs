@@ -0,0 +1,137 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use engula_api::server::v1::{GroupDesc, ReplicaDesc, ReplicaRole};
+use tracing::{info, warn};
+
+use crate::{
+    constants::ROOT_GROUP_ID,
+    engine::{Engines, GroupEngine, WriteBatch, WriteStates},
+    raftgroup::reset_to_applied,
+    serverpb::v1::ReplicaLocalState,
+    DbConfig, EngineConfig, Error, Result,
+};
+
+/// The epoch delta of the recovered `GroupDesc`. The root might have known a newer descriptor
+/// than the surviving replica, so the epoch is increased by a large delta to make sure the
+/// recovered descriptor is the newest one.
+const RECOVER_EPOCH_DELTA: u64 = 1 << 16;
+
+/// Recover a group which has permanently lost the majority of replicas, by rewriting the
+/// `GroupDesc` and raft states of the surviving replica in the data directory, so that it
+/// becomes the only voter of the group. Once the node is restarted, the replica is able to elect
+/// itself as leader and the scheduler will bring the group back to full replication.
+///
+/// It is unsafe because the entries not yet applied by the surviving replica are discarded, and
+/// the writes committed by the lost replicas might be lost. The node must be stopped, and only
+/// one surviving replica of a group should be recovered.
+///
+/// Only a voter is recovered unless `force` is specified, since a learner might lag far behind,
+/// and a witness has no data at all.
+pub async fn unsafe_recover(
+    root_dir: &Path,
+    db_cfg: &DbConfig,
+    group_id: u64,
+    force: bool,
+) -> Result<GroupDesc> {
+    let engines = Engines::open(root_dir, db_cfg)?;
+    let state_engine = engines.state();
+    let ident = state_engine.read_ident().await?.ok_or_else(|| {
+        Error::InvalidArgument(format!(
+            "{} is not the data directory of a bootstrapped node",
+            root_dir.display()
+        ))
+    })?;
+    let node_id = ident.node_id;
+
+    let replica_id = state_engine
+        .replica_states()
+        .await?
+        .into_iter()
+        .find(|(id, _, state)| *id == group_id && *state == ReplicaLocalState::Normal)
+        .map(|(_, replica_id, _)| replica_id)
+        .ok_or(Error::GroupNotFound(group_id))?;
+
+    // The root desc must be known before any modification, otherwise the node doesn't know the
+    // address of itself.
+    let root_desc = if group_id == ROOT_GROUP_ID {
+        let mut root_desc = state_engine.load_root_desc().await?.unwrap_or_default();
+        root_desc.root_nodes.retain(|n| n.id == node_id);
+        if root_desc.root_nodes.is_empty() {
+            return Err(Error::InvalidData(format!(
+                "node {node_id} is not found in the local root desc"
+            )));
+        }
+        Some(root_desc)
+    } else {
+        None
+    };
+
+    let group_engine =
+        GroupEngine::open(&EngineConfig::default(), engines.db(), group_id, replica_id)
+            .await?
+            .ok_or_else(|| {
+                Error::InvalidData(format!(
+                    "group {group_id} replica {replica_id} the group engine is not found"
+                ))
+            })?;
+    let apply_state = group_engine.flushed_apply_state()?;
+    let mut desc = group_engine.descriptor();
+    let Some(local_replica) = desc.replicas.iter().find(|r| r.id == replica_id) else {
+        return Err(Error::InvalidData(format!(
+            "group {group_id} replica {replica_id} has been removed from the group"
+        )));
+    };
+    match ReplicaRole::from_i32(local_replica.role) {
+        Some(ReplicaRole::Voter | ReplicaRole::IncomingVoter | ReplicaRole::DemotingVoter) => {}
+        role if force => {
+            warn!("group {group_id} replica {replica_id} is forced to recover from role {role:?}");
+        }
+        role => {
+            return Err(Error::InvalidArgument(format!(
+                "group {group_id} replica {replica_id} is not a voter but {role:?}, use force to recover it anyway"
+            )));
+        }
+    }
+
+    info!(
+        "group {group_id} replica {replica_id} unsafe recover from descriptor {desc:?} at applied index {}",
+        apply_state.index
+    );
+    desc.epoch += RECOVER_EPOCH_DELTA;
+    desc.replicas = vec![ReplicaDesc {
+        id: replica_id,
+        node_id,
+        role: ReplicaRole::Voter as i32,
+    }];
+    let states = WriteStates {
+        descriptor: Some(desc.clone()),
+        ..Default::default()
+    };
+    group_engine.commit(WriteBatch::default(), states, true)?;
+    reset_to_applied(&engines.log(), replica_id, apply_state).await?;
+
+    if let Some(mut root_desc) = root_desc {
+        root_desc.epoch = desc.epoch;
+        state_engine.save_root_desc(root_desc).await?;
+    }
+
+    info!(
+        "group {group_id} replica {replica_id} is recovered as the only voter, epoch {}",
+        desc.epoch
+    );
+    Ok(desc)
+}
@@ -14,7 +14,7 @@
 
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::{Deref, DerefMut},
     path::Path,
     sync::{Arc, RwLock},
@@ -40,9 +40,11 @@ pub struct WriteStates {
 }
 
 #[derive(Default)]
-#[repr(transparent)]
 pub struct WriteBatch {
     inner: rocksdb::WriteBatch,
+    /// Range deletions can't be traversed by `rocksdb::WriteBatch::iterate`, so they are recorded
+    /// separately.
+    delete_ranges: Vec<DeleteRange>,
 }
 
 /// A structure supports grouped data, metadata saving and retriving.
@@ -102,9 +104,16 @@ pub(crate) struct Snapshot<'a> {
 
 pub(crate) struct SnapshotCore<'a> {
     expect_slot: Option<u32>,
+    reverse: bool,
     db_iter: rocksdb::DBIterator<'a>,
     current_key: Option<Vec<u8>>,
     cached_entry: Option<MvccEntry>,
+
+    /// The versions of a user key are visited from the oldest to the newest in reverse mode, so
+    /// they are buffered and popped from the back.
+    reversed_entries: Vec<MvccEntry>,
+    /// The first entry of the next user key, which is read when filling `reversed_entries`.
+    peeked_entry: Option<MvccEntry>,
 }
 
 /// Traverse the data of a shard in the group engine, analyze and return the data (including
@@ -127,9 +136,57 @@ pub(crate) struct MvccEntry {
 
 #[derive(Debug)]
 pub(crate) enum SnapshotMode<'a> {
-    Start { start_key: Option<&'a [u8]> },
-    Key { key: &'a [u8] },
-    Prefix { key: &'a [u8] },
+    Start {
+        start_key: Option<&'a [u8]>,
+    },
+    /// Iterate the user keys in descending order, from the exclusive `end_key` (or the end of
+    /// shard if it is `None`) to the start of shard. The versions of a user key are still
+    /// ordered from the newest to the oldest.
+    End {
+        end_key: Option<&'a [u8]>,
+    },
+    Key {
+        key: &'a [u8],
+    },
+    Prefix {
+        key: &'a [u8],
+    },
+}
+
+/// A write of user data decoded from a [`WriteBatch`], see [`GroupEngine::user_writes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum UserWrite {
+    Put {
+        collection_id: u64,
+        shard_id: u64,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Delete {
+        collection_id: u64,
+        shard_id: u64,
+        key: Vec<u8>,
+    },
+    /// Delete the keys in range `[start, end)`, an empty key means the boundary of shard.
+    DeleteRange {
+        collection_id: u64,
+        shard_id: u64,
+        start: Vec<u8>,
+        end: Vec<u8>,
+    },
+    /// Delete all keys of a hash shard. The keys of the shard might be located in different slots
+    /// of other clusters, so the partition of the shard is recorded.
+    TruncateShard {
+        collection_id: u64,
+        shard_id: u64,
+        hash: shard_desc::HashPartition,
+    },
+}
+
+/// Collects the raw puts and deletes of a `rocksdb::WriteBatch`.
+#[derive(Default)]
+struct RawWriteCollector {
+    writes: Vec<(Box<[u8]>, Option<Box<[u8]>>)>,
 }
 
 struct ColumnFamilyDecorator<'a, 'b> {
@@ -310,6 +367,104 @@ impl GroupEngine {
         Ok(())
     }
 
+    /// Delete all versions of keys in range `[start, end)` from the corresponding shard, an empty
+    /// key means the boundary of shard. The range is clamped by the shard range, and the range of
+    /// a hash shard must be empty.
+    pub fn delete_range(
+        &self,
+        wb: &mut WriteBatch,
+        shard_id: u64,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<()> {
+        let desc = self.shard_desc(shard_id)?;
+        let collection_id = desc.collection_id;
+        debug_assert_ne!(collection_id, LOCAL_COLLECTION_ID);
+
+        let (start, end) = match shard::slot(&desc) {
+            Some(_) => {
+                if !start.is_empty() || !end.is_empty() {
+                    return Err(Error::InvalidArgument(
+                        "hash shard only supports deleting the whole shard".into(),
+                    ));
+                }
+                keys::shard_range(&desc)
+            }
+            None => {
+                let shard_start = shard::start_key(&desc);
+                let shard_end = shard::end_key(&desc);
+                let start = std::cmp::max(start, shard_start.as_slice());
+                let end = match (end.is_empty(), shard_end.is_empty()) {
+                    (true, _) => shard_end.as_slice(),
+                    (false, true) => end,
+                    (false, false) => std::cmp::min(end, shard_end.as_slice()),
+                };
+                if !end.is_empty() && start >= end {
+                    return Ok(());
+                }
+                let start_key = keys::raw(collection_id, None, start);
+                let end_key = if end.is_empty() {
+                    keys::prefix_end(&keys::raw(collection_id, None, &[]))
+                } else {
+                    keys::raw(collection_id, None, end)
+                };
+                (start_key, end_key)
+            }
+        };
+        wb.delete_range(start, end);
+
+        Ok(())
+    }
+
+    /// Delete all data of the corresponding shard, includes all versions and tombstones.
+    pub fn delete_shard(&self, wb: &mut WriteBatch, shard_id: u64) -> Result<()> {
+        let desc = self.shard_desc(shard_id)?;
+        debug_assert_ne!(desc.collection_id, LOCAL_COLLECTION_ID);
+        let (start, end) = keys::shard_range(&desc);
+        wb.delete_range(start, end);
+        Ok(())
+    }
+
+    /// Return the approximate statistics of the persisted data of the corresponding shard.
+    ///
+    /// The number of entries isn't recorded by range, so it is estimated with the proportion of
+    /// the shard size in the column family.
+    pub fn approximate_shard_stats(&self, shard_id: u64) -> Result<ShardStats> {
+        let desc = self.shard_desc(shard_id)?;
+        debug_assert_ne!(desc.collection_id, LOCAL_COLLECTION_ID);
+
+        let cf_handle = self.cf_handle();
+        let (start, end) = keys::shard_range(&desc);
+        let ranges = [rocksdb::Range::new(&start, &end)];
+        let approximate_size = self
+            .raw_db
+            .get_approximate_sizes_cf(&cf_handle, &ranges)
+            .first()
+            .cloned()
+            .unwrap_or_default();
+        let total_size = self
+            .raw_db
+            .property_int_value_cf(&cf_handle, "rocksdb.live-sst-files-size")?
+            .unwrap_or_default();
+        let total_keys = self
+            .raw_db
+            .property_int_value_cf(&cf_handle, "rocksdb.estimate-num-keys")?
+            .unwrap_or_default();
+        let approximate_keys = if total_size == 0 {
+            0
+        } else {
+            let keys = total_keys as u128 * approximate_size as u128 / total_size as u128;
+            std::cmp::min(keys as u64, total_keys)
+        };
+
+        Ok(ShardStats {
+            shard_id,
+            collection_id: desc.collection_id,
+            approximate_keys,
+            approximate_size,
+        })
+    }
+
     pub fn delete(
         &self,
         wb: &mut WriteBatch,
@@ -353,6 +508,11 @@ impl GroupEngine {
         };
         for wb in wbs {
             wb.inner.iterate(&mut decorator);
+            for range in &wb.delete_ranges {
+                decorator
+                    .wb
+                    .delete_range_cf(&cf_handle, &range.start, &range.end);
+            }
         }
         states.write(&mut inner_wb, &cf_handle);
 
@@ -387,13 +547,32 @@ impl GroupEngine {
             SnapshotMode::Start {
                 start_key: Some(start_key),
             } => {
-                debug_assert!(shard::belong_to(&desc, start_key));
+                // The user keys of a hash shard are ordered under its slot, so it could seek to any
+                // key, even the one which doesn't belong to the shard.
+                debug_assert!(shard::slot(&desc).is_some() || shard::belong_to(&desc, start_key));
                 keys::raw(collection_id, shard::slot(&desc), start_key)
             }
             SnapshotMode::Start { start_key: None } => {
                 // An empty key with hash slot is equivalent to range start key.
                 keys::raw(collection_id, None, &shard::start_key(&desc))
             }
+            SnapshotMode::End {
+                end_key: Some(end_key),
+            } if !end_key.is_empty() => {
+                debug_assert!(shard::slot(&desc).is_none());
+                keys::raw(collection_id, None, end_key)
+            }
+            SnapshotMode::End { .. } => match shard::slot(&desc) {
+                Some(slot) => keys::prefix_end(&keys::raw(collection_id, Some(slot), &[])),
+                None => {
+                    let end_key = shard::end_key(&desc);
+                    if end_key.is_empty() {
+                        keys::prefix_end(&keys::raw(collection_id, None, &[]))
+                    } else {
+                        keys::raw(collection_id, None, &end_key)
+                    }
+                }
+            },
             SnapshotMode::Key { key } => {
                 debug_assert!(shard::belong_to(&desc, key));
                 keys::raw(collection_id, shard::slot(&desc), key)
@@ -403,7 +582,13 @@ impl GroupEngine {
                 keys::raw(collection_id, shard::slot(&desc), key)
             }
         };
-        let inner_mode = IteratorMode::From(&key, Direction::Forward);
+        let direction = match &mode {
+            SnapshotMode::End { .. } => Direction::Reverse,
+            _ => Direction::Forward,
+        };
+        // The reverse iterator seeks for prev, the key which is less than or equals to the end
+        // key. There is no version equals to `u64::MAX`, so the end key itself is excluded.
+        let inner_mode = IteratorMode::From(&key, direction);
         let iter = self
             .raw_db
             .iterator_cf_opt(&self.cf_handle(), opts, inner_mode);
@@ -420,6 +605,13 @@ impl GroupEngine {
         RawIterator::new(iter)
     }
 
+    /// Compute the checksum of all data of the group engine, including the local states, so the
+    /// replicas at the same applied index should have the same checksum. The replication log is
+    /// excluded, since the replication might be only enabled on parts of nodes.
+    pub fn checksum(&self) -> Result<u32> {
+        self.raw_iter()?.checksum()
+    }
+
     /// Ingest data into group engine.
     pub fn ingest<P: AsRef<Path>>(&self, files: Vec<P>) -> Result<()> {
         use rocksdb::IngestExternalFileOptions;
@@ -439,6 +631,113 @@ impl GroupEngine {
         Ok(())
     }
 
+    /// Drop all user data of the group engine, only the local states are kept. A witness holds
+    /// no data, so it drops the data ingested from snapshots.
+    pub fn drop_user_data(&self) -> Result<()> {
+        let start = keys::prefix_end(&LOCAL_COLLECTION_ID.to_le_bytes());
+        let end = keys::prefix_end(&u64::MAX.to_le_bytes());
+        let mut wb = rocksdb::WriteBatch::default();
+        wb.delete_range_cf(&self.cf_handle(), start, end);
+        self.raw_db.write(wb)?;
+        Ok(())
+    }
+
+    /// Like `drop_user_data`, but the deletion is recorded in the write batch, so that it is
+    /// committed with the other states atomically.
+    pub fn delete_user_data(&self, wb: &mut WriteBatch) {
+        let start = keys::prefix_end(&LOCAL_COLLECTION_ID.to_le_bytes());
+        let end = keys::prefix_end(&u64::MAX.to_le_bytes());
+        wb.delete_range(start, end);
+    }
+
+    /// Decode the writes of user data with the specified version from the write batch, see
+    /// [`WriteBatch::user_writes`].
+    pub fn user_writes(&self, wb: &WriteBatch, version: u64) -> Vec<UserWrite> {
+        wb.user_writes(&self.descriptor(), version)
+    }
+
+    /// Record the user writes applied at the index in the replication log, which is truncated
+    /// once the writes are shipped to the standby cluster.
+    pub fn append_replication_log(&self, wb: &mut WriteBatch, index: u64, writes: &[UserWrite]) {
+        let log = ReplicationLog {
+            writes: writes.iter().map(ReplicationWrite::from).collect(),
+        };
+        wb.put(keys::replication_log(index), log.encode_to_vec());
+        // The last index of each shard is recorded, see `has_replication_log_of_shard`.
+        let shards = writes
+            .iter()
+            .map(UserWrite::shard_id)
+            .collect::<HashSet<_>>();
+        for shard_id in shards {
+            wb.put(
+                keys::replication_shard(shard_id),
+                index.to_be_bytes().to_vec(),
+            );
+        }
+    }
+
+    /// Read at most `limit` replication logs since the index, in the order of index.
+    pub fn replication_logs(
+        &self,
+        start_index: u64,
+        limit: usize,
+    ) -> Result<Vec<(u64, Vec<UserWrite>)>> {
+        use rocksdb::{Direction, IteratorMode, ReadOptions};
+
+        let mut opts = ReadOptions::default();
+        opts.set_iterate_upper_bound(keys::prefix_end(&keys::replication_log_prefix()));
+        let start_key = keys::replication_log(start_index);
+        let iter = self.raw_db.iterator_cf_opt(
+            &self.cf_handle(),
+            opts,
+            IteratorMode::From(&start_key, Direction::Forward),
+        );
+        let mut logs = Vec::default();
+        for item in iter.take(limit) {
+            let (key, value) = item?;
+            let log = ReplicationLog::decode(value.as_ref())?;
+            let writes = log
+                .writes
+                .into_iter()
+                .map(UserWrite::try_from)
+                .collect::<Result<_>>()?;
+            logs.push((keys::replication_log_index(&key), writes));
+        }
+        Ok(logs)
+    }
+
+    /// Return whether any write of the shard is waiting to be shipped in the replication log. It
+    /// compares the last index of the shard with the truncated index, instead of reading the log.
+    pub fn has_replication_log_of_shard(&self, shard_id: u64) -> Result<bool> {
+        let Some(last_index) = self.read_index(keys::replication_shard(shard_id))? else {
+            return Ok(false);
+        };
+        let shipped_index = self
+            .read_index(keys::replication_shipped())?
+            .unwrap_or_default();
+        Ok(last_index > shipped_index)
+    }
+
+    /// Truncate the replication logs up to the index, inclusive.
+    pub fn truncate_replication_log(&self, wb: &mut WriteBatch, index: u64) {
+        wb.delete_range(
+            keys::replication_log(0),
+            keys::replication_log(index.saturating_add(1)),
+        );
+        wb.put(keys::replication_shipped(), index.to_be_bytes().to_vec());
+    }
+
+    fn read_index(&self, key: Vec<u8>) -> Result<Option<u64>> {
+        let Some(value) = self.raw_db.get_pinned_cf(&self.cf_handle(), key)? else {
+            return Ok(None);
+        };
+        let buf = value
+            .as_ref()
+            .try_into()
+            .map_err(|_| Error::InvalidData("the replication index is corrupted".into()))?;
+        Ok(Some(u64::from_be_bytes(buf)))
+    }
+
     pub fn apply_core_states(
         &self,
         descriptor: Option<GroupDesc>,
@@ -520,6 +819,24 @@ impl<'a> RawIterator<'a> {
     pub fn descriptor(&self) -> &GroupDesc {
         &self.descriptor
     }
+
+    /// Compute the checksum of all data of the view pinned by this iterator, see
+    /// `GroupEngine::checksum`.
+    pub fn checksum(self) -> Result<u32> {
+        let replication_prefix = keys::replication_prefix();
+        let mut hasher = crc32fast::Hasher::new();
+        for item in self {
+            let (key, value) = item?;
+            if key.starts_with(&replication_prefix) {
+                continue;
+            }
+            hasher.update(&(key.len() as u64).to_le_bytes());
+            hasher.update(&key);
+            hasher.update(&(value.len() as u64).to_le_bytes());
+            hasher.update(&value);
+        }
+        Ok(hasher.finalize())
+    }
 }
 
 impl<'a> Iterator for RawIterator<'a> {
@@ -540,6 +857,7 @@ impl<'a> Snapshot<'a> {
         desc: &ShardDesc,
     ) -> Self {
         let expect_slot = shard::slot(desc);
+        let reverse = matches!(snapshot_mode, SnapshotMode::End { .. });
 
         let range = match snapshot_mode {
             SnapshotMode::Key { key } => Some(SnapshotRange::Target {
@@ -562,6 +880,17 @@ impl<'a> Snapshot<'a> {
                     .unwrap_or_else(|| shard::start_key(desc)),
                 end: shard::end_key(desc),
             }),
+            SnapshotMode::End { .. } if expect_slot.is_some() => Some(SnapshotRange::HashRange {
+                slot: expect_slot.unwrap(),
+                start: Vec::default(),
+            }),
+            SnapshotMode::End { end_key } => Some(SnapshotRange::Range {
+                start: shard::start_key(desc),
+                end: end_key
+                    .filter(|k| !k.is_empty())
+                    .map(ToOwned::to_owned)
+                    .unwrap_or_else(|| shard::end_key(desc)),
+            }),
         };
 
         Snapshot {
@@ -569,9 +898,12 @@ impl<'a> Snapshot<'a> {
             range,
             core: RefCell::new(SnapshotCore {
                 expect_slot,
+                reverse,
                 db_iter,
                 current_key: None,
                 cached_entry: None,
+                reversed_entries: Vec::default(),
+                peeked_entry: None,
             }),
         }
     }
@@ -632,6 +964,50 @@ impl<'a> Snapshot<'a> {
 
 impl<'a> SnapshotCore<'a> {
     fn next_entry(&mut self, collection_id: u64) -> Option<Result<()>> {
+        if !self.reverse {
+            return match self.read_entry(collection_id)? {
+                Ok(entry) => {
+                    self.cached_entry = Some(entry);
+                    Some(Ok(()))
+                }
+                Err(err) => Some(Err(err)),
+            };
+        }
+
+        if self.reversed_entries.is_empty() {
+            if let Err(err) = self.fill_reversed_entries(collection_id)? {
+                return Some(Err(err));
+            }
+        }
+        self.cached_entry = self.reversed_entries.pop();
+        Some(Ok(()))
+    }
+
+    /// Read all versions of the next user key in reverse mode.
+    fn fill_reversed_entries(&mut self, collection_id: u64) -> Option<Result<()>> {
+        let first = match self.peeked_entry.take() {
+            Some(entry) => entry,
+            None => match self.read_entry(collection_id)? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+        let user_key = first.user_key().to_owned();
+        self.reversed_entries.push(first);
+        while let Some(entry) = self.read_entry(collection_id) {
+            match entry {
+                Ok(entry) if entry.user_key() == user_key => self.reversed_entries.push(entry),
+                Ok(entry) => {
+                    self.peeked_entry = Some(entry);
+                    break;
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        Some(Ok(()))
+    }
+
+    fn read_entry(&mut self, collection_id: u64) -> Option<Result<MvccEntry>> {
         let (key, value) = match self.db_iter.next()? {
             Ok(v) => v,
             Err(err) => return Some(Err(err.into())),
@@ -642,8 +1018,7 @@ impl<'a> SnapshotCore<'a> {
             return None;
         }
 
-        self.cached_entry = Some(MvccEntry::new(self.expect_slot.is_some(), key, value));
-        Some(Ok(()))
+        Some(Ok(MvccEntry::new(self.expect_slot.is_some(), key, value)))
     }
 
     #[inline]
@@ -672,7 +1047,7 @@ impl<'a, 'b> Iterator for MvccIterator<'a, 'b> {
 }
 
 impl MvccEntry {
-    fn new(with_slot: bool, key: Box<[u8]>, value: Box<[u8]>) -> Self {
+    pub(crate) fn new(with_slot: bool, key: Box<[u8]>, value: Box<[u8]>) -> Self {
         let (user_key, slot) = keys::revert_mvcc_key(&key, with_slot);
         MvccEntry {
             key,
@@ -682,6 +1057,27 @@ impl MvccEntry {
         }
     }
 
+    /// Return whether the key value pair could be decoded by `MvccEntry::new`, it is used to
+    /// check the data which might be corrupted.
+    pub(crate) fn is_well_formed(with_slot: bool, key: &[u8], value: &[u8]) -> bool {
+        const L: usize = core::mem::size_of::<u64>();
+        let slot_len = if with_slot {
+            core::mem::size_of::<u32>()
+        } else {
+            0
+        };
+        let encoded_len = match key.len().checked_sub(2 * L + slot_len) {
+            Some(len) if len > 0 && len % 9 == 0 => len,
+            _ => return false,
+        };
+        let encoded_user_key = &key[(L + slot_len)..(L + slot_len + encoded_len)];
+        encoded_user_key.chunks(9).all(|group| group[8] >= b'0')
+            && matches!(
+                value.first(),
+                Some(&values::DATA) | Some(&values::TOMBSTONE)
+            )
+    }
+
     #[inline]
     pub fn slot(&self) -> Option<u32> {
         self.slot
@@ -745,10 +1141,16 @@ impl<'a> Default for SnapshotMode<'a> {
     }
 }
 
-mod keys {
+pub(crate) mod keys {
+    use engula_api::{server::v1::ShardDesc, shard};
+
     const APPLY_STATE: &[u8] = b"APPLY_STATE";
     const DESCRIPTOR: &[u8] = b"DESCRIPTOR";
     const MIGRATE_STATE: &[u8] = b"MIGRATE_STATE";
+    const REPLICATION: &[u8] = b"REPLICATION_";
+    const REPLICATION_LOG: &[u8] = b"REPLICATION_LOG";
+    const REPLICATION_SHARD: &[u8] = b"REPLICATION_SHARD";
+    const REPLICATION_SHIPPED: &[u8] = b"REPLICATION_SHIPPED";
 
     #[inline]
     pub fn raw(collection_id: u64, slot: Option<u32>, key: &[u8]) -> Vec<u8> {
@@ -797,6 +1199,14 @@ mod keys {
         buf
     }
 
+    /// Return the collection id of the raw key.
+    #[inline]
+    pub fn collection_id(key: &[u8]) -> u64 {
+        let mut buf = [0u8; core::mem::size_of::<u64>()];
+        buf.copy_from_slice(&key[..core::mem::size_of::<u64>()]);
+        u64::from_le_bytes(buf)
+    }
+
     pub fn revert_mvcc_key(key: &[u8], with_slot: bool) -> (Vec<u8>, Option<u32>) {
         use std::io::{Cursor, Read};
 
@@ -827,6 +1237,53 @@ mod keys {
         (buf, slot)
     }
 
+    /// Return the version of the mvcc key.
+    #[inline]
+    pub fn mvcc_version(key: &[u8]) -> u64 {
+        const L: usize = core::mem::size_of::<u64>();
+        if key.len() < 2 * L {
+            return 0;
+        }
+        let mut buf = [0u8; L];
+        buf.copy_from_slice(&key[key.len() - L..]);
+        !u64::from_be_bytes(buf)
+    }
+
+    /// Return the raw key range `[start, end)` of all data of the shard.
+    pub fn shard_range(desc: &ShardDesc) -> (Vec<u8>, Vec<u8>) {
+        let collection_id = desc.collection_id;
+        match shard::slot(desc) {
+            Some(slot) => {
+                let prefix = raw(collection_id, Some(slot), &[]);
+                let end = prefix_end(&prefix);
+                (prefix, end)
+            }
+            None => {
+                let start = raw(collection_id, None, &shard::start_key(desc));
+                let end = shard::end_key(desc);
+                let end = if end.is_empty() {
+                    prefix_end(&raw(collection_id, None, &[]))
+                } else {
+                    raw(collection_id, None, &end)
+                };
+                (start, end)
+            }
+        }
+    }
+
+    /// Return the smallest key which is larger than all keys with the specified prefix.
+    pub fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+        let mut end = prefix.to_owned();
+        while let Some(last) = end.pop() {
+            if last != u8::MAX {
+                end.push(last + 1);
+                return end;
+            }
+        }
+        // All bytes are 0xFF, there is no upper bound.
+        vec![u8::MAX; prefix.len() + 1]
+    }
+
     #[inline]
     pub fn apply_state() -> Vec<u8> {
         let mut buf = Vec::with_capacity(core::mem::size_of::<u64>() + APPLY_STATE.len());
@@ -850,6 +1307,59 @@ mod keys {
         buf.extend_from_slice(MIGRATE_STATE);
         buf
     }
+
+    /// The prefix of all replication states, they are not a part of checksum.
+    #[inline]
+    pub fn replication_prefix() -> Vec<u8> {
+        let mut buf = Vec::with_capacity(core::mem::size_of::<u64>() + REPLICATION.len());
+        buf.extend_from_slice(super::LOCAL_COLLECTION_ID.to_le_bytes().as_slice());
+        buf.extend_from_slice(REPLICATION);
+        buf
+    }
+
+    #[inline]
+    pub fn replication_log_prefix() -> Vec<u8> {
+        let mut buf = Vec::with_capacity(2 * core::mem::size_of::<u64>() + REPLICATION_LOG.len());
+        buf.extend_from_slice(super::LOCAL_COLLECTION_ID.to_le_bytes().as_slice());
+        buf.extend_from_slice(REPLICATION_LOG);
+        buf
+    }
+
+    /// The replication logs are ordered by index, see `GroupEngine::append_replication_log`.
+    #[inline]
+    pub fn replication_log(index: u64) -> Vec<u8> {
+        let mut buf = replication_log_prefix();
+        buf.extend_from_slice(index.to_be_bytes().as_slice());
+        buf
+    }
+
+    /// The last index of the replication logs which contain the writes of the shard.
+    #[inline]
+    pub fn replication_shard(shard_id: u64) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(2 * core::mem::size_of::<u64>() + REPLICATION_SHARD.len());
+        buf.extend_from_slice(super::LOCAL_COLLECTION_ID.to_le_bytes().as_slice());
+        buf.extend_from_slice(REPLICATION_SHARD);
+        buf.extend_from_slice(shard_id.to_be_bytes().as_slice());
+        buf
+    }
+
+    /// The index up to which the replication logs are shipped and truncated.
+    #[inline]
+    pub fn replication_shipped() -> Vec<u8> {
+        let mut buf = Vec::with_capacity(core::mem::size_of::<u64>() + REPLICATION_SHIPPED.len());
+        buf.extend_from_slice(super::LOCAL_COLLECTION_ID.to_le_bytes().as_slice());
+        buf.extend_from_slice(REPLICATION_SHIPPED);
+        buf
+    }
+
+    /// Return the index of the replication log key.
+    #[inline]
+    pub fn replication_log_index(key: &[u8]) -> u64 {
+        const L: usize = core::mem::size_of::<u64>();
+        let mut buf = [0u8; L];
+        buf.copy_from_slice(&key[key.len() - L..]);
+        u64::from_be_bytes(buf)
+    }
 }
 
 mod values {
@@ -879,13 +1389,234 @@ impl<'a, 'b> rocksdb::WriteBatchIterator for ColumnFamilyDecorator<'a, 'b> {
     }
 }
 
+impl UserWrite {
+    #[inline]
+    pub fn collection_id(&self) -> u64 {
+        match self {
+            UserWrite::Put { collection_id, .. }
+            | UserWrite::Delete { collection_id, .. }
+            | UserWrite::DeleteRange { collection_id, .. }
+            | UserWrite::TruncateShard { collection_id, .. } => *collection_id,
+        }
+    }
+
+    #[inline]
+    pub fn shard_id(&self) -> u64 {
+        match self {
+            UserWrite::Put { shard_id, .. }
+            | UserWrite::Delete { shard_id, .. }
+            | UserWrite::DeleteRange { shard_id, .. }
+            | UserWrite::TruncateShard { shard_id, .. } => *shard_id,
+        }
+    }
+}
+
+impl From<&UserWrite> for ReplicationWrite {
+    fn from(write: &UserWrite) -> Self {
+        use replication_write::Kind;
+
+        let (kind, key, value, hash) = match write {
+            UserWrite::Put { key, value, .. } => (Kind::Put, key.clone(), value.clone(), None),
+            UserWrite::Delete { key, .. } => (Kind::Delete, key.clone(), vec![], None),
+            UserWrite::DeleteRange { start, end, .. } => {
+                (Kind::DeleteRange, start.clone(), end.clone(), None)
+            }
+            UserWrite::TruncateShard { hash, .. } => {
+                (Kind::TruncateShard, vec![], vec![], Some(hash.clone()))
+            }
+        };
+        ReplicationWrite {
+            kind: kind as i32,
+            collection_id: write.collection_id(),
+            shard_id: write.shard_id(),
+            key,
+            value,
+            hash,
+        }
+    }
+}
+
+impl TryFrom<ReplicationWrite> for UserWrite {
+    type Error = Error;
+
+    fn try_from(write: ReplicationWrite) -> Result<Self> {
+        use replication_write::Kind;
+
+        let ReplicationWrite {
+            kind,
+            collection_id,
+            shard_id,
+            key,
+            value,
+            hash,
+        } = write;
+        let Some(kind) = Kind::from_i32(kind) else {
+            return Err(Error::InvalidData(format!(
+                "unknown replication write kind {kind}"
+            )));
+        };
+        Ok(match kind {
+            Kind::Put => UserWrite::Put {
+                collection_id,
+                shard_id,
+                key,
+                value,
+            },
+            Kind::Delete => UserWrite::Delete {
+                collection_id,
+                shard_id,
+                key,
+            },
+            Kind::DeleteRange => UserWrite::DeleteRange {
+                collection_id,
+                shard_id,
+                start: key,
+                end: value,
+            },
+            Kind::TruncateShard => UserWrite::TruncateShard {
+                collection_id,
+                shard_id,
+                hash: hash.unwrap_or_default(),
+            },
+        })
+    }
+}
+
+impl rocksdb::WriteBatchIterator for RawWriteCollector {
+    fn put(&mut self, key: Box<[u8]>, value: Box<[u8]>) {
+        self.writes.push((key, Some(value)));
+    }
+
+    fn delete(&mut self, key: Box<[u8]>) {
+        self.writes.push((key, None));
+    }
+}
+
 impl WriteBatch {
     #[inline]
     pub fn new(content: &[u8]) -> Self {
         WriteBatch {
             inner: rocksdb::WriteBatch::new(content),
+            delete_ranges: Vec::default(),
+        }
+    }
+
+    /// Build write batch from the serialized representation.
+    pub fn from_rep(rep: WriteBatchRep) -> Self {
+        WriteBatch {
+            inner: rocksdb::WriteBatch::new(&rep.data),
+            delete_ranges: rep.delete_ranges,
+        }
+    }
+
+    /// Return the serialized representation of this write batch.
+    pub fn rep(&self) -> WriteBatchRep {
+        WriteBatchRep {
+            data: self.inner.data().to_owned(),
+            delete_ranges: self.delete_ranges.clone(),
         }
     }
+
+    /// Delete the raw keys in range `[start, end)`.
+    #[inline]
+    pub fn delete_range(&mut self, start: Vec<u8>, end: Vec<u8>) {
+        self.delete_ranges.push(DeleteRange { start, end });
+    }
+
+    /// Decode the writes of user data with the specified version, a tombstone is decoded as a
+    /// deletion and the deletion of a hash shard is decoded as truncating the shard. The writes
+    /// which don't belong to any shard of the group are skipped, see `GroupEngine::user_writes`.
+    fn user_writes(&self, desc: &GroupDesc, version: u64) -> Vec<UserWrite> {
+        let mut collector = RawWriteCollector::default();
+        self.inner.iterate(&mut collector);
+
+        let mut writes = Vec::with_capacity(collector.writes.len() + self.delete_ranges.len());
+        for (raw_key, value) in collector.writes {
+            let collection_id = keys::collection_id(&raw_key);
+            if collection_id == LOCAL_COLLECTION_ID || keys::mvcc_version(&raw_key) != version {
+                continue;
+            }
+            let (shard_id, key) = match find_shard_by_key(desc, collection_id, &raw_key) {
+                Some(v) => v,
+                None => continue,
+            };
+            let write = match value {
+                Some(value) if value.first() == Some(&values::DATA) => UserWrite::Put {
+                    collection_id,
+                    shard_id,
+                    key,
+                    value: value[1..].to_vec(),
+                },
+                _ => UserWrite::Delete {
+                    collection_id,
+                    shard_id,
+                    key,
+                },
+            };
+            writes.push(write);
+        }
+
+        for DeleteRange { start, end } in &self.delete_ranges {
+            let collection_id = keys::collection_id(start);
+            if collection_id == LOCAL_COLLECTION_ID {
+                continue;
+            }
+            let shard = desc.shards.iter().find(|s| {
+                let (shard_start, shard_end) = keys::shard_range(s);
+                s.collection_id == collection_id && shard_start <= *start && *end <= shard_end
+            });
+            let shard = match shard {
+                Some(shard) => shard,
+                None => continue,
+            };
+            if let Some(shard_desc::Partition::Hash(hash)) = shard.partition.as_ref() {
+                // A hash shard could only be deleted entirely.
+                writes.push(UserWrite::TruncateShard {
+                    collection_id,
+                    shard_id: shard.id,
+                    hash: hash.clone(),
+                });
+                continue;
+            }
+            let prefix = keys::raw(collection_id, None, &[]);
+            let start = if *start == prefix {
+                vec![]
+            } else {
+                keys::revert_mvcc_key(start, false).0
+            };
+            let end = if *end == keys::prefix_end(&prefix) {
+                vec![]
+            } else {
+                keys::revert_mvcc_key(end, false).0
+            };
+            writes.push(UserWrite::DeleteRange {
+                collection_id,
+                shard_id: shard.id,
+                start,
+                end,
+            });
+        }
+        writes
+    }
+}
+
+/// Find the shard of the raw mvcc key in the group, and return the shard id and the user key.
+fn find_shard_by_key(
+    desc: &GroupDesc,
+    collection_id: u64,
+    raw_key: &[u8],
+) -> Option<(u64, Vec<u8>)> {
+    // The keys of a collection are prefixed with slot if it is hash partitioned.
+    let with_slot = desc
+        .shards
+        .iter()
+        .find(|s| s.collection_id == collection_id)
+        .map(|s| shard::slot(s).is_some())?;
+    let (key, _) = keys::revert_mvcc_key(raw_key, with_slot);
+    desc.shards
+        .iter()
+        .find(|s| s.collection_id == collection_id && shard::belong_to(s, &key))
+        .map(|s| (s.id, key))
 }
 
 impl Deref for WriteBatch {
@@ -1434,6 +2165,131 @@ mod tests {
         assert!(user_data_iter.next().is_none());
     }
 
+    #[test]
+    fn delete_range() {
+        fn user_keys(group_engine: &GroupEngine) -> Vec<Vec<u8>> {
+            let mut snapshot = group_engine.snapshot(1, SnapshotMode::default()).unwrap();
+            snapshot
+                .iter()
+                .map(|iter| {
+                    let mut iter = iter.unwrap();
+                    iter.next().unwrap().unwrap().user_key().to_owned()
+                })
+                .collect()
+        }
+
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let group_engine = create_engine_with_range(executor, 1, 1, b"b".to_vec(), b"f".to_vec());
+        let mut wb = WriteBatch::default();
+        group_engine.put(&mut wb, 1, b"b", b"", 123).unwrap();
+        group_engine.put(&mut wb, 1, b"c", b"", 123).unwrap();
+        group_engine.put(&mut wb, 1, b"c", b"", 124).unwrap();
+        group_engine.put(&mut wb, 1, b"cc", b"", 123).unwrap();
+        group_engine.tombstone(&mut wb, 1, b"d", 124).unwrap();
+        group_engine.put(&mut wb, 1, b"e", b"", 123).unwrap();
+        group_engine
+            .commit(wb, WriteStates::default(), false)
+            .unwrap();
+
+        // The range deletion should survive the serialized representation.
+        let mut wb = WriteBatch::default();
+        group_engine.delete_range(&mut wb, 1, b"c", b"e").unwrap();
+        let wb = WriteBatch::from_rep(wb.rep());
+        group_engine
+            .commit(wb, WriteStates::default(), false)
+            .unwrap();
+        assert_eq!(user_keys(&group_engine), vec![b"b".to_vec(), b"e".to_vec()]);
+
+        // The empty range is clamped by the shard range.
+        let mut wb = WriteBatch::default();
+        group_engine.delete_range(&mut wb, 1, b"", b"").unwrap();
+        group_engine
+            .commit(wb, WriteStates::default(), false)
+            .unwrap();
+        assert!(user_keys(&group_engine).is_empty());
+    }
+
+    #[test]
+    fn approximate_shard_stats() {
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let group_engine = create_engine_with_range(executor, 1, 1, vec![], b"m".to_vec());
+
+        let stats = group_engine.approximate_shard_stats(1).unwrap();
+        assert_eq!(stats.collection_id, 1);
+        assert_eq!(stats.approximate_keys, 0);
+        assert_eq!(stats.approximate_size, 0);
+
+        let mut wb = WriteBatch::default();
+        for i in 0..1000 {
+            let key = format!("key-{i:04}");
+            group_engine
+                .put(&mut wb, 1, key.as_bytes(), &[0u8; 128], 1)
+                .unwrap();
+        }
+        group_engine
+            .commit(wb, WriteStates::default(), false)
+            .unwrap();
+        group_engine
+            .raw_db
+            .flush_cf(&group_engine.cf_handle())
+            .unwrap();
+
+        let stats = group_engine.approximate_shard_stats(1).unwrap();
+        assert!(stats.approximate_keys > 0);
+        assert!(stats.approximate_size > 0);
+    }
+
+    #[test]
+    fn iterate_in_reverse() {
+        fn collect(group_engine: &GroupEngine, mode: SnapshotMode) -> Vec<(Vec<u8>, Vec<u64>)> {
+            let mut snapshot = group_engine.snapshot(1, mode).unwrap();
+            snapshot
+                .iter()
+                .map(|iter| {
+                    let entries = iter.unwrap().map(Result::unwrap).collect::<Vec<_>>();
+                    let versions = entries.iter().map(MvccEntry::version).collect();
+                    (entries[0].user_key().to_owned(), versions)
+                })
+                .collect()
+        }
+
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let group_engine = create_engine_with_range(executor, 1, 1, b"b".to_vec(), b"f".to_vec());
+        let mut wb = WriteBatch::default();
+        group_engine.put(&mut wb, 1, b"b", b"", 123).unwrap();
+        group_engine.put(&mut wb, 1, b"c", b"", 123).unwrap();
+        group_engine.put(&mut wb, 1, b"c", b"", 124).unwrap();
+        group_engine.tombstone(&mut wb, 1, b"c", 125).unwrap();
+        group_engine.put(&mut wb, 1, b"cc", b"", 123).unwrap();
+        group_engine.put(&mut wb, 1, b"e", b"", 123).unwrap();
+        group_engine
+            .commit(wb, WriteStates::default(), false)
+            .unwrap();
+
+        let expect = vec![
+            (b"e".to_vec(), vec![123]),
+            (b"cc".to_vec(), vec![123]),
+            (b"c".to_vec(), vec![125, 124, 123]),
+            (b"b".to_vec(), vec![123]),
+        ];
+        let mode = SnapshotMode::End { end_key: None };
+        assert_eq!(collect(&group_engine, mode), expect);
+
+        // The end key is exclusive.
+        let mode = SnapshotMode::End {
+            end_key: Some(b"e"),
+        };
+        assert_eq!(collect(&group_engine, mode), expect[1..]);
+
+        let mode = SnapshotMode::End {
+            end_key: Some(b"ca"),
+        };
+        assert_eq!(collect(&group_engine, mode), expect[2..]);
+    }
+
     #[test]
     fn iterate_in_hash_range() {
         let executor_owner = ExecutorOwner::new(1);
@@ -1441,8 +2297,9 @@ mod tests {
         let group_engine = create_engine(executor, 1, 1);
 
         let slots = 256;
-        let shard_1_slot_id = shard::key_slot(b"a", slots);
-        let shard_2_slot_id = shard::key_slot(b"b", slots);
+        let algorithm = engula_api::v1::HashAlgorithm::Xxhash64 as i32;
+        let shard_1_slot_id = shard::key_slot(b"a", slots, algorithm).unwrap();
+        let shard_2_slot_id = shard::key_slot(b"b", slots, algorithm).unwrap();
         debug_assert_ne!(shard_1_slot_id, shard_2_slot_id);
 
         // Add new shard
@@ -1458,6 +2315,8 @@ mod tests {
                         partition: Some(Partition::Hash(HashPartition {
                             slot_id: shard_1_slot_id,
                             slots,
+                            algorithm,
+                            target_slots: 0,
                         })),
                     },
                     ShardDesc {
@@ -1466,6 +2325,8 @@ mod tests {
                         partition: Some(Partition::Hash(HashPartition {
                             slot_id: shard_2_slot_id,
                             slots,
+                            algorithm,
+                            target_slots: 0,
                         })),
                     },
                 ],
@@ -1504,6 +2365,62 @@ mod tests {
         assert!(user_data_iter.next().is_none());
     }
 
+    #[test]
+    fn delete_shard() {
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let group_engine = create_engine(executor, 1, 1);
+
+        use shard_desc::*;
+        let algorithm = engula_api::v1::HashAlgorithm::Xxhash64 as i32;
+        let shards = (1..=2)
+            .map(|id| ShardDesc {
+                id,
+                collection_id: 1,
+                partition: Some(Partition::Hash(HashPartition {
+                    slot_id: id as u32,
+                    slots: 4,
+                    algorithm,
+                    target_slots: 0,
+                })),
+            })
+            .collect();
+        let states = WriteStates {
+            descriptor: Some(GroupDesc {
+                id: 1,
+                shards,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        group_engine
+            .commit(WriteBatch::default(), states, false)
+            .unwrap();
+
+        let mut wb = WriteBatch::default();
+        group_engine.put(&mut wb, 1, b"a", b"", 123).unwrap();
+        group_engine.tombstone(&mut wb, 1, b"a", 124).unwrap();
+        group_engine.put(&mut wb, 2, b"b", b"123", 123).unwrap();
+        group_engine
+            .commit(wb, WriteStates::default(), false)
+            .unwrap();
+
+        let mut wb = WriteBatch::default();
+        group_engine.delete_shard(&mut wb, 1).unwrap();
+        group_engine
+            .commit(wb, WriteStates::default(), false)
+            .unwrap();
+
+        let mut snapshot = group_engine.snapshot(1, SnapshotMode::default()).unwrap();
+        assert!(snapshot.iter().next().is_none());
+
+        // The data of other shards are not affected.
+        let mut snapshot = group_engine.snapshot(2, SnapshotMode::default()).unwrap();
+        let mut mvcc_key_iter = snapshot.iter().next().unwrap().unwrap();
+        let entry = mvcc_key_iter.next().unwrap().unwrap();
+        assert_eq!(entry.user_key(), b"b");
+    }
+
     #[test]
     fn cf_id_irrelevant_write_batch() {
         let executor_owner = ExecutorOwner::new(1);
@@ -1518,4 +2435,216 @@ mod tests {
 
         engine_2.commit(wb, WriteStates::default(), false).unwrap();
     }
+
+    #[test]
+    fn checksum() {
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let engine_1 = create_engine_with_range(executor.clone(), 1, 1, vec![], vec![]);
+        let engine_2 = create_engine_with_range(executor, 1, 1, vec![], vec![]);
+        assert_eq!(engine_1.checksum().unwrap(), engine_2.checksum().unwrap());
+
+        for engine in [&engine_1, &engine_2] {
+            let mut wb = WriteBatch::default();
+            engine.put(&mut wb, 1, b"a", b"123", 123).unwrap();
+            engine.commit(wb, WriteStates::default(), false).unwrap();
+        }
+        assert_eq!(engine_1.checksum().unwrap(), engine_2.checksum().unwrap());
+
+        let mut wb = WriteBatch::default();
+        engine_2.put(&mut wb, 1, b"b", b"123", 123).unwrap();
+        engine_2.commit(wb, WriteStates::default(), false).unwrap();
+        assert_ne!(engine_1.checksum().unwrap(), engine_2.checksum().unwrap());
+    }
+
+    #[test]
+    fn drop_user_data() {
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let engine_1 = create_engine_with_range(executor.clone(), 1, 1, vec![], vec![]);
+        let engine_2 = create_engine_with_range(executor, 1, 1, vec![], vec![]);
+
+        let mut wb = WriteBatch::default();
+        engine_1.put(&mut wb, 1, b"a", b"123", 123).unwrap();
+        engine_1.commit(wb, WriteStates::default(), false).unwrap();
+        assert_ne!(engine_1.checksum().unwrap(), engine_2.checksum().unwrap());
+
+        // The local states are kept.
+        engine_1.drop_user_data().unwrap();
+        assert_eq!(engine_1.checksum().unwrap(), engine_2.checksum().unwrap());
+        assert_eq!(engine_1.descriptor(), engine_2.descriptor());
+    }
+
+    #[test]
+    fn decode_user_writes() {
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let engine = create_engine_with_range(executor, 1, 1, vec![], vec![]);
+
+        let mut wb = WriteBatch::default();
+        engine.put(&mut wb, 1, b"a", b"123", 123).unwrap();
+        engine.tombstone(&mut wb, 1, b"b", 123).unwrap();
+        engine.delete(&mut wb, 1, b"c", 123).unwrap();
+        // The writes of other versions are skipped.
+        engine.put(&mut wb, 1, b"d", b"123", 1).unwrap();
+        engine.delete(&mut wb, 1, b"e", 1).unwrap();
+        engine.delete_range(&mut wb, 1, b"f", b"g").unwrap();
+        engine.delete_range(&mut wb, 1, b"", b"").unwrap();
+
+        let wb = WriteBatch::from_rep(wb.rep());
+        let writes = engine.user_writes(&wb, 123);
+        assert_eq!(
+            writes,
+            vec![
+                UserWrite::Put {
+                    collection_id: 1,
+                    shard_id: 1,
+                    key: b"a".to_vec(),
+                    value: b"123".to_vec(),
+                },
+                UserWrite::Delete {
+                    collection_id: 1,
+                    shard_id: 1,
+                    key: b"b".to_vec(),
+                },
+                UserWrite::Delete {
+                    collection_id: 1,
+                    shard_id: 1,
+                    key: b"c".to_vec(),
+                },
+                UserWrite::DeleteRange {
+                    collection_id: 1,
+                    shard_id: 1,
+                    start: b"f".to_vec(),
+                    end: b"g".to_vec(),
+                },
+                UserWrite::DeleteRange {
+                    collection_id: 1,
+                    shard_id: 1,
+                    start: vec![],
+                    end: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_hash_shard_deletion() {
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let engine = create_engine(executor, 1, 1);
+
+        let slots = 256;
+        let algorithm = engula_api::v1::HashAlgorithm::Xxhash64 as i32;
+        let slot_id = shard::key_slot(b"a", slots, algorithm).unwrap();
+        use shard_desc::*;
+        let states = WriteStates {
+            descriptor: Some(GroupDesc {
+                id: 1,
+                shards: vec![ShardDesc {
+                    id: 1,
+                    collection_id: 1,
+                    partition: Some(Partition::Hash(HashPartition {
+                        slot_id,
+                        slots,
+                        algorithm,
+                        target_slots: 0,
+                    })),
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        engine.commit(WriteBatch::default(), states, false).unwrap();
+
+        // The deletion of a hash shard is decoded as truncating the shard, rather than
+        // truncating the whole collection.
+        let mut wb = WriteBatch::default();
+        engine.delete_range(&mut wb, 1, b"", b"").unwrap();
+        let writes = engine.user_writes(&wb, 123);
+        assert_eq!(
+            writes,
+            vec![UserWrite::TruncateShard {
+                collection_id: 1,
+                shard_id: 1,
+                hash: HashPartition {
+                    slot_id,
+                    slots,
+                    algorithm,
+                    target_slots: 0,
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn replication_log() {
+        let executor_owner = ExecutorOwner::new(1);
+        let executor = executor_owner.executor();
+        let engine_1 = create_engine(executor.clone(), 1, 1);
+        let engine_2 = create_engine(executor, 1, 1);
+
+        let writes = vec![
+            UserWrite::Put {
+                collection_id: 1,
+                shard_id: 1,
+                key: b"a".to_vec(),
+                value: b"123".to_vec(),
+            },
+            UserWrite::DeleteRange {
+                collection_id: 1,
+                shard_id: 1,
+                start: b"b".to_vec(),
+                end: vec![],
+            },
+            UserWrite::TruncateShard {
+                collection_id: 2,
+                shard_id: 2,
+                hash: shard_desc::HashPartition {
+                    slot_id: 1,
+                    slots: 4,
+                    ..Default::default()
+                },
+            },
+        ];
+        let mut wb = WriteBatch::default();
+        for index in [5, 6, 300] {
+            engine_1.append_replication_log(&mut wb, index, &writes);
+        }
+        engine_1.commit(wb, WriteStates::default(), false).unwrap();
+        // The replication log isn't a part of checksum.
+        assert_eq!(engine_1.checksum().unwrap(), engine_2.checksum().unwrap());
+
+        let logs = engine_1.replication_logs(0, 2).unwrap();
+        assert_eq!(logs, vec![(5, writes.clone()), (6, writes.clone())]);
+        assert!(engine_1.has_replication_log_of_shard(1).unwrap());
+        assert!(engine_1.has_replication_log_of_shard(2).unwrap());
+        assert!(!engine_1.has_replication_log_of_shard(3).unwrap());
+
+        let mut wb = WriteBatch::default();
+        engine_1.truncate_replication_log(&mut wb, 6);
+        engine_1.commit(wb, WriteStates::default(), false).unwrap();
+        let logs = engine_1.replication_logs(0, 10).unwrap();
+        assert_eq!(logs, vec![(300, writes.clone())]);
+        assert!(engine_1.has_replication_log_of_shard(1).unwrap());
+
+        let mut wb = WriteBatch::default();
+        engine_1.truncate_replication_log(&mut wb, 300);
+        engine_1.commit(wb, WriteStates::default(), false).unwrap();
+        assert!(engine_1.replication_logs(0, 10).unwrap().is_empty());
+        assert!(!engine_1.has_replication_log_of_shard(1).unwrap());
+        assert!(!engine_1.has_replication_log_of_shard(2).unwrap());
+        // The replication states aren't a part of checksum either.
+        assert_eq!(engine_1.checksum().unwrap(), engine_2.checksum().unwrap());
+
+        // The unknown kind is rejected instead of panicking.
+        let write = ReplicationWrite {
+            kind: 100,
+            ..ReplicationWrite::from(&writes[0])
+        };
+        assert!(matches!(
+            UserWrite::try_from(write),
+            Err(Error::InvalidData(_))
+        ));
+    }
 }
@@ -20,13 +20,15 @@ use std::{
     sync::Arc,
 };
 
-use tracing::info;
+use tracing::{info, warn};
 
 pub(crate) use self::{
-    group::{GroupEngine, RawIterator, SnapshotMode, WriteBatch, WriteStates},
+    group::{
+        keys, GroupEngine, MvccEntry, RawIterator, SnapshotMode, UserWrite, WriteBatch, WriteStates,
+    },
     state::StateEngine,
 };
-use crate::{DbConfig, Result};
+use crate::{DbConfig, Error, Result};
 
 // The disk layouts.
 const LAYOUT_DATA: &str = "db";
@@ -108,6 +110,24 @@ impl RawDb {
     ) -> DbResult<()> {
         self.db.ingest_external_file_cf_opts(cf, opts, paths)
     }
+
+    #[inline]
+    pub fn get_approximate_sizes_cf(
+        &self,
+        cf: &impl rocksdb::AsColumnFamilyRef,
+        ranges: &[rocksdb::Range],
+    ) -> Vec<u64> {
+        self.db.get_approximate_sizes_cf(cf, ranges)
+    }
+
+    #[inline]
+    pub fn property_int_value_cf(
+        &self,
+        cf: &impl rocksdb::AsColumnFamilyRef,
+        name: &str,
+    ) -> DbResult<Option<u64>> {
+        self.db.property_int_value_cf(cf, name)
+    }
 }
 
 #[derive(Clone)]
@@ -117,6 +137,18 @@ pub(crate) struct Engines {
     log: Arc<raft_engine::Engine>,
     db: Arc<RawDb>,
     state: StateEngine,
+    /// The copy of raft engine opened for read only, it is removed after the engines are dropped.
+    _log_copy: Option<RemoveDirOnDrop>,
+}
+
+struct RemoveDirOnDrop(PathBuf);
+
+impl Drop for RemoveDirOnDrop {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_dir_all(&self.0) {
+            warn!("remove directory {}: {err}", self.0.display());
+        }
+    }
 }
 
 impl Engines {
@@ -132,6 +164,35 @@ impl Engines {
             log,
             db,
             state,
+            _log_copy: None,
+        })
+    }
+
+    /// Open the engines of an existing data directory for offline inspection, the db is opened in
+    /// read only mode. The raft engine has no read only mode, and it might purge or rewrite the
+    /// log files during recovery, so a copy of the log files is opened instead.
+    pub(crate) fn open_for_read_only(root_dir: &Path, db_cfg: &DbConfig) -> Result<Self> {
+        let db_path = root_dir.join(LAYOUT_DATA);
+        let log_path = root_dir.join(LAYOUT_LOG);
+        if !db_path.is_dir() || !log_path.is_dir() {
+            return Err(Error::InvalidArgument(format!(
+                "{} is not a data directory",
+                root_dir.display()
+            )));
+        }
+        let db = Arc::new(open_engine_for_read_only(db_cfg, &db_path)?);
+        let copy_path = std::env::temp_dir().join(format!("engula-inspect-{}", std::process::id()));
+        let log_copy = RemoveDirOnDrop(copy_path.clone());
+        copy_dir_all(&log_path.join("engine"), &copy_path.join("engine"))?;
+        let log = Arc::new(open_raft_engine(&copy_path)?);
+        let state = StateEngine::new(log.clone());
+        Ok(Engines {
+            log_path,
+            _db_path: db_path,
+            log,
+            db,
+            state,
+            _log_copy: Some(log_copy),
         })
     }
 
@@ -185,6 +246,19 @@ pub(crate) fn open_engine<P: AsRef<Path>>(cfg: &DbConfig, path: P) -> Result<Raw
     }
 }
 
+pub(crate) fn open_engine_for_read_only<P: AsRef<Path>>(cfg: &DbConfig, path: P) -> Result<RawDb> {
+    use rocksdb::DB;
+
+    let options = cfg.to_options();
+    let cfs = DB::list_cf(&options, &path)?;
+    info!(
+        "open local db in read only mode with {} column families",
+        cfs.len()
+    );
+    let db = DB::open_cf_for_read_only(&options, path, cfs, false)?;
+    Ok(RawDb { db, options })
+}
+
 fn open_raft_engine(log_path: &Path) -> Result<raft_engine::Engine> {
     use raft_engine::{Config, Engine};
     let engine_dir = log_path.join("engine");
@@ -199,6 +273,20 @@ fn open_raft_engine(log_path: &Path) -> Result<raft_engine::Engine> {
     Ok(Engine::open(engine_cfg)?)
 }
 
+fn copy_dir_all(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
 fn create_dir_all_if_not_exists<P: AsRef<Path>>(dir: &P) -> Result<()> {
     use std::io::ErrorKind;
     match std::fs::create_dir_all(dir.as_ref()) {
@@ -34,6 +34,9 @@ pub enum Error {
     #[error("{0} is exhausted")]
     ResourceExhausted(String),
 
+    #[error("database {0} exceeds the quota of {1}")]
+    QuotaExceeded(/* database_id */ u64, /* resource */ String),
+
     // internal errors
     #[error("shard {0} not found")]
     ShardNotFound(u64),
@@ -47,6 +50,9 @@ pub enum Error {
     #[error("cluster not match")]
     ClusterNotMatch,
 
+    #[error("failpoint {0} is triggered")]
+    Failpoint(String),
+
     #[error("raft {0}")]
     Raft(#[from] raft::Error),
 
@@ -105,6 +111,7 @@ pub enum BusyReason {
     PendingConfigChange,
     RequestChannelFulled,
     ProposalDropped,
+    Replicating,
 }
 
 impl std::fmt::Display for BusyReason {
@@ -116,6 +123,7 @@ impl std::fmt::Display for BusyReason {
             BusyReason::Transfering => "leader transfering",
             BusyReason::RequestChannelFulled => "request channel fulled",
             BusyReason::ProposalDropped => "proposal dropped by raft",
+            BusyReason::Replicating => "writes are not shipped to standby cluster",
         };
         f.write_str(reason)
     }
@@ -134,6 +142,13 @@ impl From<Error> for tonic::Status {
             err @ Error::AlreadyExists(_) => Status::already_exists(err.to_string()),
             Error::ResourceExhausted(msg) => Status::resource_exhausted(msg),
 
+            Error::QuotaExceeded(database_id, ref resource) => Status::with_details(
+                Code::ResourceExhausted,
+                e.to_string(),
+                v1::Error::quota_exceeded(database_id, resource.to_owned())
+                    .encode_to_vec()
+                    .into(),
+            ),
             Error::GroupNotFound(group_id) => Status::with_details(
                 Code::Unknown,
                 e.to_string(),
@@ -166,6 +181,7 @@ impl From<Error> for tonic::Status {
             err @ (Error::Canceled
             | Error::AbortScheduleTask(_)
             | Error::ClusterNotMatch
+            | Error::Failpoint(_)
             | Error::InvalidData(_)
             | Error::Transport(_)
             | Error::Io(_)
@@ -211,6 +227,9 @@ impl From<Error> for engula_api::server::v1::Error {
                 v1::Error::not_root_leader(root, term, leader)
             }
             Error::EpochNotMatch(desc) => v1::Error::not_match(desc),
+            Error::QuotaExceeded(database_id, resource) => {
+                v1::Error::quota_exceeded(database_id, resource)
+            }
 
             Error::InvalidArgument(msg) => v1::Error::status(Code::InvalidArgument.into(), msg),
             Error::DeadlineExceeded(msg) => v1::Error::status(Code::DeadlineExceeded.into(), msg),
@@ -220,9 +239,9 @@ impl From<Error> for engula_api::server::v1::Error {
             Error::GroupNotReady(_) => panic!("GroupNotReady only used inside node"),
             Error::AbortScheduleTask(_) => panic!("AbortScheduleTask only used inside node"),
             Error::AlreadyExists(msg) => v1::Error::status(Code::AlreadyExists.into(), msg),
+            Error::ResourceExhausted(msg) => v1::Error::status(Code::ResourceExhausted.into(), msg),
 
             err @ (Error::Transport(_)
-            | Error::ResourceExhausted(_)
             | Error::Raft(_)
             | Error::RaftEngine(_)
             | Error::RocksDb(_)
@@ -231,6 +250,7 @@ impl From<Error> for engula_api::server::v1::Error {
             | Error::DatabaseNotFound(_)
             | Error::ShardNotFound(_)
             | Error::ClusterNotMatch
+            | Error::Failpoint(_)
             | Error::NoAvaliableGroup
             | Error::Canceled
             | Error::Rpc(_)) => v1::Error::status(Code::Internal.into(), err.to_string()),
@@ -251,6 +271,7 @@ impl From<engula_client::Error> for Error {
             engula_client::Error::DeadlineExceeded(v) => Error::DeadlineExceeded(v),
             engula_client::Error::AlreadyExists(v) => Error::AlreadyExists(v),
             engula_client::Error::ResourceExhausted(v) => Error::ResourceExhausted(v),
+            engula_client::Error::QuotaExceeded(db, resource) => Error::QuotaExceeded(db, resource),
             engula_client::Error::Rpc(err) => Error::Rpc(err),
             engula_client::Error::Connect(err) => Error::Rpc(err),
             engula_client::Error::Transport(err) => Error::Rpc(err),
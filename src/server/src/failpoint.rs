@@ -0,0 +1,310 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Named failpoints, used to inject faults into the crash-sensitive paths at runtime.
+//!
+//! A failpoint is evaluated by [`eval`] in synchronous code or [`eval_async`] in asynchronous
+//! code, it does nothing until an [`Action`] is configured by [`enable`] or the
+//! `/admin/failpoint` endpoint. Failpoints are shared by the whole process, so all servers of a
+//! test cluster running in the same process are affected. They are only compiled with the
+//! `failpoints` feature, otherwise the evaluations are no-ops.
+//!
+//! The synchronous failpoints are installed in the raft workers, which must not block the
+//! asynchronous runtime, so they only support `return` and `panic`. `return` fails the raft worker,
+//! which stops the replica as if it is crashed, until the server restarts.
+//!
+//! The available synchronous failpoints:
+//! - `fsm_before_commit`, `fsm_after_commit`: around committing the applied entries and the apply
+//!   state into the group engine.
+//! - `raft_before_advance_apply`: after entries are applied, before the applied index of raft is
+//!   advanced.
+//!
+//! The available asynchronous failpoints:
+//! - `snap_download_chunk`: after a chunk of a snapshot is saved during downloading.
+//! - `snap_download_before_install`: after a snapshot is downloaded, before it is installed.
+//! - `migrate_next_step`: before the migration coordinator executes the next step.
+//! - `follower_read_index`: before a read learner requests the read index of a follower read.
+
+#[cfg(feature = "failpoints")]
+pub use self::imp::*;
+#[cfg(not(feature = "failpoints"))]
+pub use self::noop::*;
+
+#[cfg(feature = "failpoints")]
+mod imp {
+    use std::{
+        collections::HashMap,
+        fmt::Display,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use tokio::sync::Notify;
+    use tracing::warn;
+
+    use crate::{Error, Result};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Action {
+        /// Return an error from the failpoint.
+        Return,
+        /// Panic at the failpoint, which simulates a crash.
+        Panic,
+        /// Sleep for a while, then continue.
+        Sleep(Duration),
+        /// Block until the failpoint is reconfigured or disabled.
+        Pause,
+    }
+
+    #[derive(Default)]
+    struct FailPoint {
+        action: Mutex<Option<Action>>,
+        notify: Notify,
+    }
+
+    lazy_static::lazy_static! {
+        static ref REGISTRY: Mutex<HashMap<String, Arc<FailPoint>>> = Mutex::default();
+    }
+
+    /// The failpoints evaluated by [`eval`], which don't support `sleep` and `pause`.
+    const SYNC_FAILPOINTS: &[&str] = &[
+        "fsm_before_commit",
+        "fsm_after_commit",
+        "raft_before_advance_apply",
+    ];
+
+    /// The number of enabled failpoints, used to skip the lookup if none is enabled.
+    static NUM_ENABLED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Enable the failpoint with the action, the previous action is replaced. The synchronous
+    /// failpoints reject the actions which block.
+    pub fn enable(name: &str, action: Action) -> Result<()> {
+        if matches!(action, Action::Sleep(_) | Action::Pause) && SYNC_FAILPOINTS.contains(&name) {
+            return Err(Error::InvalidArgument(format!(
+                "failpoint {name} is synchronous, {action} is not supported"
+            )));
+        }
+        let mut registry = REGISTRY.lock().unwrap();
+        let fp = registry.entry(name.to_owned()).or_insert_with(|| {
+            NUM_ENABLED.fetch_add(1, Ordering::AcqRel);
+            Arc::default()
+        });
+        fp.set_action(Some(action));
+        Ok(())
+    }
+
+    pub fn disable(name: &str) {
+        let mut registry = REGISTRY.lock().unwrap();
+        if let Some(fp) = registry.remove(name) {
+            NUM_ENABLED.fetch_sub(1, Ordering::AcqRel);
+            fp.set_action(None);
+        }
+    }
+
+    pub fn disable_all() {
+        let mut registry = REGISTRY.lock().unwrap();
+        for (_, fp) in registry.drain() {
+            NUM_ENABLED.fetch_sub(1, Ordering::AcqRel);
+            fp.set_action(None);
+        }
+    }
+
+    /// Returns the enabled failpoints and their actions, sorted by name.
+    pub fn list() -> Vec<(String, Action)> {
+        let registry = REGISTRY.lock().unwrap();
+        let mut failpoints = registry
+            .iter()
+            .filter_map(|(name, fp)| fp.action().map(|action| (name.clone(), action)))
+            .collect::<Vec<_>>();
+        failpoints.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        failpoints
+    }
+
+    /// Evaluate the failpoint in synchronous code. It never blocks the current thread, so the
+    /// `sleep` and `pause` actions are ignored.
+    pub fn eval(name: &str) -> Result<()> {
+        let fp = match lookup(name) {
+            Some(fp) => fp,
+            None => return Ok(()),
+        };
+        match fp.action() {
+            None | Some(Action::Sleep(_)) | Some(Action::Pause) => Ok(()),
+            Some(Action::Return) => Err(Error::Failpoint(name.to_owned())),
+            Some(Action::Panic) => panic!("failpoint {name} panic"),
+        }
+    }
+
+    /// Evaluate the failpoint in asynchronous code.
+    pub async fn eval_async(name: &str) -> Result<()> {
+        let fp = match lookup(name) {
+            Some(fp) => fp,
+            None => return Ok(()),
+        };
+        match fp.action() {
+            None => Ok(()),
+            Some(Action::Return) => Err(Error::Failpoint(name.to_owned())),
+            Some(Action::Panic) => panic!("failpoint {name} panic"),
+            Some(Action::Sleep(_)) | Some(Action::Pause) => {
+                suspend(name, &fp).await;
+                Ok(())
+            }
+        }
+    }
+
+    async fn suspend(name: &str, fp: &FailPoint) {
+        match fp.action() {
+            Some(Action::Sleep(duration)) => crate::runtime::time::sleep(duration).await,
+            Some(Action::Pause) => {
+                warn!("failpoint {name} is paused");
+                loop {
+                    // The waiter must be registered before checking the action, so that the
+                    // notification will not be missed.
+                    let notified = fp.notify.notified();
+                    if !matches!(fp.action(), Some(Action::Pause)) {
+                        break;
+                    }
+                    notified.await;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lookup(name: &str) -> Option<Arc<FailPoint>> {
+        if NUM_ENABLED.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        REGISTRY.lock().unwrap().get(name).cloned()
+    }
+
+    impl FailPoint {
+        fn action(&self) -> Option<Action> {
+            self.action.lock().unwrap().clone()
+        }
+
+        fn set_action(&self, action: Option<Action>) {
+            *self.action.lock().unwrap() = action;
+            self.notify.notify_waiters();
+        }
+    }
+
+    impl FromStr for Action {
+        type Err = Error;
+
+        /// Parse action from `return`, `panic`, `pause` or `sleep(<millis>)`.
+        fn from_str(s: &str) -> Result<Self> {
+            let action = match s {
+                "return" => Action::Return,
+                "panic" => Action::Panic,
+                "pause" => Action::Pause,
+                _ => {
+                    let millis = s
+                        .strip_prefix("sleep(")
+                        .and_then(|s| s.strip_suffix(')'))
+                        .and_then(|s| s.parse::<u64>().ok())
+                        .ok_or_else(|| Error::InvalidArgument(format!("illegal action {s}")))?;
+                    Action::Sleep(Duration::from_millis(millis))
+                }
+            };
+            Ok(action)
+        }
+    }
+
+    impl Display for Action {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Action::Return => write!(f, "return"),
+                Action::Panic => write!(f, "panic"),
+                Action::Sleep(duration) => write!(f, "sleep({})", duration.as_millis()),
+                Action::Pause => write!(f, "pause"),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::runtime::ExecutorOwner;
+
+        #[test]
+        fn parse_action() {
+            for (input, action) in [
+                ("return", Action::Return),
+                ("panic", Action::Panic),
+                ("pause", Action::Pause),
+                ("sleep(10)", Action::Sleep(Duration::from_millis(10))),
+            ] {
+                assert_eq!(input.parse::<Action>().unwrap(), action);
+                assert_eq!(action.to_string(), input);
+            }
+            assert!("sleep(abc)".parse::<Action>().is_err());
+            assert!("unknown".parse::<Action>().is_err());
+        }
+
+        #[test]
+        fn return_error() {
+            let name = "failpoint_test_return_error";
+            assert!(eval(name).is_ok());
+            enable(name, Action::Return).unwrap();
+            assert!(matches!(eval(name), Err(Error::Failpoint(_))));
+            assert!(list().iter().any(|(n, _)| n == name));
+            disable(name);
+            assert!(eval(name).is_ok());
+        }
+
+        #[test]
+        fn reject_blocking_sync_failpoint() {
+            let name = "fsm_before_commit";
+            assert!(enable(name, Action::Pause).is_err());
+            assert!(enable(name, Action::Sleep(Duration::from_millis(10))).is_err());
+            assert!(!list().iter().any(|(n, _)| n == name));
+        }
+
+        #[test]
+        fn pause_and_resume() {
+            let name = "failpoint_test_pause_and_resume";
+            enable(name, Action::Pause).unwrap();
+            let owner = ExecutorOwner::new(1);
+            let async_handle =
+                owner
+                    .executor()
+                    .spawn(None, crate::runtime::TaskPriority::High, async move {
+                        eval_async(name).await
+                    });
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(!async_handle.is_finished());
+            disable(name);
+            assert!(owner.executor().block_on(async_handle).unwrap().is_ok());
+        }
+    }
+}
+
+#[cfg(not(feature = "failpoints"))]
+mod noop {
+    use crate::Result;
+
+    #[inline(always)]
+    pub fn eval(_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub async fn eval_async(_name: &str) -> Result<()> {
+        Ok(())
+    }
+}
@@ -29,10 +29,14 @@ mod schedule;
 mod service;
 mod transport;
 
+pub mod debug;
+pub mod failpoint;
 pub mod node;
 pub mod raftgroup;
 pub mod runtime;
 pub mod serverpb;
+#[cfg(feature = "sim")]
+pub mod sim;
 
 pub(crate) use tonic::async_trait;
 
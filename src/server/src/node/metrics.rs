@@ -50,6 +50,22 @@ lazy_static! {
         "The total of ingest chunks of node"
     )
     .unwrap();
+    pub static ref NODE_REPLICATION_SHIPPED_WRITES_TOTAL: IntCounter = register_int_counter!(
+        "node_replication_shipped_writes_total",
+        "The total of writes shipped to the standby cluster"
+    )
+    .unwrap();
+    pub static ref NODE_REPLICATION_PENDING_WRITES: IntGauge = register_int_gauge!(
+        "node_replication_pending_writes",
+        "The number of writes waiting to be shipped to the standby cluster"
+    )
+    .unwrap();
+    pub static ref NODE_REPLICATION_LAG_SECONDS: Histogram = register_histogram!(
+        "node_replication_lag_seconds",
+        "The intervals from writes are applied to they are shipped to the standby cluster",
+        exponential_buckets(0.0005, 1.8, 22).unwrap(),
+    )
+    .unwrap();
 }
 
 pub fn take_destory_replica_metrics() -> &'static Histogram {
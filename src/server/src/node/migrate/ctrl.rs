@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use engula_api::server::v1::{group_request_union::Request, group_response_union::Response, *};
 use engula_client::{MigrateClient, Router};
@@ -20,10 +20,13 @@ use futures::{channel::mpsc, StreamExt};
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    node::Replica, runtime::sync::WaitGroup, serverpb::v1::*, transport::TransportManager,
+    node::Replica, runtime::sync::WaitGroup, serverpb::v1::*, transport::TransportManager, Error,
     NodeConfig, Result,
 };
 
+/// The max retries of waiting for the router to watch the target shards of a resharding shard.
+const RESHARDING_ROUTE_RETRY: usize = 100;
+
 #[derive(Debug)]
 pub struct ForwardCtx {
     pub shard_id: u64,
@@ -136,11 +139,67 @@ impl MigrateController {
         let resp = resp.response.and_then(|resp| resp.response);
         Ok(resp.unwrap())
     }
+
+    /// Forward the write of a resharding shard to the shard of target slots, the writes are
+    /// applied to both shards until the resharding is finished.
+    pub async fn forward_resharding_write(
+        &self,
+        shard: &ShardDesc,
+        request: &Request,
+    ) -> Result<()> {
+        let mut request = request.clone();
+        let key = match &request {
+            Request::Put(req) => req.put.as_ref().map(|put| put.key.clone()),
+            Request::Delete(req) => req.delete.as_ref().map(|delete| delete.key.clone()),
+            _ => None,
+        }
+        .ok_or_else(|| Error::InvalidArgument("only put and delete are dual-written".into()))?;
+
+        let router = self.router();
+        let mut retry = 0;
+        let (group, target) = loop {
+            match router.find_resharding_shard(shard, &key) {
+                Ok(Some(target)) => break target,
+                Ok(None) => return Ok(()),
+                Err(engula_client::Error::NotFound(_)) if retry < RESHARDING_ROUTE_RETRY => {
+                    // The target shards are created before the source shards are marked, but the
+                    // router might not have watched them yet.
+                    retry += 1;
+                    crate::runtime::time::sleep(Duration::from_millis(10)).await;
+                }
+                Err(engula_client::Error::NotFound(msg)) => {
+                    return Err(Error::DeadlineExceeded(format!(
+                        "find the target shard of resharding shard {}: {msg}",
+                        shard.id
+                    )))
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        match &mut request {
+            Request::Put(req) => req.shard_id = target.id,
+            Request::Delete(req) => req.shard_id = target.id,
+            _ => unreachable!(),
+        }
+        let forward_ctx = ForwardCtx {
+            shard_id: target.id,
+            dest_group_id: group.id,
+            payloads: vec![],
+        };
+        self.forward(forward_ctx, &request).await?;
+        Ok(())
+    }
 }
 
 impl MigrationCoordinator {
     async fn next_step(&mut self, state: MigrationState) {
         let step = MigrationStep::from_i32(state.step).unwrap();
+        if let Err(err) = crate::failpoint::eval_async("migrate_next_step").await {
+            warn!(replica = self.replica_id, group = self.group_id, desc = %self.desc,
+                "skip migration step {step:?}: {err}");
+            return;
+        }
         if self.is_dest_group() {
             match step {
                 MigrationStep::Prepare => {
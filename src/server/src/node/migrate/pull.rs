@@ -48,12 +48,13 @@ fn shard_chunk_stream(
     shard_id: u64,
     chunk_size: usize,
     mut last_key: Vec<u8>,
+    resharding: bool,
     replica: Arc<Replica>,
 ) -> impl futures::Stream<Item = Result<ShardChunk, tonic::Status>> {
     async_stream::try_stream! {
         loop {
             match replica
-                .fetch_shard_chunk(shard_id, &last_key, chunk_size)
+                .fetch_shard_chunk(shard_id, &last_key, chunk_size, resharding)
                 .await
             {
                 Ok(shard_chunk) => {
@@ -81,8 +82,14 @@ pub struct ShardChunkStream {
 }
 
 impl ShardChunkStream {
-    pub fn new(shard_id: u64, chunk_size: usize, last_key: Vec<u8>, replica: Arc<Replica>) -> Self {
-        let inner = shard_chunk_stream(shard_id, chunk_size, last_key, replica);
+    pub fn new(
+        shard_id: u64,
+        chunk_size: usize,
+        last_key: Vec<u8>,
+        resharding: bool,
+        replica: Arc<Replica>,
+    ) -> Self {
+        let inner = shard_chunk_stream(shard_id, chunk_size, last_key, resharding, replica);
         ShardChunkStream {
             inner: Box::pin(inner),
         }
@@ -14,9 +14,11 @@
 
 mod job;
 mod metrics;
+mod quota;
 
 pub mod migrate;
 pub mod replica;
+pub mod replication;
 pub mod route_table;
 
 use std::{
@@ -26,11 +28,14 @@ use std::{
 
 use engula_api::server::v1::*;
 use futures::{channel::mpsc, lock::Mutex};
+use prost::Message;
 use tracing::{debug, info, warn};
 
 use self::{
     job::StateChannel,
     migrate::{MigrateController, ShardChunkStream},
+    quota::{QuotaManager, RequestQuota, UserRequest},
+    replication::ReplicationManager,
 };
 pub use self::{
     replica::Replica,
@@ -86,6 +91,8 @@ where
     transport_manager: TransportManager,
     engines: Engines,
     state_engine: StateEngine,
+    replication: ReplicationManager,
+    quota: QuotaManager,
 
     /// Node related metadata, including serving replicas, root desc.
     node_state: Arc<Mutex<NodeState>>,
@@ -103,15 +110,18 @@ impl Node {
         let raft_route_table = RaftRouteTable::new();
         let trans_mgr = ChannelManager::build(
             transport_manager.address_resolver(),
+            transport_manager.conn_manager().clone(),
             raft_route_table.clone(),
+            cfg.raft.clone(),
         )
         .await;
         let snap_dir = engines.snap_dir();
-        let snap_mgr = SnapManager::recovery(snap_dir).await?;
+        let snap_mgr = SnapManager::recovery(snap_dir, &cfg.node.replica).await?;
         let raft_mgr =
             RaftManager::open(cfg.raft.clone(), engines.log(), snap_mgr, trans_mgr).await?;
         let migrate_ctrl = MigrateController::new(cfg.node.clone(), transport_manager.clone());
         let state_engine = engines.state();
+        let replication = ReplicationManager::new(cfg.replication, transport_manager.clone());
         Ok(Node {
             cfg: cfg.node,
             transport_manager,
@@ -121,6 +131,8 @@ impl Node {
             migrate_ctrl,
             engines,
             state_engine,
+            replication,
+            quota: QuotaManager::default(),
             node_state: Arc::new(Mutex::new(NodeState::default())),
             replica_mutation: Arc::default(),
         })
@@ -325,6 +337,7 @@ impl Node {
             info.clone(),
             lease_state.clone(),
             channel.clone(),
+            self.replication.clone(),
             group_engine.clone(),
             wait_group.clone(),
         )
@@ -336,6 +349,7 @@ impl Node {
             info.clone(),
             lease_state.clone(),
             channel,
+            self.replication.clone(),
         ));
         let replica = Replica::new(
             info.clone(),
@@ -362,6 +376,9 @@ impl Node {
             wait_group.clone(),
         );
 
+        self.replication
+            .watch_replica(replica.clone(), wait_group.clone());
+
         // Now that all initialization work is done, the replica is ready to serve, mark it as
         // normal state.
         if matches!(local_state, ReplicaLocalState::Initial) {
@@ -413,7 +430,48 @@ impl Node {
             }
         };
 
-        forwardable_execute(&self.migrate_ctrl, &replica, &ExecCtx::default(), request).await
+        if self.replication.is_enabled() && is_replicated_write(&replica, request) {
+            self.replication.throttle(request.group_id).await?;
+        }
+        let quota = self.request_quota(&replica, request);
+        let exec_ctx = ExecCtx::with_quota(quota.clone());
+        let resp = forwardable_execute(&self.migrate_ctrl, &replica, &exec_ctx, request).await?;
+        if let Some(quota) = quota {
+            quota.consume_read_bytes(resp.encoded_len() as u64);
+        }
+        Ok(resp)
+    }
+
+    /// Return the quota of the databases which the user request belongs to, it is acquired by the
+    /// replica once the request passes the early checks. The writes of a batch are charged to the
+    /// databases owning their shards.
+    fn request_quota(&self, replica: &Replica, request: &GroupRequest) -> Option<RequestQuota> {
+        if self.quota.is_empty() {
+            return None;
+        }
+
+        let user_requests = request
+            .request
+            .as_ref()
+            .and_then(|r| r.request.as_ref())
+            .map(UserRequest::parse)
+            .unwrap_or_default();
+        let desc = replica.descriptor();
+        let mut quota: Option<RequestQuota> = None;
+        for user_request in user_requests {
+            // The shard might be migrated, leave it to the replica.
+            let Some(shard) = desc.shards.iter().find(|s| s.id == user_request.shard_id) else {
+                continue;
+            };
+            let Some(limiter) = self.quota.find(shard.collection_id) else {
+                continue;
+            };
+            match quota.as_mut() {
+                Some(quota) => quota.charge(limiter, user_request),
+                None => quota = Some(RequestQuota::new(limiter, user_request)),
+            }
+        }
+        quota
     }
 
     pub async fn pull_shard_chunks(&self, request: PullRequest) -> Result<ShardChunkStream> {
@@ -423,11 +481,16 @@ impl Node {
                 return Err(Error::GroupNotFound(request.group_id));
             }
         };
-        replica.check_migrating_request_early(request.shard_id)?;
+        if request.resharding {
+            replica.check_resharding_request_early(request.shard_id)?;
+        } else {
+            replica.check_migrating_request_early(request.shard_id)?;
+        }
         Ok(ShardChunkStream::new(
             request.shard_id,
             self.cfg.shard_chunk_size,
             request.last_key,
+            request.resharding,
             replica,
         ))
     }
@@ -498,6 +561,38 @@ impl Node {
         Ok(MigrateResponse {})
     }
 
+    pub fn collect_checksum(
+        &self,
+        request: &CollectChecksumRequest,
+    ) -> Result<CollectChecksumResponse> {
+        let group_id = request.group_id;
+        let replica = match self.replica_route_table.find(group_id) {
+            Some(replica) => replica,
+            None => {
+                return Err(Error::GroupNotFound(group_id));
+            }
+        };
+
+        let replica_id = replica.replica_info().replica_id;
+        Ok(match replica.checksum(request.check_id) {
+            Some(result) => CollectChecksumResponse {
+                replica_id,
+                computed: true,
+                index: result.index,
+                checksum: result.checksum,
+            },
+            None => CollectChecksumResponse {
+                replica_id,
+                ..Default::default()
+            },
+        })
+    }
+
+    #[inline]
+    pub fn replication(&self) -> &ReplicationManager {
+        &self.replication
+    }
+
     #[inline]
     pub fn replica_table(&self) -> &ReplicaRouteTable {
         &self.replica_route_table
@@ -544,11 +639,28 @@ impl Node {
                 let replica_state = replica.replica_state();
                 if replica_state.role == RaftRole::Leader as i32 {
                     ns.leader_count += 1;
+                    let group_engine = replica.group_engine();
+                    let shard_stats = descriptor
+                        .shards
+                        .iter()
+                        .filter_map(|shard| {
+                            group_engine
+                                .approximate_shard_stats(shard.id)
+                                .map_err(|err| {
+                                    warn!(
+                                        "group {} shard {} collect approximate stats: {err:?}",
+                                        info.group_id, shard.id
+                                    );
+                                })
+                                .ok()
+                        })
+                        .collect();
                     let gs = GroupStats {
                         group_id: info.group_id,
                         shard_count: descriptor.shards.len() as u64,
                         read_qps: 0.,
                         write_qps: 0.,
+                        shard_stats,
                     };
                     group_stats.push(gs);
                 }
@@ -654,6 +766,42 @@ impl Node {
         resp
     }
 
+    pub async fn sync_node_liveness(
+        &self,
+        req: &SyncNodeLivenessRequest,
+    ) -> SyncNodeLivenessResponse {
+        if !req.dead_nodes.is_empty() {
+            let dead_nodes = Arc::new(req.dead_nodes.iter().cloned().collect::<HashSet<_>>());
+            for group_id in self.serving_group_id_list().await {
+                if let Some(replica) = self.replica_route_table.find(group_id) {
+                    replica.raft_node().report_dead_nodes(dead_nodes.clone());
+                }
+            }
+        }
+        SyncNodeLivenessResponse {}
+    }
+
+    pub async fn sync_database_quota(
+        &self,
+        req: &SyncDatabaseQuotaRequest,
+    ) -> SyncDatabaseQuotaResponse {
+        let node_id = match self.node_state.lock().await.ident.as_ref() {
+            Some(ident) => ident.node_id,
+            None => return SyncDatabaseQuotaResponse {},
+        };
+        self.quota.sync(req, node_id);
+        SyncDatabaseQuotaResponse {}
+    }
+
+    pub fn sync_replication_state(
+        &self,
+        req: &SyncReplicationStateRequest,
+    ) -> SyncReplicationStateResponse {
+        SyncReplicationStateResponse {
+            pending_writes: self.replication.sync(req.stopped),
+        }
+    }
+
     #[inline]
     async fn serving_group_id_list(&self) -> Vec<u64> {
         let node_state = self.node_state.lock().await;
@@ -668,6 +816,27 @@ impl NodeState {
     }
 }
 
+/// Return whether the request writes user data which is shipped to the standby cluster.
+fn is_replicated_write(replica: &Replica, request: &GroupRequest) -> bool {
+    let user_requests = request
+        .request
+        .as_ref()
+        .and_then(|r| r.request.as_ref())
+        .map(UserRequest::parse)
+        .unwrap_or_default();
+    if user_requests.iter().all(|r| r.read_only) {
+        return false;
+    }
+    let desc = replica.descriptor();
+    user_requests.iter().any(|r| {
+        desc.shards
+            .iter()
+            .find(|s| s.id == r.shard_id)
+            .map(|s| replication::is_replicated_collection(request.group_id, s.collection_id))
+            .unwrap_or_default()
+    })
+}
+
 async fn open_group_engine(
     cfg: &EngineConfig,
     raw_db: Arc<RawDb>,
@@ -692,20 +861,25 @@ async fn start_raft_group(
     info: Arc<ReplicaInfo>,
     lease_state: Arc<std::sync::Mutex<LeaseState>>,
     channel: StateChannel,
+    replication: ReplicationManager,
     group_engine: GroupEngine,
     wait_group: WaitGroup,
 ) -> Result<RaftNodeFacade> {
     let group_id = info.group_id;
+    let fsm_replication =
+        (replication.is_enabled() && group_id != ROOT_GROUP_ID).then(|| replication.clone());
     let state_observer = Box::new(LeaseStateObserver::new(
         info.clone(),
         lease_state.clone(),
         channel,
+        replication,
     ));
     let fsm = GroupStateMachine::new(
         cfg.replica.clone(),
         info.clone(),
         group_engine.clone(),
         state_observer.clone(),
+        fsm_replication,
     );
     raft_mgr
         .start_raft_group(
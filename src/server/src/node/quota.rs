@@ -0,0 +1,560 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use engula_api::{
+    server::v1::{group_request_union::Request, *},
+    v1::DatabaseQuota,
+};
+use prost::Message as _;
+
+use crate::{runtime::time::Instant, Error, Result};
+
+/// Throttles the user requests of databases with the quotas synced from root. The rate limits are
+/// enforced by each node independently, so they are split among nodes by the number of shards
+/// led by each node, see `DatabaseQuotaState::node_leaders`.
+#[derive(Default)]
+pub struct QuotaManager {
+    /// The limiter of databases which have any limits, indexed by collection id.
+    limiters: Mutex<HashMap<u64, Arc<DatabaseLimiter>>>,
+}
+
+pub struct DatabaseLimiter {
+    database_id: u64,
+    core: Mutex<LimiterCore>,
+}
+
+struct LimiterCore {
+    storage_bytes: u64,
+    storage_limit: u64,
+    read_qps: TokenBucket,
+    write_qps: TokenBucket,
+    read_bytes: TokenBucket,
+    write_bytes: TokenBucket,
+}
+
+/// A token bucket refills `rate` tokens per second, and holds at most the tokens of one second. The
+/// bandwidth buckets are allowed to be in debt, so that a request larger than the rate could be
+/// served and the following requests are throttled until the debt is paid off.
+struct TokenBucket {
+    /// Zero means unlimited.
+    rate: u64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// The user request issued to a shard, or the writes of a batch issued to the same shard.
+#[derive(Clone, Copy)]
+pub struct UserRequest {
+    pub shard_id: u64,
+    pub read_only: bool,
+    /// Whether this request could grow the size of database.
+    pub grow: bool,
+    pub bytes: u64,
+}
+
+/// The quota of a user request. It is acquired once the request passes the early checks of the
+/// leader, see `Replica::execute`, so the requests rejected by replicas consume no tokens.
+#[derive(Clone)]
+pub struct RequestQuota {
+    /// The requests charged to each database, the writes of a batch might belong to the shards of
+    /// different databases.
+    charges: Vec<Charge>,
+}
+
+#[derive(Clone)]
+struct Charge {
+    limiter: Arc<DatabaseLimiter>,
+    request: UserRequest,
+    acquired: bool,
+}
+
+impl QuotaManager {
+    /// Replace the quotas of databases, the token buckets of existing databases are retained.
+    pub fn sync(&self, req: &SyncDatabaseQuotaRequest, node_id: u64) {
+        let mut limiters = self.limiters.lock().unwrap();
+        let mut databases = limiters
+            .values()
+            .map(|l| (l.database_id, l.clone()))
+            .collect::<HashMap<_, _>>();
+        let mut new_limiters = HashMap::default();
+        for state in &req.databases {
+            let quota = local_quota(state, node_id);
+            let limiter = match databases.remove(&state.database_id) {
+                Some(limiter) => {
+                    limiter.update(&quota, state.storage_bytes);
+                    limiter
+                }
+                None => Arc::new(DatabaseLimiter::new(
+                    state.database_id,
+                    &quota,
+                    state.storage_bytes,
+                )),
+            };
+            for collection_id in &state.collections {
+                new_limiters.insert(*collection_id, limiter.clone());
+            }
+        }
+        *limiters = new_limiters;
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.limiters.lock().unwrap().is_empty()
+    }
+
+    pub fn find(&self, collection_id: u64) -> Option<Arc<DatabaseLimiter>> {
+        self.limiters.lock().unwrap().get(&collection_id).cloned()
+    }
+}
+
+impl RequestQuota {
+    pub fn new(limiter: Arc<DatabaseLimiter>, request: UserRequest) -> Self {
+        RequestQuota {
+            charges: vec![Charge {
+                limiter,
+                request,
+                acquired: false,
+            }],
+        }
+    }
+
+    /// Charge the request of another shard to its database. The writes charged to the same
+    /// database are merged, so that a batch consumes one write of each database.
+    pub fn charge(&mut self, limiter: Arc<DatabaseLimiter>, request: UserRequest) {
+        match self
+            .charges
+            .iter_mut()
+            .find(|c| c.limiter.database_id == limiter.database_id)
+        {
+            Some(charge) => {
+                charge.request.grow |= request.grow;
+                charge.request.bytes += request.bytes;
+            }
+            None => self.charges.push(Charge {
+                limiter,
+                request,
+                acquired: false,
+            }),
+        }
+    }
+
+    /// Acquire the tokens to execute the request, the tokens are only acquired once even if the
+    /// request is retried.
+    pub fn acquire(&mut self) -> Result<()> {
+        for charge in &mut self.charges {
+            if !charge.acquired {
+                charge.limiter.acquire(&charge.request)?;
+                charge.acquired = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Consume the bytes read by the request after it is executed.
+    pub fn consume_read_bytes(&self, bytes: u64) {
+        for charge in &self.charges {
+            if charge.request.read_only {
+                charge.limiter.consume_read_bytes(bytes);
+            }
+        }
+    }
+}
+
+impl DatabaseLimiter {
+    fn new(database_id: u64, quota: &DatabaseQuota, storage_bytes: u64) -> Self {
+        DatabaseLimiter {
+            database_id,
+            core: Mutex::new(LimiterCore {
+                storage_bytes,
+                storage_limit: quota.storage_bytes,
+                read_qps: TokenBucket::new(quota.read_qps),
+                write_qps: TokenBucket::new(quota.write_qps),
+                read_bytes: TokenBucket::new(quota.read_bytes_per_sec),
+                write_bytes: TokenBucket::new(quota.write_bytes_per_sec),
+            }),
+        }
+    }
+
+    fn update(&self, quota: &DatabaseQuota, storage_bytes: u64) {
+        let mut core = self.core.lock().unwrap();
+        core.storage_bytes = storage_bytes;
+        core.storage_limit = quota.storage_bytes;
+        core.read_qps.set_rate(quota.read_qps);
+        core.write_qps.set_rate(quota.write_qps);
+        core.read_bytes.set_rate(quota.read_bytes_per_sec);
+        core.write_bytes.set_rate(quota.write_bytes_per_sec);
+    }
+
+    /// Acquire the tokens to execute the request, the bytes read are consumed after the request is
+    /// executed, see `DatabaseLimiter::consume_read_bytes`.
+    pub fn acquire(&self, request: &UserRequest) -> Result<()> {
+        let now = Instant::now();
+        let mut core = self.core.lock().unwrap();
+        if request.read_only {
+            if !core.read_qps.available(now, 1) {
+                return Err(self.exceeded("read_qps"));
+            }
+            if !core.read_bytes.available(now, 0) {
+                return Err(self.exceeded("read_bytes_per_sec"));
+            }
+            core.read_qps.consume(1);
+        } else {
+            if request.grow && core.storage_limit != 0 && core.storage_bytes >= core.storage_limit {
+                return Err(self.exceeded("storage_bytes"));
+            }
+            if !core.write_qps.available(now, 1) {
+                return Err(self.exceeded("write_qps"));
+            }
+            if !core.write_bytes.available(now, request.bytes) {
+                return Err(self.exceeded("write_bytes_per_sec"));
+            }
+            core.write_qps.consume(1);
+            core.write_bytes.consume(request.bytes);
+        }
+        Ok(())
+    }
+
+    pub fn consume_read_bytes(&self, bytes: u64) {
+        let mut core = self.core.lock().unwrap();
+        core.read_bytes.refill(Instant::now());
+        core.read_bytes.consume(bytes);
+    }
+
+    fn exceeded(&self, resource: &str) -> Error {
+        Error::QuotaExceeded(self.database_id, resource.to_owned())
+    }
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        TokenBucket {
+            rate,
+            tokens: rate as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn set_rate(&mut self, rate: u64) {
+        if self.rate != rate {
+            self.rate = rate;
+            self.tokens = self.tokens.min(rate as f64);
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+    }
+
+    /// Whether the request of `n` tokens could be served, a request larger than the rate only
+    /// requires a full bucket.
+    fn available(&mut self, now: Instant, n: u64) -> bool {
+        if self.rate == 0 {
+            return true;
+        }
+        self.refill(now);
+        self.tokens >= 0.0 && self.tokens >= n.min(self.rate) as f64
+    }
+
+    fn consume(&mut self, n: u64) {
+        if self.rate != 0 {
+            self.tokens -= n as f64;
+        }
+    }
+}
+
+/// Return the rate limits of this node, which are split by the share of shards led by this node.
+/// A node leading no shards is still given the share of one shard, since the leaders might be moved
+/// to it before the next sync.
+fn local_quota(state: &DatabaseQuotaState, node_id: u64) -> DatabaseQuota {
+    let mut quota = state.quota.clone().unwrap_or_default();
+    let total = state.node_leaders.values().sum::<u64>();
+    if total == 0 {
+        return quota;
+    }
+
+    let local = state
+        .node_leaders
+        .get(&node_id)
+        .cloned()
+        .unwrap_or_default()
+        .max(1);
+    let total = total.max(local);
+    let split = |rate: u64| {
+        if rate == 0 {
+            0
+        } else {
+            ((rate as u128 * local as u128 + total as u128 - 1) / total as u128).max(1) as u64
+        }
+    };
+    quota.read_qps = split(quota.read_qps);
+    quota.write_qps = split(quota.write_qps);
+    quota.read_bytes_per_sec = split(quota.read_bytes_per_sec);
+    quota.write_bytes_per_sec = split(quota.write_bytes_per_sec);
+    quota
+}
+
+impl UserRequest {
+    /// Parse the user request into the requests of each shard, the writes of a batch are split by
+    /// the shards they belong to. Nothing is returned if the request isn't issued by users.
+    pub fn parse(request: &Request) -> Vec<Self> {
+        let (shard_id, read_only, grow) = match request {
+            Request::Get(req) => (req.shard_id, true, false),
+            Request::Scan(req) => (req.shard_id, true, false),
+            Request::PrefixList(req) => (req.shard_id, true, false),
+            Request::Put(req) => (req.shard_id, false, true),
+            Request::Delete(req) => (req.shard_id, false, false),
+            Request::DeleteRange(req) => (req.shard_id, false, false),
+            Request::BatchWrite(req) => return Self::parse_batch_write(req),
+            Request::CreateShard(_)
+            | Request::RemoveShard(_)
+            | Request::ChangeReplicas(_)
+            | Request::AcceptShard(_)
+            | Request::Transfer(_)
+            | Request::MoveReplicas(_)
+            | Request::Ingest(_) => return vec![],
+        };
+        let bytes = if read_only {
+            0
+        } else {
+            request.encoded_len() as u64
+        };
+        vec![UserRequest {
+            shard_id,
+            read_only,
+            grow,
+            bytes,
+        }]
+    }
+
+    fn parse_batch_write(req: &BatchWriteRequest) -> Vec<Self> {
+        let mut requests: Vec<UserRequest> = Vec::new();
+        let writes = req
+            .puts
+            .iter()
+            .map(|put| (put.shard_id, true, put.encoded_len()))
+            .chain(
+                req.deletes
+                    .iter()
+                    .map(|del| (del.shard_id, false, del.encoded_len())),
+            );
+        for (shard_id, grow, bytes) in writes {
+            match requests.iter_mut().find(|r| r.shard_id == shard_id) {
+                Some(request) => {
+                    request.grow |= grow;
+                    request.bytes += bytes as u64;
+                }
+                None => requests.push(UserRequest {
+                    shard_id,
+                    read_only: false,
+                    grow,
+                    bytes: bytes as u64,
+                }),
+            }
+        }
+        requests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use engula_api::v1::{DeleteRequest, PutRequest};
+
+    use super::*;
+
+    #[test]
+    fn token_bucket_refill() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(10);
+        bucket.last_refill = now;
+        for _ in 0..10 {
+            assert!(bucket.available(now, 1));
+            bucket.consume(1);
+        }
+        assert!(!bucket.available(now, 1));
+        assert!(bucket.available(now + Duration::from_millis(200), 1));
+    }
+
+    #[test]
+    fn token_bucket_debt() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(10);
+        bucket.last_refill = now;
+        // A request larger than the rate is served with a full bucket.
+        assert!(bucket.available(now, 100));
+        bucket.consume(100);
+        assert!(!bucket.available(now + Duration::from_secs(5), 0));
+        assert!(bucket.available(now + Duration::from_secs(9), 0));
+    }
+
+    #[test]
+    fn unlimited_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(0);
+        for _ in 0..1000 {
+            assert!(bucket.available(now, 1 << 20));
+            bucket.consume(1 << 20);
+        }
+    }
+
+    #[test]
+    fn acquire_request_quota_once() {
+        let quota = DatabaseQuota {
+            write_qps: 1,
+            ..Default::default()
+        };
+        let limiter = Arc::new(DatabaseLimiter::new(1, &quota, 0));
+        let put = UserRequest {
+            shard_id: 1,
+            read_only: false,
+            grow: true,
+            bytes: 10,
+        };
+        let mut request_quota = RequestQuota::new(limiter.clone(), put);
+        // The retried request acquires no more tokens.
+        assert!(request_quota.acquire().is_ok());
+        assert!(request_quota.acquire().is_ok());
+
+        let mut request_quota = RequestQuota::new(limiter, put);
+        assert!(matches!(
+            request_quota.acquire(),
+            Err(Error::QuotaExceeded(1, _))
+        ));
+    }
+
+    #[test]
+    fn charge_batch_write_to_databases() {
+        let put = |shard_id: u64| ShardPutRequest {
+            shard_id,
+            put: Some(PutRequest {
+                key: b"key".to_vec(),
+                value: b"value".to_vec(),
+            }),
+        };
+        let delete = |shard_id: u64| ShardDeleteRequest {
+            shard_id,
+            delete: Some(DeleteRequest {
+                key: b"key".to_vec(),
+            }),
+        };
+        let batch = Request::BatchWrite(BatchWriteRequest {
+            puts: vec![put(1), put(2)],
+            deletes: vec![delete(1), delete(3)],
+        });
+        let requests = UserRequest::parse(&batch);
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|r| !r.read_only));
+        let grows = requests
+            .iter()
+            .map(|r| (r.shard_id, r.grow))
+            .collect::<Vec<_>>();
+        assert_eq!(grows, vec![(1, true), (2, true), (3, false)]);
+        assert_eq!(
+            requests[0].bytes,
+            (put(1).encoded_len() + delete(1).encoded_len()) as u64
+        );
+
+        // The shards 1 and 2 belong to database 1, and the shard 3 belongs to database 2.
+        let quota = DatabaseQuota {
+            write_qps: 1,
+            ..Default::default()
+        };
+        let db1 = Arc::new(DatabaseLimiter::new(1, &quota, 0));
+        let db2 = Arc::new(DatabaseLimiter::new(2, &quota, 0));
+        let mut request_quota = RequestQuota::new(db1.clone(), requests[0]);
+        request_quota.charge(db1.clone(), requests[1]);
+        request_quota.charge(db2.clone(), requests[2]);
+        assert_eq!(request_quota.charges.len(), 2);
+        assert!(request_quota.acquire().is_ok());
+
+        // Each database is charged one write.
+        for limiter in [db1, db2] {
+            let mut request_quota = RequestQuota::new(limiter.clone(), requests[2]);
+            assert!(matches!(
+                request_quota.acquire(),
+                Err(Error::QuotaExceeded(id, _)) if id == limiter.database_id
+            ));
+        }
+    }
+
+    #[test]
+    fn split_rate_by_node_leaders() {
+        let state = DatabaseQuotaState {
+            database_id: 1,
+            quota: Some(DatabaseQuota {
+                storage_bytes: 100,
+                read_qps: 100,
+                write_qps: 10,
+                ..Default::default()
+            }),
+            node_leaders: HashMap::from([(1, 3), (2, 1)]),
+            ..Default::default()
+        };
+
+        let quota = local_quota(&state, 1);
+        assert_eq!(quota.storage_bytes, 100);
+        assert_eq!(quota.read_qps, 75);
+        assert_eq!(quota.write_qps, 8);
+        assert_eq!(quota.read_bytes_per_sec, 0);
+
+        let quota = local_quota(&state, 2);
+        assert_eq!(quota.read_qps, 25);
+        assert_eq!(quota.write_qps, 3);
+
+        // A node leading no shards is given the share of one shard.
+        let quota = local_quota(&state, 3);
+        assert_eq!(quota.read_qps, 25);
+
+        // The limits are not split if the leaders are unknown.
+        let state = DatabaseQuotaState {
+            node_leaders: HashMap::default(),
+            ..state
+        };
+        assert_eq!(local_quota(&state, 1).read_qps, 100);
+    }
+
+    #[test]
+    fn storage_quota() {
+        let quota = DatabaseQuota {
+            storage_bytes: 100,
+            ..Default::default()
+        };
+        let limiter = DatabaseLimiter::new(1, &quota, 100);
+        let put = UserRequest {
+            shard_id: 1,
+            read_only: false,
+            grow: true,
+            bytes: 10,
+        };
+        let delete = UserRequest { grow: false, ..put };
+        assert!(matches!(
+            limiter.acquire(&put),
+            Err(Error::QuotaExceeded(1, _))
+        ));
+        assert!(limiter.acquire(&delete).is_ok());
+
+        limiter.update(&quota, 50);
+        assert!(limiter.acquire(&put).is_ok());
+    }
+}
@@ -28,5 +28,6 @@ pub async fn accept_shard(group_id: u64, epoch: u64, req: &AcceptShardRequest) -
     EvalResult {
         batch: None,
         op: Some(sync_op),
+        user_writes: false,
     }
 }
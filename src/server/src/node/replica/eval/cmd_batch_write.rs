@@ -16,7 +16,7 @@ use engula_api::server::v1::BatchWriteRequest;
 use crate::{
     engine::{GroupEngine, WriteBatch},
     node::replica::ExecCtx,
-    serverpb::v1::{EvalResult, WriteBatchRep},
+    serverpb::v1::EvalResult,
     Error, Result,
 };
 
@@ -57,9 +57,8 @@ pub(crate) async fn batch_write(
         )?;
     }
     Ok(Some(EvalResult {
-        batch: Some(WriteBatchRep {
-            data: wb.data().to_owned(),
-        }),
+        batch: Some(wb.rep()),
+        user_writes: true,
         ..Default::default()
     }))
 }
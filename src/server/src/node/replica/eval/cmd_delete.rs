@@ -17,7 +17,7 @@ use engula_api::server::v1::ShardDeleteRequest;
 use crate::{
     engine::{GroupEngine, SnapshotMode, WriteBatch},
     node::{migrate::ForwardCtx, replica::ExecCtx},
-    serverpb::v1::{EvalResult, WriteBatchRep},
+    serverpb::v1::EvalResult,
     Error, Result,
 };
 
@@ -53,9 +53,8 @@ pub(crate) async fn delete(
         group_engine.delete(&mut wb, req.shard_id, &delete.key, super::FLAT_KEY_VERSION)?;
     }
     Ok(EvalResult {
-        batch: Some(WriteBatchRep {
-            data: wb.data().to_owned(),
-        }),
+        batch: Some(wb.rep()),
+        user_writes: true,
         ..Default::default()
     })
 }
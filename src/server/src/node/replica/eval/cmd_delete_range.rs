@@ -0,0 +1,50 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use engula_api::server::v1::ShardDeleteRangeRequest;
+
+use crate::{
+    engine::{GroupEngine, WriteBatch},
+    error::BusyReason,
+    node::replica::ExecCtx,
+    serverpb::v1::EvalResult,
+    Error, Result,
+};
+
+pub(crate) async fn delete_range(
+    exec_ctx: &ExecCtx,
+    group_engine: &GroupEngine,
+    req: &ShardDeleteRangeRequest,
+) -> Result<EvalResult> {
+    // The range deletion can't be forwarded to the dest group, because the keys of the range might
+    // not be ingested yet. The migration state updates hold the write acl guard, so the migration
+    // can't be started until this request is proposed.
+    if exec_ctx.forward_shard_id.is_some() || exec_ctx.is_migrating_shard(req.shard_id) {
+        return Err(Error::ServiceIsBusy(BusyReason::Migrating));
+    }
+
+    if !req.start_key.is_empty() && !req.end_key.is_empty() && req.start_key >= req.end_key {
+        return Err(Error::InvalidArgument(
+            "ShardDeleteRangeRequest::start_key should be less than end_key".into(),
+        ));
+    }
+
+    let mut wb = WriteBatch::default();
+    group_engine.delete_range(&mut wb, req.shard_id, &req.start_key, &req.end_key)?;
+    Ok(EvalResult {
+        batch: Some(wb.rep()),
+        user_writes: true,
+        ..Default::default()
+    })
+}
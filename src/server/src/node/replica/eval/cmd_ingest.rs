@@ -0,0 +1,42 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use engula_api::server::v1::ShardIngestRequest;
+
+use crate::{
+    engine::{GroupEngine, WriteBatch},
+    serverpb::v1::EvalResult,
+    Result,
+};
+
+/// Write the shard data with the versions they carry. The data copied from the source shards of
+/// a resharding collection are tagged with `MIGRATING_KEY_VERSION`, so they never overwrite the
+/// dual-written keys.
+pub(crate) async fn ingest(
+    group_engine: &GroupEngine,
+    req: &ShardIngestRequest,
+) -> Result<Option<EvalResult>> {
+    if req.data.is_empty() {
+        return Ok(None);
+    }
+
+    let mut wb = WriteBatch::default();
+    for data in &req.data {
+        group_engine.put(&mut wb, req.shard_id, &data.key, &data.value, data.version)?;
+    }
+    Ok(Some(EvalResult {
+        batch: Some(wb.rep()),
+        ..Default::default()
+    }))
+}
@@ -17,7 +17,7 @@ use engula_api::server::v1::ShardPutRequest;
 use crate::{
     engine::{GroupEngine, WriteBatch},
     node::{migrate::ForwardCtx, replica::ExecCtx},
-    serverpb::v1::{EvalResult, WriteBatchRep},
+    serverpb::v1::EvalResult,
     Error, Result,
 };
 
@@ -52,9 +52,8 @@ pub(crate) async fn put(
         super::FLAT_KEY_VERSION,
     )?;
     Ok(EvalResult {
-        batch: Some(WriteBatchRep {
-            data: wb.data().to_owned(),
-        }),
+        batch: Some(wb.rep()),
+        user_writes: true,
         ..Default::default()
     })
 }
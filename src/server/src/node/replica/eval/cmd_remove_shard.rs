@@ -0,0 +1,43 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use engula_api::server::v1::RemoveShardRequest;
+
+use crate::{
+    engine::{GroupEngine, WriteBatch},
+    error::BusyReason,
+    node::replica::ExecCtx,
+    serverpb::v1::{EvalResult, SyncOp},
+    Error, Result,
+};
+
+/// Remove the shard from the group descriptor, and delete all its data in the same batch. So that
+/// the data of a removed shard won't be read or be mixed into a new shard with the same key range.
+pub(crate) fn remove_shard(
+    exec_ctx: &ExecCtx,
+    group_engine: &GroupEngine,
+    req: &RemoveShardRequest,
+) -> Result<EvalResult> {
+    if exec_ctx.forward_shard_id.is_some() || exec_ctx.is_migrating_shard(req.shard_id) {
+        return Err(Error::ServiceIsBusy(BusyReason::Migrating));
+    }
+
+    let mut wb = WriteBatch::default();
+    group_engine.delete_shard(&mut wb, req.shard_id)?;
+    Ok(EvalResult {
+        batch: Some(wb.rep()),
+        op: Some(SyncOp::remove_shard(req.shard_id)),
+        ..Default::default()
+    })
+}
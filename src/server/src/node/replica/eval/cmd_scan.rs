@@ -0,0 +1,107 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use engula_api::{
+    server::v1::{ShardData, ShardScanRequest, ShardScanResponse},
+    shard,
+};
+
+use crate::{
+    engine::{GroupEngine, SnapshotMode},
+    Error, Result,
+};
+
+/// Scan the key-value pairs of the specified range or prefix of a shard.
+pub(crate) async fn scan(
+    engine: &GroupEngine,
+    req: &ShardScanRequest,
+) -> Result<ShardScanResponse> {
+    let desc = engine
+        .descriptor()
+        .shards
+        .into_iter()
+        .find(|s| s.id == req.shard_id)
+        .ok_or(Error::ShardNotFound(req.shard_id))?;
+
+    let start_key = std::cmp::max(req.start_key.as_slice(), req.prefix.as_slice());
+    let snapshot_mode = if req.reverse {
+        // The end key is excluded, so it is also allowed to seek for prev from the shard end key.
+        let end_key = req.end_key.as_slice();
+        if shard::slot(&desc).is_none()
+            && !end_key.is_empty()
+            && shard::in_range(&shard::start_key(&desc), &shard::end_key(&desc), end_key)
+        {
+            SnapshotMode::End {
+                end_key: Some(end_key),
+            }
+        } else {
+            SnapshotMode::End { end_key: None }
+        }
+    } else if shard::slot(&desc).is_some() || shard::belong_to(&desc, start_key) {
+        // The user keys of a hash shard are ordered in the same slot, so it could seek to the start
+        // key even if it doesn't belong to this shard.
+        SnapshotMode::Start {
+            start_key: Some(start_key),
+        }
+    } else {
+        SnapshotMode::Start { start_key: None }
+    };
+
+    let mut snapshot = engine.snapshot(req.shard_id, snapshot_mode)?;
+    let mut data = Vec::new();
+    for mvcc_iter in snapshot.iter() {
+        let mut mvcc_iter = mvcc_iter?;
+        let entry = match mvcc_iter.next() {
+            Some(entry) => entry?,
+            None => continue,
+        };
+        let key = entry.user_key();
+        let is_before_start = key < start_key;
+        let is_after_end = !req.end_key.is_empty() && key >= req.end_key.as_slice();
+        if req.reverse {
+            if is_after_end {
+                continue;
+            }
+            if is_before_start {
+                break;
+            }
+            if !key.starts_with(&req.prefix) {
+                // The key is larger than all keys with the prefix.
+                continue;
+            }
+        } else {
+            if is_before_start {
+                continue;
+            }
+            if is_after_end {
+                break;
+            }
+            if !key.starts_with(&req.prefix) {
+                // All keys with the prefix have been consumed.
+                break;
+            }
+        }
+        if let Some(value) = entry.value() {
+            data.push(ShardData {
+                key: key.to_owned(),
+                value: value.to_owned(),
+                version: entry.version(),
+            });
+            if req.limit != 0 && data.len() as u64 >= req.limit {
+                break;
+            }
+        }
+    }
+    Ok(ShardScanResponse { data })
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 use std::path::Path;
 
-use engula_api::server::v1::GroupDesc;
+use engula_api::server::v1::{GroupDesc, ReplicaRole};
 use tracing::{debug, error, info};
 
 use crate::{
@@ -25,20 +25,38 @@ use crate::{
 
 pub struct GroupSnapshotBuilder {
     cfg: ReplicaConfig,
+    replica_id: u64,
     engine: GroupEngine,
 }
 
 impl GroupSnapshotBuilder {
-    pub(crate) fn new(cfg: ReplicaConfig, engine: GroupEngine) -> Self {
-        GroupSnapshotBuilder { cfg, engine }
+    pub(crate) fn new(cfg: ReplicaConfig, replica_id: u64, engine: GroupEngine) -> Self {
+        GroupSnapshotBuilder {
+            cfg,
+            replica_id,
+            engine,
+        }
     }
 }
 
 #[crate::async_trait]
 impl SnapshotBuilder for GroupSnapshotBuilder {
     async fn checkpoint(&self, base_dir: &Path) -> Result<(ApplyState, GroupDesc)> {
-        std::fs::create_dir_all(base_dir)?;
         let mut iter = self.engine.raw_iter()?;
+        let is_witness = iter
+            .descriptor()
+            .replicas
+            .iter()
+            .any(|r| r.id == self.replica_id && r.role == ReplicaRole::Witness as i32);
+        if is_witness {
+            // The witness doesn't hold any data.
+            return Err(Error::InvalidArgument(format!(
+                "witness {} can't build snapshot",
+                self.replica_id
+            )));
+        }
+
+        std::fs::create_dir_all(base_dir)?;
         for i in 0.. {
             if write_partial_to_file(&self.cfg, &mut iter, base_dir, i)
                 .await?
@@ -239,7 +257,7 @@ mod tests {
             };
             std::fs::create_dir_all(&snap_dir).unwrap();
             let data = snap_dir.join("DATA");
-            let builder = GroupSnapshotBuilder::new(cfg, engine);
+            let builder = GroupSnapshotBuilder::new(cfg, 1, engine);
             builder.checkpoint(&data).await.unwrap();
         });
     }
@@ -265,7 +283,7 @@ mod tests {
             };
             std::fs::create_dir_all(&snap_dir).unwrap();
             let data = snap_dir.join("DATA");
-            let builder = GroupSnapshotBuilder::new(cfg, engine.clone());
+            let builder = GroupSnapshotBuilder::new(cfg, 1, engine.clone());
             builder.checkpoint(&data).await.unwrap();
             apply_snapshot(&engine, 1, &data).unwrap();
         });
@@ -20,11 +20,12 @@ use engula_api::server::v1::{
     ChangeReplica, ChangeReplicaType, ChangeReplicas, GroupDesc, MigrationDesc, ReplicaDesc,
     ReplicaRole,
 };
-use tracing::{info, trace, warn};
+use tracing::{error, info, trace, warn};
 
-use super::ReplicaInfo;
+use super::{eval::FLAT_KEY_VERSION, ReplicaInfo};
 use crate::{
-    engine::{GroupEngine, WriteBatch, WriteStates},
+    engine::{GroupEngine, UserWrite, WriteBatch, WriteStates},
+    node::replication::{is_replicated_collection, ReplicationManager},
     raftgroup::{ApplyEntry, SnapshotBuilder, StateMachine},
     serverpb::v1::*,
     ReplicaConfig, Result,
@@ -50,6 +51,25 @@ pub trait StateMachineObserver: Send + Sync {
 
     /// This function will be called once the migrate state changes.
     fn on_migrate_state_updated(&mut self, migrate_state: Option<MigrationState>);
+
+    /// Return a function to report the checksum of a consistency check. The checksum is
+    /// computed in background, so the function is called outside of the state machine.
+    fn checksum_reporter(&self) -> Box<dyn FnOnce(ChecksumResult) + Send>;
+
+    /// This function will be called every time a batch of user writes is recorded in the
+    /// replication log, see `EvalResult::user_writes`.
+    fn on_user_writes_applied(&mut self, index: u64, writes: &[UserWrite]);
+
+    /// This function will be called once the replication log is truncated up to the index.
+    fn on_replication_shipped(&mut self, index: u64);
+}
+
+/// The checksum of group engine computed at the applied index of a consistency check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumResult {
+    pub check_id: u64,
+    pub index: u64,
+    pub checksum: u32,
 }
 
 pub struct GroupStateMachine
@@ -69,6 +89,10 @@ where
     desc_updated: bool,
     migration_state_updated: bool,
     last_applied_term: u64,
+    /// Whether the local replica is a witness, which doesn't apply any data.
+    witness: bool,
+    /// Records the user writes in the replication log if it is enabled, see `ReplicationManager`.
+    replication: Option<ReplicationManager>,
 }
 
 impl GroupStateMachine {
@@ -77,10 +101,12 @@ impl GroupStateMachine {
         info: Arc<ReplicaInfo>,
         group_engine: GroupEngine,
         observer: Box<dyn StateMachineObserver>,
+        replication: Option<ReplicationManager>,
     ) -> Self {
         let apply_state = group_engine
             .flushed_apply_state()
             .expect("access flushed index");
+        let witness = is_witness(&group_engine.descriptor(), info.replica_id);
         GroupStateMachine {
             cfg,
             info,
@@ -91,6 +117,8 @@ impl GroupStateMachine {
             desc_updated: false,
             migration_state_updated: false,
             last_applied_term: apply_state.term,
+            witness,
+            replication,
         }
     }
 }
@@ -109,33 +137,77 @@ impl GroupStateMachine {
             }
         }
         desc.epoch += CONFIG_CHANGE_DELTA;
+        let witness = is_witness(&desc, local_id);
+        if witness && !self.witness {
+            info!(
+                "group {} replica {local_id} becomes witness, drop user data",
+                self.info.group_id
+            );
+            // The user data is deleted with the new descriptor in the same commit.
+            let mut wb = WriteBatch::default();
+            self.group_engine.delete_user_data(&mut wb);
+            self.plugged_write_batches.clear();
+            self.plugged_write_batches.push(wb);
+        }
+        self.witness = witness;
         self.desc_updated = true;
         self.plugged_write_states.descriptor = Some(desc);
 
         Ok(())
     }
 
-    fn apply_proposal(&mut self, eval_result: EvalResult) -> Result<()> {
+    fn apply_proposal(&mut self, index: u64, eval_result: EvalResult) -> Result<()> {
         if let Some(wb) = eval_result.batch {
-            self.plugged_write_batches.push(WriteBatch::new(&wb.data));
+            // A witness only persists the group states.
+            if !self.witness {
+                let wb = WriteBatch::from_rep(wb);
+                let recording = self
+                    .replication
+                    .as_ref()
+                    .map(ReplicationManager::is_recording)
+                    .unwrap_or_default();
+                if eval_result.user_writes && recording {
+                    self.append_replication_log(index, &wb);
+                }
+                self.plugged_write_batches.push(wb);
+            }
         }
 
         if let Some(op) = eval_result.op {
+            if let Some(ReplicationShipped { index }) = op.replication_shipped {
+                let mut wb = WriteBatch::default();
+                self.group_engine.truncate_replication_log(&mut wb, index);
+                self.plugged_write_batches.push(wb);
+                self.observer.on_replication_shipped(index);
+                return Ok(());
+            }
+
             let mut desc = self.descriptor();
             if let Some(AddShard { shard: Some(shard) }) = op.add_shard {
-                for existed_shard in &desc.shards {
-                    if existed_shard.id == shard.id {
-                        todo!("shard {} already existed in group", shard.id);
-                    }
+                self.desc_updated = true;
+                desc.epoch += SHARD_UPDATE_DELTA;
+                if let Some(existed_shard) = desc.shards.iter_mut().find(|s| s.id == shard.id) {
+                    info!("group {} update shard {}", self.info.group_id, shard.id);
+                    *existed_shard = shard;
+                } else {
+                    info!("group {} add shard {}", self.info.group_id, shard.id);
+                    desc.shards.push(shard);
                 }
-                info!("group {} add shard {}", self.info.group_id, shard.id);
+            }
+            if let Some(RemoveShard { shard_id }) = op.remove_shard {
+                info!("group {} remove shard {}", self.info.group_id, shard_id);
                 self.desc_updated = true;
                 desc.epoch += SHARD_UPDATE_DELTA;
-                desc.shards.push(shard);
+                desc.shards.retain(|s| s.id != shard_id);
             }
             if let Some(m) = op.migration {
                 self.apply_migration_event(m, &mut desc);
             }
+            if op.compute_checksum.is_some() {
+                // The checksum is computed after all plugged writes are committed, see
+                // `GroupStateMachine::apply`.
+                return Ok(());
+            }
 
             // Any sync_op will update group desc.
             self.plugged_write_states.descriptor = Some(desc);
@@ -144,6 +216,22 @@ impl GroupStateMachine {
         Ok(())
     }
 
+    /// Record the user writes in the replication log. The log is committed with the writes
+    /// atomically, so no write is lost even if the node restarts or the leader changes.
+    fn append_replication_log(&mut self, index: u64, wb: &WriteBatch) {
+        let mut writes = self.group_engine.user_writes(wb, FLAT_KEY_VERSION);
+        writes.retain(|w| is_replicated_collection(self.info.group_id, w.collection_id()));
+        if writes.is_empty() {
+            return;
+        }
+
+        let mut log_wb = WriteBatch::default();
+        self.group_engine
+            .append_replication_log(&mut log_wb, index, &writes);
+        self.plugged_write_batches.push(log_wb);
+        self.observer.on_user_writes_applied(index, &writes);
+    }
+
     fn apply_migration_event(&mut self, migration: Migration, group_desc: &mut GroupDesc) {
         let event = MigrationEvent::from_i32(migration.event).expect("unknown migration event");
         if let Some(desc) = migration.migration_desc.as_ref() {
@@ -267,6 +355,48 @@ impl GroupStateMachine {
         }
     }
 
+    /// Compute the checksum at the applied index. Scanning all data is expensive, so only the
+    /// iterator, which pins a consistent view of the group engine, is created before returning,
+    /// the data is scanned in background.
+    fn compute_checksum(&mut self, check_id: u64, index: u64) {
+        if self.witness {
+            return;
+        }
+
+        let info = self.info.clone();
+        let group_engine = self.group_engine.clone();
+        let reporter = self.observer.checksum_reporter();
+        let (pinned_tx, pinned_rx) = std::sync::mpsc::sync_channel(1);
+        crate::runtime::current().spawn_blocking(move || {
+            let iter = group_engine.raw_iter();
+            pinned_tx.send(()).unwrap_or_default();
+            let result = iter.and_then(|iter| {
+                debug_assert_eq!(iter.apply_state().index, index);
+                iter.checksum()
+            });
+            match result {
+                Ok(checksum) => {
+                    info!(
+                        "group {} replica {} compute checksum {checksum} of check {check_id} at index {index}",
+                        info.group_id, info.replica_id
+                    );
+                    reporter(ChecksumResult {
+                        check_id,
+                        index,
+                        checksum,
+                    });
+                }
+                Err(err) => {
+                    error!(
+                        "group {} replica {} compute checksum of check {check_id}: {err}",
+                        info.group_id, info.replica_id
+                    );
+                }
+            }
+        });
+        pinned_rx.recv().unwrap_or_default();
+    }
+
     #[inline]
     fn flushed_apply_state(&self) -> ApplyState {
         self.group_engine
@@ -304,7 +434,18 @@ impl StateMachine for GroupStateMachine {
                 self.apply_change_replicas(change_replicas)?;
             }
             ApplyEntry::Proposal { eval_result } => {
-                self.apply_proposal(eval_result)?;
+                let compute_checksum = eval_result
+                    .op
+                    .as_ref()
+                    .and_then(|op| op.compute_checksum.clone());
+                self.apply_proposal(index, eval_result)?;
+                if let Some(ComputeChecksum { check_id }) = compute_checksum {
+                    // Commit the plugged writes, so the checksum covers all entries up to this
+                    // index.
+                    self.plugged_write_states.apply_state = Some(ApplyState { index, term });
+                    self.finish_plug()?;
+                    self.compute_checksum(check_id, index);
+                }
             }
         }
         self.plugged_write_states.apply_state = Some(ApplyState { index, term });
@@ -316,19 +457,32 @@ impl StateMachine for GroupStateMachine {
         let Some(ApplyState { term, .. }) = self.plugged_write_states.apply_state else {
             panic!("invoke GroupStateMachine::finish_plug but WriteStates::apply_states is None");
         };
-        self.group_engine.group_commit(
-            self.plugged_write_batches.as_slice(),
-            std::mem::take(&mut self.plugged_write_states),
-            false,
-        )?;
-        self.plugged_write_batches.clear();
+        // The plugged writes are taken before committing, so that nothing is left to the next
+        // plug if the commit fails.
+        let mut write_batches = std::mem::take(&mut self.plugged_write_batches);
+        let write_states = std::mem::take(&mut self.plugged_write_states);
+        crate::failpoint::eval("fsm_before_commit")?;
+        self.group_engine
+            .group_commit(write_batches.as_slice(), write_states, false)?;
+        crate::failpoint::eval("fsm_after_commit")?;
+        write_batches.clear();
+        self.plugged_write_batches = write_batches;
         self.flush_updated_events(term);
 
         Ok(())
     }
 
     fn apply_snapshot(&mut self, snap_dir: &Path) -> Result<()> {
-        checkpoint::apply_snapshot(&self.group_engine, self.info.replica_id, snap_dir)?;
+        let replica_id = self.info.replica_id;
+        checkpoint::apply_snapshot(&self.group_engine, replica_id, snap_dir)?;
+        self.witness = is_witness(&self.group_engine.descriptor(), replica_id);
+        if self.witness {
+            info!(
+                "group {} replica {replica_id} is witness, drop user data of snapshot",
+                self.info.group_id
+            );
+            self.group_engine.drop_user_data()?;
+        }
         self.observer
             .on_descriptor_updated(self.group_engine.descriptor());
         let apply_state = self.flushed_apply_state();
@@ -339,6 +493,7 @@ impl StateMachine for GroupStateMachine {
     fn snapshot_builder(&self) -> Box<dyn SnapshotBuilder> {
         Box::new(checkpoint::GroupSnapshotBuilder::new(
             self.cfg.clone(),
+            self.info.replica_id,
             self.group_engine.clone(),
         ))
     }
@@ -402,6 +557,30 @@ fn apply_simple_change(local_id: u64, desc: &mut GroupDesc, change: &ChangeRepli
                 });
             }
         }
+        Some(ChangeReplicaType::AddWitness) => {
+            info!("group {group_id} replica {local_id} add witness {replica_id}");
+            if let Some(replica) = exist {
+                replica.role = ReplicaRole::Witness.into();
+            } else {
+                desc.replicas.push(ReplicaDesc {
+                    id: replica_id,
+                    node_id,
+                    role: ReplicaRole::Witness.into(),
+                });
+            }
+        }
+        Some(ChangeReplicaType::AddReadLearner) => {
+            info!("group {group_id} replica {local_id} add read learner {replica_id}");
+            if let Some(replica) = exist {
+                replica.role = ReplicaRole::ReadLearner.into();
+            } else {
+                desc.replicas.push(ReplicaDesc {
+                    id: replica_id,
+                    node_id,
+                    role: ReplicaRole::ReadLearner.into(),
+                });
+            }
+        }
         Some(ChangeReplicaType::Remove) => {
             info!("group {group_id} replica {local_id} remove voter {replica_id}");
             desc.replicas.drain_filter(|rep| rep.id == replica_id);
@@ -486,31 +665,49 @@ fn apply_leave_joint(local_id: u64, desc: &mut GroupDesc) {
 fn group_role_digest(desc: &GroupDesc) -> String {
     let mut voters = vec![];
     let mut learners = vec![];
+    let mut witnesses = vec![];
+    let mut read_learners = vec![];
     for r in &desc.replicas {
         match ReplicaRole::from_i32(r.role) {
             Some(ReplicaRole::Voter | ReplicaRole::IncomingVoter | ReplicaRole::DemotingVoter) => {
                 voters.push(r.id)
             }
             Some(ReplicaRole::Learner) => learners.push(r.id),
+            Some(ReplicaRole::Witness) => witnesses.push(r.id),
+            Some(ReplicaRole::ReadLearner) => read_learners.push(r.id),
             _ => continue,
         }
     }
-    format!("voters {voters:?} learners {learners:?}")
+    format!(
+        "voters {voters:?} learners {learners:?} witnesses {witnesses:?} read learners {read_learners:?}"
+    )
 }
 
 fn change_replicas_digest(changes: &[ChangeReplica]) -> String {
     let mut add_voters = vec![];
     let mut remove_replicas = vec![];
     let mut add_learners = vec![];
+    let mut add_witnesses = vec![];
+    let mut add_read_learners = vec![];
     for cc in changes {
         match ChangeReplicaType::from_i32(cc.change_type) {
             Some(ChangeReplicaType::Add) => add_voters.push(cc.replica_id),
             Some(ChangeReplicaType::AddLearner) => add_learners.push(cc.replica_id),
+            Some(ChangeReplicaType::AddWitness) => add_witnesses.push(cc.replica_id),
+            Some(ChangeReplicaType::AddReadLearner) => add_read_learners.push(cc.replica_id),
             Some(ChangeReplicaType::Remove) => remove_replicas.push(cc.replica_id),
             _ => continue,
         }
     }
-    format!("add voters {add_voters:?} learners {add_learners:?} remove {remove_replicas:?}")
+    format!(
+        "add voters {add_voters:?} learners {add_learners:?} witnesses {add_witnesses:?} read learners {add_read_learners:?} remove {remove_replicas:?}"
+    )
+}
+
+fn is_witness(desc: &GroupDesc, replica_id: u64) -> bool {
+    desc.replicas
+        .iter()
+        .any(|r| r.id == replica_id && r.role == ReplicaRole::Witness as i32)
 }
 
 fn find_replica_mut(desc: &mut GroupDesc, replica_id: u64) -> Option<&mut ReplicaDesc> {
@@ -615,6 +812,32 @@ mod tests {
                 replica_id: 2,
                 expects: vec![(1, ReplicaRole::Learner)],
             },
+            Test {
+                tips: "9. add not exists witness",
+                change_type: ChangeReplicaType::AddWitness,
+                replica_id: 3,
+                expects: vec![
+                    (1, ReplicaRole::Learner),
+                    (2, ReplicaRole::Voter),
+                    (3, ReplicaRole::Witness),
+                ],
+            },
+            Test {
+                tips: "10. demote voter to witness",
+                change_type: ChangeReplicaType::AddWitness,
+                replica_id: 2,
+                expects: vec![(1, ReplicaRole::Learner), (2, ReplicaRole::Witness)],
+            },
+            Test {
+                tips: "11. add not exists read learner",
+                change_type: ChangeReplicaType::AddReadLearner,
+                replica_id: 3,
+                expects: vec![
+                    (1, ReplicaRole::Learner),
+                    (2, ReplicaRole::Voter),
+                    (3, ReplicaRole::ReadLearner),
+                ],
+            },
         ];
 
         let base_group_desc = GroupDesc {
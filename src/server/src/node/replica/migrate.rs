@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use engula_api::server::v1::*;
 use tracing::{debug, info};
 
 use super::{LeaseState, Replica, ReplicaInfo};
 use crate::{
     engine::{SnapshotMode, WriteBatch},
+    error::BusyReason,
     serverpb::v1::*,
     Error, Result,
 };
@@ -28,9 +31,14 @@ impl Replica {
         shard_id: u64,
         last_key: &[u8],
         chunk_size: usize,
+        resharding: bool,
     ) -> Result<ShardChunk> {
         let _acl_guard = self.take_read_acl_guard().await;
-        self.check_migrating_request_early(shard_id)?;
+        if resharding {
+            self.check_resharding_request_early(shard_id)?;
+        } else {
+            self.check_migrating_request_early(shard_id)?;
+        }
 
         let mut kvs = vec![];
         let mut size = 0;
@@ -97,10 +105,9 @@ impl Replica {
         };
 
         let eval_result = EvalResult {
-            batch: Some(WriteBatchRep {
-                data: wb.data().to_owned(),
-            }),
+            batch: Some(wb.rep()),
             op: sync_op,
+            user_writes: false,
         };
         self.raft_node.clone().propose(eval_result).await?;
 
@@ -121,10 +128,9 @@ impl Replica {
         }
 
         let eval_result = EvalResult {
-            batch: Some(WriteBatchRep {
-                data: wb.data().to_owned(),
-            }),
+            batch: Some(wb.rep()),
             op: None,
+            user_writes: false,
         };
         self.raft_node.clone().propose(eval_result).await?;
 
@@ -132,6 +138,13 @@ impl Replica {
     }
 
     pub async fn setup_migration(&self, desc: &MigrationDesc) -> Result<()> {
+        // The writes of the migrating shard are shipped to the standby cluster by the dest group
+        // since now, so the former writes must be shipped first to keep the order of writes.
+        let shard_id = desc.get_shard_desc().id;
+        while self.group_engine.has_replication_log_of_shard(shard_id)? {
+            self.check_leader_early()?;
+            crate::runtime::time::sleep(Duration::from_millis(10)).await;
+        }
         self.update_migration_state(desc, MigrationEvent::Setup)
             .await
     }
@@ -171,11 +184,20 @@ impl Replica {
         if !self.check_migration_state_update_early(desc, event)? {
             return Ok(());
         }
+        if event == MigrationEvent::Setup
+            && self
+                .group_engine
+                .has_replication_log_of_shard(desc.get_shard_desc().id)?
+        {
+            // The writes are applied before the acl guard is taken.
+            return Err(Error::ServiceIsBusy(BusyReason::Replicating));
+        }
 
         let sync_op = SyncOp::migration(event, desc.clone());
         let eval_result = EvalResult {
             batch: None,
             op: Some(sync_op),
+            user_writes: false,
         };
         self.raft_node.clone().propose(eval_result).await?;
 
@@ -197,6 +219,30 @@ impl Replica {
         }
     }
 
+    /// Resharding copies the data of a serving shard, so the shard must exists and not be
+    /// migrating, otherwise the copied data might be stale.
+    pub fn check_resharding_request_early(&self, shard_id: u64) -> Result<()> {
+        let lease_state = self.lease_state.lock().unwrap();
+        if !lease_state.is_ready_for_serving() {
+            Err(Error::NotLeader(
+                self.info.group_id,
+                lease_state.applied_term,
+                lease_state.leader_descriptor(),
+            ))
+        } else if lease_state.is_migrating_shard(shard_id) {
+            Err(Error::ServiceIsBusy(BusyReason::Migrating))
+        } else if !lease_state
+            .descriptor
+            .shards
+            .iter()
+            .any(|shard| shard.id == shard_id)
+        {
+            Err(Error::ShardNotFound(shard_id))
+        } else {
+            Ok(())
+        }
+    }
+
     fn check_migration_state_update_early(
         &self,
         desc: &MigrationDesc,
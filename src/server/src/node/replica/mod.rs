@@ -21,20 +21,26 @@ mod state;
 use std::{
     sync::{atomic::AtomicI32, Arc, Mutex},
     task::Poll,
+    time::Duration,
 };
 
 use engula_api::{
     server::v1::{group_request_union::Request, group_response_union::Response, *},
+    shard,
     v1::{DeleteResponse, GetResponse, PutResponse},
 };
 use serde::Serialize;
 use tracing::info;
 
-pub use self::state::{LeaseState, LeaseStateObserver};
+pub use self::{
+    fsm::ChecksumResult,
+    state::{LeaseState, LeaseStateObserver},
+};
 pub use crate::raftgroup::RaftNodeFacade as RaftSender;
 use crate::{
     engine::GroupEngine,
     error::BusyReason,
+    node::quota::RequestQuota,
     raftgroup::{
         perf_point_micros, write_initial_state, RaftManager, RaftNodeFacade, ReadPolicy,
         WorkerPerfContext,
@@ -44,6 +50,12 @@ use crate::{
     Error, Result,
 };
 
+/// The max duration of waiting for the read index of a follower read.
+const FOLLOWER_READ_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The number of latches serializing the writes of resharding shards, the keys are hashed to them.
+const RESHARDING_LATCHES: u32 = 64;
+
 #[derive(Debug, Default, Clone, Serialize)]
 pub struct ReplicaPerfContext {
     pub raft: Box<WorkerPerfContext>,
@@ -76,6 +88,20 @@ pub struct ExecCtx {
 
     /// The migration desc, filled by `check_request_early`.
     migration_desc: Option<MigrationDesc>,
+
+    /// The quota of the user request, it is acquired once the request passes the early checks.
+    pub quota: Option<RequestQuota>,
+
+    /// The write of a resharding shard, filled by `take_resharding_latch`.
+    resharding_write: Option<Arc<ReshardingWrite>>,
+}
+
+/// A write of a resharding shard, it should be forwarded to the shard of target slots before the
+/// latch of the key is released, so the writes of the same key are applied to both shards in the
+/// same order.
+pub struct ReshardingWrite {
+    pub shard: ShardDesc,
+    _latch: tokio::sync::OwnedMutexGuard<()>,
 }
 
 pub struct Replica
@@ -88,6 +114,7 @@ where
     lease_state: Arc<Mutex<LeaseState>>,
     move_replicas_provider: Arc<MoveReplicasProvider>,
     meta_acl: Arc<tokio::sync::RwLock<()>>,
+    resharding_latches: Vec<Arc<tokio::sync::Mutex<()>>>,
 }
 
 impl Replica {
@@ -129,6 +156,7 @@ impl Replica {
             lease_state,
             move_replicas_provider,
             meta_acl: Arc::default(),
+            resharding_latches: (0..RESHARDING_LATCHES).map(|_| Arc::default()).collect(),
         }
     }
 
@@ -159,7 +187,12 @@ impl Replica {
         }
 
         let _acl_guard = self.take_acl_guard(request).await;
+        if self.is_follower_read(request) {
+            return self.execute_follower_read(exec_ctx, request).await;
+        }
         self.check_request_early(exec_ctx, request)?;
+        exec_ctx.acquire_quota()?;
+        self.take_resharding_latch(exec_ctx, request).await;
         self.evaluate_command(exec_ctx, request).await
     }
 
@@ -177,7 +210,11 @@ impl Replica {
         let _acl_guard = self
             .try_take_acl_guard(request)
             .ok_or(Error::ServiceIsBusy(BusyReason::AclGuard))?;
+        if self.is_follower_read(request) {
+            return self.execute_follower_read(&mut exec_ctx, request).await;
+        }
         self.check_request_early(&mut exec_ctx, request)?;
+        exec_ctx.acquire_quota()?;
         self.evaluate_command(&exec_ctx, request).await
     }
 
@@ -253,6 +290,42 @@ impl Replica {
         self.lease_state.lock().unwrap().schedule_state.clone()
     }
 
+    /// Propose a consistency check, each replica computes the checksum of the group engine once
+    /// the check is applied.
+    pub async fn compute_checksum(&self, check_id: u64) -> Result<()> {
+        self.check_leader_early()?;
+        let eval_result = EvalResult {
+            batch: None,
+            op: Some(SyncOp::compute_checksum(check_id)),
+            user_writes: false,
+        };
+        self.raft_node.clone().propose(eval_result).await?;
+        Ok(())
+    }
+
+    /// Propose to truncate the replication log up to the index, whose writes have been shipped to
+    /// the standby cluster.
+    pub async fn truncate_replication_log(&self, index: u64) -> Result<()> {
+        self.check_leader_early()?;
+        let eval_result = EvalResult {
+            batch: None,
+            op: Some(SyncOp::replication_shipped(index)),
+            user_writes: false,
+        };
+        self.raft_node.clone().propose(eval_result).await?;
+        Ok(())
+    }
+
+    /// Return the checksum computed by the specified consistency check, `None` is returned if
+    /// the check has not been applied yet.
+    #[inline]
+    pub fn checksum(&self, check_id: u64) -> Option<ChecksumResult> {
+        let lease_state = self.lease_state.lock().unwrap();
+        lease_state
+            .last_checksum
+            .filter(|checksum| checksum.check_id == check_id)
+    }
+
     pub async fn monitor(&self) -> Result<ReplicaPerfContext> {
         let take_acl_guard = perf_point_micros();
         let _acl_guard = self.take_read_acl_guard().await;
@@ -314,26 +387,69 @@ impl Replica {
                 let eval_result = eval::delete(exec_ctx, &self.group_engine, req).await?;
                 (Some(eval_result), Response::Delete(DeleteResponse {}))
             }
+            Request::DeleteRange(req) => {
+                let eval_result = eval::delete_range(exec_ctx, &self.group_engine, req).await?;
+                (
+                    Some(eval_result),
+                    Response::DeleteRange(ShardDeleteRangeResponse {}),
+                )
+            }
             Request::PrefixList(req) => {
                 let eval_result = eval::prefix_list(&self.group_engine, req).await?;
                 (None, Response::PrefixList(eval_result))
             }
+            Request::Scan(req) => {
+                let eval_result = eval::scan(&self.group_engine, req).await?;
+                (None, Response::Scan(eval_result))
+            }
             Request::BatchWrite(req) => {
                 let eval_result = eval::batch_write(exec_ctx, &self.group_engine, req).await?;
                 (eval_result, Response::BatchWrite(BatchWriteResponse {}))
             }
+            Request::Ingest(req) => {
+                let eval_result = eval::ingest(&self.group_engine, req).await?;
+                (eval_result, Response::Ingest(ShardIngestResponse {}))
+            }
             Request::CreateShard(req) => {
-                // TODO(walter) check the existing of shard.
                 let shard = req
                     .shard
                     .as_ref()
                     .cloned()
                     .ok_or_else(|| Error::InvalidArgument("CreateShard::shard".into()))?;
                 let resp = CreateShardResponse {};
+                let desc = self.descriptor();
+                match desc.shards.iter().find(|s| s.id == shard.id) {
+                    Some(existed) if *existed == shard => {
+                        // The shard is already created or updated.
+                        return Ok(Response::CreateShard(resp));
+                    }
+                    Some(_) if !req.update => {
+                        return Err(Error::AlreadyExists(format!("shard {}", shard.id)));
+                    }
+                    None if req.update => return Err(Error::ShardNotFound(shard.id)),
+                    _ => {}
+                }
+                check_shard_key_conflict(&desc, &shard)?;
                 (Some(eval::add_shard(shard)), Response::CreateShard(resp))
             }
+            Request::RemoveShard(req) => {
+                let resp = Response::RemoveShard(RemoveShardResponse {});
+                if !self
+                    .descriptor()
+                    .shards
+                    .iter()
+                    .any(|s| s.id == req.shard_id)
+                {
+                    // The shard is already removed.
+                    return Ok(resp);
+                }
+                let eval_result = eval::remove_shard(exec_ctx, &self.group_engine, req)?;
+                (Some(eval_result), resp)
+            }
             Request::ChangeReplicas(req) => {
                 if let Some(change) = &req.change_replicas {
+                    check_witness_changes(&self.descriptor(), change)?;
+                    check_read_learner_changes(&self.descriptor(), change)?;
                     self.raft_node.clone().change_config(change.clone()).await?;
                 }
                 let resp = ChangeReplicasResponse {};
@@ -347,6 +463,9 @@ impl Replica {
                 (None, Response::MoveReplicas(resp))
             }
             Request::AcceptShard(req) => {
+                if let Some(shard) = req.shard_desc.as_ref() {
+                    check_shard_key_conflict(&self.descriptor(), shard)?;
+                }
                 let eval_result = eval::accept_shard(self.info.group_id, exec_ctx.epoch, req).await;
                 let resp = AcceptShardResponse {};
                 (Some(eval_result), Response::AcceptShard(resp))
@@ -405,6 +524,100 @@ impl Replica {
         }
     }
 
+    /// Take the latch of the written key if the shard is resharding. The descriptor can't be
+    /// changed here since the acl guard is held.
+    async fn take_resharding_latch(&self, exec_ctx: &mut ExecCtx, req: &Request) {
+        if exec_ctx.forward_shard_id.is_some() {
+            return;
+        }
+        let (shard_id, key) = match req {
+            Request::Put(ShardPutRequest {
+                shard_id,
+                put: Some(put),
+            }) => (*shard_id, &put.key),
+            Request::Delete(ShardDeleteRequest {
+                shard_id,
+                delete: Some(delete),
+            }) => (*shard_id, &delete.key),
+            _ => return,
+        };
+        let desc = self.descriptor();
+        let Some(shard) = desc.shards.into_iter().find(|s| s.id == shard_id) else {
+            return;
+        };
+        match shard.partition.as_ref() {
+            Some(shard_desc::Partition::Hash(hash)) if hash.target_slots != 0 => {}
+            _ => return,
+        }
+        let slot = crc32fast::hash(key) % RESHARDING_LATCHES;
+        let latch = self.resharding_latches[slot as usize]
+            .clone()
+            .lock_owned()
+            .await;
+        exec_ctx.resharding_write = Some(Arc::new(ReshardingWrite {
+            shard,
+            _latch: latch,
+        }));
+    }
+
+    #[inline]
+    fn is_follower_read(&self, request: &Request) -> bool {
+        is_read_only_request(request) && self.lease_state.lock().unwrap().is_read_learner()
+    }
+
+    /// Serve a read-only request on a read learner. The read index is fetched from the leader,
+    /// and the request is evaluated once the entries before it are applied, so the result is as
+    /// fresh as the one read from the leader.
+    async fn execute_follower_read(
+        &self,
+        exec_ctx: &mut ExecCtx,
+        request: &Request,
+    ) -> Result<Response> {
+        let read = async {
+            crate::failpoint::eval_async("follower_read_index").await?;
+            self.raft_node.clone().read(ReadPolicy::ReadIndex).await
+        };
+        match tokio::time::timeout(FOLLOWER_READ_TIMEOUT, read).await {
+            Ok(result) => result?,
+            Err(_) => {
+                // The read index request might be lost if the leader is changed, let the client
+                // fall back to the leader.
+                let lease_state = self.lease_state.lock().unwrap();
+                return Err(Error::NotLeader(
+                    self.info.group_id,
+                    lease_state.applied_term,
+                    lease_state.leader_descriptor(),
+                ));
+            }
+        }
+        self.check_follower_read_early(exec_ctx)?;
+        exec_ctx.acquire_quota()?;
+        self.evaluate_command(exec_ctx, request).await
+    }
+
+    fn check_follower_read_early(&self, exec_ctx: &mut ExecCtx) -> Result<()> {
+        let group_id = self.info.group_id;
+        exec_ctx.group_id = group_id;
+        exec_ctx.replica_id = self.info.replica_id;
+        let lease_state = self.lease_state.lock().unwrap();
+        if lease_state.is_migrating() || exec_ctx.forward_shard_id.is_some() {
+            // The requests of migrating shards are forwarded by the leader.
+            Err(Error::NotLeader(
+                group_id,
+                lease_state.applied_term,
+                lease_state.leader_descriptor(),
+            ))
+        } else if exec_ctx.epoch < lease_state.descriptor.epoch {
+            Err(Error::EpochNotMatch(lease_state.descriptor.clone()))
+        } else if exec_ctx.epoch > lease_state.descriptor.epoch {
+            // The read index has been applied, so it only happens if the epoch carried in the
+            // request is from the future.
+            Err(Error::GroupNotReady(group_id))
+        } else {
+            Ok(())
+        }
+    }
+
     fn check_leader_early(&self) -> Result<()> {
         let lease_state = self.lease_state.lock().unwrap();
         if !lease_state.is_ready_for_serving() {
@@ -486,8 +699,29 @@ impl ExecCtx {
         }
     }
 
+    pub fn with_quota(quota: Option<RequestQuota>) -> Self {
+        ExecCtx {
+            quota,
+            ..Default::default()
+        }
+    }
+
     pub fn reset(&mut self) {
         self.migration_desc = None;
+        self.resharding_write = None;
+    }
+
+    #[inline]
+    pub fn take_resharding_write(&mut self) -> Option<Arc<ReshardingWrite>> {
+        self.resharding_write.take()
+    }
+
+    #[inline]
+    fn acquire_quota(&mut self) -> Result<()> {
+        match self.quota.as_mut() {
+            Some(quota) => quota.acquire(),
+            None => Ok(()),
+        }
     }
 
     #[inline]
@@ -504,13 +738,108 @@ pub(self) fn is_change_meta_request(request: &Request) -> bool {
     match request {
         Request::ChangeReplicas(_)
         | Request::CreateShard(_)
+        | Request::RemoveShard(_)
         | Request::AcceptShard(_)
         | Request::MoveReplicas(_)
         | Request::Transfer(_) => true,
         Request::Get(_)
         | Request::Put(_)
         | Request::Delete(_)
+        | Request::DeleteRange(_)
         | Request::BatchWrite(_)
-        | Request::PrefixList(_) => false,
+        | Request::PrefixList(_)
+        | Request::Scan(_)
+        | Request::Ingest(_) => false,
+    }
+}
+
+fn is_read_only_request(request: &Request) -> bool {
+    matches!(
+        request,
+        Request::Get(_) | Request::Scan(_) | Request::PrefixList(_)
+    )
+}
+
+fn check_shard_key_conflict(desc: &GroupDesc, shard: &ShardDesc) -> Result<()> {
+    match desc
+        .shards
+        .iter()
+        .find(|s| shard::is_key_conflict(s, shard))
+    {
+        Some(existed) => Err(Error::InvalidArgument(format!(
+            "shard {} conflicts with the existing shard {} of collection {}",
+            shard.id, existed.id, shard.collection_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// A witness has no data, so it can't be promoted to a voter or a learner, and it doesn't
+/// participate in joint consensus, which might leave a group without enough data replicas.
+fn check_witness_changes(desc: &GroupDesc, change_replicas: &ChangeReplicas) -> Result<()> {
+    let is_witness = |replica_id: u64| {
+        desc.replicas
+            .iter()
+            .any(|r| r.id == replica_id && r.role == ReplicaRole::Witness as i32)
+    };
+    let changes = &change_replicas.changes;
+    for change in changes {
+        let involve_witness = change.change_type == ChangeReplicaType::AddWitness as i32
+            || is_witness(change.replica_id);
+        if !involve_witness {
+            continue;
+        }
+        if changes.len() > 1 {
+            return Err(Error::InvalidArgument(format!(
+                "witness {} can't be changed in joint consensus",
+                change.replica_id
+            )));
+        }
+        let promote = change.change_type == ChangeReplicaType::Add as i32
+            || change.change_type == ChangeReplicaType::AddLearner as i32;
+        if promote {
+            return Err(Error::InvalidArgument(format!(
+                "witness {} can't be promoted, remove it first",
+                change.replica_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A read learner is kept permanently for follower reads, so it is only added and removed by
+/// simple changes, and never changes its role.
+fn check_read_learner_changes(desc: &GroupDesc, change_replicas: &ChangeReplicas) -> Result<()> {
+    let is_read_learner = |replica_id: u64| {
+        desc.replicas
+            .iter()
+            .any(|r| r.id == replica_id && r.role == ReplicaRole::ReadLearner as i32)
+    };
+    let changes = &change_replicas.changes;
+    for change in changes {
+        let add_read_learner = change.change_type == ChangeReplicaType::AddReadLearner as i32;
+        let exists = is_read_learner(change.replica_id);
+        if !add_read_learner && !exists {
+            continue;
+        }
+        if changes.len() > 1 {
+            return Err(Error::InvalidArgument(format!(
+                "read learner {} can't be changed in joint consensus",
+                change.replica_id
+            )));
+        }
+        if exists && !add_read_learner && change.change_type != ChangeReplicaType::Remove as i32 {
+            return Err(Error::InvalidArgument(format!(
+                "read learner {} can't change its role, remove it first",
+                change.replica_id
+            )));
+        }
+        if add_read_learner && !exists && desc.replicas.iter().any(|r| r.id == change.replica_id) {
+            return Err(Error::InvalidArgument(format!(
+                "replica {} already exists and can't become a read learner",
+                change.replica_id
+            )));
+        }
     }
+    Ok(())
 }
@@ -88,6 +88,15 @@ async fn execute_internal(
         exec_ctx.reset();
         match replica.execute(&mut exec_ctx, request).await {
             Ok(resp) => {
+                if let Some(write) = exec_ctx.take_resharding_write() {
+                    // Forward the write while the latch of the key is held, so the shard of
+                    // target slots applies the writes in the same order as this shard.
+                    if let Some(ctrl) = migrate_ctrl {
+                        ctrl.forward_resharding_write(&write.shard, request).await?;
+                    } else {
+                        panic!("receive resharding write but no migration controller set");
+                    }
+                }
                 let resp = if let Some(descriptor) = freshed_descriptor {
                     GroupResponse::with_error(resp, Error::EpochNotMatch(descriptor).into())
                 } else {
@@ -155,6 +164,8 @@ fn is_executable(descriptor: &GroupDesc, request: &Request) -> bool {
             Request::PrefixList(req) => {
                 is_target_shard_exists(descriptor, req.shard_id, &req.prefix)
             }
+            Request::Scan(req) => descriptor.shards.iter().any(|s| s.id == req.shard_id),
+            Request::DeleteRange(req) => descriptor.shards.iter().any(|s| s.id == req.shard_id),
             Request::BatchWrite(req) => {
                 for delete in &req.deletes {
                     if !is_target_shard_exists(
@@ -176,6 +187,10 @@ fn is_executable(descriptor: &GroupDesc, request: &Request) -> bool {
                 }
                 true
             }
+            Request::Ingest(req) => req
+                .data
+                .iter()
+                .all(|data| is_target_shard_exists(descriptor, req.shard_id, &data.key)),
             _ => unreachable!(),
         };
     }
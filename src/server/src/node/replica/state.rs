@@ -19,14 +19,20 @@ use std::{
 };
 
 use engula_api::server::v1::{
-    GroupDesc, MigrationDesc, RaftRole, ReplicaDesc, ReplicaState, ScheduleState,
+    GroupDesc, MigrationDesc, RaftRole, ReplicaDesc, ReplicaRole, ReplicaState, ScheduleState,
 };
 use futures::channel::mpsc;
 use tracing::info;
 
-use super::{fsm::StateMachineObserver, ReplicaInfo};
+use super::{
+    fsm::{ChecksumResult, StateMachineObserver},
+    ReplicaInfo,
+};
 use crate::{
-    node::job::StateChannel, raftgroup::StateObserver, schedule::ScheduleStateObserver,
+    engine::UserWrite,
+    node::{job::StateChannel, replication::ReplicationManager},
+    raftgroup::StateObserver,
+    schedule::ScheduleStateObserver,
     serverpb::v1::MigrationState,
 };
 
@@ -39,6 +45,8 @@ pub struct LeaseState {
     pub migration_state: Option<MigrationState>,
     pub migration_state_subscriber: mpsc::UnboundedSender<MigrationState>,
     pub schedule_state: ScheduleState,
+    /// The checksum of the latest consistency check applied by the state machine.
+    pub last_checksum: Option<ChecksumResult>,
     pub leader_subscribers: HashMap<&'static str, Waker>,
 }
 
@@ -49,6 +57,7 @@ pub struct LeaseStateObserver {
     info: Arc<ReplicaInfo>,
     lease_state: Arc<Mutex<LeaseState>>,
     state_channel: StateChannel,
+    replication: ReplicationManager,
 }
 
 impl LeaseState {
@@ -64,6 +73,7 @@ impl LeaseState {
             leader_id: 0,
             applied_term: 0,
             schedule_state: ScheduleState::default(),
+            last_checksum: None,
             replica_state: ReplicaState::default(),
             leader_subscribers: HashMap::default(),
         }
@@ -71,7 +81,28 @@ impl LeaseState {
 
     #[inline]
     pub fn is_raft_leader(&self) -> bool {
-        self.replica_state.role == RaftRole::Leader as i32
+        self.replica_state.role == RaftRole::Leader as i32 && !self.is_witness()
+    }
+
+    /// A witness has no data, so it never serves requests even if it is elected as the raft
+    /// leader before transferring leadership away.
+    #[inline]
+    pub fn is_witness(&self) -> bool {
+        let replica_id = self.replica_state.replica_id;
+        self.descriptor
+            .replicas
+            .iter()
+            .any(|r| r.id == replica_id && r.role == ReplicaRole::Witness as i32)
+    }
+
+    /// A read learner serves the follower reads, see `Replica::execute_follower_read`.
+    #[inline]
+    pub fn is_read_learner(&self) -> bool {
+        let replica_id = self.replica_state.replica_id;
+        self.descriptor
+            .replicas
+            .iter()
+            .any(|r| r.id == replica_id && r.role == ReplicaRole::ReadLearner as i32)
     }
 
     /// At least one log for the current term has been applied?
@@ -131,11 +162,13 @@ impl LeaseStateObserver {
         info: Arc<ReplicaInfo>,
         lease_state: Arc<Mutex<LeaseState>>,
         state_channel: StateChannel,
+        replication: ReplicationManager,
     ) -> Self {
         LeaseStateObserver {
             info,
             lease_state,
             state_channel,
+            replication,
         }
     }
 
@@ -234,6 +267,23 @@ impl StateMachineObserver for LeaseStateObserver {
             }
         }
     }
+
+    fn checksum_reporter(&self) -> Box<dyn FnOnce(ChecksumResult) + Send> {
+        let lease_state = self.lease_state.clone();
+        Box::new(move |checksum| {
+            let mut lease_state = lease_state.lock().unwrap();
+            lease_state.last_checksum = Some(checksum);
+        })
+    }
+
+    fn on_user_writes_applied(&mut self, index: u64, writes: &[UserWrite]) {
+        // All replicas record the writes, since any of them might become the leader to ship them.
+        self.replication.record(self.info.group_id, index, writes);
+    }
+
+    fn on_replication_shipped(&mut self, index: u64) {
+        self.replication.truncate(self.info.group_id, index);
+    }
 }
 
 impl ScheduleStateObserver for LeaseStateObserver {
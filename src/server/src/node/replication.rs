@@ -0,0 +1,643 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replicate the user writes to a standby cluster asynchronously.
+//!
+//! Each replica records the user writes in the replication log of the group engine, which is
+//! committed with the writes atomically, see `GroupEngine::append_replication_log`. The leader of
+//! each group reads the log in the applied order, ships the writes to the standby cluster via
+//! `EngulaClient`, then proposes to truncate the shipped log. So the writes survive restarts and
+//! leader changes, and the writes of a group are always replayed in order. The writes of
+//! different groups are shipped concurrently.
+//!
+//! The replication log is bounded by throttling the user writes, see
+//! `ReplicationConfig::max_pending_writes`. A shard is migrated only if all of its writes in the
+//! source group are shipped, so the writes of a shard are replayed in order even if the shard is
+//! migrated between groups.
+//!
+//! The replication is stopped by failover cluster-wide, the state is persisted by root and synced
+//! to nodes by heartbeats, see `Root::failover_replication`. After that the user writes are no
+//! longer recorded, and the recorded writes are shipped until the replication log is drained.
+//!
+//! There are some limitations:
+//! 1. The writes are shipped at least once. A write might be shipped again after the leader is
+//!    changed, but the following writes are shipped again too, so the standby cluster converges.
+//! 2. Deleting a hash shard is replayed as a range deletion of the same slot if the standby
+//!    collection has the same layout, otherwise the keys of the slot are scanned page by page and
+//!    deleted one by one.
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use engula_api::server::v1::shard_desc::HashPartition;
+use engula_client::{AppResult, ClientOptions, Collection, EngulaClient};
+use futures::StreamExt;
+use serde::Serialize;
+use tokio::sync::{Notify, OnceCell};
+use tracing::{debug, info, warn};
+
+use super::{metrics::*, Replica};
+use crate::{
+    constants::ROOT_GROUP_ID,
+    engine::{GroupEngine, UserWrite},
+    root::USER_COLLECTION_INIT_ID,
+    runtime::{sync::WaitGroup, time::Instant, TaskPriority},
+    transport::TransportManager,
+    Error, ReplicationConfig, Result,
+};
+
+/// The number of replication logs shipped in a batch, the log is truncated after each batch.
+const SHIP_BATCH_SIZE: usize = 64;
+
+/// The number of keys whose writes are shipped concurrently.
+const SHIP_CONCURRENCY: usize = 32;
+
+/// The number of keys scanned in a page when deleting a hash shard key by key.
+const TRUNCATE_SCAN_LIMIT: usize = 256;
+
+#[derive(Clone)]
+pub struct ReplicationManager {
+    core: Arc<ReplicationCore>,
+}
+
+struct ReplicationCore {
+    cfg: Option<ReplicationConfig>,
+    transport_manager: TransportManager,
+    standby: OnceCell<EngulaClient>,
+    state: Mutex<ReplicationState>,
+}
+
+#[derive(Default)]
+struct ReplicationState {
+    /// The replication is stopped by failover, the later writes are not recorded any more. The
+    /// state is persisted by root and synced to nodes by heartbeats, see
+    /// `Root::failover_replication`.
+    stopped: bool,
+    groups: HashMap<u64, GroupProgress>,
+}
+
+/// The replication log of a group in memory, it is reloaded from the group engine once the local
+/// replica becomes leader.
+#[derive(Default)]
+struct GroupProgress {
+    /// The batches of writes recorded in the replication log, in the applied order.
+    pending: BTreeMap<u64, PendingWrites>,
+    pending_writes: usize,
+    shards: HashMap<u64, ShardProgress>,
+    /// Wake the leader to ship the new writes.
+    notify: Arc<Notify>,
+}
+
+#[derive(Default)]
+struct ShardProgress {
+    collection_id: u64,
+    applied_index: u64,
+    shipped_index: u64,
+}
+
+struct PendingWrites {
+    applied_at: Instant,
+    /// The collection id and the number of writes of each shard.
+    shards: HashMap<u64, (u64, usize)>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ReplicationStatus {
+    pub enabled: bool,
+    pub stopped: bool,
+    pub shards: Vec<ShardReplicationStatus>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ShardReplicationStatus {
+    pub shard_id: u64,
+    pub group_id: u64,
+    pub collection_id: u64,
+    /// The index of the last user writes recorded in the replication log.
+    pub applied_index: u64,
+    /// The index of the last user writes shipped to the standby cluster.
+    pub shipped_index: u64,
+    pub pending_writes: usize,
+    /// The duration since the oldest pending writes are applied.
+    pub lag_ms: u64,
+}
+
+/// Applies the writes of a group to the standby cluster.
+struct StandbyApplier {
+    cfg: ReplicationConfig,
+    transport_manager: TransportManager,
+    standby: EngulaClient,
+    collections: Mutex<HashMap<u64, Collection>>,
+}
+
+impl ReplicationManager {
+    pub(crate) fn new(cfg: Option<ReplicationConfig>, transport_manager: TransportManager) -> Self {
+        let cfg = cfg.filter(|cfg| !cfg.standby_addrs.is_empty());
+        ReplicationManager {
+            core: Arc::new(ReplicationCore {
+                cfg,
+                transport_manager,
+                standby: OnceCell::new(),
+                state: Mutex::default(),
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.core.cfg.is_some()
+    }
+
+    /// Ship the replication log of the replica once it becomes leader, it does nothing if the
+    /// replication is disabled.
+    pub(crate) fn watch_replica(&self, replica: Arc<Replica>, wait_group: WaitGroup) {
+        let cfg = match self.core.cfg.clone() {
+            Some(cfg) => cfg,
+            None => return,
+        };
+        let group_id = replica.replica_info().group_id;
+        if group_id == ROOT_GROUP_ID {
+            return;
+        }
+        let manager = self.clone();
+        crate::runtime::current().spawn(Some(group_id), TaskPriority::IoLow, async move {
+            manager.ship_group_writes(cfg, replica).await;
+            drop(wait_group);
+        });
+    }
+
+    /// Record the user writes applied at the index, they are shipped by the leader later.
+    pub(crate) fn record(&self, group_id: u64, index: u64, writes: &[UserWrite]) {
+        let writes = writes
+            .iter()
+            .filter(|w| is_replicated_collection(group_id, w.collection_id()))
+            .cloned()
+            .collect::<Vec<_>>();
+        if writes.is_empty() {
+            return;
+        }
+        let shards = count_shard_writes(&writes);
+        let mut state = self.core.state.lock().unwrap();
+        let progress = state.groups.entry(group_id).or_default();
+        for (shard_id, (collection_id, _)) in &shards {
+            let shard = progress.shards.entry(*shard_id).or_default();
+            shard.collection_id = *collection_id;
+            shard.applied_index = index;
+        }
+        let pending = PendingWrites {
+            applied_at: Instant::now(),
+            shards,
+        };
+        if progress.pending.insert(index, pending).is_none() {
+            progress.pending_writes += writes.len();
+            NODE_REPLICATION_PENDING_WRITES.add(writes.len() as i64);
+        }
+        progress.notify.notify_one();
+    }
+
+    /// Forget the writes recorded up to the index, since the replication log is truncated.
+    pub(crate) fn truncate(&self, group_id: u64, index: u64) {
+        let mut state = self.core.state.lock().unwrap();
+        if let Some(progress) = state.groups.get_mut(&group_id) {
+            progress.truncate(index);
+        }
+    }
+
+    /// Throttle the user writes of the group if too many writes are waiting to be shipped, so
+    /// that the replication log is bounded.
+    pub(crate) async fn throttle(&self, group_id: u64) -> Result<()> {
+        let cfg = match self.core.cfg.as_ref() {
+            Some(cfg) => cfg,
+            None => return Ok(()),
+        };
+
+        let deadline = Instant::now() + cfg.rpc_timeout();
+        loop {
+            {
+                let state = self.core.state.lock().unwrap();
+                let pending_writes = state
+                    .groups
+                    .get(&group_id)
+                    .map(|p| p.pending_writes)
+                    .unwrap_or_default();
+                if state.stopped || pending_writes < cfg.max_pending_writes {
+                    return Ok(());
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::ResourceExhausted(format!(
+                    "too many writes of group {group_id} are waiting to be shipped to standby cluster"
+                )));
+            }
+            crate::runtime::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Return the replication progress of shards.
+    pub fn status(&self) -> ReplicationStatus {
+        let now = Instant::now();
+        let state = self.core.state.lock().unwrap();
+        let mut shards = vec![];
+        for (group_id, progress) in &state.groups {
+            for (shard_id, shard) in &progress.shards {
+                let mut pending_writes = 0;
+                let mut oldest_applied_at = None;
+                for pending in progress.pending.values() {
+                    if let Some((_, num_writes)) = pending.shards.get(shard_id) {
+                        pending_writes += num_writes;
+                        oldest_applied_at.get_or_insert(pending.applied_at);
+                    }
+                }
+                shards.push(ShardReplicationStatus {
+                    shard_id: *shard_id,
+                    group_id: *group_id,
+                    collection_id: shard.collection_id,
+                    applied_index: shard.applied_index,
+                    shipped_index: shard.shipped_index,
+                    pending_writes,
+                    lag_ms: oldest_applied_at
+                        .map(|t| (now - t).as_millis() as u64)
+                        .unwrap_or_default(),
+                });
+            }
+        }
+        shards.sort_unstable_by_key(|s| s.shard_id);
+        ReplicationStatus {
+            enabled: self.is_enabled(),
+            stopped: state.stopped,
+            shards,
+        }
+    }
+
+    /// Apply the replication state persisted by root, and return the number of writes recorded
+    /// by the replicas of this node which are waiting to be shipped.
+    ///
+    /// Once the replication is stopped by failover, the user writes are no longer recorded, and
+    /// the recorded writes are still shipped until the replication log is drained.
+    pub(crate) fn sync(&self, stopped: bool) -> u64 {
+        let mut state = self.core.state.lock().unwrap();
+        if stopped && !state.stopped {
+            info!("replication is stopped by failover");
+            state.stopped = true;
+        }
+        state.groups.values().map(|p| p.pending_writes as u64).sum()
+    }
+
+    /// Return whether the user writes are recorded in the replication log.
+    pub(crate) fn is_recording(&self) -> bool {
+        self.is_enabled() && !self.core.state.lock().unwrap().stopped
+    }
+
+    async fn ship_group_writes(self, cfg: ReplicationConfig, replica: Arc<Replica>) {
+        let info = replica.replica_info();
+        let group_id = info.group_id;
+        let replica_id = info.replica_id;
+        drop(info);
+
+        let standby = self
+            .core
+            .standby
+            .get_or_init(|| connect_standby_cluster(&cfg))
+            .await
+            .clone();
+        let mut applier = StandbyApplier {
+            cfg: cfg.clone(),
+            transport_manager: self.core.transport_manager.clone(),
+            standby,
+            collections: Mutex::default(),
+        };
+        while let Ok(Some(term)) = replica.on_leader("replication", false).await {
+            if let Err(err) = self.ship_replication_log(&applier, &replica, term).await {
+                warn!("group {group_id} replica {replica_id} ship replication log: {err}");
+                crate::runtime::time::sleep(cfg.retry_interval()).await;
+            }
+        }
+        self.remove_group(group_id);
+        debug!("group {group_id} replica {replica_id} replication is stopped");
+    }
+
+    /// Ship the replication log until the local replica isn't the leader of the term.
+    async fn ship_replication_log(
+        &self,
+        applier: &StandbyApplier,
+        replica: &Replica,
+        term: u64,
+    ) -> Result<()> {
+        let group_id = replica.replica_info().group_id;
+        let group_engine = replica.group_engine();
+        let notify = self.reload(group_id, &group_engine)?;
+        let mut next_index = 0;
+        while is_leader_of(replica, term).await {
+            let logs = group_engine.replication_logs(next_index, SHIP_BATCH_SIZE)?;
+            let last_index = match logs.last() {
+                Some((index, _)) => *index,
+                None => {
+                    // The leadership is checked periodically, even if no writes are recorded.
+                    let _ =
+                        tokio::time::timeout(applier.cfg.retry_interval(), notify.notified()).await;
+                    continue;
+                }
+            };
+            let applied_at = logs
+                .iter()
+                .map(|(index, _)| self.applied_at(group_id, *index))
+                .collect::<Vec<_>>();
+            // The logs recorded by the former versions might contain system writes.
+            let writes = logs
+                .into_iter()
+                .flat_map(|(_, writes)| writes)
+                .filter(|w| is_replicated_collection(group_id, w.collection_id()))
+                .collect::<Vec<_>>();
+            let num_writes = writes.len();
+            if !applier.apply_batch(writes, replica, term).await {
+                return Ok(());
+            }
+            NODE_REPLICATION_SHIPPED_WRITES_TOTAL.inc_by(num_writes as u64);
+            for applied_at in applied_at.into_iter().flatten() {
+                NODE_REPLICATION_LAG_SECONDS.observe(applied_at.elapsed().as_secs_f64());
+            }
+            replica.truncate_replication_log(last_index).await?;
+            next_index = last_index + 1;
+        }
+        Ok(())
+    }
+
+    /// Reload the replication log of the group from the group engine, the log might be changed
+    /// by the snapshots since the local replica was leader.
+    fn reload(&self, group_id: u64, group_engine: &GroupEngine) -> Result<Arc<Notify>> {
+        let logs = group_engine.replication_logs(0, usize::MAX)?;
+        let mut state = self.core.state.lock().unwrap();
+        let progress = state.groups.entry(group_id).or_default();
+        progress.truncate(u64::MAX);
+        let applied_at = Instant::now();
+        for (index, writes) in logs {
+            let shards = count_shard_writes(&writes);
+            for (shard_id, (collection_id, _)) in &shards {
+                let shard = progress.shards.entry(*shard_id).or_default();
+                shard.collection_id = *collection_id;
+                shard.applied_index = index;
+            }
+            progress.pending_writes += writes.len();
+            NODE_REPLICATION_PENDING_WRITES.add(writes.len() as i64);
+            progress
+                .pending
+                .insert(index, PendingWrites { applied_at, shards });
+        }
+        Ok(progress.notify.clone())
+    }
+
+    fn remove_group(&self, group_id: u64) {
+        let mut state = self.core.state.lock().unwrap();
+        if let Some(mut progress) = state.groups.remove(&group_id) {
+            progress.truncate(u64::MAX);
+        }
+    }
+
+    fn applied_at(&self, group_id: u64, index: u64) -> Option<Instant> {
+        let state = self.core.state.lock().unwrap();
+        state
+            .groups
+            .get(&group_id)
+            .and_then(|p| p.pending.get(&index))
+            .map(|p| p.applied_at)
+    }
+}
+
+impl GroupProgress {
+    fn truncate(&mut self, index: u64) {
+        let shipped = self
+            .pending
+            .range(..=index)
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        for shipped_index in shipped {
+            let pending = self.pending.remove(&shipped_index).unwrap();
+            for (shard_id, (_, num_writes)) in pending.shards {
+                if let Some(shard) = self.shards.get_mut(&shard_id) {
+                    shard.shipped_index = shipped_index;
+                }
+                self.pending_writes -= num_writes;
+                NODE_REPLICATION_PENDING_WRITES.sub(num_writes as i64);
+            }
+        }
+    }
+}
+
+impl StandbyApplier {
+    /// Apply the writes to the standby cluster concurrently. The writes of the same key are
+    /// applied in order, and a range deletion is applied after all the former writes are applied.
+    /// Return whether all writes are applied.
+    async fn apply_batch(&self, writes: Vec<UserWrite>, replica: &Replica, term: u64) -> bool {
+        let mut lanes: Vec<Vec<UserWrite>> = vec![];
+        let mut key_lanes: HashMap<(u64, Vec<u8>), usize> = HashMap::default();
+        for write in writes {
+            let key = match &write {
+                UserWrite::Put {
+                    collection_id, key, ..
+                }
+                | UserWrite::Delete {
+                    collection_id, key, ..
+                } => (*collection_id, key.clone()),
+                UserWrite::DeleteRange { .. } | UserWrite::TruncateShard { .. } => {
+                    key_lanes.clear();
+                    if !self
+                        .apply_lanes(std::mem::take(&mut lanes), replica, term)
+                        .await
+                        || !self.apply(&write, replica, term).await
+                    {
+                        return false;
+                    }
+                    continue;
+                }
+            };
+            match key_lanes.get(&key) {
+                Some(&lane) => lanes[lane].push(write),
+                None => {
+                    key_lanes.insert(key, lanes.len());
+                    lanes.push(vec![write]);
+                }
+            }
+        }
+        self.apply_lanes(lanes, replica, term).await
+    }
+
+    /// Apply the lanes of writes concurrently, the writes of a lane are applied in order.
+    async fn apply_lanes(&self, lanes: Vec<Vec<UserWrite>>, replica: &Replica, term: u64) -> bool {
+        let applied = futures::stream::iter(lanes)
+            .map(|lane| async move {
+                for write in &lane {
+                    if !self.apply(write, replica, term).await {
+                        return false;
+                    }
+                }
+                true
+            })
+            .buffer_unordered(SHIP_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        applied.into_iter().all(|v| v)
+    }
+
+    /// Apply the write to the standby cluster, it retries until the write is applied or the
+    /// local replica isn't the leader of the term. Return whether the write is applied.
+    async fn apply(&self, write: &UserWrite, replica: &Replica, term: u64) -> bool {
+        loop {
+            match self.try_apply(write).await {
+                Ok(()) => return true,
+                Err(_) if !is_leader_of(replica, term).await => return false,
+                Err(err) => {
+                    warn!(
+                        "ship write of shard {} to standby cluster: {err}",
+                        write.shard_id()
+                    );
+                    // The collection might be recreated, so reopen it in next retry.
+                    self.collections
+                        .lock()
+                        .unwrap()
+                        .remove(&write.collection_id());
+                    crate::runtime::time::sleep(self.cfg.retry_interval()).await;
+                }
+            }
+        }
+    }
+
+    async fn try_apply(&self, write: &UserWrite) -> AppResult<()> {
+        let collection = self.open_collection(write.collection_id()).await?;
+        match write {
+            UserWrite::Put { key, value, .. } => collection.put(key.clone(), value.clone()).await,
+            UserWrite::Delete { key, .. } => collection.delete(key.clone()).await,
+            UserWrite::DeleteRange { start, end, .. } => {
+                collection.delete_range(start.clone(), end.clone()).await
+            }
+            UserWrite::TruncateShard { hash, .. } => truncate_shard(&collection, hash).await,
+        }
+    }
+
+    /// Open the collection in standby cluster, which has the same name as the collection of this
+    /// cluster.
+    async fn open_collection(&self, collection_id: u64) -> AppResult<Collection> {
+        use engula_client::AppError;
+
+        if let Some(collection) = self.collections.lock().unwrap().get(&collection_id) {
+            return Ok(collection.clone());
+        }
+
+        let desc = self
+            .transport_manager
+            .router()
+            .find_collection(collection_id)
+            .ok_or_else(|| AppError::NotFound(format!("collection {collection_id}")))?;
+        let opts = ClientOptions {
+            connect_timeout: Some(Duration::from_millis(250)),
+            timeout: Some(self.cfg.rpc_timeout()),
+        };
+        let database = self
+            .transport_manager
+            .build_client(opts)
+            .list_database()
+            .await?
+            .into_iter()
+            .find(|db| db.desc().id == desc.db)
+            .ok_or_else(|| AppError::NotFound(format!("database {}", desc.db)))?;
+        let collection = self
+            .standby
+            .open_database(database.name())
+            .await?
+            .open_collection(desc.name)
+            .await?;
+        self.collections
+            .lock()
+            .unwrap()
+            .insert(collection_id, collection.clone());
+        Ok(collection)
+    }
+}
+
+/// Delete the keys of the hash shard of this cluster from the standby collection.
+async fn truncate_shard(collection: &Collection, hash: &HashPartition) -> AppResult<()> {
+    use engula_api::{server::v1::collection_desc::Partition, shard::key_slot};
+
+    match collection.desc().partition {
+        Some(Partition::Hash(standby))
+            if standby.slots == hash.slots
+                && standby.algorithm == hash.algorithm
+                && standby.target_slots == 0 =>
+        {
+            collection.delete_slot(hash.slot_id).await
+        }
+        _ => {
+            // The keys of the shard are located in different shards of the standby collection, so
+            // they are scanned page by page, instead of loading the whole collection.
+            let mut after = vec![];
+            loop {
+                let entries = collection
+                    .prefix_scan_after(vec![], after, TRUNCATE_SCAN_LIMIT)
+                    .await?;
+                let Some((last_key, _)) = entries.last() else {
+                    return Ok(());
+                };
+                after = last_key.clone();
+                let num_entries = entries.len();
+                for (key, _) in entries {
+                    if key_slot(&key, hash.slots, hash.algorithm) == Some(hash.slot_id) {
+                        collection.delete(key).await?;
+                    }
+                }
+                if num_entries < TRUNCATE_SCAN_LIMIT {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Return whether the writes of the collection are shipped to the standby cluster. The root group
+/// and the system collections describe the metadata of this cluster, they are never shipped.
+pub(crate) fn is_replicated_collection(group_id: u64, collection_id: u64) -> bool {
+    group_id != ROOT_GROUP_ID && collection_id >= USER_COLLECTION_INIT_ID
+}
+
+/// Return the collection id and the number of writes of each shard.
+fn count_shard_writes(writes: &[UserWrite]) -> HashMap<u64, (u64, usize)> {
+    let mut counts: HashMap<u64, (u64, usize)> = HashMap::default();
+    for write in writes {
+        let entry = counts
+            .entry(write.shard_id())
+            .or_insert((write.collection_id(), 0));
+        entry.1 += 1;
+    }
+    counts
+}
+
+async fn is_leader_of(replica: &Replica, term: u64) -> bool {
+    matches!(replica.on_leader("replication", true).await, Ok(Some(t)) if t == term)
+}
+
+async fn connect_standby_cluster(cfg: &ReplicationConfig) -> EngulaClient {
+    let opts = ClientOptions {
+        connect_timeout: Some(Duration::from_millis(250)),
+        timeout: Some(cfg.rpc_timeout()),
+    };
+    loop {
+        match EngulaClient::new(opts.clone(), cfg.standby_addrs.clone()).await {
+            Ok(client) => return client,
+            Err(err) => {
+                warn!("connect to standby cluster {:?}: {err}", cfg.standby_addrs);
+                crate::runtime::time::sleep(cfg.retry_interval()).await;
+            }
+        }
+    }
+}
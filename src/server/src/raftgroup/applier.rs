@@ -57,6 +57,9 @@ pub struct Applier<M: StateMachine> {
     read_states: Vec<ReadState>,
 
     last_applied_index: u64,
+    /// The number of config changes and snapshots applied, the roles of replicas are only changed
+    /// by them.
+    conf_updates: u64,
     state_machine: M,
 }
 
@@ -69,6 +72,7 @@ impl<M: StateMachine> Applier<M> {
             read_requests: HashMap::default(),
             read_states: Vec::default(),
             last_applied_index: state_machine.flushed_index(),
+            conf_updates: 0,
             state_machine,
         }
     }
@@ -133,9 +137,15 @@ impl<M: StateMachine> Applier<M> {
         let state_machine = self.mut_state_machine();
         state_machine.apply_snapshot(snap_dir)?;
         self.last_applied_index = state_machine.flushed_index();
+        self.conf_updates += 1;
         Ok(())
     }
 
+    #[inline]
+    pub fn conf_updates(&self) -> u64 {
+        self.conf_updates
+    }
+
     #[inline]
     pub fn applied_index(&self) -> u64 {
         self.last_applied_index
@@ -153,7 +163,7 @@ impl<M: StateMachine> Applier<M> {
         raw_node: &mut RawNode<Storage>,
         replica_cache: &mut ReplicaCache,
         committed_entries: Vec<Entry>,
-    ) -> u64 {
+    ) -> Result<u64> {
         record_latency!(&RAFTGROUP_WORKER_APPLY_DURATION_SECONDS);
         RAFTGROUP_WORKER_APPLY_ENTRIES_SIZE.observe(committed_entries.len() as f64);
 
@@ -184,7 +194,7 @@ impl<M: StateMachine> Applier<M> {
         }
 
         record_perf_point(&mut perf_ctx.finish_plug);
-        self.state_machine.finish_plug().expect("finish_plug");
+        self.state_machine.finish_plug()?;
 
         record_perf_point(&mut perf_ctx.response_proposals);
         entry_ids
@@ -193,7 +203,7 @@ impl<M: StateMachine> Applier<M> {
 
         // Since the `last_applied_index` updated, try advance cached read states.
         self.response_cached_read_states();
-        self.last_applied_index
+        Ok(self.last_applied_index)
     }
 
     fn apply_conf_change(
@@ -226,6 +236,7 @@ impl<M: StateMachine> Applier<M> {
             .expect("apply config change");
         replica_cache.batch_insert(&self.state_machine.descriptor().replicas);
         raw_node.apply_conf_change(&conf_change).unwrap_or_default();
+        self.conf_updates += 1;
     }
 
     fn apply_normal_entry(&mut self, entry: Entry) {
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::Instant;
+use std::{collections::HashSet, sync::Arc, time::Instant};
 
 use engula_api::server::v1::ChangeReplicas;
 use futures::channel::{mpsc, oneshot};
@@ -118,6 +118,11 @@ impl RaftNodeFacade {
             .unwrap_or_default()
     }
 
+    /// Wake up the hibernated group if its leader is located in the dead nodes.
+    pub fn report_dead_nodes(&mut self, nodes: Arc<HashSet<u64>>) {
+        self.send(Request::DeadNodes { nodes }).unwrap_or_default()
+    }
+
     pub async fn monitor(&mut self) -> Result<Box<WorkerPerfContext>> {
         let (sender, receiver) = oneshot::channel();
         if self.send(Request::Monitor(sender)).is_err() {
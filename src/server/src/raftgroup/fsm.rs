@@ -22,6 +22,7 @@ use crate::{
 };
 
 /// A helper structure to used to access the internal field of entries.
+#[derive(Debug)]
 pub enum ApplyEntry {
     Empty,
     ConfigChange { change_replicas: ChangeReplicas },
@@ -0,0 +1,480 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use engula_api::server::v1::ReplicaDesc;
+use futures::{channel::mpsc, Future, Stream, StreamExt};
+use prost::Message as _;
+use raft::prelude::{Message, MessageType};
+use tokio::time::Sleep;
+use tracing::warn;
+
+use crate::{
+    raftgroup::snap::compress::{compress, decompress},
+    serverpb::v1::{RaftHeartbeat, RaftMessage, RaftMessageBatch, SnapshotCompression},
+    Error, RaftConfig, Result,
+};
+
+/// The pairs of (from replica, to replica) whose messages have passed through a batcher.
+pub(super) type PeerSet = Arc<Mutex<HashSet<(u64, u64)>>>;
+
+/// Coalesce the messages sent to the same node into [`RaftMessageBatch`]es.
+///
+/// All messages queued are taken into a batch until it exceeds `max_msg_batch_size`, and the
+/// batch is delayed for `msg_batch_window_us` to wait for more messages. The heartbeats are
+/// converted into the compact form, since thousands of groups send them at every tick.
+pub(super) struct MessageBatcher {
+    to_node_id: u64,
+    max_batch_size: usize,
+    window: Duration,
+    compression: SnapshotCompression,
+    compression_threshold: usize,
+
+    receiver: mpsc::UnboundedReceiver<RaftMessage>,
+    terminated: bool,
+    /// The message which exceeds the size limit of the last batch.
+    pending: Option<RaftMessage>,
+    batch: BatchBuilder,
+    delay: Option<Pin<Box<Sleep>>>,
+    peers: PeerSet,
+}
+
+#[derive(Default)]
+struct BatchBuilder {
+    messages: Vec<RaftMessage>,
+    heartbeats: Vec<RaftHeartbeat>,
+    size: usize,
+}
+
+impl MessageBatcher {
+    pub(super) fn new(
+        cfg: &RaftConfig,
+        to_node_id: u64,
+        receiver: mpsc::UnboundedReceiver<RaftMessage>,
+    ) -> Self {
+        MessageBatcher {
+            to_node_id,
+            max_batch_size: cfg.max_msg_batch_size as usize,
+            window: Duration::from_micros(cfg.msg_batch_window_us),
+            compression: cfg.msg_compression.into(),
+            compression_threshold: cfg.msg_compression_threshold as usize,
+            receiver,
+            terminated: false,
+            pending: None,
+            batch: BatchBuilder::default(),
+            delay: None,
+            peers: PeerSet::default(),
+        }
+    }
+
+    #[inline]
+    pub(super) fn peers(&self) -> PeerSet {
+        self.peers.clone()
+    }
+
+    /// Close the batcher and record the peers of all queued messages, which will never be sent.
+    pub(super) fn close(&mut self) {
+        self.receiver.close();
+        let mut peers = self.peers.lock().unwrap();
+        while let Ok(Some(msg)) = self.receiver.try_next() {
+            peers.insert(peer_of(&msg));
+        }
+    }
+
+    fn build_batch(&mut self) -> RaftMessageBatch {
+        let batch = std::mem::take(&mut self.batch);
+        let size = batch.size;
+        let batch = RaftMessageBatch {
+            to_node_id: self.to_node_id,
+            messages: batch.messages,
+            heartbeats: batch.heartbeats,
+            ..Default::default()
+        };
+        if self.compression == SnapshotCompression::NoCompression
+            || size < self.compression_threshold
+        {
+            return batch;
+        }
+
+        match compress(self.compression, batch.encode_to_vec()) {
+            Ok(payload) => RaftMessageBatch {
+                compression: self.compression.into(),
+                payload,
+                ..Default::default()
+            },
+            Err(err) => {
+                warn!(
+                    "compress message batch to node {}: {err:?}, send it without compression",
+                    self.to_node_id
+                );
+                batch
+            }
+        }
+    }
+}
+
+impl Stream for MessageBatcher {
+    type Item = RaftMessageBatch;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(msg) = this.pending.take() {
+            this.batch.push(msg);
+        }
+
+        let mut full = false;
+        while !this.terminated {
+            match this.receiver.poll_next_unpin(cx) {
+                Poll::Ready(Some(msg)) => {
+                    this.peers.lock().unwrap().insert(peer_of(&msg));
+                    let size = msg.encoded_len();
+                    if !this.batch.is_empty() && this.batch.size + size > this.max_batch_size {
+                        this.pending = Some(msg);
+                        full = true;
+                        break;
+                    }
+                    this.batch.push(msg);
+                }
+                Poll::Ready(None) => this.terminated = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if this.batch.is_empty() {
+            return if this.terminated {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            };
+        }
+
+        if !full && !this.terminated && !this.window.is_zero() {
+            let window = this.window;
+            let delay = this
+                .delay
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(window)));
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+
+        this.delay = None;
+        Poll::Ready(Some(this.build_batch()))
+    }
+}
+
+impl BatchBuilder {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.messages.is_empty() && self.heartbeats.is_empty()
+    }
+
+    fn push(&mut self, msg: RaftMessage) {
+        match compact_heartbeats(&msg) {
+            Some(heartbeats) => {
+                self.size += heartbeats.iter().map(|hb| hb.encoded_len()).sum::<usize>();
+                self.heartbeats.extend(heartbeats);
+            }
+            None => {
+                self.size += msg.encoded_len();
+                self.messages.push(msg);
+            }
+        }
+    }
+}
+
+/// Split a [`RaftMessageBatch`] into the [`RaftMessage`]s of each group.
+pub fn unpack_message_batch(batch: RaftMessageBatch) -> Result<Vec<RaftMessage>> {
+    let batch = match SnapshotCompression::from_i32(batch.compression) {
+        Some(SnapshotCompression::NoCompression) => batch,
+        Some(compression) => {
+            let payload = decompress(compression, batch.payload)?;
+            RaftMessageBatch::decode(payload.as_slice())?
+        }
+        None => {
+            return Err(Error::InvalidData(format!(
+                "unknown compression {} of message batch",
+                batch.compression
+            )))
+        }
+    };
+
+    let to_node_id = batch.to_node_id;
+    let mut msgs = batch.messages;
+    msgs.extend(
+        batch
+            .heartbeats
+            .into_iter()
+            .map(|hb| expand_heartbeat(hb, to_node_id)),
+    );
+    Ok(msgs)
+}
+
+#[inline]
+fn peer_of(msg: &RaftMessage) -> (u64, u64) {
+    let from = msg.from_replica.as_ref().unwrap().id;
+    let to = msg.to_replica.as_ref().unwrap().id;
+    (from, to)
+}
+
+/// Convert the messages into the compact form, if all of them are heartbeats which could be
+/// restored without losing any fields. The node of the target replica is restored from the
+/// `to_node_id` of the batch.
+fn compact_heartbeats(msg: &RaftMessage) -> Option<Vec<RaftHeartbeat>> {
+    if msg.messages.is_empty() || msg.hibernate {
+        return None;
+    }
+
+    let from_replica = msg.from_replica.as_ref().unwrap();
+    let to_replica = msg.to_replica.as_ref().unwrap();
+    let mut heartbeats = Vec::with_capacity(msg.messages.len());
+    for m in &msg.messages {
+        let response = match m.get_msg_type() {
+            MessageType::MsgHeartbeat => false,
+            MessageType::MsgHeartbeatResponse => true,
+            _ => return None,
+        };
+        let hb = RaftHeartbeat {
+            group_id: msg.group_id,
+            from_replica: Some(from_replica.clone()),
+            to_replica_id: to_replica.id,
+            to_replica_role: to_replica.role,
+            term: m.term,
+            commit: m.commit,
+            response,
+            context: m.context.clone(),
+        };
+        if heartbeat_message(&hb) != *m {
+            return None;
+        }
+        heartbeats.push(hb);
+    }
+    Some(heartbeats)
+}
+
+fn heartbeat_message(hb: &RaftHeartbeat) -> Message {
+    let mut msg = Message {
+        from: hb.from_replica.as_ref().unwrap().id,
+        to: hb.to_replica_id,
+        term: hb.term,
+        commit: hb.commit,
+        context: hb.context.clone(),
+        ..Default::default()
+    };
+    msg.set_msg_type(if hb.response {
+        MessageType::MsgHeartbeatResponse
+    } else {
+        MessageType::MsgHeartbeat
+    });
+    msg
+}
+
+fn expand_heartbeat(hb: RaftHeartbeat, to_node_id: u64) -> RaftMessage {
+    let msg = heartbeat_message(&hb);
+    RaftMessage {
+        group_id: hb.group_id,
+        from_replica: hb.from_replica,
+        to_replica: Some(ReplicaDesc {
+            id: hb.to_replica_id,
+            node_id: to_node_id,
+            role: hb.to_replica_role,
+        }),
+        messages: vec![msg],
+        hibernate: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engula_api::server::v1::ReplicaRole;
+    use raft::prelude::Entry;
+
+    use super::*;
+    use crate::{runtime::ExecutorOwner, SnapCompression};
+
+    fn replica(id: u64, node_id: u64) -> ReplicaDesc {
+        ReplicaDesc {
+            id,
+            node_id,
+            ..Default::default()
+        }
+    }
+
+    fn heartbeat(group_id: u64, from: u64, to: u64, response: bool) -> RaftMessage {
+        let hb = RaftHeartbeat {
+            group_id,
+            from_replica: Some(replica(from, 1)),
+            to_replica_id: to,
+            term: 5,
+            commit: 10,
+            response,
+            ..Default::default()
+        };
+        RaftMessage {
+            group_id,
+            from_replica: Some(replica(from, 1)),
+            to_replica: Some(replica(to, 2)),
+            messages: vec![heartbeat_message(&hb)],
+            hibernate: false,
+        }
+    }
+
+    fn append(group_id: u64, from: u64, to: u64, data: Vec<u8>) -> RaftMessage {
+        let mut msg = Message {
+            from,
+            to,
+            term: 5,
+            index: 10,
+            log_term: 5,
+            entries: vec![Entry {
+                index: 11,
+                term: 5,
+                data,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        msg.set_msg_type(MessageType::MsgAppend);
+        RaftMessage {
+            group_id,
+            from_replica: Some(replica(from, 1)),
+            to_replica: Some(replica(to, 2)),
+            messages: vec![msg],
+            hibernate: false,
+        }
+    }
+
+    fn collect_batches(cfg: &RaftConfig, msgs: Vec<RaftMessage>) -> Vec<RaftMessageBatch> {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let (sender, receiver) = mpsc::unbounded();
+            for msg in msgs {
+                sender.unbounded_send(msg).unwrap();
+            }
+            drop(sender);
+            MessageBatcher::new(cfg, 2, receiver).collect().await
+        })
+    }
+
+    #[test]
+    fn coalesce_queued_messages() {
+        let msgs = vec![
+            heartbeat(1, 1, 2, false),
+            append(2, 3, 4, b"value".to_vec()),
+            heartbeat(3, 5, 6, true),
+        ];
+        let batches = collect_batches(&RaftConfig::default(), msgs.clone());
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].messages.len(), 1);
+        assert_eq!(batches[0].heartbeats.len(), 2);
+
+        let mut unpacked = unpack_message_batch(batches[0].clone()).unwrap();
+        unpacked.sort_by_key(|msg| msg.group_id);
+        assert_eq!(unpacked, msgs);
+    }
+
+    #[test]
+    fn split_batch_by_size() {
+        let cfg = RaftConfig {
+            max_msg_batch_size: 1024,
+            ..Default::default()
+        };
+        let msgs = (0..8)
+            .map(|i| append(i, 1, 2, vec![0; 600]))
+            .collect::<Vec<_>>();
+        let batches = collect_batches(&cfg, msgs.clone());
+        assert_eq!(batches.len(), 8);
+
+        let unpacked = batches
+            .into_iter()
+            .flat_map(|batch| unpack_message_batch(batch).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(unpacked, msgs);
+    }
+
+    #[test]
+    fn restore_role_of_heartbeat_target() {
+        let mut msgs = vec![heartbeat(1, 1, 2, false), heartbeat(2, 3, 4, true)];
+        msgs[0].to_replica.as_mut().unwrap().role = ReplicaRole::Learner as i32;
+        msgs[1].to_replica.as_mut().unwrap().role = ReplicaRole::Witness as i32;
+        let batches = collect_batches(&RaftConfig::default(), msgs.clone());
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].heartbeats.len(), 2);
+        assert_eq!(unpack_message_batch(batches[0].clone()).unwrap(), msgs);
+    }
+
+    #[test]
+    fn keep_heartbeats_with_extra_fields() {
+        let mut msg = heartbeat(1, 1, 2, true);
+        msg.messages[0].reject = true;
+        assert!(compact_heartbeats(&msg).is_none());
+
+        let mut msg = heartbeat(1, 1, 2, false);
+        msg.hibernate = true;
+        assert!(compact_heartbeats(&msg).is_none());
+
+        let mut msg = heartbeat(1, 1, 2, false);
+        msg.messages
+            .push(append(1, 1, 2, vec![]).messages.pop().unwrap());
+        assert!(compact_heartbeats(&msg).is_none());
+    }
+
+    #[test]
+    fn compress_large_batch() {
+        for compression in [SnapCompression::Lz4, SnapCompression::Zstd] {
+            let cfg = RaftConfig {
+                msg_compression: compression,
+                msg_compression_threshold: 1024,
+                ..Default::default()
+            };
+            let msgs = vec![
+                append(1, 1, 2, b"append entries ".repeat(1024)),
+                heartbeat(2, 3, 4, false),
+            ];
+            let batches = collect_batches(&cfg, msgs.clone());
+            assert_eq!(batches.len(), 1);
+            assert_eq!(
+                batches[0].compression(),
+                SnapshotCompression::from(compression)
+            );
+            assert!(batches[0].messages.is_empty());
+            assert!(batches[0].payload.len() < 1024);
+            assert_eq!(unpack_message_batch(batches[0].clone()).unwrap(), msgs);
+
+            // The small batches are sent without compression.
+            let msgs = vec![append(1, 1, 2, b"value".to_vec())];
+            let batches = collect_batches(&cfg, msgs);
+            assert_eq!(batches[0].compression(), SnapshotCompression::NoCompression);
+        }
+    }
+
+    #[test]
+    fn record_peers_of_unsent_messages() {
+        let (sender, receiver) = mpsc::unbounded();
+        sender.unbounded_send(heartbeat(1, 1, 2, false)).unwrap();
+        sender.unbounded_send(heartbeat(2, 3, 4, false)).unwrap();
+        let mut batcher = MessageBatcher::new(&RaftConfig::default(), 2, receiver);
+        batcher.close();
+        assert!(sender.is_closed());
+        let peers = batcher.peers();
+        let peers = peers.lock().unwrap();
+        assert_eq!(*peers, HashSet::from([(1, 2), (3, 4)]));
+    }
+}
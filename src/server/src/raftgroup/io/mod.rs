@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod batch;
 mod log_writer;
 mod purge;
 mod transport;
 
-pub use self::{log_writer::LogWriter, purge::start_purging_expired_files, transport::*};
+pub use self::{
+    batch::unpack_message_batch, log_writer::LogWriter, purge::start_purging_expired_files,
+    transport::*,
+};
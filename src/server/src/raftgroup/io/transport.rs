@@ -11,20 +11,33 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use engula_api::server::v1::{NodeDesc, ReplicaDesc};
+use engula_client::ConnManager;
 use futures::{channel::mpsc, StreamExt};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
+use super::batch::{MessageBatcher, PeerSet};
 use crate::{
     node::route_table::RaftRouteTable,
     raftgroup::RaftNodeFacade,
-    runtime::TaskPriority,
-    serverpb::v1::{raft_client::RaftClient, RaftMessage, SnapshotChunk, SnapshotRequest},
-    Result,
+    runtime::{time::Instant, TaskPriority},
+    serverpb::v1::{
+        raft_client::RaftClient, RaftMessage, SnapshotChunk, SnapshotCompression, SnapshotRequest,
+    },
+    Error, RaftConfig, Result,
 };
 
+/// The intervals of probing whether a node, which doesn't support `SendMessages`, is upgraded.
+const UNBATCHED_NODE_PROBE_INTERVAL: Duration = Duration::from_secs(300);
+
+type UnbatchedNodes = Arc<Mutex<HashMap<u64, Instant>>>;
+
 struct StreamingRequest {
     from: ReplicaDesc,
     to: ReplicaDesc,
@@ -34,10 +47,21 @@ struct StreamingRequest {
 
 struct StreamingTask {
     resolver: Arc<dyn AddressResolver>,
+    conn_manager: ConnManager,
     raft_node: RaftNodeFacade,
     request: StreamingRequest,
 }
 
+/// Streams the batched messages of all replicas to the target node.
+struct BatchingTask {
+    resolver: Arc<dyn AddressResolver>,
+    conn_manager: ConnManager,
+    route_table: RaftRouteTable,
+    unbatched_nodes: UnbatchedNodes,
+    node_id: u64,
+    batcher: MessageBatcher,
+}
+
 /// An abstraction for resolving address by node id.
 #[crate::async_trait]
 pub trait AddressResolver: Send + Sync {
@@ -61,8 +85,15 @@ where
     Self: Send + Sync,
 {
     resolver: Arc<dyn AddressResolver>,
+    conn_manager: ConnManager,
     sender: mpsc::UnboundedSender<StreamingRequest>,
     route_table: RaftRouteTable,
+    cfg: Arc<RaftConfig>,
+    /// The senders of batching tasks, indexed by the target node id.
+    batchers: Arc<Mutex<HashMap<u64, mpsc::UnboundedSender<RaftMessage>>>>,
+    /// The nodes which don't support `SendMessages`, eg. the old versions during a rolling
+    /// upgrade, and the instants they are found.
+    unbatched_nodes: UnbatchedNodes,
 }
 
 impl Channel {
@@ -84,6 +115,12 @@ impl Channel {
                 }
             }
 
+            let node_id = msg.to_replica.as_ref().unwrap().node_id;
+            if self.transport_mgr.is_batching_enabled(node_id) {
+                self.sender = Some(self.transport_mgr.batching_sender(node_id));
+                continue;
+            }
+
             // Try create new connection if we reaches here.
             let (sender, receiver) = mpsc::unbounded();
             let req = StreamingRequest {
@@ -99,12 +136,21 @@ impl Channel {
 }
 
 impl ChannelManager {
-    pub async fn build(resolver: Arc<dyn AddressResolver>, route_table: RaftRouteTable) -> Self {
+    pub async fn build(
+        resolver: Arc<dyn AddressResolver>,
+        conn_manager: ConnManager,
+        route_table: RaftRouteTable,
+        cfg: RaftConfig,
+    ) -> Self {
         let (sender, receiver) = mpsc::unbounded();
         let mgr = ChannelManager {
             resolver,
+            conn_manager,
             sender,
             route_table,
+            cfg: Arc::new(cfg),
+            batchers: Arc::default(),
+            unbatched_nodes: Arc::default(),
         };
 
         let cloned_mgr = mgr.clone();
@@ -121,6 +167,48 @@ impl ChannelManager {
             .expect("transport worker lifetime should large that replicas");
     }
 
+    fn is_batching_enabled(&self, node_id: u64) -> bool {
+        if !self.cfg.enable_msg_batch {
+            return false;
+        }
+        let mut unbatched_nodes = self.unbatched_nodes.lock().unwrap();
+        match unbatched_nodes.get(&node_id) {
+            Some(found_at) if found_at.elapsed() < UNBATCHED_NODE_PROBE_INTERVAL => false,
+            Some(_) => {
+                // Probe again, the node might be upgraded.
+                unbatched_nodes.remove(&node_id);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the sender of the batching task to the target node, a new task is spawned if the
+    /// previous one is finished.
+    fn batching_sender(&self, node_id: u64) -> mpsc::UnboundedSender<RaftMessage> {
+        let mut batchers = self.batchers.lock().unwrap();
+        if let Some(sender) = batchers.get(&node_id) {
+            if !sender.is_closed() {
+                return sender.clone();
+            }
+        }
+
+        let (sender, receiver) = mpsc::unbounded();
+        let task = BatchingTask {
+            resolver: self.resolver.clone(),
+            conn_manager: self.conn_manager.clone(),
+            route_table: self.route_table.clone(),
+            unbatched_nodes: self.unbatched_nodes.clone(),
+            node_id,
+            batcher: MessageBatcher::new(&self.cfg, node_id, receiver),
+        };
+        crate::runtime::current().spawn(None, TaskPriority::IoHigh, async move {
+            task.run().await;
+        });
+        batchers.insert(node_id, sender.clone());
+        sender
+    }
+
     async fn run(self, mut receiver: mpsc::UnboundedReceiver<StreamingRequest>) {
         while let Some(request) = receiver.next().await {
             let raft_node = match self.route_table.find(request.from.id) {
@@ -136,6 +224,7 @@ impl ChannelManager {
 
             let task = StreamingTask {
                 resolver: self.resolver.clone(),
+                conn_manager: self.conn_manager.clone(),
                 raft_node,
                 request,
             };
@@ -160,8 +249,8 @@ impl StreamingTask {
         let from_id = self.request.from.id;
         let node_id = self.request.to.node_id;
         let node_desc = resolve_address(&*self.resolver, self.request.to.node_id).await?;
-        let address = format!("http://{}", node_desc.addr);
-        let mut client = RaftClient::connect(address).await?;
+        let channel = self.conn_manager.connect(&node_desc.addr).await?;
+        let mut client = RaftClient::new(channel);
         if let Err(e) = client.send_message(self.request.receiver).await {
             warn!("serve request to node {node_id} replica {target_id} from {from_id}: {e:?}");
         }
@@ -169,17 +258,82 @@ impl StreamingTask {
     }
 }
 
+impl BatchingTask {
+    async fn run(self) {
+        let node_id = self.node_id;
+        let route_table = self.route_table.clone();
+        let peers = self.batcher.peers();
+        let unbatched_nodes = self.unbatched_nodes.clone();
+        if let Err(err) = self.serve_batching_request().await {
+            if matches!(&err, Error::Rpc(status) if status.code() == tonic::Code::Unimplemented) {
+                info!("node {node_id} doesn't support batching messages, send them one by one");
+                unbatched_nodes
+                    .lock()
+                    .unwrap()
+                    .insert(node_id, Instant::now());
+            } else {
+                warn!("send batched messages to node {node_id}: {err:?}");
+            }
+            report_unreachable_peers(&route_table, peers);
+        }
+    }
+
+    async fn serve_batching_request(mut self) -> Result<()> {
+        let node_desc = match resolve_address(&*self.resolver, self.node_id).await {
+            Ok(node_desc) => node_desc,
+            Err(err) => {
+                self.batcher.close();
+                return Err(err);
+            }
+        };
+        let channel = match self.conn_manager.connect(&node_desc.addr).await {
+            Ok(channel) => channel,
+            Err(err) => {
+                self.batcher.close();
+                return Err(err.into());
+            }
+        };
+        let mut client = RaftClient::new(channel);
+        client
+            .send_messages(self.batcher)
+            .await
+            .map_err(Error::Rpc)?;
+        Ok(())
+    }
+}
+
+/// The messages of a broken stream are lost, reports the targets as unreachable so that the
+/// leaders probe them again.
+fn report_unreachable_peers(route_table: &RaftRouteTable, peers: PeerSet) {
+    let peers = std::mem::take(&mut *peers.lock().unwrap());
+    for (from_id, to_id) in peers {
+        if let Some(mut raft_node) = route_table.find(from_id) {
+            raft_node.report_unreachable(to_id);
+        }
+    }
+}
+
+/// Retrive the chunks of a snapshot from the target replica, starting from the `offset` of the
+/// file at `file_index`.
 pub async fn retrive_snapshot(
     trans_mgr: &ChannelManager,
     target_replica: ReplicaDesc,
     snapshot_id: Vec<u8>,
+    file_index: u64,
+    offset: u64,
 ) -> Result<impl futures::Stream<Item = Result<SnapshotChunk, tonic::Status>>> {
     let node_desc = resolve_address(&*trans_mgr.resolver, target_replica.node_id).await?;
-    let address = format!("http://{}", node_desc.addr);
-    let mut client = RaftClient::connect(address).await?;
+    let channel = trans_mgr.conn_manager.connect(&node_desc.addr).await?;
+    let mut client = RaftClient::new(channel);
     let request = SnapshotRequest {
         replica_id: target_replica.id,
         snapshot_id,
+        file_index,
+        offset,
+        accepted_compressions: vec![
+            SnapshotCompression::Lz4 as i32,
+            SnapshotCompression::Zstd as i32,
+        ],
     };
     let resp = client.retrieve_snapshot(request).await?;
     Ok(resp.into_inner())
@@ -113,6 +113,16 @@ lazy_static! {
         "The total of unreachable of raftgroup",
     )
     .unwrap();
+    pub static ref RAFTGROUP_HIBERNATE_TOTAL: IntCounter = register_int_counter!(
+        "raftgroup_hibernate_total",
+        "The total of hibernate of raftgroup",
+    )
+    .unwrap();
+    pub static ref RAFTGROUP_WAKE_UP_TOTAL: IntCounter = register_int_counter!(
+        "raftgroup_wake_up_total",
+        "The total of wake up of raftgroup",
+    )
+    .unwrap();
 }
 
 lazy_static! {
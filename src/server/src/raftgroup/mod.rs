@@ -28,21 +28,26 @@ use engula_api::server::v1::*;
 use raft::prelude::{
     ConfChangeSingle, ConfChangeTransition, ConfChangeType, ConfChangeV2, ConfState,
 };
+use tracing::error;
 
 pub use self::{
     facade::RaftNodeFacade,
     fsm::{ApplyEntry, SnapshotBuilder, StateMachine},
-    io::{retrive_snapshot, AddressResolver, ChannelManager},
+    io::{retrive_snapshot, unpack_message_batch, AddressResolver, ChannelManager},
     monitor::*,
     snap::SnapManager,
-    storage::{destory as destory_storage, write_initial_state},
+    storage::{
+        destory as destory_storage, dump_raft_log, reset_to_applied, write_initial_state,
+        RaftLogDump,
+    },
     worker::{RaftGroupState, StateObserver},
 };
 use self::{io::LogWriter, worker::RaftWorker};
 use crate::{
     raftgroup::io::start_purging_expired_files,
     runtime::{sync::WaitGroup, TaskPriority},
-    RaftConfig, Result,
+    serverpb::v1::EvalResult,
+    Error, RaftConfig, Result,
 };
 
 /// `ReadPolicy` is used to control `RaftNodeFacade::read` behavior.
@@ -113,8 +118,20 @@ impl RaftManager {
         let facade = RaftNodeFacade::open(worker.request_sender());
         let log_writer = self.log_writer.clone();
         crate::runtime::current().spawn(Some(group_id), TaskPriority::High, async move {
-            // TODO(walter) handle result.
-            worker.run(log_writer).await.unwrap();
+            match worker.run(log_writer).await {
+                Ok(()) => {}
+                // The failpoint simulates a crash, only the replica is stopped until the server
+                // restarts.
+                Err(Error::Failpoint(name)) => {
+                    error!(
+                        "group {group_id} replica {replica_id} raft worker is stopped by failpoint {name}"
+                    );
+                }
+                // TODO(walter) handle result.
+                Err(err) => {
+                    panic!("group {group_id} replica {replica_id} raft worker: {err:?}");
+                }
+            }
             drop(wait_group);
         });
         Ok(facade)
@@ -127,9 +144,12 @@ fn encode_to_conf_change(change_replicas: ChangeReplicas) -> ConfChangeV2 {
     let mut conf_changes = vec![];
     for c in &change_replicas.changes {
         let change_type = match ChangeReplicaType::from_i32(c.change_type) {
-            Some(ChangeReplicaType::Add) => ConfChangeType::AddNode,
+            // A witness is a voter from the view of raft.
+            Some(ChangeReplicaType::Add | ChangeReplicaType::AddWitness) => ConfChangeType::AddNode,
             Some(ChangeReplicaType::Remove) => ConfChangeType::RemoveNode,
-            Some(ChangeReplicaType::AddLearner) => ConfChangeType::AddLearnerNode,
+            Some(ChangeReplicaType::AddLearner | ChangeReplicaType::AddReadLearner) => {
+                ConfChangeType::AddLearnerNode
+            }
             None => panic!("such change replica operation isn't supported"),
         };
         conf_changes.push(ConfChangeSingle {
@@ -152,15 +172,45 @@ fn decode_from_conf_change(conf_change: &ConfChangeV2) -> ChangeReplicas {
         .expect("ChangeReplicas is saved in ConfChangeV2::context")
 }
 
+/// Decode the payload of a raft log entry.
+pub fn decode_entry(entry: &raft::prelude::Entry) -> Result<ApplyEntry> {
+    use prost::Message;
+    use raft::prelude::EntryType;
+
+    Ok(match entry.get_entry_type() {
+        EntryType::EntryNormal if entry.data.is_empty() => ApplyEntry::Empty,
+        EntryType::EntryNormal => ApplyEntry::Proposal {
+            eval_result: EvalResult::decode(&*entry.data)?,
+        },
+        EntryType::EntryConfChange => {
+            return Err(Error::InvalidData(format!(
+                "entry {} ConfChangeV1 is not supported",
+                entry.index
+            )));
+        }
+        EntryType::EntryConfChangeV2 => {
+            let conf_change = if !entry.data.is_empty() {
+                ConfChangeV2::decode(&*entry.data)?
+            } else {
+                // This entry is generated by `AutoLeave`.
+                ConfChangeV2::default()
+            };
+            ApplyEntry::ConfigChange {
+                change_replicas: decode_from_conf_change(&conf_change),
+            }
+        }
+    })
+}
+
 pub fn conf_state_from_group_descriptor(desc: &GroupDesc) -> ConfState {
     let mut cs = ConfState::default();
     let mut in_joint = false;
     for replica in desc.replicas.iter() {
         match ReplicaRole::from_i32(replica.role).unwrap_or(ReplicaRole::Voter) {
-            ReplicaRole::Voter => {
+            ReplicaRole::Voter | ReplicaRole::Witness => {
                 cs.voters.push(replica.id);
             }
-            ReplicaRole::Learner => {
+            ReplicaRole::Learner | ReplicaRole::ReadLearner => {
                 cs.learners.push(replica.id);
             }
             ReplicaRole::IncomingVoter => {
@@ -14,7 +14,7 @@
 
 use engula_api::server::v1::RaftRole;
 use futures::channel::oneshot;
-use raft::{prelude::*, ConfChangeI, StateRole, Storage as RaftStorage};
+use raft::{prelude::*, ConfChangeI, ProgressState, StateRole, Storage as RaftStorage, INVALID_ID};
 use raft_engine::LogBatch;
 use tracing::{info, trace};
 
@@ -249,8 +249,23 @@ where
 
         if !self.read_index_requests.is_empty() {
             let requests = std::mem::take(&mut self.read_index_requests);
-            let read_state_ctx = self.applier.delegate_read_requests(requests);
-            self.raw_node.read_index(read_state_ctx);
+            if self.raw_node.raft.state != StateRole::Leader
+                && self.raw_node.raft.leader_id == INVALID_ID
+            {
+                // The read index requests of followers are forwarded to the leader, it would be
+                // dropped silently if there is no leader.
+                for req in requests {
+                    req.send(Err(Error::NotLeader(
+                        self.group_id,
+                        self.raw_node.raft.term,
+                        None,
+                    )))
+                    .unwrap_or_default();
+                }
+            } else {
+                let read_state_ctx = self.applier.delegate_read_requests(requests);
+                self.raw_node.read_index(read_state_ctx);
+            }
         }
     }
 
@@ -260,18 +275,21 @@ where
     }
 
     /// Advance raft node, persist, apply entries and send messages.
+    ///
+    /// An error means the state machine fails to apply the committed entries, the raft node
+    /// should not be advanced anymore.
     pub(super) fn advance(
         &mut self,
         perf_ctx: &mut AdvancePerfContext,
         template: &mut impl AdvanceTemplate,
-    ) -> Option<WriteTask> {
+    ) -> Result<Option<WriteTask>> {
         self.advance_read_requests();
         if !self.raw_node.has_ready() {
             if !self.read_states.is_empty() {
                 self.applier
                     .apply_read_states(std::mem::take(&mut self.read_states));
             }
-            return None;
+            return Ok(None);
         }
 
         record_perf_point(&mut perf_ctx.take_ready);
@@ -296,7 +314,7 @@ where
             template.send_messages(ready.take_messages());
         }
 
-        self.handle_apply(perf_ctx, template, &mut ready);
+        self.handle_apply(perf_ctx, template, &mut ready)?;
 
         let write_task = self.build_write_task(&mut ready);
         if write_task.is_none() {
@@ -306,7 +324,7 @@ where
         } else {
             self.raw_node.advance_append_async(ready);
         }
-        write_task
+        Ok(write_task)
     }
 
     pub(super) fn post_advance(
@@ -336,6 +354,12 @@ where
         self.applier.mut_state_machine()
     }
 
+    /// The number of config changes and snapshots applied, see [`Applier::conf_updates`].
+    #[inline]
+    pub fn conf_updates(&self) -> u64 {
+        self.applier.conf_updates()
+    }
+
     #[inline]
     pub fn raft(&self) -> &Raft<Storage> {
         &self.raw_node.raft
@@ -351,12 +375,46 @@ where
         self.raw_node.raft.raft_log.committed
     }
 
+    /// Whether this leader is idle, that there are no pending proposals, reads or config changes,
+    /// and all entries are applied and replicated to the other replicas.
+    pub fn is_idle_leader(&self) -> bool {
+        let raft = &self.raw_node.raft;
+        if raft.state != StateRole::Leader
+            || raft.lead_transferee.is_some()
+            || self.has_pending_config_change()
+        {
+            return false;
+        }
+
+        if !self.lease_read_requests.is_empty()
+            || !self.read_index_requests.is_empty()
+            || !self.read_states.is_empty()
+            || raft.pending_read_count() > 0
+            || raft.ready_read_count() > 0
+        {
+            return false;
+        }
+
+        let raft_log = &raft.raft_log;
+        let last_index = raft_log.last_index();
+        if raft_log.committed != last_index
+            || raft_log.persisted != last_index
+            || raft_log.applied != last_index
+        {
+            return false;
+        }
+
+        raft.prs()
+            .iter()
+            .all(|(_, pr)| pr.matched == last_index && pr.state == ProgressState::Replicate)
+    }
+
     fn handle_apply(
         &mut self,
         perf_ctx: &mut AdvancePerfContext,
         template: &mut impl AdvanceTemplate,
         ready: &mut Ready,
-    ) {
+    ) -> Result<()> {
         if !self.read_states.is_empty() {
             self.applier
                 .apply_read_states(std::mem::take(&mut self.read_states));
@@ -378,7 +436,8 @@ where
                 &mut self.raw_node,
                 replica_cache,
                 ready.take_committed_entries(),
-            );
+            )?;
+            crate::failpoint::eval("raft_before_advance_apply")?;
             self.raw_node.advance_apply_to(applied);
 
             let last_applied_index = self.applier.applied_index();
@@ -388,6 +447,7 @@ where
         if !ready.snapshot().is_empty() {
             template.apply_snapshot(&mut self.applier, ready.snapshot());
         }
+        Ok(())
     }
 
     fn build_write_task(&mut self, ready: &mut Ready) -> Option<WriteTask> {
@@ -520,6 +580,7 @@ mod tests {
     use std::{path::PathBuf, sync::Arc};
 
     use engula_api::server::v1::{GroupDesc, NodeDesc, ReplicaDesc, ReplicaRole};
+    use engula_client::ConnManager;
     use raft_engine::*;
 
     use super::*;
@@ -880,7 +941,13 @@ mod tests {
             let snap_dir = dir.path().join("snap");
             let snap_mgr = SnapManager::new(snap_dir.clone());
             let resolver = Arc::new(MockedAddressResolver {});
-            let transport_mgr = ChannelManager::build(resolver, RaftRouteTable::new()).await;
+            let transport_mgr = ChannelManager::build(
+                resolver,
+                ConnManager::new(),
+                RaftRouteTable::new(),
+                RaftConfig::default(),
+            )
+            .await;
             let log_writer = LogWriter::new(64 << 10, engine.clone());
             let raft_mgr = RaftManager {
                 cfg: RaftConfig::default(),
@@ -947,7 +1014,7 @@ mod tests {
                 replica_cache: ReplicaCache::default(),
             };
             let mut perf_ctx = AdvancePerfContext::default();
-            while let Some(task) = node.advance(&mut perf_ctx, &mut template) {
+            while let Some(task) = node.advance(&mut perf_ctx, &mut template).unwrap() {
                 let mut batch = LogBatch::default();
                 node.mut_store()
                     .write(&mut batch, &task)
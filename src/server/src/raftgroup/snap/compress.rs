@@ -0,0 +1,69 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{serverpb::v1::SnapshotCompression, Error, Result, SnapCompression};
+
+/// The level of zstd, the fastest one is chosen since snapshot chunks are compressed on the fly.
+const ZSTD_LEVEL: i32 = 1;
+
+impl From<SnapCompression> for SnapshotCompression {
+    fn from(compression: SnapCompression) -> Self {
+        match compression {
+            SnapCompression::None => SnapshotCompression::NoCompression,
+            SnapCompression::Lz4 => SnapshotCompression::Lz4,
+            SnapCompression::Zstd => SnapshotCompression::Zstd,
+        }
+    }
+}
+
+pub fn compress(compression: SnapshotCompression, data: Vec<u8>) -> Result<Vec<u8>> {
+    match compression {
+        SnapshotCompression::NoCompression => Ok(data),
+        SnapshotCompression::Lz4 => Ok(lz4_flex::compress_prepend_size(&data)),
+        SnapshotCompression::Zstd => Ok(zstd::bulk::compress(&data, ZSTD_LEVEL)?),
+    }
+}
+
+pub fn decompress(compression: SnapshotCompression, data: Vec<u8>) -> Result<Vec<u8>> {
+    match compression {
+        SnapshotCompression::NoCompression => Ok(data),
+        SnapshotCompression::Lz4 => lz4_flex::decompress_size_prepended(&data)
+            .map_err(|err| Error::InvalidData(format!("decompress lz4 chunk: {err}"))),
+        SnapshotCompression::Zstd => zstd::stream::decode_all(data.as_slice())
+            .map_err(|err| Error::InvalidData(format!("decompress zstd chunk: {err}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compress_and_decompress() {
+        let data = b"snapshot chunk ".repeat(1024);
+        for compression in [
+            SnapshotCompression::NoCompression,
+            SnapshotCompression::Lz4,
+            SnapshotCompression::Zstd,
+        ] {
+            let compressed = compress(compression, data.clone()).unwrap();
+            if compression != SnapshotCompression::NoCompression {
+                assert!(compressed.len() < data.len());
+            }
+            assert_eq!(decompress(compression, compressed).unwrap(), data);
+        }
+
+        assert!(decompress(SnapshotCompression::Lz4, vec![0xff; 16]).is_err());
+        assert!(decompress(SnapshotCompression::Zstd, vec![0xff; 16]).is_err());
+    }
+}
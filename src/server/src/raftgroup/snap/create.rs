@@ -94,6 +94,7 @@ pub(super) async fn create_snapshot(
         apply_state: Some(apply_state),
         group_desc: Some(descriptor),
         files,
+        ..Default::default()
     };
 
     stable_snapshot_meta(&snap_dir, &snap_meta).await?;
@@ -17,22 +17,39 @@ use std::{
     fs::File,
     os::unix::ffi::OsStringExt,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use engula_api::server::v1::ReplicaDesc;
 use futures::{channel::mpsc, SinkExt, StreamExt};
 use raft::eraftpb::Message;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use super::SnapManager;
+use super::{compress::decompress, SnapManager};
 use crate::{
     raftgroup::{metrics::*, retrive_snapshot, worker::Request, ChannelManager},
     record_latency,
-    runtime::TaskPriority,
-    serverpb::v1::{snapshot_chunk, SnapshotChunk, SnapshotFile, SnapshotMeta},
+    runtime::{time::sleep, TaskPriority},
+    serverpb::v1::{
+        snapshot_chunk, SnapshotChunk, SnapshotCompression, SnapshotFile, SnapshotMeta,
+    },
     Error, Result,
 };
 
+/// The max times of resuming an interrupted snapshot transmission in a download, the download
+/// fails after that, and the received files are kept for the next download of the same snapshot.
+const MAX_RESUME_TIMES: u64 = 3;
+
+/// Identify the snapshot a download receives. The snapshot id is only unique within the sender,
+/// and it might be reused once the sender restarts, so the index and term are also compared.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct DownloadKey {
+    pub from_replica: u64,
+    pub snapshot_id: Vec<u8>,
+    pub index: u64,
+    pub term: u64,
+}
+
 struct PartialFile {
     meta: SnapshotFile,
     file: File,
@@ -40,36 +57,57 @@ struct PartialFile {
     crc32: crc32fast::Hasher,
 }
 
-struct SnapshotBuilder {
+/// Build a snapshot from the incoming chunks. The received files and the partial file are kept
+/// if the transmission is interrupted, so that it could be resumed from where it stopped.
+pub(super) struct SnapshotBuilder {
     replica_id: u64,
     base_dir: PathBuf,
     meta: SnapshotMeta,
-    file_name: Vec<u8>,
+    /// The compression of the incoming chunks, which is announced by the snapshot meta.
+    compression: SnapshotCompression,
+    /// The files announced by the snapshot meta of sender.
+    announced_files: Vec<SnapshotFile>,
     file: Option<PartialFile>,
 }
 
 impl SnapshotBuilder {
-    fn new(replica_id: u64, base_dir: &Path) -> Self {
+    pub(super) fn new(replica_id: u64, base_dir: &Path) -> Self {
         SnapshotBuilder {
             replica_id,
             base_dir: base_dir.to_owned(),
             meta: SnapshotMeta::default(),
-            file_name: vec![],
+            compression: SnapshotCompression::NoCompression,
+            announced_files: vec![],
             file: None,
         }
     }
 
+    /// Returns the file index and the offset of that file to resume the transmission from.
+    pub(super) fn resume_point(&self) -> (u64, u64) {
+        let file_index = self.meta.files.len() as u64;
+        let offset = self
+            .file
+            .as_ref()
+            .map(|f| f.size as u64)
+            .unwrap_or_default();
+        (file_index, offset)
+    }
+
     async fn append(&mut self, chunk: SnapshotChunk) -> Result<()> {
         match chunk.value {
-            Some(snapshot_chunk::Value::File(file)) => self.switch_file(file).await,
+            Some(snapshot_chunk::Value::File(file)) => self.switch_file(file, chunk.offset).await,
             Some(snapshot_chunk::Value::ChunkData(data)) => match self.file.as_mut() {
                 Some(file) => {
                     RAFTGROUP_DOWNLOAD_SNAPSHOT_BYTES_TOTAL.inc_by(data.len() as u64);
+                    let data = decompress(self.compression, data)?;
                     file.write_all(&data).await
                 }
                 None => Err(Error::InvalidData("missing file meta".to_string())),
             },
             Some(snapshot_chunk::Value::Meta(meta)) => {
+                // The files are saved without compression.
+                self.compression = meta.compression();
+                self.announced_files = meta.files;
                 self.meta.apply_state = meta.apply_state;
                 self.meta.group_desc = meta.group_desc;
                 Ok(())
@@ -78,11 +116,28 @@ impl SnapshotBuilder {
         }
     }
 
-    async fn switch_file(&mut self, file_meta: SnapshotFile) -> Result<()> {
+    async fn switch_file(&mut self, file_meta: SnapshotFile, offset: u64) -> Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            if file.meta == file_meta {
+                // The sender resumes the partial file.
+                if offset != file.size as u64 {
+                    return Err(Error::InvalidData(format!(
+                        "resume file from offset {offset}, but {} bytes are received",
+                        file.size
+                    )));
+                }
+                return file.resume();
+            }
+        }
+        if offset != 0 {
+            return Err(Error::InvalidData(format!(
+                "resume file from offset {offset}, but no partial file exists"
+            )));
+        }
+
         self.finish_partial_file().await?;
 
-        let name = file_meta.name.clone();
-        let str = OsString::from_vec(name.clone());
+        let str = OsString::from_vec(file_meta.name.clone());
         let path = self.base_dir.join(str);
         if let Some(parent) = path.parent() {
             if !std::fs::try_exists(parent)? {
@@ -90,7 +145,6 @@ impl SnapshotBuilder {
             }
         }
 
-        self.file_name = name;
         self.file = Some(PartialFile::new(self.replica_id, &path, file_meta)?);
 
         Ok(())
@@ -105,6 +159,13 @@ impl SnapshotBuilder {
 
     async fn finish(mut self) -> Result<SnapshotMeta> {
         self.finish_partial_file().await?;
+        if self.meta.files != self.announced_files {
+            return Err(Error::InvalidData(format!(
+                "{} files are received, but {} files are announced",
+                self.meta.files.len(),
+                self.announced_files.len()
+            )));
+        }
         super::create::stable_snapshot_meta(&self.base_dir, &self.meta).await?;
         Ok(self.meta)
     }
@@ -121,7 +182,11 @@ impl PartialFile {
             file_meta.crc32
         );
 
-        let file = OpenOptions::new().write(true).create(true).open(path)?;
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
 
         Ok(PartialFile {
             meta: file_meta,
@@ -134,12 +199,27 @@ impl PartialFile {
     async fn write_all(&mut self, buf: &[u8]) -> Result<()> {
         use std::io::Write;
 
+        if (self.size + buf.len()) as u64 > self.meta.size {
+            return Err(Error::InvalidData(format!(
+                "the size of file exceeds {}",
+                self.meta.size
+            )));
+        }
         self.file.write_all(buf)?;
         self.crc32.update(buf);
         self.size += buf.len();
         Ok(())
     }
 
+    /// Discard the bytes after the received size, which might be left by a failed write.
+    fn resume(&mut self) -> Result<()> {
+        use std::io::{Seek, SeekFrom};
+
+        self.file.set_len(self.size as u64)?;
+        self.file.seek(SeekFrom::Start(self.size as u64))?;
+        Ok(())
+    }
+
     async fn finish(self) -> Result<SnapshotFile> {
         self.file.sync_all()?;
 
@@ -153,8 +233,8 @@ impl PartialFile {
         let crc32 = self.crc32.finalize();
         if crc32 != self.meta.crc32 {
             return Err(Error::InvalidData(format!(
-                "checksum is not equals, expect {}, but got {}",
-                self.meta.size, self.size
+                "checksum is not equals, expect {}, but got {crc32}",
+                self.meta.crc32
             )));
         }
 
@@ -186,7 +266,8 @@ pub fn dispatch_downloading_snap_task(
     });
 }
 
-/// Download snapshot from target and returns the local snapshot id.
+/// Download snapshot from target and returns the local snapshot id. The interrupted
+/// transmission is resumed from where it stopped.
 async fn download_snap(
     replica_id: u64,
     tran_mgr: ChannelManager,
@@ -198,18 +279,79 @@ async fn download_snap(
     assert!(msg.has_snapshot() && !msg.get_snapshot().is_empty());
     let snapshot = msg.get_snapshot();
     let snapshot_id = snapshot.data.clone();
-    let chunk_stream = retrive_snapshot(&tran_mgr, from_replica, snapshot_id).await?;
-    save_snapshot(&snap_mgr, replica_id, chunk_stream).await
+    let key = DownloadKey {
+        from_replica: from_replica.id,
+        snapshot_id: snapshot_id.clone(),
+        index: snapshot.get_metadata().index,
+        term: snapshot.get_metadata().term,
+    };
+    let _permit = snap_mgr.throttle().acquire_recv().await;
+
+    let (base_dir, mut snap_builder) = match snap_mgr.take_partial_download(replica_id, &key) {
+        Some((base_dir, snap_builder)) => {
+            info!(
+                "replica {replica_id} resume downloading snapshot into {}",
+                base_dir.display()
+            );
+            (base_dir, snap_builder)
+        }
+        None => {
+            let base_dir = prepare_snapshot_dir(&snap_mgr, replica_id)?;
+            let snap_builder = SnapshotBuilder::new(replica_id, &base_dir);
+            (base_dir, snap_builder)
+        }
+    };
+    let mut resume_times = 0;
+    loop {
+        let (file_index, offset) = snap_builder.resume_point();
+        let result = match retrive_snapshot(
+            &tran_mgr,
+            from_replica.clone(),
+            snapshot_id.clone(),
+            file_index,
+            offset,
+        )
+        .await
+        {
+            Ok(chunk_stream) => receive_chunks(&snap_mgr, &mut snap_builder, chunk_stream).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => break,
+            Err(err) if resume_times < MAX_RESUME_TIMES && is_interrupted(&err) => {
+                resume_times += 1;
+                warn!(
+                    "replica {replica_id} download snapshot from file {file_index} offset {offset}: {err}, resume it later"
+                );
+                sleep(Duration::from_millis(200 * resume_times)).await;
+            }
+            Err(err) => {
+                if is_interrupted(&err) {
+                    snap_mgr.keep_partial_download(replica_id, key, base_dir, snap_builder);
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    install_snapshot(&snap_mgr, replica_id, &base_dir, snap_builder).await
 }
 
 pub(super) async fn save_snapshot<S>(
     snap_mgr: &SnapManager,
     replica_id: u64,
-    mut chunk_stream: S,
+    chunk_stream: S,
 ) -> Result<Vec<u8>>
 where
     S: futures::Stream<Item = Result<SnapshotChunk, tonic::Status>> + Unpin,
 {
+    let base_dir = prepare_snapshot_dir(snap_mgr, replica_id)?;
+    let mut snap_builder = SnapshotBuilder::new(replica_id, &base_dir);
+    receive_chunks(snap_mgr, &mut snap_builder, chunk_stream).await?;
+    install_snapshot(snap_mgr, replica_id, &base_dir, snap_builder).await
+}
+
+pub(super) fn prepare_snapshot_dir(snap_mgr: &SnapManager, replica_id: u64) -> Result<PathBuf> {
     let base_dir = snap_mgr.create(replica_id);
     info!(
         "replica {replica_id} save incoming snapshot chunk stream into {}",
@@ -217,12 +359,45 @@ where
     );
 
     std::fs::create_dir_all(&base_dir)?;
-    let mut snap_builder = SnapshotBuilder::new(replica_id, &base_dir);
+    Ok(base_dir)
+}
+
+pub(super) async fn receive_chunks<S>(
+    snap_mgr: &SnapManager,
+    snap_builder: &mut SnapshotBuilder,
+    mut chunk_stream: S,
+) -> Result<()>
+where
+    S: futures::Stream<Item = Result<SnapshotChunk, tonic::Status>> + Unpin,
+{
     while let Some(resp) = chunk_stream.next().await {
         let chunk = resp?;
+        let bytes = prost::Message::encoded_len(&chunk);
         snap_builder.append(chunk).await?;
+        snap_mgr.throttle().recv_rate.consume(bytes).await;
+        crate::failpoint::eval_async("snap_download_chunk").await?;
     }
+    Ok(())
+}
 
+pub(super) async fn install_snapshot(
+    snap_mgr: &SnapManager,
+    replica_id: u64,
+    base_dir: &Path,
+    snap_builder: SnapshotBuilder,
+) -> Result<Vec<u8>> {
     let snap_meta = snap_builder.finish().await?;
-    Ok(snap_mgr.install(replica_id, &base_dir, &snap_meta))
+    crate::failpoint::eval_async("snap_download_before_install").await?;
+    Ok(snap_mgr.install(replica_id, base_dir, &snap_meta))
+}
+
+/// Whether the transmission is interrupted by the network or sender, which could be resumed.
+fn is_interrupted(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Rpc(_)
+            | Error::Transport(_)
+            | Error::DeadlineExceeded(_)
+            | Error::ResourceExhausted(_)
+    )
 }
@@ -13,16 +13,18 @@
 // limitations under the License.
 
 pub mod apply;
+pub(super) mod compress;
 pub mod create;
 pub mod download;
 pub mod send;
+mod throttle;
 
 use std::{
     collections::HashMap,
     ffi::OsStr,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use futures::{channel::mpsc, StreamExt};
@@ -30,7 +32,12 @@ use raft::prelude::{Snapshot, SnapshotMetadata};
 use tracing::{error, info, warn};
 
 pub use self::{create::dispatch_creating_snap_task, download::dispatch_downloading_snap_task};
-use crate::{runtime::TaskPriority, serverpb::v1::SnapshotMeta, Result};
+use self::{download::DownloadKey, throttle::SnapThrottle};
+use crate::{
+    runtime::{time::Instant, TaskPriority},
+    serverpb::v1::{SnapshotCompression, SnapshotMeta},
+    ReplicaConfig, Result,
+};
 
 const SNAP_DATA: &str = "DATA";
 const SNAP_TEMP: &str = "TEMP";
@@ -66,6 +73,15 @@ pub struct ReplicaSnapManager {
     base_dir: PathBuf,
     next_snapshot_index: usize,
     snapshots: Vec<SnapshotInfo>,
+    /// The interrupted download, which is resumed by the next download of the same snapshot.
+    partial_download: Option<PartialDownload>,
+}
+
+/// The files received by an interrupted download, they are saved in `base_dir`.
+pub(super) struct PartialDownload {
+    key: DownloadKey,
+    base_dir: PathBuf,
+    builder: download::SnapshotBuilder,
 }
 
 #[derive(Clone)]
@@ -79,6 +95,9 @@ where
 struct SnapManagerShared {
     root_dir: PathBuf,
     min_keep_intervals: Duration,
+    throttle: SnapThrottle,
+    /// The compression of sending snapshot chunks.
+    compression: SnapshotCompression,
     inner: Mutex<SnapManagerInner>,
 }
 
@@ -95,6 +114,11 @@ impl SnapManager {
             shared: Arc::new(SnapManagerShared {
                 root_dir: dir,
                 min_keep_intervals: Duration::from_secs(0),
+                throttle: SnapThrottle::new(&ReplicaConfig {
+                    snap_max_bytes_per_sec: 0,
+                    ..Default::default()
+                }),
+                compression: SnapshotCompression::NoCompression,
                 inner: Mutex::new(SnapManagerInner {
                     sender,
                     replicas: HashMap::default(),
@@ -103,7 +127,7 @@ impl SnapManager {
         }
     }
 
-    pub async fn recovery<P: AsRef<Path>>(root_dir: P) -> Result<SnapManager> {
+    pub async fn recovery<P: AsRef<Path>>(root_dir: P, cfg: &ReplicaConfig) -> Result<SnapManager> {
         use prost::Message;
 
         let (mut sender, receiver) = mpsc::unbounded();
@@ -169,11 +193,42 @@ impl SnapManager {
             shared: Arc::new(SnapManagerShared {
                 root_dir: root_dir.to_owned(),
                 min_keep_intervals: Duration::from_secs(180),
+                throttle: SnapThrottle::new(cfg),
+                compression: cfg.snap_compression.into(),
                 inner: Mutex::new(SnapManagerInner { sender, replicas }),
             }),
         })
     }
 
+    /// Read the metadata of all snapshots in the root dir without any modification, it is used by
+    /// offline inspection. `None` is returned for the snapshot whose meta is missing or corrupted.
+    pub fn list_snapshots<P: AsRef<Path>>(
+        root_dir: P,
+    ) -> Result<Vec<(u64, PathBuf, Option<SnapshotMeta>)>> {
+        use prost::Message;
+
+        let mut snapshots = vec![];
+        for (replica_id, replica_dir) in list_numeric_path(root_dir.as_ref())? {
+            for (_, snap_dir) in list_numeric_path(&replica_dir)? {
+                let meta_name = snap_dir.join(SNAP_META);
+                let meta = match std::fs::read(&meta_name) {
+                    Ok(bytes) => SnapshotMeta::decode(&*bytes).ok(),
+                    Err(_) => None,
+                };
+                snapshots.push((replica_id, snap_dir, meta));
+            }
+        }
+        Ok(snapshots)
+    }
+
+    fn throttle(&self) -> &SnapThrottle {
+        &self.shared.throttle
+    }
+
+    fn compression(&self) -> SnapshotCompression {
+        self.shared.compression
+    }
+
     /// Mark group as creating, and return a dir to save snapshot.
     pub fn create(&self, replica_id: u64) -> PathBuf {
         let mut inner = self.shared.inner.lock().unwrap();
@@ -184,6 +239,55 @@ impl SnapManager {
             .next_snapshot_dir()
     }
 
+    /// Take the partial download of the replica to resume, if it downloads the same snapshot. The
+    /// partial download of a different snapshot is recycled.
+    pub(super) fn take_partial_download(
+        &self,
+        replica_id: u64,
+        key: &DownloadKey,
+    ) -> Option<(PathBuf, download::SnapshotBuilder)> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        let partial = inner
+            .replicas
+            .get_mut(&replica_id)
+            .and_then(|replica| replica.partial_download.take())?;
+        if partial.key == *key {
+            return Some((partial.base_dir, partial.builder));
+        }
+        inner
+            .sender
+            .start_send((replica_id, partial.base_dir))
+            .unwrap_or_default();
+        None
+    }
+
+    /// Keep the files received by an interrupted download, so that the next download of the same
+    /// snapshot resumes from where it stopped.
+    pub(super) fn keep_partial_download(
+        &self,
+        replica_id: u64,
+        key: DownloadKey,
+        base_dir: PathBuf,
+        builder: download::SnapshotBuilder,
+    ) {
+        let mut inner = self.shared.inner.lock().unwrap();
+        let partial = PartialDownload {
+            key,
+            base_dir,
+            builder,
+        };
+        let replaced = match inner.replicas.get_mut(&replica_id) {
+            Some(replica) => replica.partial_download.replace(partial),
+            None => Some(partial),
+        };
+        if let Some(partial) = replaced {
+            inner
+                .sender
+                .start_send((replica_id, partial.base_dir))
+                .unwrap_or_default();
+        }
+    }
+
     /// Install a snapshot and returns snapshot id.
     pub fn install(&self, replica_id: u64, dir_name: &Path, meta: &SnapshotMeta) -> Vec<u8> {
         // TODO(walter) check snapshot data integrity.
@@ -265,6 +369,7 @@ impl SnapManager {
                         .snapshots
                         .iter()
                         .map(|info| info.base_dir.clone())
+                        .chain(replica.partial_download.take().map(|p| p.base_dir))
                         .collect::<Vec<_>>();
                     inner.replicas.remove(&replica_id);
                     snapshots
@@ -288,6 +393,7 @@ impl ReplicaSnapManager {
             base_dir,
             next_snapshot_index: 0,
             snapshots: vec![],
+            partial_download: None,
         }
     }
 
@@ -413,7 +519,7 @@ mod tests {
     use crate::{
         raftgroup::SnapshotBuilder,
         runtime::{time::sleep, ExecutorOwner},
-        serverpb::v1::ApplyState,
+        serverpb::v1::{snapshot_chunk, ApplyState},
     };
 
     struct SimpleSnapshotBuilder {
@@ -483,7 +589,9 @@ mod tests {
 
             let replica_id_1: u64 = 1;
             let replica_id_2: u64 = 2;
-            let snap_manager = SnapManager::recovery(&root_dir).await.unwrap();
+            let snap_manager = SnapManager::recovery(&root_dir, &ReplicaConfig::default())
+                .await
+                .unwrap();
 
             let snap_id_1 = build_snapshot(&snap_manager, replica_id_1, 1, vec![1]).await;
             let snap_id_2 = build_snapshot(&snap_manager, replica_id_1, 2, vec![2]).await;
@@ -492,7 +600,9 @@ mod tests {
 
             drop(snap_manager);
 
-            let snap_manager = SnapManager::recovery(&root_dir).await.unwrap();
+            let snap_manager = SnapManager::recovery(&root_dir, &ReplicaConfig::default())
+                .await
+                .unwrap();
             for snap_id in &replica_snaps_1 {
                 assert!(
                     snap_manager
@@ -519,16 +629,19 @@ mod tests {
             std::fs::create_dir_all(&root_dir).unwrap();
 
             let replica_id: u64 = 1;
-            let snap_manager = SnapManager::recovery(&root_dir).await.unwrap();
+            let snap_manager = SnapManager::recovery(&root_dir, &ReplicaConfig::default())
+                .await
+                .unwrap();
 
             // Prepare snapshot
             let content = vec![1, 2, 3, 4, 5, 6, 7];
             let snap_id = build_snapshot(&snap_manager, replica_id, 0, content.clone()).await;
 
             // Send snapshot on leader side.
-            let snapshot_chunk_stream = send::send_snapshot(&snap_manager, replica_id, snap_id)
-                .await
-                .unwrap();
+            let snapshot_chunk_stream =
+                send::send_snapshot(&snap_manager, replica_id, snap_id, 0, 0)
+                    .await
+                    .unwrap();
 
             // Save snapshot on follower side.
             let new_snap_id =
@@ -556,7 +669,9 @@ mod tests {
             std::fs::create_dir_all(&root_dir).unwrap();
 
             let replica_id: u64 = 1;
-            let snap_manager = SnapManager::recovery(&root_dir).await.unwrap();
+            let snap_manager = SnapManager::recovery(&root_dir, &ReplicaConfig::default())
+                .await
+                .unwrap();
 
             // Prepare snapshot
             let content_1 = vec![1, 2, 3, 4, 5, 6, 7, 1];
@@ -572,9 +687,10 @@ mod tests {
                 .unwrap();
 
             // Send snapshot on leader side.
-            let snapshot_chunk_stream = send::send_snapshot(&snap_manager, replica_id, snap_id)
-                .await
-                .unwrap();
+            let snapshot_chunk_stream =
+                send::send_snapshot(&snap_manager, replica_id, snap_id, 0, 0)
+                    .await
+                    .unwrap();
 
             // Save snapshot on follower side.
             let new_snap_id =
@@ -598,6 +714,231 @@ mod tests {
         });
     }
 
+    #[test]
+    fn send_and_save_compressed_snapshot() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            for compression in [crate::SnapCompression::Lz4, crate::SnapCompression::Zstd] {
+                let root_dir = TempDir::new("download-compressed-snapshot").unwrap();
+                std::fs::create_dir_all(&root_dir).unwrap();
+
+                let cfg = ReplicaConfig {
+                    snap_compression: compression,
+                    ..Default::default()
+                };
+                let replica_id: u64 = 1;
+                let snap_manager = SnapManager::recovery(&root_dir, &cfg).await.unwrap();
+
+                // Prepare snapshot, which is larger than a chunk.
+                let content = b"compressed snapshot ".repeat(4096);
+                let snap_id = build_snapshot(&snap_manager, replica_id, 0, content.clone()).await;
+
+                let accepted_compressions = [SnapshotCompression::from(compression) as i32];
+                let snapshot_chunk_stream =
+                    send::send_snapshot(&snap_manager, replica_id, snap_id, 0, 0)
+                        .await
+                        .unwrap()
+                        .negotiate_compression(&accepted_compressions);
+                let new_snap_id =
+                    download::save_snapshot(&snap_manager, replica_id + 1, snapshot_chunk_stream)
+                        .await
+                        .unwrap();
+
+                // The snapshot is saved without compression.
+                let snap = snap_manager
+                    .lock_snap(replica_id + 1, &new_snap_id)
+                    .unwrap();
+                assert_eq!(snap.meta.compression(), SnapshotCompression::NoCompression);
+                let data = snap.base_dir.join(SNAP_DATA);
+                let received_content = std::fs::read(data).unwrap();
+                assert_eq!(received_content, content);
+            }
+        });
+    }
+
+    #[test]
+    fn send_snapshot_without_accepted_compression() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let root_dir = TempDir::new("send-snapshot-without-accepted-compression").unwrap();
+            std::fs::create_dir_all(&root_dir).unwrap();
+
+            let cfg = ReplicaConfig {
+                snap_compression: crate::SnapCompression::Zstd,
+                ..Default::default()
+            };
+            let replica_id: u64 = 1;
+            let snap_manager = SnapManager::recovery(&root_dir, &cfg).await.unwrap();
+            let snap_id = build_snapshot(&snap_manager, replica_id, 0, vec![1, 2, 3]).await;
+
+            // The receiver doesn't advertise any compression, eg. it is an old version.
+            for accepted_compressions in [vec![], vec![SnapshotCompression::Lz4 as i32]] {
+                let mut snapshot_chunk_stream =
+                    send::send_snapshot(&snap_manager, replica_id, snap_id.clone(), 0, 0)
+                        .await
+                        .unwrap()
+                        .negotiate_compression(&accepted_compressions);
+                let chunk = snapshot_chunk_stream.next().await.unwrap().unwrap();
+                match chunk.value {
+                    Some(snapshot_chunk::Value::Meta(meta)) => {
+                        assert_eq!(meta.compression(), SnapshotCompression::NoCompression);
+                    }
+                    _ => panic!("the snapshot meta is expected"),
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn resume_interrupted_snapshot() {
+        use futures::stream;
+
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let root_dir = TempDir::new("resume-snapshot").unwrap();
+            std::fs::create_dir_all(&root_dir).unwrap();
+
+            let replica_id: u64 = 1;
+            let snap_manager = SnapManager::recovery(&root_dir, &ReplicaConfig::default())
+                .await
+                .unwrap();
+
+            // Prepare snapshot, each file is consisted of several chunks.
+            let content_1 = vec![1; 100 * 1024];
+            let content_2 = vec![2; 100 * 1024];
+            let builder: Box<dyn SnapshotBuilder> = Box::new(MultiFilesSnapshotBuilder {
+                index: 1,
+                content_1: content_1.clone(),
+                content_2: content_2.clone(),
+            });
+            let snap_id = create::create_snapshot(replica_id, &snap_manager, builder)
+                .await
+                .unwrap();
+
+            // The transmission is interrupted after the meta, the first file meta and a chunk.
+            let base_dir = download::prepare_snapshot_dir(&snap_manager, replica_id + 1).unwrap();
+            let mut snap_builder = download::SnapshotBuilder::new(replica_id + 1, &base_dir);
+            let chunk_stream =
+                send::send_snapshot(&snap_manager, replica_id, snap_id.clone(), 0, 0)
+                    .await
+                    .unwrap()
+                    .take(3)
+                    .chain(stream::iter([Err(tonic::Status::unavailable(
+                        "interrupted",
+                    ))]));
+            let result =
+                download::receive_chunks(&snap_manager, &mut snap_builder, chunk_stream).await;
+            assert!(result.is_err());
+            assert_eq!(snap_builder.resume_point(), (0, 32 * 1024));
+
+            // Resume from the partial file.
+            let (file_index, offset) = snap_builder.resume_point();
+            let chunk_stream =
+                send::send_snapshot(&snap_manager, replica_id, snap_id, file_index, offset)
+                    .await
+                    .unwrap();
+            download::receive_chunks(&snap_manager, &mut snap_builder, chunk_stream)
+                .await
+                .unwrap();
+            let new_snap_id =
+                download::install_snapshot(&snap_manager, replica_id + 1, &base_dir, snap_builder)
+                    .await
+                    .unwrap();
+
+            // Validate snapshot content.
+            let snap = snap_manager
+                .lock_snap(replica_id + 1, &new_snap_id)
+                .unwrap();
+            let data = snap.base_dir.join(SNAP_DATA);
+            assert_eq!(std::fs::read(data.join("1")).unwrap(), content_1);
+            assert_eq!(std::fs::read(data.join("2")).unwrap(), content_2);
+        });
+    }
+
+    #[test]
+    fn resume_partial_download_of_same_snapshot() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let root_dir = TempDir::new("resume-partial-download").unwrap();
+            std::fs::create_dir_all(&root_dir).unwrap();
+
+            let replica_id: u64 = 1;
+            let snap_manager = SnapManager::new(root_dir.path().to_owned());
+            let key = |snapshot_id: &[u8], index: u64| DownloadKey {
+                from_replica: 2,
+                snapshot_id: snapshot_id.to_owned(),
+                index,
+                term: 1,
+            };
+
+            let base_dir = download::prepare_snapshot_dir(&snap_manager, replica_id).unwrap();
+            let snap_builder = download::SnapshotBuilder::new(replica_id, &base_dir);
+            snap_manager.keep_partial_download(
+                replica_id,
+                key(b"1", 10),
+                base_dir.clone(),
+                snap_builder,
+            );
+            let (resumed_dir, _) = snap_manager
+                .take_partial_download(replica_id, &key(b"1", 10))
+                .unwrap();
+            assert_eq!(resumed_dir, base_dir);
+            assert!(snap_manager
+                .take_partial_download(replica_id, &key(b"1", 10))
+                .is_none());
+
+            // The sender reuses the snapshot id after restarting.
+            let snap_builder = download::SnapshotBuilder::new(replica_id, &base_dir);
+            snap_manager.keep_partial_download(
+                replica_id,
+                key(b"1", 10),
+                base_dir.clone(),
+                snap_builder,
+            );
+            assert!(snap_manager
+                .take_partial_download(replica_id, &key(b"1", 20))
+                .is_none());
+            assert!(snap_manager
+                .take_partial_download(replica_id, &key(b"1", 10))
+                .is_none());
+        });
+    }
+
+    #[test]
+    fn resume_snapshot_from_invalid_point() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let root_dir = TempDir::new("resume-snapshot-invalid-point").unwrap();
+            std::fs::create_dir_all(&root_dir).unwrap();
+
+            let replica_id: u64 = 1;
+            let snap_manager = SnapManager::new(root_dir.path().to_owned());
+            let snap_id = build_snapshot(&snap_manager, replica_id, 1, vec![1, 2, 3]).await;
+
+            // Only one file exists in snapshot.
+            assert!(
+                send::send_snapshot(&snap_manager, replica_id, snap_id.clone(), 0, 3)
+                    .await
+                    .is_ok()
+            );
+            assert!(
+                send::send_snapshot(&snap_manager, replica_id, snap_id.clone(), 0, 4)
+                    .await
+                    .is_err()
+            );
+            assert!(
+                send::send_snapshot(&snap_manager, replica_id, snap_id.clone(), 1, 0)
+                    .await
+                    .is_ok()
+            );
+            assert!(
+                send::send_snapshot(&snap_manager, replica_id, snap_id, 2, 0)
+                    .await
+                    .is_err()
+            );
+        });
+    }
+
     #[test]
     fn recycle() {
         let owner = ExecutorOwner::new(1);
@@ -646,7 +987,7 @@ mod tests {
             let snap_meta = SnapshotMeta {
                 apply_state: Some(ApplyState::default()),
                 group_desc: Some(GroupDesc::default()),
-                files: vec![],
+                ..Default::default()
             };
 
             // Install snap in reversed orders.
@@ -672,7 +1013,7 @@ mod tests {
             let snap_meta = SnapshotMeta {
                 apply_state: Some(ApplyState::default()),
                 group_desc: Some(GroupDesc::default()),
-                files: vec![],
+                ..Default::default()
             };
             snap_mgr.recycle_snapshots(replica_id, RecycleSnapMode::RequiredIndex(123123));
             snap_mgr.install(replica_id, &snap_dir_1, &snap_meta);
@@ -14,33 +14,49 @@
 use std::{
     ffi::OsStr,
     fs::File,
-    io::Read,
+    future::Future,
+    io::{Read, Seek, SeekFrom},
     os::unix::ffi::OsStrExt,
     pin::Pin,
     task::{Context, Poll},
 };
 
+use tokio::{sync::OwnedSemaphorePermit, time::Sleep};
 use tracing::debug;
 
-use super::{SnapManager, SnapshotGuard};
+use super::{compress::compress, SnapManager, SnapshotGuard};
 use crate::{
     raftgroup::metrics::*,
-    serverpb::v1::{snapshot_chunk, SnapshotChunk},
+    serverpb::v1::{snapshot_chunk, SnapshotChunk, SnapshotCompression},
     Error, Result,
 };
 
 type SnapResult = Result<SnapshotChunk, tonic::Status>;
 
+/// Stream the snapshot meta first, so that the receiver knows the compression of chunks, then
+/// the files, each file is sent as a file meta followed by the data chunks.
 pub struct SnapshotChunkStream {
     info: SnapshotGuard,
+    snap_mgr: SnapManager,
+    compression: SnapshotCompression,
+    meta_sent: bool,
     file: Option<File>,
     file_index: usize,
+    /// The offset to resume the file at `file_index` from, it is consumed once the file is opened.
+    offset: u64,
+    /// The delay before sending the next chunk, to limit the bandwidth.
+    delay: Option<Pin<Box<Sleep>>>,
+    _permit: OwnedSemaphorePermit,
 }
 
+/// Send the snapshot from the `offset` of the file at `file_index`, both of them are zero unless
+/// the receiver resumes an interrupted transmission.
 pub async fn send_snapshot(
     snap_mgr: &SnapManager,
     replica_id: u64,
     snapshot_id: Vec<u8>,
+    file_index: u64,
+    offset: u64,
 ) -> Result<SnapshotChunkStream> {
     let snapshot_info = match snap_mgr.lock_snap(replica_id, &snapshot_id) {
         Some(snap_info) => snap_info,
@@ -48,23 +64,73 @@ pub async fn send_snapshot(
             return Err(Error::InvalidArgument("no such snapshot".to_string()));
         }
     };
+    let files = &snapshot_info.meta.files;
+    let valid = match files.get(file_index as usize) {
+        Some(file_meta) => offset <= file_meta.size,
+        None => file_index as usize == files.len() && offset == 0,
+    };
+    if !valid {
+        return Err(Error::InvalidArgument(format!(
+            "resume snapshot from file {file_index} offset {offset}"
+        )));
+    }
+    let permit = match snap_mgr.throttle().try_acquire_send() {
+        Some(permit) => permit,
+        None => {
+            return Err(Error::ResourceExhausted(
+                "too many snapshots are sending".to_string(),
+            ));
+        }
+    };
 
     RAFTGROUP_SEND_SNAPSHOT_TOTAL.inc();
-    Ok(SnapshotChunkStream::new(snapshot_info))
+    let mut stream = SnapshotChunkStream::new(snapshot_info, snap_mgr.clone(), permit);
+    stream.file_index = file_index as usize;
+    stream.offset = offset;
+    Ok(stream)
 }
 
 impl SnapshotChunkStream {
-    fn new(info: SnapshotGuard) -> Self {
+    fn new(info: SnapshotGuard, snap_mgr: SnapManager, permit: OwnedSemaphorePermit) -> Self {
         SnapshotChunkStream {
             info,
+            compression: SnapshotCompression::NoCompression,
+            snap_mgr,
+            meta_sent: false,
             file: None,
             file_index: 0,
+            offset: 0,
+            delay: None,
+            _permit: permit,
+        }
+    }
+
+    /// Compress the chunks with the configured compression, if it is accepted by the receiver.
+    /// The chunks are sent without compression by default, so that the receivers which don't
+    /// know the compression still work.
+    pub fn negotiate_compression(mut self, accepted_compressions: &[i32]) -> Self {
+        let compression = self.snap_mgr.compression();
+        if accepted_compressions.contains(&(compression as i32)) {
+            self.compression = compression;
         }
+        self
     }
 
     fn next_chunk(&mut self) -> Option<SnapResult> {
         use std::{fs::OpenOptions, io::ErrorKind};
 
+        if !self.meta_sent {
+            // Send snapshot meta.
+            self.meta_sent = true;
+            let mut meta = self.info.meta.clone();
+            meta.set_compression(self.compression);
+            let value = snapshot_chunk::Value::Meta(meta);
+            return Some(Ok(SnapshotChunk {
+                value: Some(value),
+                ..Default::default()
+            }));
+        }
+
         match self.file.as_mut() {
             // Send snapshot file chunk.
             Some(file) => {
@@ -87,34 +153,47 @@ impl SnapshotChunkStream {
                     }
                 }
                 chunk_data.truncate(num_read);
-                RAFTGROUP_SEND_SNAPSHOT_BYTES_TOTAL.inc_by(num_read as u64);
+                let chunk_data = match compress(self.compression, chunk_data) {
+                    Ok(chunk_data) => chunk_data,
+                    Err(err) => return Some(Err(err.into())),
+                };
+                RAFTGROUP_SEND_SNAPSHOT_BYTES_TOTAL.inc_by(chunk_data.len() as u64);
+                let send_rate = &self.snap_mgr.throttle().send_rate;
+                if let Some(deadline) = send_rate.reserve(chunk_data.len()) {
+                    self.delay = Some(Box::pin(tokio::time::sleep_until(deadline)));
+                }
                 let value = snapshot_chunk::Value::ChunkData(chunk_data);
-                Some(Ok(SnapshotChunk { value: Some(value) }))
+                Some(Ok(SnapshotChunk {
+                    value: Some(value),
+                    ..Default::default()
+                }))
             }
             // Open new file and send file meta.
             None if self.file_index < self.info.meta.files.len() => {
                 let file_meta = &self.info.meta.files[self.file_index];
                 let path = self.info.base_dir.join(OsStr::from_bytes(&file_meta.name)); // Eg: `DATA/1.sst`.
+                let offset = std::mem::take(&mut self.offset);
                 debug!(
-                    "send file {} to remote, crc32 {}, size {}",
+                    "send file {} to remote, crc32 {}, size {}, offset {offset}",
                     path.display(),
                     file_meta.crc32,
                     file_meta.size
                 );
-                match OpenOptions::new().read(true).open(&path) {
+                let file = OpenOptions::new()
+                    .read(true)
+                    .open(&path)
+                    .and_then(|mut file| file.seek(SeekFrom::Start(offset)).map(|_| file));
+                match file {
                     Ok(file) => self.file = Some(file),
                     Err(err) => return Some(Err(err.into())),
                 }
                 let value = snapshot_chunk::Value::File(file_meta.to_owned());
-                Some(Ok(SnapshotChunk { value: Some(value) }))
-            }
-            // Send snapshot meta.
-            None if self.file_index == self.info.meta.files.len() => {
-                self.file_index += 1;
-                let value = snapshot_chunk::Value::Meta(self.info.meta.clone());
-                Some(Ok(SnapshotChunk { value: Some(value) }))
+                Some(Ok(SnapshotChunk {
+                    value: Some(value),
+                    offset,
+                }))
             }
-            // All files and meta are send.
+            // All files are send.
             None => None,
         }
     }
@@ -123,7 +202,12 @@ impl SnapshotChunkStream {
 impl futures::Stream for SnapshotChunkStream {
     type Item = SnapResult;
 
-    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Poll::Ready(self.get_mut().next_chunk())
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(delay) = this.delay.as_mut() {
+            futures::ready!(delay.as_mut().poll(cx));
+            this.delay = None;
+        }
+        Poll::Ready(this.next_chunk())
     }
 }
@@ -0,0 +1,146 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Node wide throttling of snapshot transmission, so that rebuilding several replicas at once
+//! doesn't saturate the network and disks and hurt the foreground requests.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{runtime::time::Instant, ReplicaConfig};
+
+/// A rate limiter without burst, each consumer reserves the time slot of its bytes in order, and
+/// waits until the slot begins.
+pub struct RateLimiter {
+    /// No limit if it is zero.
+    bytes_per_sec: u64,
+    next_available: Mutex<Instant>,
+}
+
+pub struct SnapThrottle {
+    pub send_rate: RateLimiter,
+    pub recv_rate: RateLimiter,
+    send_slots: Arc<Semaphore>,
+    recv_slots: Arc<Semaphore>,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        RateLimiter {
+            bytes_per_sec,
+            next_available: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserve the time slot for `bytes`, and returns the instant the bytes could be consumed at,
+    /// `None` means they could be consumed immediately.
+    pub fn reserve(&self, bytes: usize) -> Option<Instant> {
+        if self.bytes_per_sec == 0 {
+            return None;
+        }
+
+        let now = Instant::now();
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        let mut next_available = self.next_available.lock().unwrap();
+        let start = std::cmp::max(*next_available, now);
+        *next_available = start + cost;
+        if start > now {
+            Some(start)
+        } else {
+            None
+        }
+    }
+
+    pub async fn consume(&self, bytes: usize) {
+        if let Some(deadline) = self.reserve(bytes) {
+            tokio::time::sleep_until(deadline).await;
+        }
+    }
+}
+
+impl SnapThrottle {
+    pub fn new(cfg: &ReplicaConfig) -> Self {
+        SnapThrottle {
+            send_rate: RateLimiter::new(cfg.snap_max_bytes_per_sec),
+            recv_rate: RateLimiter::new(cfg.snap_max_bytes_per_sec),
+            send_slots: Arc::new(Semaphore::new(slots(cfg.snap_max_concurrent_send))),
+            recv_slots: Arc::new(Semaphore::new(slots(cfg.snap_max_concurrent_recv))),
+        }
+    }
+
+    /// Take a slot for sending snapshot, `None` is returned if all slots are taken.
+    pub fn try_acquire_send(&self) -> Option<OwnedSemaphorePermit> {
+        self.send_slots.clone().try_acquire_owned().ok()
+    }
+
+    /// Take a slot for receiving snapshot, wait until a slot is released if all slots are taken.
+    pub async fn acquire_recv(&self) -> OwnedSemaphorePermit {
+        self.recv_slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed")
+    }
+}
+
+fn slots(limit: usize) -> usize {
+    if limit == 0 {
+        Semaphore::MAX_PERMITS
+    } else {
+        limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::ExecutorOwner;
+
+    #[test]
+    fn rate_limiter() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let limiter = RateLimiter::new(0);
+            assert!(limiter.reserve(usize::MAX).is_none());
+
+            let limiter = RateLimiter::new(1024);
+            let start = Instant::now();
+            assert!(limiter.reserve(512).is_none());
+            let deadline = limiter.reserve(512).unwrap();
+            assert!(deadline >= start + Duration::from_millis(500));
+            let deadline = limiter.reserve(1024).unwrap();
+            assert!(deadline >= start + Duration::from_secs(1));
+        });
+    }
+
+    #[test]
+    fn concurrent_slots() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let cfg = ReplicaConfig {
+                snap_max_concurrent_send: 1,
+                ..Default::default()
+            };
+            let throttle = SnapThrottle::new(&cfg);
+            let permit = throttle.try_acquire_send();
+            assert!(permit.is_some());
+            assert!(throttle.try_acquire_send().is_none());
+            drop(permit);
+            assert!(throttle.try_acquire_send().is_some());
+        });
+    }
+}
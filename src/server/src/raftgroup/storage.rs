@@ -24,7 +24,7 @@ use raft::{prelude::*, GetEntriesContext, RaftState};
 use raft_engine::{Command, Engine, LogBatch, MessageExt};
 use tracing::{debug, error, info};
 
-use super::{node::WriteTask, snap::SnapManager, RaftConfig};
+use super::{node::WriteTask, snap::SnapManager, ApplyEntry, RaftConfig};
 use crate::{
     serverpb::v1::{EntryId, EvalResult, RaftLocalState},
     Result,
@@ -535,10 +535,11 @@ pub async fn write_initial_state(
             last_index += 1;
             let change_replicas = ChangeReplicas {
                 changes: vec![ChangeReplica {
-                    change_type: if replica.role == ReplicaRole::Learner as i32 {
-                        ChangeReplicaType::AddLearner.into()
-                    } else {
-                        ChangeReplicaType::Add.into()
+                    change_type: match ReplicaRole::from_i32(replica.role) {
+                        Some(ReplicaRole::Learner) => ChangeReplicaType::AddLearner.into(),
+                        Some(ReplicaRole::Witness) => ChangeReplicaType::AddWitness.into(),
+                        Some(ReplicaRole::ReadLearner) => ChangeReplicaType::AddReadLearner.into(),
+                        _ => ChangeReplicaType::Add.into(),
                     },
                     replica_id,
                     node_id,
@@ -605,6 +606,92 @@ pub async fn write_initial_state(
     Ok(())
 }
 
+/// Discard all log entries of the replica, and reset the raft states as if the log were truncated
+/// at the applied entry, so that the replica restarts from the applied entry.
+///
+/// It is used to recover a group which has lost the majority of replicas, the entries which are
+/// not applied might contain config changes which are no longer achievable.
+pub async fn reset_to_applied(engine: &Engine, replica_id: u64, applied: EntryId) -> Result<()> {
+    let mut hard_state = engine
+        .get_message::<HardState>(replica_id, keys::HARD_STATE_KEY)?
+        .unwrap_or_default();
+    hard_state.term = std::cmp::max(hard_state.term, applied.term);
+    hard_state.commit = applied.index;
+    let local_state = RaftLocalState {
+        replica_id,
+        last_truncated: Some(applied.clone()),
+    };
+
+    let mut batch = LogBatch::default();
+    batch.add_command(replica_id, Command::Clean);
+    batch
+        .put_message(replica_id, keys::HARD_STATE_KEY.to_owned(), &hard_state)
+        .unwrap();
+    batch
+        .put_message(replica_id, keys::LOCAL_STATE_KEY.to_owned(), &local_state)
+        .unwrap();
+    engine.write(&mut batch, true)?;
+
+    info!(
+        "reset storage state of {replica_id} to applied index {} term {}",
+        applied.index, applied.term
+    );
+
+    Ok(())
+}
+
+/// The persisted raft states and log entries of a replica, which are read by offline inspection.
+pub struct RaftLogDump {
+    pub hard_state: Option<HardState>,
+    pub local_state: Option<RaftLocalState>,
+    /// The range of log entries in engine, in `[first_index, last_index]`.
+    pub first_index: u64,
+    pub last_index: u64,
+    pub entries: Vec<(EntryId, ApplyEntry)>,
+}
+
+/// Read the raft states and at most `limit` log entries since `start` of the replica.
+pub fn dump_raft_log(
+    engine: &Engine,
+    replica_id: u64,
+    start: Option<u64>,
+    limit: usize,
+) -> Result<RaftLogDump> {
+    let hard_state = engine.get_message::<HardState>(replica_id, keys::HARD_STATE_KEY)?;
+    let local_state = engine.get_message::<RaftLocalState>(replica_id, keys::LOCAL_STATE_KEY)?;
+    let first_index = engine.first_index(replica_id).unwrap_or(1);
+    let last_index = engine.last_index(replica_id).unwrap_or(0);
+
+    let start = std::cmp::max(start.unwrap_or(first_index), first_index);
+    let end = std::cmp::min(last_index + 1, start.saturating_add(limit as u64));
+    let mut raw_entries = vec![];
+    if start < end {
+        engine.fetch_entries_to::<MessageExtTyped>(
+            replica_id,
+            start,
+            end,
+            None,
+            &mut raw_entries,
+        )?;
+    }
+    let mut entries = Vec::with_capacity(raw_entries.len());
+    for entry in &raw_entries {
+        let entry_id = EntryId {
+            index: entry.index,
+            term: entry.term,
+        };
+        entries.push((entry_id, super::decode_entry(entry)?));
+    }
+
+    Ok(RaftLogDump {
+        hard_state,
+        local_state,
+        first_index,
+        last_index,
+        entries,
+    })
+}
+
 pub async fn destory(engine: &Engine, replica_id: u64) -> Result<()> {
     let mut batch = LogBatch::default();
     batch.add_command(replica_id, Command::Clean);
@@ -933,6 +1020,92 @@ mod tests {
         });
     }
 
+    #[test]
+    fn reset_storage_to_applied() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let dir = TempDir::new("reset-storage-to-applied").unwrap();
+
+            let cfg = Config {
+                dir: dir.path().join("db").to_str().unwrap().to_owned(),
+                ..Default::default()
+            };
+            let engine = Arc::new(Engine::open(cfg).unwrap());
+
+            let replicas = (1..=3)
+                .map(|id| ReplicaDesc {
+                    id,
+                    node_id: id,
+                    role: ReplicaRole::Voter as i32,
+                })
+                .collect::<Vec<_>>();
+            write_initial_state(&RaftConfig::default(), engine.as_ref(), 1, replicas, vec![])
+                .await
+                .unwrap();
+
+            let applied = EntryId { index: 2, term: 1 };
+            reset_to_applied(engine.as_ref(), 1, applied).await.unwrap();
+
+            let snap_mgr = SnapManager::new(dir.path().join("snap"));
+            let storage = Storage::open(
+                &RaftConfig::default(),
+                1,
+                2,
+                ConfState::default(),
+                engine.clone(),
+                snap_mgr,
+            )
+            .await
+            .unwrap();
+            assert_eq!(storage.hard_state.commit, 2);
+            assert_eq!(storage.hard_state.term, 1);
+            assert_eq!(storage.truncated_index(), 2);
+            assert_eq!(storage.truncated_term(), 1);
+            assert_eq!(storage.first_index, 3);
+            assert_eq!(storage.last_index, 2);
+        });
+    }
+
+    #[test]
+    fn dump_raft_log_entries() {
+        let owner = ExecutorOwner::new(1);
+        owner.executor().block_on(async move {
+            let dir = TempDir::new("dump-raft-log-entries").unwrap();
+
+            let cfg = Config {
+                dir: dir.path().join("db").to_str().unwrap().to_owned(),
+                ..Default::default()
+            };
+            let engine = Engine::open(cfg).unwrap();
+
+            let replicas = (1..=3)
+                .map(|id| ReplicaDesc {
+                    id,
+                    node_id: id,
+                    role: ReplicaRole::Voter as i32,
+                })
+                .collect::<Vec<_>>();
+            write_initial_state(&RaftConfig::default(), &engine, 1, replicas, vec![])
+                .await
+                .unwrap();
+
+            let dump = dump_raft_log(&engine, 1, Some(2), 10).unwrap();
+            assert_eq!(dump.hard_state.unwrap().commit, 3);
+            assert_eq!(dump.first_index, 1);
+            assert_eq!(dump.last_index, 3);
+            assert_eq!(dump.entries.len(), 2);
+            for (idx, (entry_id, entry)) in dump.entries.into_iter().enumerate() {
+                assert_eq!(entry_id.index, idx as u64 + 2);
+                match entry {
+                    ApplyEntry::ConfigChange { change_replicas } => {
+                        assert_eq!(change_replicas.changes[0].replica_id, idx as u64 + 2);
+                    }
+                    _ => panic!("expect config change entry"),
+                }
+            }
+        });
+    }
+
     #[test]
     fn fetch_entries_from_both_engine_and_cache_should_be_continuously() {
         let owner = ExecutorOwner::new(1);
@@ -13,14 +13,14 @@
 // limitations under the License.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     marker::PhantomData,
     sync::Arc,
     task::Context,
     time::{Duration, Instant},
 };
 
-use engula_api::server::v1::{ChangeReplicas, RaftRole, ReplicaDesc};
+use engula_api::server::v1::{ChangeReplicas, GroupDesc, RaftRole, ReplicaDesc, ReplicaRole};
 use futures::{
     channel::{mpsc, oneshot},
     stream::FusedStream,
@@ -42,6 +42,7 @@ use super::{
     RaftManager, ReadPolicy,
 };
 use crate::{
+    constants::ROOT_GROUP_ID,
     raftgroup::monitor::record_perf_point,
     record_latency,
     serverpb::v1::{EvalResult, RaftMessage},
@@ -76,6 +77,10 @@ pub enum Request {
     Unreachable {
         target_id: u64,
     },
+    /// The nodes considered dead by root, see `RaftConfig::enable_hibernation` for details.
+    DeadNodes {
+        nodes: Arc<HashSet<u64>>,
+    },
     State(oneshot::Sender<RaftGroupState>),
     Monitor(oneshot::Sender<Box<WorkerPerfContext>>),
     Start,
@@ -125,6 +130,7 @@ struct AdvanceImpl<'a> {
     group_id: u64,
     replica_id: u64,
     desc: ReplicaDesc,
+    witness: bool,
     channels: &'a mut HashMap<u64, Channel>,
     trans_mgr: &'a ChannelManager,
     snap_mgr: &'a SnapManager,
@@ -136,6 +142,15 @@ impl<'a> super::node::AdvanceTemplate for AdvanceImpl<'a> {
     fn send_messages(&mut self, msgs: Vec<Message>) {
         let mut seperated_msgs: HashMap<u64, Vec<Message>> = HashMap::default();
         for msg in msgs {
+            if self.witness
+                && matches!(
+                    msg.get_msg_type(),
+                    MessageType::MsgRequestPreVote | MessageType::MsgRequestVote
+                )
+            {
+                // A witness never campaigns, so it won't be elected as leader.
+                continue;
+            }
             seperated_msgs
                 .entry(msg.to)
                 .or_insert_with(Vec::default)
@@ -161,6 +176,7 @@ impl<'a> super::node::AdvanceTemplate for AdvanceImpl<'a> {
                     from_replica: Some(self.desc.clone()),
                     to_replica: Some(to_replica),
                     messages: msgs,
+                    hibernate: false,
                 });
         }
     }
@@ -199,10 +215,25 @@ where
     engine: Arc<Engine>,
     observer: Box<dyn StateObserver>,
     replica_cache: ReplicaCache,
+    hibernation: Hibernation,
+    /// Whether the local replica is a witness, refreshed on ticks once the descriptor is changed.
+    witness: bool,
+    /// The `RaftNode::conf_updates` when `witness` is refreshed.
+    witness_conf_updates: u64,
 
     marker: PhantomData<M>,
 }
 
+/// The hibernation state of a group, see `RaftConfig::enable_hibernation` for details.
+#[derive(Default)]
+struct Hibernation {
+    /// The number of consecutive ticks the leader is idle.
+    idle_ticks: usize,
+    /// The number of ticks since the last hibernation heartbeat is sent or received.
+    ticks: usize,
+    hibernated: bool,
+}
+
 #[derive(Default)]
 struct WorkerContext {
     accumulated_bytes: usize,
@@ -229,7 +260,9 @@ where
         };
         let mut replica_cache = ReplicaCache::default();
         replica_cache.insert(desc.clone());
-        replica_cache.batch_insert(&state_machine.descriptor().replicas);
+        let group_desc = state_machine.descriptor();
+        replica_cache.batch_insert(&group_desc.replicas);
+        let witness = is_witness(&group_desc, replica_id);
         let raft_node = RaftNode::new(group_id, replica_id, raft_mgr, state_machine).await?;
 
         let (mut request_sender, request_receiver) =
@@ -256,6 +289,9 @@ where
             engine: raft_mgr.engine.clone(),
             observer,
             replica_cache,
+            hibernation: Hibernation::default(),
+            witness,
+            witness_conf_updates: 0,
             marker: PhantomData,
         })
     }
@@ -297,7 +333,21 @@ where
         ctx: &mut WorkerContext,
         interval: &mut Interval,
     ) -> Result<()> {
-        if !self.raft_node.has_ready() {
+        if !self.raft_node.has_ready() && self.hibernation.hibernated {
+            // A hibernated group doesn't tick raft, until it is woken by requests or messages.
+            crate::runtime::select! {
+                biased;
+                _ = interval.tick().fuse() => {
+                    self.on_hibernated_tick();
+                },
+                request = self.request_receiver.next() => {
+                    if let Some(req) = request {
+                        self.handle_request(ctx, req)?;
+                    }
+                },
+            }
+            record_perf_point(&mut ctx.perf_ctx.wake);
+        } else if !self.raft_node.has_ready() {
             crate::runtime::select! {
                 biased;
                 _ = interval.tick().fuse() => {
@@ -315,7 +365,7 @@ where
             // wait for a new input event, but the tick may be ready at this time, and we need to
             // process it first to avoid the tick delay being too long.
             let mut cx = Context::from_waker(futures::task::noop_waker_ref());
-            if interval.poll_tick(&mut cx).is_ready() {
+            if !self.hibernation.hibernated && interval.poll_tick(&mut cx).is_ready() {
                 self.on_tick_fire(ctx);
             }
         }
@@ -325,6 +375,10 @@ where
     fn on_tick_fire(&mut self, ctx: &mut WorkerContext) {
         self.raft_node.tick();
         self.compact_log(ctx);
+        self.check_witness();
+        if self.cfg.enable_hibernation && !self.witness {
+            self.try_hibernate();
+        }
     }
 
     fn consume_requests(&mut self, ctx: &mut WorkerContext) -> Result<()> {
@@ -347,6 +401,7 @@ where
             replica_id: self.desc.id,
             group_id: self.group_id,
             desc: self.desc.clone(),
+            witness: self.witness,
             channels: &mut self.channels,
             trans_mgr: &self.trans_mgr,
             snap_mgr: &self.snap_mgr,
@@ -355,7 +410,7 @@ where
         };
         if let Some(write_task) = self
             .raft_node
-            .advance(&mut ctx.perf_ctx.advance, &mut template)
+            .advance(&mut ctx.perf_ctx.advance, &mut template)?
         {
             let mut batch = LogBatch::default();
             self.raft_node
@@ -401,6 +456,17 @@ where
 
     fn handle_request(&mut self, ctx: &mut WorkerContext, request: Request) -> Result<()> {
         ctx.perf_ctx.num_requests += 1;
+        if matches!(
+            request,
+            Request::Propose { .. }
+                | Request::Read { .. }
+                | Request::ChangeConfig { .. }
+                | Request::Transfer { .. }
+                | Request::InstallSnapshot { .. }
+                | Request::RejectSnapshot { .. }
+        ) {
+            self.wake_up();
+        }
         match request {
             Request::Propose {
                 eval_result,
@@ -423,6 +489,15 @@ where
             Request::Unreachable { target_id } => {
                 self.raft_node.report_unreachable(target_id);
             }
+            Request::DeadNodes { nodes } => {
+                if self.hibernation.hibernated && self.is_leader_located_in(&nodes) {
+                    info!(
+                        "group {} replica {} wake up since the node of leader is dead",
+                        self.group_id, self.desc.id
+                    );
+                    self.wake_up();
+                }
+            }
             Request::RejectSnapshot { msg: input } => {
                 let mut msg = Message::default();
                 msg.set_msg_type(MessageType::MsgSnapStatus);
@@ -439,6 +514,7 @@ where
                             from_replica: Some(self.desc.clone()),
                             to_replica: Some(to_replica),
                             messages: vec![msg],
+                            hibernate: false,
                         });
                 }
             }
@@ -464,6 +540,20 @@ where
     fn handle_msg(&mut self, ctx: &mut WorkerContext, raft_msg: RaftMessage) -> Result<()> {
         let from_replica = raft_msg.from_replica.unwrap();
         self.replica_cache.insert(from_replica.clone());
+        // The heartbeat responses are sent to a hibernated leader, they shouldn't wake it up.
+        if !raft_msg.hibernate
+            && raft_msg
+                .messages
+                .iter()
+                .any(|msg| msg.get_msg_type() != MessageType::MsgHeartbeatResponse)
+        {
+            self.wake_up();
+        }
+        let hibernate_msg = if raft_msg.hibernate {
+            raft_msg.messages.first().cloned()
+        } else {
+            None
+        };
         for msg in raft_msg.messages {
             if msg.get_msg_type() == MessageType::MsgSnapshot {
                 // TODO(walter) In order to avoid useless downloads, should check whether this
@@ -476,15 +566,184 @@ where
                     from_replica.clone(),
                     msg,
                 );
+            } else if self.witness && msg.get_msg_type() == MessageType::MsgTimeoutNow {
+                // A witness never becomes leader, the leadership transferring is ignored.
+                warn!(
+                    "group {} witness {} ignore leadership transferring from {}",
+                    self.group_id, self.desc.id, msg.from
+                );
             } else {
                 ctx.accumulated_bytes += msg.entries.iter().map(|e| e.data.len()).sum::<usize>();
                 ctx.perf_ctx.num_step_msg += 1;
                 self.raft_node.step(msg)?;
             }
         }
+        if let Some(msg) = hibernate_msg {
+            self.follow_hibernation(&msg);
+        }
         Ok(())
     }
 
+    /// A witness has no data. It never campaigns, but it might still be the leader if it is
+    /// demoted from a voter, so transfer the leadership to an up-to-date voter.
+    fn check_witness(&mut self) {
+        let conf_updates = self.raft_node.conf_updates();
+        if self.witness_conf_updates != conf_updates {
+            let desc = self.raft_node.mut_state_machine().descriptor();
+            self.witness = is_witness(&desc, self.desc.id);
+            self.witness_conf_updates = conf_updates;
+        }
+        if !self.witness {
+            return;
+        }
+        let raft = self.raft_node.raft();
+        if raft.state != StateRole::Leader || raft.lead_transferee.is_some() {
+            return;
+        }
+
+        let desc = self.raft_node.mut_state_machine().descriptor();
+        let raft = self.raft_node.raft();
+        let last_index = raft.raft_log.last_index();
+        let transferee = desc
+            .replicas
+            .iter()
+            .filter(|r| r.role == ReplicaRole::Voter as i32)
+            .find(|r| {
+                raft.prs()
+                    .get(r.id)
+                    .map(|p| p.matched == last_index)
+                    .unwrap_or_default()
+            })
+            .map(|r| r.id);
+        if let Some(transferee) = transferee {
+            info!(
+                "group {} witness {} transfer leadership to {transferee}",
+                self.group_id, self.desc.id
+            );
+            self.raft_node.transfer_leader(transferee);
+        }
+    }
+
+    /// Hibernate the group if the leader has been idle for `hibernate_idle_ticks`. The followers
+    /// are notified by the last heartbeat. The root group never hibernates.
+    fn try_hibernate(&mut self) {
+        if self.group_id == ROOT_GROUP_ID || !self.raft_node.is_idle_leader() {
+            self.hibernation.idle_ticks = 0;
+            return;
+        }
+
+        self.hibernation.idle_ticks += 1;
+        if self.hibernation.idle_ticks < self.cfg.hibernate_idle_ticks {
+            return;
+        }
+
+        if !self.send_hibernation_heartbeats() {
+            return;
+        }
+        self.hibernation.hibernated = true;
+        self.hibernation.ticks = 0;
+        RAFTGROUP_HIBERNATE_TOTAL.inc();
+    }
+
+    /// The hibernated leader keeps sending hibernation heartbeats, and a hibernated follower wakes
+    /// itself up if it misses `hibernate_missed_heartbeats` of them, in case the leader is lost
+    /// but the node liveness of root isn't available.
+    fn on_hibernated_tick(&mut self) {
+        self.hibernation.ticks += 1;
+        let heartbeat_ticks = self.cfg.hibernate_idle_ticks.max(1);
+        if self.raft_node.raft().state == StateRole::Leader {
+            if self.hibernation.ticks % heartbeat_ticks == 0 {
+                self.send_hibernation_heartbeats();
+            }
+        } else if self.hibernation.ticks >= heartbeat_ticks * self.cfg.hibernate_missed_heartbeats {
+            info!(
+                "group {} replica {} wake up since missing {} hibernation heartbeats",
+                self.group_id, self.desc.id, self.cfg.hibernate_missed_heartbeats
+            );
+            self.wake_up();
+        }
+    }
+
+    /// Send heartbeats with the hibernation flag to all followers, returns false if the
+    /// descriptor of any follower is unknown.
+    fn send_hibernation_heartbeats(&mut self) -> bool {
+        let raft = self.raft_node.raft();
+        let (term, commit) = (raft.term, raft.raft_log.committed);
+        let mut msgs = Vec::default();
+        for (&target_id, _) in raft.prs().iter() {
+            if target_id == self.desc.id {
+                continue;
+            }
+            let to_replica = match self.replica_cache.get(target_id) {
+                Some(to_replica) => to_replica,
+                None => return false,
+            };
+            let mut msg = Message {
+                from: self.desc.id,
+                to: target_id,
+                term,
+                commit,
+                ..Default::default()
+            };
+            msg.set_msg_type(MessageType::MsgHeartbeat);
+            msgs.push(RaftMessage {
+                group_id: self.group_id,
+                from_replica: Some(self.desc.clone()),
+                to_replica: Some(to_replica),
+                messages: vec![msg],
+                hibernate: true,
+            });
+        }
+
+        debug!(
+            "group {} replica {} send hibernation heartbeats, term {term} commit {commit}",
+            self.group_id, self.desc.id
+        );
+        for msg in msgs {
+            let target_id = msg.to_replica.as_ref().unwrap().id;
+            self.channels
+                .entry(target_id)
+                .or_insert_with(|| Channel::new(self.trans_mgr.clone()))
+                .send_message(msg);
+        }
+        true
+    }
+
+    /// Hibernate the follower if it has caught up the leader, which sends the heartbeat.
+    fn follow_hibernation(&mut self, msg: &Message) {
+        let raft = self.raft_node.raft();
+        if self.cfg.enable_hibernation
+            && raft.state == StateRole::Follower
+            && raft.leader_id == msg.from
+            && raft.term == msg.term
+            && raft.raft_log.committed == msg.commit
+            && raft.raft_log.last_index() == msg.commit
+        {
+            self.hibernation.ticks = 0;
+            if !self.hibernation.hibernated {
+                self.hibernation.hibernated = true;
+                RAFTGROUP_HIBERNATE_TOTAL.inc();
+            }
+        }
+    }
+
+    fn wake_up(&mut self) {
+        self.hibernation.idle_ticks = 0;
+        self.hibernation.ticks = 0;
+        if self.hibernation.hibernated {
+            self.hibernation.hibernated = false;
+            RAFTGROUP_WAKE_UP_TOTAL.inc();
+        }
+    }
+
+    fn is_leader_located_in(&self, nodes: &HashSet<u64>) -> bool {
+        let leader_id = self.raft_node.raft().leader_id;
+        self.replica_cache
+            .get(leader_id)
+            .map(|r| nodes.contains(&r.node_id))
+            .unwrap_or_default()
+    }
+
     fn handle_proposal(
         &mut self,
         ctx: &mut WorkerContext,
@@ -577,6 +836,12 @@ where
     }
 }
 
+fn is_witness(desc: &GroupDesc, replica_id: u64) -> bool {
+    desc.replicas
+        .iter()
+        .any(|r| r.id == replica_id && r.role == ReplicaRole::Witness as i32)
+}
+
 impl SlowIoGuard {
     fn new(threshold: u64) -> Self {
         SlowIoGuard {
@@ -113,6 +113,10 @@ impl<T: AllocSource> Allocator<T> {
         self.config.replicas_per_group
     }
 
+    pub fn witnesses_per_group(&self) -> usize {
+        self.config.witnesses_per_group
+    }
+
     /// Compute group change action.
     pub async fn compute_group_action(&self) -> Result<GroupAction> {
         if !self.config.enable_group_balance {
@@ -206,6 +210,18 @@ impl<T: AllocSource> Allocator<T> {
             .allocate_group_replica(existing_replica_nodes, wanted_count)
     }
 
+    /// Allocate new read learners in one group, on the nodes labelled for read replicas.
+    pub async fn allocate_read_replica(
+        &self,
+        existing_replica_nodes: Vec<u64>,
+        wanted_count: usize,
+    ) -> Result<Vec<NodeDesc>> {
+        self.alloc_source.refresh_all().await?;
+
+        ReplicaCountPolicy::with(self.alloc_source.to_owned(), self.ongoing_stats.to_owned())
+            .allocate_read_replica(existing_replica_nodes, wanted_count)
+    }
+
     /// Find a group to place shard.
     pub async fn place_group_for_shard(&self, n: usize) -> Result<Vec<GroupDesc>> {
         self.alloc_source.refresh_all().await?;
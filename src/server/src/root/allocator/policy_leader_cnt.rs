@@ -109,7 +109,8 @@ impl<T: AllocSource> LeaderCountPolicy<T> {
             let exist_replica_in_nodes = group
                 .replicas
                 .iter()
-                .filter(|r| r.id != replica.id)
+                // Only voters could be leader, a witness never becomes leader.
+                .filter(|r| r.id != replica.id && r.role == ReplicaRole::Voter as i32)
                 .map(|r| (r.node_id, r.to_owned()))
                 .collect::<HashMap<u64, ReplicaDesc>>();
 
@@ -41,7 +41,32 @@ impl<T: AllocSource> ReplicaCountPolicy<T> {
         existing_replica_nodes: Vec<u64>,
         wanted_count: usize,
     ) -> Result<Vec<NodeDesc>> {
-        let mut candidate_nodes = self.alloc_source.nodes(NodeFilter::Schedulable);
+        self.allocate_replica_in(
+            NodeFilter::Schedulable,
+            existing_replica_nodes,
+            wanted_count,
+        )
+    }
+
+    pub fn allocate_read_replica(
+        &self,
+        existing_replica_nodes: Vec<u64>,
+        wanted_count: usize,
+    ) -> Result<Vec<NodeDesc>> {
+        self.allocate_replica_in(
+            NodeFilter::ReadReplica,
+            existing_replica_nodes,
+            wanted_count,
+        )
+    }
+
+    fn allocate_replica_in(
+        &self,
+        filter: NodeFilter,
+        existing_replica_nodes: Vec<u64>,
+        wanted_count: usize,
+    ) -> Result<Vec<NodeDesc>> {
+        let mut candidate_nodes = self.alloc_source.nodes(filter);
 
         // skip the nodes already have group replicas.
         candidate_nodes.retain(|n| !existing_replica_nodes.iter().any(|rn| *rn == n.id));
@@ -132,11 +132,20 @@ impl<T: AllocSource> ShardCountPolicy<T> {
     fn preferred_remove_shard(
         &self,
         src_group: &GroupDesc,
-        _target_group: &GroupDesc,
+        target_group: &GroupDesc,
     ) -> Option<ShardDesc> {
-        let replicas = src_group.shards.to_owned();
         // TODO: ranking shards and choose the preferred one
-        replicas.get(0).map(ToOwned::to_owned)
+        src_group
+            .shards
+            .iter()
+            .find(|shard| {
+                // The shards of a resharding collection might share the same key range.
+                !target_group
+                    .shards
+                    .iter()
+                    .any(|s| engula_api::shard::is_key_conflict(s, shard))
+            })
+            .map(ToOwned::to_owned)
     }
 
     fn current_user_groups(&self) -> Vec<GroupDesc> {
@@ -56,6 +56,7 @@ fn sim_boostrap_join_node_balance() {
                 leader_count: 1,
             }),
             status: NodeStatus::Active as i32,
+            labels: vec![],
         }]);
         p.set_replica_states(vec![ReplicaState {
             replica_id: 1,
@@ -82,6 +83,7 @@ fn sim_boostrap_join_node_balance() {
                     leader_count: 0,
                 }),
                 status: NodeStatus::Active as i32,
+                labels: vec![],
             },
             NodeDesc {
                 id: 3,
@@ -92,6 +94,7 @@ fn sim_boostrap_join_node_balance() {
                     leader_count: 0,
                 }),
                 status: NodeStatus::Active as i32,
+                labels: vec![],
             },
         ]);
         p.set_nodes(nodes);
@@ -236,6 +239,7 @@ fn sim_boostrap_join_node_balance() {
                 leader_count: 0,
             }),
             status: NodeStatus::Active as i32,
+            labels: vec![],
         }]);
         p.set_nodes(nodes);
         p.display();
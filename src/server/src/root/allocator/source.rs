@@ -28,6 +28,8 @@ pub enum NodeFilter {
     Alive,
     Schedulable,
     NotDecommissioned,
+    /// The schedulable nodes labelled for read replicas.
+    ReadReplica,
 }
 
 #[crate::async_trait]
@@ -49,6 +51,7 @@ pub trait AllocSource {
 pub struct SysAllocSource {
     root: Arc<RootShared>,
     liveness: Arc<Liveness>,
+    read_replica_label: String,
 
     nodes: Arc<Mutex<Vec<NodeDesc>>>,
     groups: Arc<Mutex<GroupInfo>>,
@@ -67,10 +70,11 @@ struct ReplicaInfo {
 }
 
 impl SysAllocSource {
-    pub fn new(root: Arc<RootShared>, liveness: Arc<Liveness>) -> Self {
+    pub fn new(root: Arc<RootShared>, liveness: Arc<Liveness>, read_replica_label: String) -> Self {
         Self {
             root,
             liveness,
+            read_replica_label,
             nodes: Default::default(),
             groups: Default::default(),
             replicas: Default::default(),
@@ -100,14 +104,16 @@ impl AllocSource for SysAllocSource {
                 .collect::<Vec<_>>(),
             NodeFilter::Schedulable => all_nodes
                 .into_iter()
-                .filter(|n| {
-                    n.status == NodeStatus::Active as i32 && !self.liveness.get(&n.id).is_dead()
-                })
+                .filter(|n| self.is_schedulable(n) && !self.is_read_replica_node(n))
                 .collect::<Vec<_>>(),
             NodeFilter::NotDecommissioned => all_nodes
                 .into_iter()
                 .filter(|n| n.status != NodeStatus::Decommissioned as i32)
                 .collect::<Vec<_>>(),
+            NodeFilter::ReadReplica => all_nodes
+                .into_iter()
+                .filter(|n| self.is_schedulable(n) && self.is_read_replica_node(n))
+                .collect::<Vec<_>>(),
         }
     }
 
@@ -141,6 +147,14 @@ impl AllocSource for SysAllocSource {
 }
 
 impl SysAllocSource {
+    fn is_schedulable(&self, n: &NodeDesc) -> bool {
+        n.status == NodeStatus::Active as i32 && !self.liveness.get(&n.id).is_dead()
+    }
+
+    fn is_read_replica_node(&self, n: &NodeDesc) -> bool {
+        n.labels.iter().any(|l| *l == self.read_replica_label)
+    }
+
     async fn reload_nodes(&self) -> Result<()> {
         let schema = self.root.schema()?;
         let cur_nodes = schema.list_node().await?;
@@ -13,13 +13,13 @@
 // limitations under the License.
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     sync::{atomic, Arc, Mutex},
     task::{Poll, Waker},
 };
 
-use engula_api::server::v1::{GroupDesc, ReplicaDesc, ReplicaRole, RootDesc, ShardDesc};
-use futures::future::poll_fn;
+use engula_api::server::v1::{GroupDesc, ReplicaDesc, ReplicaRole, RootDesc, ShardData, ShardDesc};
+use futures::{future::poll_fn, StreamExt};
 use prometheus::HistogramTimer;
 use tokio::time::Instant;
 use tracing::{error, info, warn};
@@ -49,6 +49,7 @@ impl Jobs {
                 heartbeat_queue,
                 mem_jobs: Default::default(),
                 res_locks: Default::default(),
+                canceled: Default::default(),
                 enable: Default::default(),
             },
         }
@@ -63,6 +64,37 @@ impl Jobs {
         Ok(())
     }
 
+    /// Cancel an ongoing job, the job is rolled back the next time its handler checks it. Only
+    /// the reshard collection jobs which haven't been switched to the target slots are supported.
+    pub fn cancel(&self, job_id: u64) -> Result<()> {
+        self.core.check_root_leader()?;
+        let job = self
+            .core
+            .need_handle_jobs()
+            .into_iter()
+            .find(|job| job.id == job_id)
+            .ok_or_else(|| crate::Error::InvalidArgument(format!("job {job_id} not found")))?;
+        match job.job.as_ref().unwrap() {
+            background_job::Job::ReshardCollection(reshard_collection) => {
+                let status =
+                    ReshardCollectionJobStatus::from_i32(reshard_collection.status).unwrap();
+                if !is_reshard_cancelable(status) {
+                    return Err(crate::Error::InvalidArgument(format!(
+                        "job {job_id} can't be canceled in status {status:?}"
+                    )));
+                }
+            }
+            _ => {
+                return Err(crate::Error::InvalidArgument(
+                    "only reshard collection job could be canceled".into(),
+                ))
+            }
+        }
+        info!("cancel background job {job_id}");
+        self.core.canceled.lock().unwrap().insert(job_id);
+        Ok(())
+    }
+
     pub async fn wait_more_jobs(&self) {
         self.core.wait_more_jobs().await;
     }
@@ -101,6 +133,10 @@ impl Jobs {
             background_job::Job::PurgeDatabase(purge_database) => {
                 self.handle_purge_database(job, purge_database).await
             }
+            background_job::Job::ReshardCollection(reshard_collection) => {
+                self.handle_reshard_collection(job, reshard_collection)
+                    .await
+            }
         };
         info!("backgroud job: {job:?}, handle result: {r:?}");
         r
@@ -155,7 +191,7 @@ impl Jobs {
             let shard = shard.unwrap();
             let groups = self.core.alloc.place_group_for_shard(1).await?;
             if groups.is_empty() {
-                return Err(crate::Error::ResourceExhausted("no engouth groups".into()));
+                return Err(crate::Error::ResourceExhausted("no enough groups".into()));
             }
             let group = groups.first().unwrap();
             info!(
@@ -347,13 +383,24 @@ impl Jobs {
             .allocate_group_replica(vec![], create_group.request_replica_cnt as usize)
             .await?;
         let group_id = schema.next_group_id().await?;
+        // The witnesses must be the minority of the group.
+        let num_witnesses = std::cmp::min(
+            self.core.alloc.witnesses_per_group(),
+            nodes.len().saturating_sub(1) / 2,
+        );
+        let num_voters = nodes.len() - num_witnesses;
         let mut replicas = Vec::new();
-        for n in &nodes {
+        for (i, n) in nodes.iter().enumerate() {
             let replica_id = schema.next_replica_id().await?;
+            let role = if i < num_voters {
+                ReplicaRole::Voter
+            } else {
+                ReplicaRole::Witness
+            };
             replicas.push(ReplicaDesc {
                 id: replica_id,
                 node_id: n.id,
-                role: ReplicaRole::Voter.into(),
+                role: role.into(),
             });
         }
         let group_desc = GroupDesc {
@@ -566,6 +613,398 @@ impl Jobs {
     }
 }
 
+impl Jobs {
+    // handle reshard_collection.
+    //
+    // The shards of target slots are created first, then the current shards are marked with
+    // `target_slots` so that their leaders forward the writes to the target shards. After all
+    // data of current shards are copied, the collection is switched to the target slots.
+    async fn handle_reshard_collection(
+        &self,
+        job: &BackgroundJob,
+        reshard_collection: &ReshardCollectionJob,
+    ) -> Result<()> {
+        let mut reshard_collection = reshard_collection.to_owned();
+        loop {
+            if self.core.take_canceled(job.id) {
+                self.handle_cancel_reshard(job.id, &mut reshard_collection)
+                    .await?;
+            }
+            let status = ReshardCollectionJobStatus::from_i32(reshard_collection.status).unwrap();
+            match status {
+                ReshardCollectionJobStatus::ReshardCollectionCreating => {
+                    self.handle_create_target_shards(job.id, &mut reshard_collection)
+                        .await?;
+                }
+                ReshardCollectionJobStatus::ReshardCollectionDualWrite => {
+                    self.handle_begin_dual_write(job.id, &mut reshard_collection)
+                        .await?;
+                }
+                ReshardCollectionJobStatus::ReshardCollectionCopying => {
+                    self.handle_copy_shards(job.id, &mut reshard_collection)
+                        .await?;
+                }
+                ReshardCollectionJobStatus::ReshardCollectionCutover => {
+                    self.handle_reshard_cutover(job.id, &mut reshard_collection)
+                        .await?;
+                }
+                ReshardCollectionJobStatus::ReshardCollectionCleanup
+                | ReshardCollectionJobStatus::ReshardCollectionRollbacking => {
+                    self.handle_cleanup_reshard_shards(job.id, &mut reshard_collection)
+                        .await?;
+                }
+                ReshardCollectionJobStatus::ReshardCollectionFinish
+                | ReshardCollectionJobStatus::ReshardCollectionAbort => {
+                    let mut job = job.to_owned();
+                    job.job = Some(background_job::Job::ReshardCollection(reshard_collection));
+                    self.core.finish(job).await?;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_cancel_reshard(
+        &self,
+        job_id: u64,
+        reshard_collection: &mut ReshardCollectionJob,
+    ) -> Result<()> {
+        let status = ReshardCollectionJobStatus::from_i32(reshard_collection.status).unwrap();
+        if !is_reshard_cancelable(status) {
+            warn!(
+                collection = reshard_collection.collection_id,
+                "skip canceling resharding in status {status:?}"
+            );
+            return Ok(());
+        }
+        info!(
+            collection = reshard_collection.collection_id,
+            "resharding is canceled in status {status:?}, try to rollback"
+        );
+        reshard_collection.remark = "canceled".to_owned();
+        reshard_collection.wait_cleanup = std::mem::take(&mut reshard_collection.created);
+        reshard_collection.status = ReshardCollectionJobStatus::ReshardCollectionRollbacking as i32;
+        self.save_reshard_collection(job_id, reshard_collection)
+            .await
+    }
+
+    async fn handle_create_target_shards(
+        &self,
+        job_id: u64,
+        reshard_collection: &mut ReshardCollectionJob,
+    ) -> Result<()> {
+        while let Some(shard) = reshard_collection.wait_create.pop() {
+            // The shards of the same slot in the current and target slots share the same key
+            // range, so they must not be placed in the same group.
+            let groups = self.core.alloc.place_group_for_shard(usize::MAX).await?;
+            let group = groups
+                .iter()
+                .find(|g| {
+                    !g.shards
+                        .iter()
+                        .any(|s| engula_api::shard::is_key_conflict(s, &shard))
+                })
+                .ok_or_else(|| crate::Error::ResourceExhausted("no enough groups".into()))?;
+            if let Err(err) = self.try_create_shard(group.id, &shard).await {
+                error!(group=group.id, shard=shard.id, err=?err, "create target shard of resharding error and try to rollback");
+                reshard_collection.remark = format!("{err:?}");
+                reshard_collection.wait_cleanup = std::mem::take(&mut reshard_collection.created);
+                reshard_collection.wait_cleanup.push(shard);
+                reshard_collection.status =
+                    ReshardCollectionJobStatus::ReshardCollectionRollbacking as i32;
+                self.save_reshard_collection(job_id, reshard_collection)
+                    .await?;
+                return Ok(());
+            }
+            reshard_collection.created.push(shard);
+            self.save_reshard_collection(job_id, reshard_collection)
+                .await?;
+        }
+        reshard_collection.status = ReshardCollectionJobStatus::ReshardCollectionDualWrite as i32;
+        self.save_reshard_collection(job_id, reshard_collection)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_begin_dual_write(
+        &self,
+        job_id: u64,
+        reshard_collection: &mut ReshardCollectionJob,
+    ) -> Result<()> {
+        let target_slots = reshard_collection.target_slots;
+        self.update_collection_partition(reshard_collection, |hash| {
+            hash.target_slots = target_slots;
+        })
+        .await?;
+
+        // Mark the current shards with the target slots before copying, once a shard is marked
+        // its leader forwards every write to the target shard under the latch of the key, so the
+        // writes missed by the copying are applied to the target shards in order.
+        self.mark_resharding_shards(reshard_collection, target_slots)
+            .await?;
+        reshard_collection.status = ReshardCollectionJobStatus::ReshardCollectionCopying as i32;
+        self.save_reshard_collection(job_id, reshard_collection)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_copy_shards(
+        &self,
+        job_id: u64,
+        reshard_collection: &mut ReshardCollectionJob,
+    ) -> Result<()> {
+        let schema = self.core.root_shared.schema()?;
+        let target_slots = reshard_collection.target_slots;
+        while let Some(shard) = reshard_collection.wait_copy.last().cloned() {
+            let group_shards = schema
+                .get_collection_shards(reshard_collection.collection_id)
+                .await?;
+            let src_group = group_shards
+                .iter()
+                .find(|(_, s)| s.id == shard.id)
+                .map(|(group, _)| *group)
+                .ok_or(crate::Error::ShardNotFound(shard.id))?;
+            let targets = reshard_collection
+                .created
+                .iter()
+                .filter_map(|target| {
+                    let group = group_shards
+                        .iter()
+                        .find(|(_, s)| s.id == target.id)
+                        .map(|(group, _)| *group)?;
+                    Some((engula_api::shard::slot(target)?, (group, target.id)))
+                })
+                .collect::<HashMap<_, _>>();
+
+            let last_key = reshard_collection.last_copied_key.clone();
+            let mut streaming = self
+                .core
+                .root_shared
+                .transport_manager
+                .lazy_group_client(src_group)
+                .retryable_pull_for_resharding(shard.id, last_key)
+                .await?;
+            while let Some(chunk) = streaming.next().await {
+                if self.core.is_canceled(job_id) {
+                    // The copied chunks are saved, let the caller rollback the job.
+                    return Ok(());
+                }
+                let chunk = chunk?;
+                let last_key = match chunk.data.last() {
+                    Some(data) => data.key.clone(),
+                    None => continue,
+                };
+                let mut slot_data: HashMap<u32, Vec<ShardData>> = HashMap::default();
+                for data in chunk.data {
+                    let slot = engula_api::shard::key_slot(
+                        &data.key,
+                        target_slots,
+                        reshard_collection.algorithm,
+                    )
+                    .ok_or_else(|| {
+                        crate::Error::InvalidData(format!(
+                            "hash algorithm {} of collection {}",
+                            reshard_collection.algorithm, reshard_collection.collection_id
+                        ))
+                    })?;
+                    slot_data.entry(slot).or_default().push(data);
+                }
+                for (slot, data) in slot_data {
+                    let (group, target_shard) = targets.get(&slot).cloned().ok_or_else(|| {
+                        crate::Error::InvalidData(format!("target shard of slot {slot}"))
+                    })?;
+                    self.core
+                        .root_shared
+                        .transport_manager
+                        .lazy_group_client(group)
+                        .ingest(target_shard, data)
+                        .await?;
+                }
+                reshard_collection.last_copied_key = last_key;
+                self.save_reshard_collection(job_id, reshard_collection)
+                    .await?;
+            }
+
+            info!(
+                collection = reshard_collection.collection_id,
+                shard = shard.id,
+                "copy shard data of resharding collection finished"
+            );
+            reshard_collection.wait_copy.pop();
+            reshard_collection.last_copied_key.clear();
+            self.save_reshard_collection(job_id, reshard_collection)
+                .await?;
+        }
+        reshard_collection.status = ReshardCollectionJobStatus::ReshardCollectionCutover as i32;
+        self.save_reshard_collection(job_id, reshard_collection)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_reshard_cutover(
+        &self,
+        job_id: u64,
+        reshard_collection: &mut ReshardCollectionJob,
+    ) -> Result<()> {
+        let target_slots = reshard_collection.target_slots;
+        self.update_collection_partition(reshard_collection, |hash| {
+            hash.slots = target_slots;
+            hash.target_slots = 0;
+        })
+        .await?;
+
+        let schema = self.core.root_shared.schema()?;
+        reshard_collection.wait_cleanup = schema
+            .get_collection_shards(reshard_collection.collection_id)
+            .await?
+            .into_iter()
+            .map(|(_, shard)| shard)
+            .filter(|shard| {
+                !reshard_collection
+                    .created
+                    .iter()
+                    .any(|target| target.id == shard.id)
+            })
+            .collect();
+        reshard_collection.status = ReshardCollectionJobStatus::ReshardCollectionCleanup as i32;
+        self.save_reshard_collection(job_id, reshard_collection)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_cleanup_reshard_shards(
+        &self,
+        job_id: u64,
+        reshard_collection: &mut ReshardCollectionJob,
+    ) -> Result<()> {
+        let rollbacking = matches!(
+            ReshardCollectionJobStatus::from_i32(reshard_collection.status).unwrap(),
+            ReshardCollectionJobStatus::ReshardCollectionRollbacking
+        );
+        if rollbacking {
+            // Stop dual writing before the target shards are removed.
+            self.mark_resharding_shards(reshard_collection, 0).await?;
+        }
+
+        let schema = self.core.root_shared.schema()?;
+        let group_shards = schema
+            .get_collection_shards(reshard_collection.collection_id)
+            .await?;
+        while let Some(shard) = reshard_collection.wait_cleanup.pop() {
+            if let Some((group, _)) = group_shards.iter().find(|(_, s)| s.id == shard.id) {
+                self.try_remove_shard(*group, shard.id).await?;
+            }
+            self.save_reshard_collection(job_id, reshard_collection)
+                .await?;
+        }
+
+        reshard_collection.status = if rollbacking {
+            self.update_collection_partition(reshard_collection, |hash| {
+                hash.target_slots = 0;
+            })
+            .await?;
+            ReshardCollectionJobStatus::ReshardCollectionAbort as i32
+        } else {
+            ReshardCollectionJobStatus::ReshardCollectionFinish as i32
+        };
+        self.save_reshard_collection(job_id, reshard_collection)
+            .await?;
+        Ok(())
+    }
+
+    /// Update the `target_slots` of the current shards of the resharding collection.
+    async fn mark_resharding_shards(
+        &self,
+        reshard_collection: &ReshardCollectionJob,
+        target_slots: u32,
+    ) -> Result<()> {
+        use engula_api::server::v1::shard_desc;
+
+        let schema = self.core.root_shared.schema()?;
+        let group_shards = schema
+            .get_collection_shards(reshard_collection.collection_id)
+            .await?;
+        for (group, mut shard) in group_shards {
+            if reshard_collection
+                .created
+                .iter()
+                .any(|target| target.id == shard.id)
+            {
+                continue;
+            }
+            match shard.partition.as_mut() {
+                Some(shard_desc::Partition::Hash(hash)) if hash.target_slots != target_slots => {
+                    hash.target_slots = target_slots;
+                }
+                _ => continue,
+            }
+            self.core
+                .root_shared
+                .transport_manager
+                .lazy_group_client(group)
+                .update_shard(&shard)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn update_collection_partition<F>(
+        &self,
+        reshard_collection: &ReshardCollectionJob,
+        f: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut engula_api::v1::collection_desc::HashPartition),
+    {
+        use engula_api::{server::v1::watch_response::*, v1::collection_desc};
+
+        let schema = self.core.root_shared.schema()?;
+        let mut desc = schema
+            .get_collection(
+                reshard_collection.database,
+                &reshard_collection.collection_name,
+            )
+            .await?
+            .filter(|desc| desc.id == reshard_collection.collection_id)
+            .ok_or_else(|| {
+                crate::Error::InvalidArgument(format!(
+                    "collection {} not found",
+                    reshard_collection.collection_name
+                ))
+            })?;
+        match desc.partition.as_mut() {
+            Some(collection_desc::Partition::Hash(hash)) => f(hash),
+            _ => unreachable!("only hash collection could be resharded"),
+        }
+        schema.update_collection(desc.to_owned()).await?;
+        self.core
+            .root_shared
+            .watcher_hub
+            .notify_updates(vec![UpdateEvent {
+                event: Some(update_event::Event::Collection(desc)),
+            }])
+            .await;
+        Ok(())
+    }
+
+    async fn save_reshard_collection(
+        &self,
+        job_id: u64,
+        reshard_collection: &ReshardCollectionJob,
+    ) -> Result<()> {
+        self.core
+            .update(BackgroundJob {
+                id: job_id,
+                job: Some(background_job::Job::ReshardCollection(
+                    reshard_collection.to_owned(),
+                )),
+            })
+            .await?;
+        Ok(())
+    }
+}
+
 impl Jobs {
     async fn try_create_shard(&self, group_id: u64, desc: &ShardDesc) -> Result<()> {
         let mut group_client = self
@@ -620,8 +1059,13 @@ impl Jobs {
         Ok(())
     }
 
-    async fn try_remove_shard(&self, _group: u64, _shard: u64) -> Result<()> {
-        // TODO: impl remove shard.
+    async fn try_remove_shard(&self, group_id: u64, shard_id: u64) -> Result<()> {
+        let mut group_client = self
+            .core
+            .root_shared
+            .transport_manager
+            .lazy_group_client(group_id);
+        group_client.remove_shard(shard_id).await?;
         Ok(())
     }
 }
@@ -630,6 +1074,8 @@ struct JobCore {
     root_shared: Arc<RootShared>,
     mem_jobs: Arc<Mutex<MemJobs>>,
     res_locks: Arc<Mutex<HashSet<Vec<u8>>>>,
+    /// The jobs requested to be canceled, they are only kept in the memory of root leader.
+    canceled: Arc<Mutex<HashSet<u64>>>,
     alloc: Arc<Allocator<SysAllocSource>>,
     heartbeat_queue: Arc<HeartbeatQueue>,
     enable: atomic::AtomicBool,
@@ -694,18 +1140,28 @@ impl JobCore {
             let mut res_locks = self.res_locks.lock().unwrap();
             res_locks.clear();
         }
+        self.canceled.lock().unwrap().clear();
     }
 
     pub async fn append(&self, job: BackgroundJob) -> Result<BackgroundJob> {
         let schema = self.root_shared.schema()?;
-        if let Some(res_key) = res_key(&job) {
-            if !self.try_lock_res(res_key) {
+        let res_key = res_key(&job);
+        if let Some(res_key) = &res_key {
+            if !self.try_lock_res(res_key.to_owned()) {
                 return Err(crate::Error::AlreadyExists(
                     "job for target resource already exist".into(),
                 ));
             }
         }
-        let job = schema.append_job(job).await?;
+        let job = match self.check_and_append(&schema, job).await {
+            Ok(job) => job,
+            Err(err) => {
+                if let Some(res_key) = &res_key {
+                    self.unlock_res(res_key);
+                }
+                return Err(err);
+            }
+        };
         {
             let mut mem_jobs = self.mem_jobs.lock().unwrap();
             mem_jobs.jobs.push(job.to_owned());
@@ -717,6 +1173,61 @@ impl JobCore {
         Ok(job)
     }
 
+    async fn check_and_append(&self, schema: &Schema, job: BackgroundJob) -> Result<BackgroundJob> {
+        if let Some(Job::ReshardCollection(reshard_collection)) = job.job.as_ref() {
+            self.check_resharding_collection(schema, reshard_collection)
+                .await?;
+        }
+        schema.append_job(job).await
+    }
+
+    /// Check the collection again after the resource is locked. The collection desc used to build
+    /// the job is read without the lock, another reshard job might be submitted and finished in
+    /// the meantime.
+    async fn check_resharding_collection(
+        &self,
+        schema: &Schema,
+        reshard_collection: &ReshardCollectionJob,
+    ) -> Result<()> {
+        use engula_api::v1::collection_desc;
+
+        let name = &reshard_collection.collection_name;
+        let desc = schema
+            .get_collection(reshard_collection.database, name)
+            .await?
+            .filter(|desc| desc.id == reshard_collection.collection_id)
+            .ok_or_else(|| crate::Error::InvalidArgument(format!("collection {name} not found")))?;
+        let resharding = match desc.partition.as_ref() {
+            Some(collection_desc::Partition::Hash(hash)) => {
+                hash.target_slots != 0 || hash.slots == reshard_collection.target_slots
+            }
+            _ => {
+                return Err(crate::Error::InvalidArgument(
+                    "only hash collection could be resharded".into(),
+                ))
+            }
+        };
+        let mut current = schema
+            .get_collection_shards(desc.id)
+            .await?
+            .into_iter()
+            .map(|(_, shard)| shard.id)
+            .collect::<Vec<_>>();
+        let mut wait_copy = reshard_collection
+            .wait_copy
+            .iter()
+            .map(|shard| shard.id)
+            .collect::<Vec<_>>();
+        current.sort_unstable();
+        wait_copy.sort_unstable();
+        if resharding || current != wait_copy {
+            return Err(crate::Error::AlreadyExists(format!(
+                "resharding of collection {name}"
+            )));
+        }
+        Ok(())
+    }
+
     pub async fn finish(&self, job: BackgroundJob) -> Result<()> {
         let schema = self.root_shared.schema()?;
         schema.remove_job(&job).await?;
@@ -812,6 +1323,14 @@ impl JobCore {
         jobs.jobs.to_owned()
     }
 
+    fn is_canceled(&self, job_id: u64) -> bool {
+        self.canceled.lock().unwrap().contains(&job_id)
+    }
+
+    fn take_canceled(&self, job_id: u64) -> bool {
+        self.canceled.lock().unwrap().remove(&job_id)
+    }
+
     fn try_lock_res(&self, res_key: Vec<u8>) -> bool {
         let mut res_locks = self.res_locks.lock().unwrap();
         res_locks.insert(res_key)
@@ -823,6 +1342,17 @@ impl JobCore {
     }
 }
 
+/// The target shards are removed when a resharding is rolled back, so it can't be canceled once
+/// the collection is switched to them.
+fn is_reshard_cancelable(status: ReshardCollectionJobStatus) -> bool {
+    matches!(
+        status,
+        ReshardCollectionJobStatus::ReshardCollectionCreating
+            | ReshardCollectionJobStatus::ReshardCollectionDualWrite
+            | ReshardCollectionJobStatus::ReshardCollectionCopying
+    )
+}
+
 fn res_key(job: &BackgroundJob) -> Option<Vec<u8>> {
     match job.job.as_ref().unwrap() {
         background_job::Job::CreateCollection(job) => {
@@ -835,6 +1365,11 @@ fn res_key(job: &BackgroundJob) -> Option<Vec<u8>> {
             key.extend_from_slice(job.collection_name.as_bytes());
             Some(key)
         }
+        background_job::Job::ReshardCollection(job) => {
+            let mut key = job.database.to_le_bytes().to_vec();
+            key.extend_from_slice(job.collection_name.as_bytes());
+            Some(key)
+        }
         background_job::Job::CreateOneGroup(_) | background_job::Job::PurgeDatabase(_) => None,
     }
 }
@@ -49,6 +49,8 @@ struct RootCollectorShared {
     replica_counts: IntGaugeVec,
     leader_counts: IntGaugeVec,
     shard_counts: IntGaugeVec,
+    collection_keys: IntGaugeVec,
+    collection_sizes: IntGaugeVec,
 }
 
 impl RootCollectorShared {
@@ -86,6 +88,24 @@ impl RootCollectorShared {
         )
         .unwrap();
         descs.extend(shard_counts.desc().into_iter().cloned());
+        let collection_keys = IntGaugeVec::new(
+            Opts::new(
+                "cluster_collection_approximate_keys",
+                "approximate key count for each collection",
+            ),
+            &["database", "collection"],
+        )
+        .unwrap();
+        descs.extend(collection_keys.desc().into_iter().cloned());
+        let collection_sizes = IntGaugeVec::new(
+            Opts::new(
+                "cluster_collection_approximate_bytes",
+                "approximate size in bytes for each collection",
+            ),
+            &["database", "collection"],
+        )
+        .unwrap();
+        descs.extend(collection_sizes.desc().into_iter().cloned());
         let is_root_leader = false.into();
         Self {
             server,
@@ -95,6 +115,8 @@ impl RootCollectorShared {
             replica_counts,
             leader_counts,
             shard_counts,
+            collection_keys,
+            collection_sizes,
             is_root_leader,
         }
     }
@@ -158,6 +180,22 @@ impl RootCollector {
                     .with_label_values(&[&n.id.to_string()])
                     .set(n.leaders.len() as i64);
             }
+
+            // approximate collection stats.
+            self.shared.collection_keys.reset();
+            self.shared.collection_sizes.reset();
+            for db in &info.databases {
+                for co in &db.collections {
+                    self.shared
+                        .collection_keys
+                        .with_label_values(&[&db.name, &co.name])
+                        .set(co.approximate_keys as i64);
+                    self.shared
+                        .collection_sizes
+                        .with_label_values(&[&db.name, &co.name])
+                        .set(co.approximate_size as i64);
+                }
+            }
         } else {
             self.shared.is_root_leader.store(false, Ordering::Relaxed);
         }
@@ -179,6 +217,8 @@ impl core::Collector for RootCollector {
             mfs.extend(self.shared.replica_counts.collect());
             mfs.extend(self.shared.leader_counts.collect());
             mfs.extend(self.shared.shard_counts.collect());
+            mfs.extend(self.shared.collection_keys.collect());
+            mfs.extend(self.shared.collection_sizes.collect());
         }
         mfs
     }
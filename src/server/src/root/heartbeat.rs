@@ -12,11 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashSet, ops::Add, sync::Arc, vec};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Add,
+    sync::Arc,
+    vec,
+};
 
-use engula_api::server::v1::{
-    watch_response::{update_event, UpdateEvent},
-    *,
+use engula_api::{
+    server::v1::{
+        watch_response::{update_event, UpdateEvent},
+        *,
+    },
+    v1::DatabaseQuota,
 };
 use tokio::time::Instant;
 use tracing::{info, trace, warn};
@@ -35,6 +43,8 @@ impl Root {
         let nodes = all_nodes
             .iter()
             .filter(|n| tasks.iter().any(|t| t.node_id == n.id))
+            // The decommissioned node is not a member of the cluster anymore.
+            .filter(|n| n.status != NodeStatus::Decommissioned as i32)
             .collect::<Vec<_>>();
 
         info!("sending heartbeat to {:?}", &nodes);
@@ -75,6 +85,38 @@ impl Root {
             })
         }
 
+        // The followers of hibernated groups rely on the node liveness to detect the failure of
+        // leader.
+        let dead_nodes = all_nodes
+            .iter()
+            .filter(|n| self.liveness.get(&n.id).is_dead())
+            .map(|n| n.id)
+            .collect::<Vec<_>>();
+        if !dead_nodes.is_empty() {
+            piggybacks.push(PiggybackRequest {
+                info: Some(piggyback_request::Info::SyncNodeLiveness(
+                    SyncNodeLivenessRequest { dead_nodes },
+                )),
+            });
+        }
+
+        // The quotas are synced even if there is no limit, so that the nodes could drop the removed
+        // quotas.
+        let databases = self.database_quota_states(&schema).await?;
+        piggybacks.push(PiggybackRequest {
+            info: Some(piggyback_request::Info::SyncDatabaseQuota(
+                SyncDatabaseQuotaRequest { databases },
+            )),
+        });
+
+        // The nodes lose the replication state once restarted, so it is synced every time.
+        let stopped = schema.is_replication_stopped().await?;
+        piggybacks.push(PiggybackRequest {
+            info: Some(piggyback_request::Info::SyncReplicationState(
+                SyncReplicationStateRequest { stopped },
+            )),
+        });
+
         let resps = {
             let _timer = metrics::HEARTBEAT_NODES_RPC_DURATION_SECONDS.start_timer();
             metrics::HEARTBEAT_NODES_BATCH_SIZE.set(nodes.len() as i64);
@@ -118,7 +160,10 @@ impl Root {
                     for resp in &res.piggybacks {
                         match resp.info.as_ref().unwrap() {
                             piggyback_response::Info::SyncRoot(_)
-                            | piggyback_response::Info::CollectMigrationState(_) => {}
+                            | piggyback_response::Info::CollectMigrationState(_)
+                            | piggyback_response::Info::SyncNodeLiveness(_)
+                            | piggyback_response::Info::SyncDatabaseQuota(_)
+                            | piggyback_response::Info::SyncReplicationState(_) => {}
                             piggyback_response::Info::CollectStats(ref resp) => {
                                 self.handle_collect_stats(&schema, resp, n.to_owned())
                                     .await?
@@ -155,12 +200,99 @@ impl Root {
         Ok(())
     }
 
+    /// Collect the databases which have any limits. The states derived from the schema are cached
+    /// until the schema is changed, only the approximate sizes are refreshed from the shard stats
+    /// reported by group leaders.
+    async fn database_quota_states(&self, schema: &Schema) -> Result<Vec<DatabaseQuotaState>> {
+        // The version is read before building, so the changes during building invalidate the
+        // cached states.
+        let version = self.watcher_hub().version();
+        let mut states = match self.quota_states.get(version) {
+            Some(states) => states,
+            None => {
+                let states = self.build_database_quota_states(schema).await?;
+                self.quota_states.set(version, states.clone());
+                states
+            }
+        };
+        for state in &mut states {
+            state.storage_bytes = state
+                .collections
+                .iter()
+                .map(|id| self.collection_stats(*id).approximate_size)
+                .sum();
+        }
+        Ok(states)
+    }
+
+    async fn build_database_quota_states(
+        &self,
+        schema: &Schema,
+    ) -> Result<Vec<DatabaseQuotaState>> {
+        let databases = schema
+            .list_database()
+            .await?
+            .into_iter()
+            .filter(|db| matches!(&db.quota, Some(quota) if *quota != DatabaseQuota::default()))
+            .collect::<Vec<_>>();
+        if databases.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let collections = schema.list_collection().await?;
+        let groups = schema.list_group().await?;
+        let group_descs = groups.iter().map(|g| (g.id, g)).collect::<HashMap<_, _>>();
+        let leader_nodes = schema
+            .list_group_state()
+            .await?
+            .into_iter()
+            .filter_map(|state| {
+                let leader_id = state.leader_id?;
+                let group = group_descs.get(&state.group_id)?;
+                let leader = group.replicas.iter().find(|r| r.id == leader_id)?;
+                Some((state.group_id, leader.node_id))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut states = Vec::with_capacity(databases.len());
+        for db in databases {
+            let collections = collections
+                .iter()
+                .filter(|c| c.db == db.id)
+                .map(|c| c.id)
+                .collect::<Vec<_>>();
+            let mut node_leaders = HashMap::<u64, u64>::default();
+            for group in &groups {
+                let num_shards = group
+                    .shards
+                    .iter()
+                    .filter(|s| collections.contains(&s.collection_id))
+                    .count() as u64;
+                if num_shards == 0 {
+                    continue;
+                }
+                if let Some(node_id) = leader_nodes.get(&group.id) {
+                    *node_leaders.entry(*node_id).or_default() += num_shards;
+                }
+            }
+            states.push(DatabaseQuotaState {
+                database_id: db.id,
+                quota: db.quota,
+                collections,
+                storage_bytes: 0,
+                node_leaders,
+            });
+        }
+        Ok(states)
+    }
+
     async fn handle_collect_stats(
         &self,
         schema: &Schema,
         resp: &CollectStatsResponse,
         node: &NodeDesc,
     ) -> Result<()> {
+        self.approximate_stats.update_group_stats(&resp.group_stats);
         if let Some(ns) = &resp.node_stats {
             let mut node = node.to_owned();
             let _timer = super::metrics::HEARTBEAT_HANDLE_NODE_STATS_DURATION_SECONDS.start_timer();
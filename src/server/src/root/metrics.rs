@@ -51,6 +51,7 @@ make_static_metric! {
             transfer_leader,
             shed_group_leaders,
             shed_root_leader,
+            decommission_node,
             create_group,
         }
     }
@@ -63,6 +64,7 @@ make_static_metric! {
             create_collection_shards,
             shed_group_leaders,
             shed_root_leader,
+            decommission_node,
         }
     }
     pub struct ReconcileScheduleCreateGroupStepDuration: Histogram {
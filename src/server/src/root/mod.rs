@@ -28,8 +28,9 @@ use std::{collections::*, sync::*, task::Poll, time::Duration};
 use engula_api::{
     server::v1::{report_request::GroupUpdates, watch_response::*, *},
     v1::{
-        collection_desc as co_desc, create_collection_request as co_req, CollectionDesc,
-        DatabaseDesc,
+        collection_desc as co_desc, create_collection_request as co_req,
+        update_collection_request as co_update_req, update_database_request as db_update_req,
+        CollectionDesc, CollectionStats, DatabaseDesc, HashAlgorithm,
     },
 };
 use tokio::time::Instant;
@@ -63,6 +64,8 @@ pub struct Root {
     scheduler: Arc<ReconcileScheduler>,
     heartbeat_queue: Arc<HeartbeatQueue>,
     ongoing_stats: Arc<OngoingStats>,
+    approximate_stats: Arc<ApproximateStats>,
+    quota_states: Arc<QuotaStates>,
     jobs: Arc<Jobs>,
 }
 
@@ -108,7 +111,11 @@ impl Root {
         let liveness = Arc::new(liveness::Liveness::new(Duration::from_secs(
             cfg.root.liveness_threshold_sec,
         )));
-        let info = Arc::new(SysAllocSource::new(shared.clone(), liveness.to_owned()));
+        let info = Arc::new(SysAllocSource::new(
+            shared.clone(),
+            liveness.to_owned(),
+            cfg.root.read_replica_label.clone(),
+        ));
         let alloc = Arc::new(allocator::Allocator::new(
             info,
             ongoing_stats.clone(),
@@ -137,6 +144,8 @@ impl Root {
             scheduler,
             heartbeat_queue,
             ongoing_stats,
+            approximate_stats: Default::default(),
+            quota_states: Default::default(),
             jobs,
         }
     }
@@ -283,6 +292,8 @@ impl Root {
         self::metrics::LEADER_STATE_INFO.set(1);
 
         self.ongoing_stats.reset();
+        self.approximate_stats.reset();
+        self.quota_states.reset();
         self.heartbeat_queue.enable(true).await;
         self.jobs.on_step_leader().await?;
 
@@ -316,6 +327,8 @@ impl Root {
         self.heartbeat_queue.enable(false).await;
         self.jobs.on_drop_leader();
         self.ongoing_stats.reset();
+        self.approximate_stats.reset();
+        self.quota_states.reset();
         {
             self.liveness.reset();
 
@@ -346,6 +359,89 @@ impl Root {
         Ok(())
     }
 
+    /// Stop the replication to the standby cluster and wait until the writes recorded by all nodes
+    /// are shipped, so that the standby cluster could be promoted to serve the writes of users.
+    ///
+    /// The stopped state is persisted before waiting, so the failover is retried by calling again
+    /// if the pending writes are not drained before the timeout. The users should stop writing to
+    /// this cluster before failover, otherwise the later writes are not replicated.
+    pub async fn failover_replication(&self, timeout: Duration) -> Result<()> {
+        let schema = self.schema()?;
+        schema.stop_replication().await?;
+        info!("replication is stopped by failover, wait pending writes to be shipped");
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let pending_writes = self.replication_pending_writes(&schema).await?;
+            if pending_writes == 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::DeadlineExceeded(format!(
+                    "{pending_writes} writes are waiting to be shipped"
+                )));
+            }
+            runtime::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Sync the stopped replication state to all nodes and sum the writes waiting to be shipped.
+    /// An unreachable node is counted as one pending write, since its writes are unknown.
+    async fn replication_pending_writes(&self, schema: &Schema) -> Result<u64> {
+        let req = HeartbeatRequest {
+            timestamp: 0,
+            piggybacks: vec![PiggybackRequest {
+                info: Some(piggyback_request::Info::SyncReplicationState(
+                    SyncReplicationStateRequest { stopped: true },
+                )),
+            }],
+        };
+        let mut pending_writes = 0;
+        for node in schema.list_node().await? {
+            if node.status == NodeStatus::Decommissioned as i32 {
+                continue;
+            }
+            let client = self
+                .shared
+                .transport_manager
+                .get_node_client(node.addr.clone())?;
+            match client.root_heartbeat(req.clone()).await {
+                Ok(resp) => {
+                    for piggyback in resp.piggybacks {
+                        if let Some(piggyback_response::Info::SyncReplicationState(resp)) =
+                            piggyback.info
+                        {
+                            pending_writes += resp.pending_writes;
+                        }
+                    }
+                }
+                Err(err) => {
+                    warn!(node = node.id, err = ?err, "sync replication state");
+                    pending_writes += 1;
+                }
+            }
+        }
+        Ok(pending_writes)
+    }
+
+    /// Replace the labels of a node, a node labelled with `RootConfig::read_replica_label` only
+    /// accepts the read learners.
+    pub async fn label_node(&self, node_id: u64, labels: Vec<String>) -> Result<()> {
+        let schema = self.schema()?;
+        let mut node_desc = schema
+            .get_node(node_id)
+            .await?
+            .ok_or_else(|| crate::Error::InvalidArgument("node not found".into()))?;
+        node_desc.labels = labels;
+        schema.update_node(node_desc.to_owned()).await?; // TODO: cas
+        self.watcher_hub()
+            .notify_updates(vec![UpdateEvent {
+                event: Some(update_event::Event::Node(node_desc)),
+            }])
+            .await;
+        Ok(())
+    }
+
     pub async fn uncordon_node(&self, node_id: u64) -> Result<()> {
         let schema = self.schema()?;
         let mut node_desc = schema
@@ -354,10 +450,7 @@ impl Root {
             .ok_or_else(|| crate::Error::InvalidArgument("node not found".into()))?;
 
         let current_status = NodeStatus::from_i32(node_desc.status).unwrap();
-        if !matches!(
-            current_status,
-            NodeStatus::Cordoned | NodeStatus::Drained | NodeStatus::Decommissioned
-        ) {
+        if !matches!(current_status, NodeStatus::Cordoned | NodeStatus::Drained) {
             return Err(crate::Error::InvalidArgument(
                 "node status unsupport uncordon".into(),
             ));
@@ -409,6 +502,181 @@ impl Root {
         Ok(())
     }
 
+    /// Decommission a node: stop placing new replicas on it and move all replicas and leaders
+    /// out of it, the node will be marked as decommissioned once all groups become healthy.
+    pub async fn begin_decommission(&self, node_id: u64) -> Result<()> {
+        let schema = self.schema()?;
+
+        if self.current_node_id() == node_id {
+            info!("try to decommission root leader and move root leadership out first");
+            self.scheduler
+                .setup_task(ReconcileTask {
+                    task: Some(reconcile_task::Task::ShedRoot(ShedRootLeaderTask {
+                        node_id,
+                    })),
+                })
+                .await;
+            return Err(crate::Error::InvalidArgument(
+                "node is root leader, try again later".into(),
+            ));
+        }
+
+        let mut node_desc = schema
+            .get_node(node_id)
+            .await?
+            .ok_or_else(|| crate::Error::InvalidArgument("node not found".into()))?;
+
+        let current_status = NodeStatus::from_i32(node_desc.status).unwrap();
+        if matches!(
+            current_status,
+            NodeStatus::Decommissioning | NodeStatus::Decommissioned
+        ) {
+            return Err(crate::Error::InvalidArgument(
+                "node is already decommissioned".into(),
+            ));
+        }
+
+        let candidates = schema
+            .list_node()
+            .await?
+            .into_iter()
+            .filter(|n| n.id != node_id && n.status == NodeStatus::Active as i32)
+            .count();
+        if candidates < self.cfg.replicas_per_group {
+            return Err(crate::Error::InvalidArgument(
+                "no enough active nodes to hold the replicas of decommissioning node".into(),
+            ));
+        }
+
+        node_desc.status = NodeStatus::Decommissioning as i32;
+        schema.update_node(node_desc).await?; // TODO: cas
+
+        self.scheduler
+            .setup_task(ReconcileTask {
+                task: Some(reconcile_task::Task::DecommissionNode(
+                    DecommissionNodeTask {
+                        node_id,
+                        groups: vec![],
+                    },
+                )),
+            })
+            .await;
+        info!(node = node_id, "begin decommission node");
+
+        Ok(())
+    }
+
+    /// Remove a node from the cluster, the node must be drained and hold no replicas.
+    pub async fn remove_node(&self, node_id: u64) -> Result<()> {
+        let schema = self.schema()?;
+        let node_desc = schema
+            .get_node(node_id)
+            .await?
+            .ok_or_else(|| crate::Error::InvalidArgument("node not found".into()))?;
+
+        let current_status = NodeStatus::from_i32(node_desc.status).unwrap();
+        if !matches!(
+            current_status,
+            NodeStatus::Drained | NodeStatus::Decommissioned
+        ) {
+            return Err(crate::Error::InvalidArgument(
+                "only drained or decommissioned node can be removed".into(),
+            ));
+        }
+
+        let groups = schema.list_group().await?;
+        if groups
+            .iter()
+            .any(|g| g.replicas.iter().any(|r| r.node_id == node_id))
+        {
+            return Err(crate::Error::InvalidArgument(
+                "node still has replicas".into(),
+            ));
+        }
+
+        schema.delete_node(node_id).await?;
+        Ok(())
+    }
+
+    /// Transfer the leadership of a group to the target replica. The transferring is executed
+    /// by the reconcile scheduler in background.
+    pub async fn transfer_leader(&self, group_id: u64, replica_id: u64) -> Result<()> {
+        let schema = self.schema()?;
+        let group = schema
+            .get_group(group_id)
+            .await?
+            .ok_or_else(|| crate::Error::InvalidArgument("group not found".into()))?;
+        let target = group
+            .replicas
+            .iter()
+            .find(|r| r.id == replica_id)
+            .ok_or_else(|| crate::Error::InvalidArgument("replica not found".into()))?;
+        if target.role != ReplicaRole::Voter as i32 {
+            return Err(crate::Error::InvalidArgument(
+                "only voter could be leader".into(),
+            ));
+        }
+
+        let leader = schema
+            .group_replica_states(group_id)
+            .await?
+            .into_iter()
+            .find(|s| s.role == RaftRole::Leader as i32)
+            .ok_or_else(|| crate::Error::InvalidArgument("group leader not found".into()))?;
+        if leader.replica_id == replica_id {
+            return Ok(());
+        }
+
+        self.scheduler
+            .setup_task(ReconcileTask {
+                task: Some(reconcile_task::Task::TransferGroupLeader(
+                    TransferGroupLeaderTask {
+                        group: group_id,
+                        target_replica: replica_id,
+                        src_node: leader.node_id,
+                        dest_node: target.node_id,
+                    },
+                )),
+            })
+            .await;
+        Ok(())
+    }
+
+    /// Move a shard to the target group. The migration is executed by the reconcile scheduler in
+    /// background.
+    pub async fn move_shard(&self, shard_id: u64, dest_group: u64) -> Result<()> {
+        let schema = self.schema()?;
+        let groups = schema.list_group().await?;
+        let src_group = groups
+            .iter()
+            .find(|g| g.shards.iter().any(|s| s.id == shard_id))
+            .ok_or_else(|| crate::Error::InvalidArgument("shard not found".into()))?;
+        if !groups.iter().any(|g| g.id == dest_group) {
+            return Err(crate::Error::InvalidArgument("group not found".into()));
+        }
+        if src_group.id == ROOT_GROUP_ID || dest_group == ROOT_GROUP_ID {
+            return Err(crate::Error::InvalidArgument(
+                "shards of root group can't be moved".into(),
+            ));
+        }
+        if src_group.id == dest_group {
+            return Err(crate::Error::InvalidArgument(
+                "shard already in target group".into(),
+            ));
+        }
+
+        self.scheduler
+            .setup_task(ReconcileTask {
+                task: Some(reconcile_task::Task::MigrateShard(MigrateShardTask {
+                    shard: shard_id,
+                    src_group: src_group.id,
+                    dest_group,
+                })),
+            })
+            .await;
+        Ok(())
+    }
+
     pub async fn node_status(&self, node_id: u64) -> Result<NodeStatus> {
         let schema = self.schema()?;
         let node_desc = schema
@@ -430,10 +698,14 @@ impl Root {
         None
     }
 
+    pub fn cancel_job(&self, job_id: u64) -> Result<()> {
+        self.jobs.cancel(job_id)
+    }
+
     pub async fn job_state(&self) -> Result<String> {
         use serde_json::json;
         fn to_json(j: &BackgroundJob) -> serde_json::Value {
-            match j.job.as_ref().unwrap() {
+            let mut value = match j.job.as_ref().unwrap() {
                 Job::CreateCollection(c) => {
                     let state = format!(
                         "{:?}",
@@ -479,7 +751,26 @@ impl Root {
                         "database": p.database_id,
                     })
                 }
-            }
+                Job::ReshardCollection(r) => {
+                    let status = format!(
+                        "{:?}",
+                        ReshardCollectionJobStatus::from_i32(r.status).unwrap()
+                    );
+                    json!({
+                        "type": "reshard collection",
+                        "name": r.collection_name,
+                        "collection": r.collection_id,
+                        "status": status,
+                        "target_slots": r.target_slots,
+                        "wait_create": r.wait_create.len(),
+                        "wait_copy": r.wait_copy.len(),
+                        "wait_cleanup": r.wait_cleanup.len(),
+                        "remark": r.remark,
+                    })
+                }
+            };
+            value["id"] = j.id.into();
+            value
         }
 
         let schema = self.schema()?;
@@ -549,17 +840,28 @@ impl Root {
                         .filter(|c| c.db == d.id)
                         .map(|c| {
                             let mode = match c.partition.as_ref().unwrap() {
-                                co_desc::Partition::Hash(co_desc::HashPartition { slots }) => {
-                                    format!("hash({slots})")
+                                co_desc::Partition::Hash(co_desc::HashPartition {
+                                    slots,
+                                    target_slots,
+                                    ..
+                                }) => {
+                                    if *target_slots != 0 {
+                                        format!("hash({slots} -> {target_slots})")
+                                    } else {
+                                        format!("hash({slots})")
+                                    }
                                 }
                                 co_desc::Partition::Range(co_desc::RangePartition {}) => {
                                     "range".to_owned()
                                 }
                             };
+                            let stats = self.collection_stats(c.id);
                             Collection {
                                 id: c.id,
                                 name: c.name.to_owned(),
                                 mode,
+                                approximate_keys: stats.approximate_keys,
+                                approximate_size: stats.approximate_size,
                             }
                         })
                         .collect::<Vec<_>>(),
@@ -592,6 +894,7 @@ impl Root {
                                 shard_desc::Partition::Hash(shard_desc::HashPartition {
                                     slot_id,
                                     slots,
+                                    ..
                                 }) => {
                                     format!("hash: {slot_id} of {slots}")
                                 }
@@ -669,6 +972,42 @@ impl Root {
         Ok(())
     }
 
+    pub async fn update_database(
+        &self,
+        name: &str,
+        action: Option<db_update_req::Action>,
+    ) -> Result<DatabaseDesc> {
+        let schema = self.schema()?;
+        let mut db = schema
+            .get_database(name)
+            .await?
+            .ok_or_else(|| Error::DatabaseNotFound(name.to_owned()))?;
+        match action {
+            Some(db_update_req::Action::SetQuota(set_quota)) => {
+                if db.id == SYSTEM_DATABASE_ID {
+                    return Err(Error::InvalidArgument(
+                        "unsupported quota of system database".into(),
+                    ));
+                }
+                db.quota = set_quota.quota;
+            }
+            None => {
+                return Err(Error::InvalidArgument(
+                    "UpdateDatabaseRequest::action is required".into(),
+                ))
+            }
+        }
+
+        schema.update_database(db.to_owned()).await?;
+        self.watcher_hub()
+            .notify_updates(vec![UpdateEvent {
+                event: Some(update_event::Event::Database(db.to_owned())),
+            }])
+            .await;
+        info!(database = name, quota = ?db.quota, "update database quota");
+        Ok(db)
+    }
+
     pub async fn create_collection(
         &self,
         name: String,
@@ -681,13 +1020,32 @@ impl Root {
             .await?
             .ok_or_else(|| Error::DatabaseNotFound(database.to_owned()))?;
 
+        if let Some(co_req::Partition::Hash(hash)) = partition.as_ref() {
+            if hash.slots == 0 {
+                return Err(Error::InvalidArgument("slots should be positive".into()));
+            }
+            if let Some(algorithm) = hash.algorithm {
+                if HashAlgorithm::from_i32(algorithm).is_none() {
+                    return Err(Error::InvalidArgument(format!(
+                        "unknown hash algorithm {algorithm}"
+                    )));
+                }
+            }
+        }
+
         let collection = schema
             .prepare_create_collection(CollectionDesc {
                 name: name.to_owned(),
                 db: db.id,
                 partition: partition.map(|p| match p {
                     co_req::Partition::Hash(hash) => {
-                        co_desc::Partition::Hash(co_desc::HashPartition { slots: hash.slots })
+                        co_desc::Partition::Hash(co_desc::HashPartition {
+                            slots: hash.slots,
+                            target_slots: 0,
+                            algorithm: hash
+                                .algorithm
+                                .unwrap_or(engula_api::shard::DEFAULT_HASH_ALGORITHM as i32),
+                        })
                     }
                     co_req::Partition::Range(_) => {
                         co_desc::Partition::Range(co_desc::RangePartition {})
@@ -721,6 +1079,8 @@ impl Root {
                 .as_ref()
                 .unwrap_or(&co_desc::Partition::Hash(co_desc::HashPartition {
                     slots: 1,
+                    target_slots: 0,
+                    algorithm: engula_api::shard::DEFAULT_HASH_ALGORITHM as i32,
                 }));
 
             let partitions = match partition {
@@ -730,6 +1090,8 @@ impl Root {
                         ps.push(shard_desc::Partition::Hash(shard_desc::HashPartition {
                             slot_id: id,
                             slots: hash_partition.slots.to_owned(),
+                            algorithm: hash_partition.algorithm,
+                            target_slots: 0,
                         }));
                     }
                     ps
@@ -821,6 +1183,159 @@ impl Root {
         Ok(())
     }
 
+    pub async fn update_collection(
+        &self,
+        name: &str,
+        database: &DatabaseDesc,
+        action: Option<co_update_req::Action>,
+    ) -> Result<CollectionDesc> {
+        match action {
+            Some(co_update_req::Action::Reshard(reshard)) => {
+                self.reshard_collection(name, database, reshard.slots).await
+            }
+            Some(co_update_req::Action::ReadReplicas(read_replicas)) => {
+                self.set_read_replicas(name, database, read_replicas.replicas)
+                    .await
+            }
+            None => Err(Error::InvalidArgument(
+                "UpdateCollectionRequest::action is required".into(),
+            )),
+        }
+    }
+
+    /// Reshard a hash collection to the specified number of slots. The shards of target slots are
+    /// created and filled in background, see `Jobs::handle_reshard_collection` for details.
+    async fn reshard_collection(
+        &self,
+        name: &str,
+        database: &DatabaseDesc,
+        slots: u32,
+    ) -> Result<CollectionDesc> {
+        if slots == 0 {
+            return Err(Error::InvalidArgument("slots should be positive".into()));
+        }
+
+        let schema = self.schema()?;
+        let db = self
+            .get_database(&database.name)
+            .await?
+            .ok_or_else(|| Error::DatabaseNotFound(database.name.clone()))?;
+        let collection = schema
+            .get_collection(db.id, name)
+            .await?
+            .ok_or_else(|| Error::InvalidArgument(format!("collection {name} not found")))?;
+        if collection.id < USER_COLLECTION_INIT_ID {
+            return Err(Error::InvalidArgument(
+                "unsupported reshard system collection".into(),
+            ));
+        }
+        let hash = match collection.partition.as_ref() {
+            Some(co_desc::Partition::Hash(hash)) => hash.to_owned(),
+            _ => {
+                return Err(Error::InvalidArgument(
+                    "only hash collection could be resharded".into(),
+                ))
+            }
+        };
+        if hash.target_slots != 0 {
+            return Err(Error::AlreadyExists(format!(
+                "resharding of collection {name}"
+            )));
+        }
+        if hash.slots == slots {
+            return Ok(collection);
+        }
+
+        let mut wait_create = Vec::with_capacity(slots as usize);
+        for slot_id in 0..slots {
+            let id = schema.next_shard_id().await?;
+            wait_create.push(ShardDesc {
+                id,
+                collection_id: collection.id,
+                partition: Some(shard_desc::Partition::Hash(shard_desc::HashPartition {
+                    slot_id,
+                    slots,
+                    algorithm: hash.algorithm,
+                    target_slots: 0,
+                })),
+            });
+        }
+        let wait_copy = schema
+            .get_collection_shards(collection.id)
+            .await?
+            .into_iter()
+            .map(|(_, shard)| shard)
+            .collect::<Vec<_>>();
+
+        self.jobs
+            .submit(
+                BackgroundJob {
+                    job: Some(Job::ReshardCollection(ReshardCollectionJob {
+                        database: db.id,
+                        collection_name: collection.name.to_owned(),
+                        collection_id: collection.id,
+                        target_slots: slots,
+                        algorithm: hash.algorithm,
+                        wait_create,
+                        wait_copy,
+                        status: ReshardCollectionJobStatus::ReshardCollectionCreating as i32,
+                        created_time: format!("{:?}", Instant::now()),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                },
+                false,
+            )
+            .await?;
+        trace!(
+            collection = name,
+            slots,
+            "reshard collection, database {}",
+            database.name
+        );
+        Ok(collection)
+    }
+
+    /// Set the number of read learners of each group serving the collection. The read learners
+    /// are added and removed by the group scheduler, see `ReadReplicaGroup` for details.
+    async fn set_read_replicas(
+        &self,
+        name: &str,
+        database: &DatabaseDesc,
+        replicas: u32,
+    ) -> Result<CollectionDesc> {
+        let schema = self.schema()?;
+        let db = self
+            .get_database(&database.name)
+            .await?
+            .ok_or_else(|| Error::DatabaseNotFound(database.name.clone()))?;
+        let mut collection = schema
+            .get_collection(db.id, name)
+            .await?
+            .ok_or_else(|| Error::InvalidArgument(format!("collection {name} not found")))?;
+        if collection.id < USER_COLLECTION_INIT_ID {
+            return Err(Error::InvalidArgument(
+                "unsupported read replicas of system collection".into(),
+            ));
+        }
+        if collection.read_replicas == replicas {
+            return Ok(collection);
+        }
+
+        collection.read_replicas = replicas;
+        schema.update_collection(collection.to_owned()).await?;
+        self.watcher_hub()
+            .notify_updates(vec![UpdateEvent {
+                event: Some(update_event::Event::Collection(collection.to_owned())),
+            }])
+            .await;
+        info!(
+            collection = name,
+            replicas, "set read replicas, database {}", database.name
+        );
+        Ok(collection)
+    }
+
     pub async fn list_database(&self) -> Result<Vec<DatabaseDesc>> {
         self.schema()?.list_database().await
     }
@@ -856,6 +1371,13 @@ impl Root {
         self.schema()?.get_collection(db.id, name).await
     }
 
+    /// Return the approximate statistics of a collection, aggregated from the shard stats reported
+    /// by group leaders.
+    #[inline]
+    pub fn collection_stats(&self, collection_id: u64) -> CollectionStats {
+        self.approximate_stats.collection_stats(collection_id)
+    }
+
     pub async fn watch(&self, cur_groups: HashMap<u64, u64>) -> Result<Watcher> {
         let schema = self.schema()?;
 
@@ -875,6 +1397,21 @@ impl Root {
         capacity: NodeCapacity,
     ) -> Result<(Vec<u8>, NodeDesc, RootDesc)> {
         let schema = self.schema()?;
+        // A node only joins the cluster once, it reuses the persisted node ident after restarting.
+        // So a joining node is always a new node and a new node id is assigned, even if it reuses
+        // the address of a decommissioned node. The decommissioned node id is kept as a tombstone,
+        // see `Root::report`.
+        if schema
+            .list_node()
+            .await?
+            .iter()
+            .any(|n| n.addr == addr && n.status == NodeStatus::Decommissioning as i32)
+        {
+            warn!(addr = ?addr, "reject node join cluster, the address is decommissioning");
+            return Err(crate::Error::InvalidArgument(
+                "the address is decommissioning".into(),
+            ));
+        }
         let node = schema
             .add_node(NodeDesc {
                 addr,
@@ -908,6 +1445,25 @@ impl Root {
 
         let ongoing_stats = self.ongoing_stats.clone();
         let schema = self.schema()?;
+
+        // The decommissioned node might be restarted with its node ident, its reports are
+        // rejected since it isn't a member of the cluster anymore.
+        let mut reported_nodes = HashSet::new();
+        for state in updates.iter().filter_map(|u| u.replica_state.as_ref()) {
+            if !reported_nodes.insert(state.node_id) {
+                continue;
+            }
+            if let Some(node) = schema.get_node(state.node_id).await? {
+                if node.status == NodeStatus::Decommissioned as i32 {
+                    warn!(node = node.id, "reject report from decommissioned node");
+                    return Err(Error::InvalidArgument(format!(
+                        "node {} has been decommissioned",
+                        node.id
+                    )));
+                }
+            }
+        }
+
         let mut update_events = Vec::new();
         let mut changed_group_states = Vec::new();
         for u in updates {
@@ -996,6 +1552,7 @@ impl Root {
         group_id: u64,
         epoch: u64,
         requested_cnt: u64,
+        read_learner: bool,
     ) -> Result<Vec<ReplicaDesc>> {
         let schema = self.schema()?;
         let group_desc = schema
@@ -1019,25 +1576,33 @@ impl Root {
             "attempt allocate {requested_cnt} replicas for exist group"
         );
 
-        let nodes = self
-            .alloc
-            .allocate_group_replica(
-                existing_replicas.into_iter().collect(),
-                requested_cnt as usize,
-            )
-            .await?;
+        let existing_replicas = existing_replicas.into_iter().collect();
+        let nodes = if read_learner {
+            self.alloc
+                .allocate_read_replica(existing_replicas, requested_cnt as usize)
+                .await?
+        } else {
+            self.alloc
+                .allocate_group_replica(existing_replicas, requested_cnt as usize)
+                .await?
+        };
         if nodes.len() != requested_cnt as usize {
             warn!("non enough nodes to allocate replicas, exist nodes: {}, requested: {requested_cnt}", nodes.len());
             return Err(Error::ResourceExhausted("no enough nodes".to_owned()));
         }
 
+        let role = if read_learner {
+            ReplicaRole::ReadLearner
+        } else {
+            ReplicaRole::Voter
+        };
         let mut replicas = Vec::with_capacity(nodes.len());
         for n in &nodes {
             let replica_id = schema.next_replica_id().await?;
             replicas.push(ReplicaDesc {
                 id: replica_id,
                 node_id: n.id,
-                role: ReplicaRole::Voter.into(),
+                role: role.into(),
             });
         }
         info!(
@@ -1250,6 +1815,66 @@ impl OngoingStats {
     }
 }
 
+/// The approximate statistics of shards, which are reported by the group leaders through
+/// heartbeats and only kept in the memory of root leader.
+#[derive(Default)]
+pub struct ApproximateStats {
+    shards: Mutex<HashMap<u64 /* shard */, (u64 /* group */, ShardStats)>>,
+}
+
+impl ApproximateStats {
+    fn update_group_stats(&self, group_stats: &[GroupStats]) {
+        let mut shards = self.shards.lock().unwrap();
+        for gs in group_stats {
+            // The shards not reported have been moved out of the group.
+            shards.retain(|_, (group_id, _)| *group_id != gs.group_id);
+            for stats in &gs.shard_stats {
+                shards.insert(stats.shard_id, (gs.group_id, stats.to_owned()));
+            }
+        }
+    }
+
+    fn collection_stats(&self, collection_id: u64) -> CollectionStats {
+        let shards = self.shards.lock().unwrap();
+        shards
+            .values()
+            .filter(|(_, stats)| stats.collection_id == collection_id)
+            .fold(CollectionStats::default(), |mut acc, (_, stats)| {
+                acc.approximate_keys += stats.approximate_keys;
+                acc.approximate_size += stats.approximate_size;
+                acc
+            })
+    }
+
+    fn reset(&self) {
+        self.shards.lock().unwrap().clear();
+    }
+}
+
+/// The quota states of databases derived from the schema, they are cached until the schema is
+/// changed, see `WatchHub::version`.
+#[derive(Default)]
+pub struct QuotaStates {
+    cached: Mutex<Option<(u64 /* version */, Vec<DatabaseQuotaState>)>>,
+}
+
+impl QuotaStates {
+    fn get(&self, version: u64) -> Option<Vec<DatabaseQuotaState>> {
+        match self.cached.lock().unwrap().as_ref() {
+            Some((cached_version, states)) if *cached_version == version => Some(states.clone()),
+            _ => None,
+        }
+    }
+
+    fn set(&self, version: u64, states: Vec<DatabaseQuotaState>) {
+        *self.cached.lock().unwrap() = Some((version, states));
+    }
+
+    fn reset(&self) {
+        *self.cached.lock().unwrap() = None;
+    }
+}
+
 impl SchedStats {
     fn replace_state(&mut self, updates: &[ScheduleState]) -> bool {
         let mut updated = false;
@@ -1420,6 +2045,7 @@ mod root_test {
             let _create_db1_event = Some(update_event::Event::Database(DatabaseDesc {
                 id: 1,
                 name: "db1".into(),
+                quota: None,
             }));
             let mut w = {
                 let (w, mut initializer) = hub.create_watcher().await;
@@ -1442,6 +2068,7 @@ mod root_test {
             let _create_db2_event = Some(update_event::Event::Database(DatabaseDesc {
                 id: 2,
                 name: "db2".into(),
+                quota: None,
             }));
             hub.notify_updates(vec![UpdateEvent {
                 event: _create_db2_event,
@@ -1479,6 +2106,8 @@ pub mod diagnosis {
         pub id: u64,
         pub mode: String,
         pub name: String,
+        pub approximate_keys: u64,
+        pub approximate_size: u64,
     }
 
     #[derive(Serialize, Deserialize)]
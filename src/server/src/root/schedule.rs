@@ -276,6 +276,12 @@ impl ReconcileScheduler {
                     .shed_root_leader
                     .start_timer()
             }
+            Task::DecommissionNode(_) => {
+                metrics::RECONCILE_HANDLE_TASK_TOTAL.decommission_node.inc();
+                metrics::RECONCILE_HANDLE_TASK_DURATION_SECONDS
+                    .decommission_node
+                    .start_timer()
+            }
         }
     }
 
@@ -290,6 +296,9 @@ impl ReconcileScheduler {
             }
             Task::ShedLeader(_) => metrics::RECONCILE_RETRY_TASK_TOTAL.shed_group_leaders.inc(),
             Task::ShedRoot(_) => metrics::RECONCILE_RETRY_TASK_TOTAL.shed_root_leader.inc(),
+            Task::DecommissionNode(_) => {
+                metrics::RECONCILE_RETRY_TASK_TOTAL.decommission_node.inc()
+            }
         }
     }
 }
@@ -331,6 +340,9 @@ impl ScheduleContext {
             }
             Task::ShedLeader(shed_leader) => self.handle_shed_leader(shed_leader).await,
             Task::ShedRoot(shed_root) => self.handle_shed_root(shed_root).await,
+            Task::DecommissionNode(decommission) => {
+                self.handle_decommission_node(decommission).await
+            }
         }
     }
 
@@ -385,6 +397,17 @@ impl ScheduleContext {
             return Ok((true, false));
         }
 
+        if src_replica.unwrap().role == ReplicaRole::ReadLearner as i32 {
+            // The read learners are replaced by the group scheduler on the nodes labelled for
+            // read replicas, see `ReadReplicaGroup` for details.
+            warn!(
+                group = group,
+                replica = task.src_replica,
+                "source replica is a read learner, abort reallocate replica task."
+            );
+            return Ok((true, false));
+        }
+
         info!(
             group = group,
             src_node = task.src_node,
@@ -392,13 +415,19 @@ impl ScheduleContext {
             "start move replica"
         );
         let next_replica = schema.next_replica_id().await?;
+        // A witness is moved as a witness, which holds no data.
+        let role = if src_replica.unwrap().role == ReplicaRole::Witness as i32 {
+            ReplicaRole::Witness
+        } else {
+            ReplicaRole::Voter
+        };
         match self
             .try_move_replica(
                 group,
                 ReplicaDesc {
                     id: next_replica,
                     node_id: task.dest_node.as_ref().unwrap().id,
-                    role: ReplicaRole::Voter as i32,
+                    role: role as i32,
                 },
                 src_replica.unwrap().to_owned(),
             )
@@ -608,6 +637,98 @@ impl ScheduleContext {
 }
 
 impl ScheduleContext {
+    /// Move all replicas out of the decommissioning node, the leaders are shed before moving.
+    /// The node is marked as decommissioned once it has no replicas and all groups are healthy.
+    async fn handle_decommission_node(
+        &self,
+        task: &mut DecommissionNodeTask,
+    ) -> Result<(
+        bool, /* ack current */
+        bool, /* immediately step next tick */
+    )> {
+        let node = task.node_id;
+        let schema = self.shared.schema()?;
+        match schema.get_node(node).await? {
+            Some(desc) if desc.status == NodeStatus::Decommissioning as i32 => {}
+            _ => {
+                warn!(node = node, "decommission node task cancelled");
+                return Ok((true, false));
+            }
+        }
+
+        let groups = schema.list_group().await?;
+        let mut moving = false;
+        for group in &groups {
+            let Some(replica) = group.replicas.iter().find(|r| r.node_id == node) else {
+                continue;
+            };
+            moving = true;
+            if !task.groups.contains(&group.id) {
+                task.groups.push(group.id);
+            }
+            if is_in_joint(group) {
+                // The previous moving is still in progress.
+                continue;
+            }
+
+            let existing_nodes = group.replicas.iter().map(|r| r.node_id).collect::<Vec<_>>();
+            let dest_node = match self.alloc.allocate_group_replica(existing_nodes, 1).await {
+                Ok(nodes) if !nodes.is_empty() => nodes[0].to_owned(),
+                Ok(_) | Err(_) => {
+                    warn!(
+                        node = node,
+                        group = group.id,
+                        "decommission node fail due to no suitable target node, retry later"
+                    );
+                    continue;
+                }
+            };
+            let mut reallocate = ReallocateReplicaTask {
+                group: group.id,
+                src_node: node,
+                src_replica: replica.id,
+                dest_node: Some(dest_node),
+                dest_replica: None,
+            };
+            if let Err(err) = self.handle_reallocate_replica(&mut reallocate).await {
+                warn!(node = node, group = group.id, err = ?err, "move replica out of decommissioning node fail, retry later");
+            }
+        }
+        if moving {
+            return Ok((false, false));
+        }
+
+        // Wait until the groups moved out finish the membership changes and elect leaders, the
+        // unhealthy groups which have no relation with the node are ignored.
+        let states = schema.list_replica_state().await?;
+        let unhealthy = groups
+            .iter()
+            .filter(|g| task.groups.contains(&g.id))
+            .find(|g| {
+                let has_leader = states
+                    .iter()
+                    .any(|s| s.group_id == g.id && s.role == RaftRole::Leader as i32);
+                is_in_joint(g) || !has_leader
+            });
+        if let Some(group) = unhealthy {
+            info!(
+                node = node,
+                group = group.id,
+                "decommission node wait group become healthy"
+            );
+            return Ok((false, false));
+        }
+
+        if let Some(mut desc) = schema.get_node(node).await? {
+            if desc.status == NodeStatus::Decommissioning as i32 {
+                desc.status = NodeStatus::Decommissioned as i32;
+                schema.update_node(desc).await?; // TODO: cas
+                info!(node = node, "node is decommissioned");
+            }
+        }
+        Ok((true, false))
+    }
+
     async fn get_group_leader(&self, group_id: u64) -> Result<Option<GroupDesc>> {
         let schema = self.shared.schema()?;
         let group = schema.get_group(group_id).await?;
@@ -725,3 +846,9 @@ impl ScheduleContext {
             .map(|(_, r)| r.node_id))
     }
 }
+
+fn is_in_joint(group: &GroupDesc) -> bool {
+    group.replicas.iter().any(|r| {
+        r.role == ReplicaRole::IncomingVoter as i32 || r.role == ReplicaRole::DemotingVoter as i32
+    })
+}
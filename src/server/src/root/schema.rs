@@ -75,6 +75,7 @@ const META_NODE_ID_KEY: &str = "node_id";
 const META_REPLICA_ID_KEY: &str = "replica_id";
 const META_SHARD_ID_KEY: &str = "shard_id";
 const META_JOB_ID_KEY: &str = "job_id";
+const META_REPLICATION_STOPPED_KEY: &str = "replication_stopped";
 
 lazy_static::lazy_static! {
     pub static ref SYSTEM_COLLECTION_SHARD: BTreeMap<u64, u64> = BTreeMap::from([
@@ -118,6 +119,24 @@ impl Schema {
         Ok(None)
     }
 
+    /// Return whether the replication to the standby cluster is stopped by failover.
+    pub async fn is_replication_stopped(&self) -> Result<bool> {
+        let stopped = self
+            .get_meta(META_REPLICATION_STOPPED_KEY.as_bytes())
+            .await?;
+        Ok(stopped.is_some())
+    }
+
+    /// Stop the replication to the standby cluster, it could not be restarted.
+    pub async fn stop_replication(&self) -> Result<()> {
+        self.batch_write(
+            PutBatchBuilder::default()
+                .put_meta(META_REPLICATION_STOPPED_KEY.as_bytes().to_vec(), vec![1])
+                .build(),
+        )
+        .await
+    }
+
     pub async fn create_database(&self, desc: DatabaseDesc) -> Result<DatabaseDesc> {
         if self.get_database(&desc.name).await?.is_some() {
             return Err(Error::AlreadyExists(format!(
@@ -149,8 +168,9 @@ impl Schema {
         Ok(Some(desc))
     }
 
-    pub async fn update_database(&self, _desc: DatabaseDesc) -> Result<()> {
-        todo!()
+    pub async fn update_database(&self, desc: DatabaseDesc) -> Result<()> {
+        self.batch_write(PutBatchBuilder::default().put_database(desc).build())
+            .await
     }
 
     pub async fn delete_database(&self, db: &DatabaseDesc) -> Result<u64> {
@@ -228,8 +248,9 @@ impl Schema {
         Ok(group_shards)
     }
 
-    pub async fn update_collection(&self, _desc: CollectionDesc) -> Result<()> {
-        todo!()
+    pub async fn update_collection(&self, desc: CollectionDesc) -> Result<()> {
+        self.batch_write(PutBatchBuilder::default().put_collection(desc).build())
+            .await
     }
 
     pub async fn delete_collection(&self, collection: CollectionDesc) -> Result<()> {
@@ -710,6 +731,7 @@ impl Schema {
         batch.put_database(DatabaseDesc {
             id: SYSTEM_DATABASE_ID.to_owned(),
             name: SYSTEM_DATABASE_NAME.to_owned(),
+            quota: None,
         });
 
         batch.put_node(NodeDesc {
@@ -721,6 +743,7 @@ impl Schema {
                 leader_count: 0,
             }),
             status: NodeStatus::Active as i32,
+            labels: vec![],
         });
 
         batch.put_group(GroupDesc {
@@ -813,6 +836,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(self_collection);
 
@@ -823,6 +847,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(db_collection);
 
@@ -833,6 +858,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(meta_collection);
 
@@ -843,6 +869,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(node_collection);
 
@@ -853,6 +880,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(group_collection);
 
@@ -863,6 +891,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(replica_state_collection);
 
@@ -873,6 +902,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(job_collection);
 
@@ -883,6 +913,7 @@ impl Schema {
             partition: Some(collection_desc::Partition::Range(
                 collection_desc::RangePartition {},
             )),
+            read_replicas: 0,
         };
         batch.put_collection(job_history_collection);
     }
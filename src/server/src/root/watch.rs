@@ -14,7 +14,10 @@
 
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     task::{Poll, Waker},
     vec,
 };
@@ -31,6 +34,9 @@ use crate::{Error, Result};
 #[derive(Default)]
 pub struct WatchHub {
     inner: Arc<RwLock<WatchHubInner>>,
+    /// Increased once any events are notified, so the caches derived from the schema could detect
+    /// the changes.
+    version: AtomicU64,
 }
 
 #[derive(Default)]
@@ -72,6 +78,11 @@ impl WatchHub {
         )
     }
 
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
     pub async fn remove_watcher(&self, id: u64) {
         let mut inner = self.inner.write().await;
         inner.watchers.remove(&id);
@@ -96,6 +107,7 @@ impl WatchHub {
         deletes: Vec<DeleteEvent>,
         _err: Option<Error>,
     ) {
+        self.version.fetch_add(1, Ordering::AcqRel);
         let inner = self.inner.read().await;
         for w in inner.watchers.values() {
             w.notify(&updates, &deletes, None) // TODO: clonable error
@@ -100,6 +100,22 @@ impl ExecutorOwner {
         ExecutorOwner { runtime }
     }
 
+    /// New a single threaded executor whose clock is paused, for deterministic simulation. The
+    /// clock auto-advances to the next pending timer once all tasks are idle.
+    ///
+    /// The executor is not seeded, tasks are polled in the order they are woken. The only seeded
+    /// timing of the servers is the election timeout of raft, see [`crate::sim`].
+    #[cfg(feature = "sim")]
+    pub fn simulated() -> Self {
+        use tokio::runtime::Builder;
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .expect("build tokio runtime");
+        ExecutorOwner { runtime }
+    }
+
     pub fn executor(&self) -> Executor {
         Executor {
             handle: self.runtime.handle().clone(),
@@ -126,6 +142,8 @@ impl Executor {
         // TODO(walter) support per thread task set.
         let _ = tag;
         take_spawn_metrics(priority);
+        #[cfg(feature = "sim")]
+        let future = crate::sim::bind_current_node(future);
         self.handle.spawn(FutureWrapper::new(future))
     }
 
@@ -147,6 +165,8 @@ impl Executor {
         // TODO(walter) support per thread task set.
         let _ = tag;
         take_spawn_metrics(priority);
+        #[cfg(feature = "sim")]
+        let future = crate::sim::bind_current_node(future);
         let inner = self.handle.spawn(FutureWrapper::new(future));
         DispatchHandle { inner }
     }
@@ -167,6 +187,11 @@ impl Executor {
         F: FnOnce() -> R + Send + 'static,
         R: Send + 'static,
     {
+        #[cfg(feature = "sim")]
+        if crate::sim::is_simulating() {
+            // Run on the simulation thread, so the blocking task is scheduled deterministically.
+            return self.handle.spawn(async move { func() });
+        }
         self.handle.spawn_blocking(func)
     }
 
@@ -176,7 +201,7 @@ impl Executor {
         F: FnOnce() -> R + Send + 'static,
         R: Send + 'static,
     {
-        let inner = self.handle.spawn_blocking(func);
+        let inner = self.spawn_blocking(func);
         DispatchHandle { inner }
     }
 }
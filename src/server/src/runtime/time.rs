@@ -11,8 +11,27 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::time::Duration;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A measurement of the monotonic clock, which is driven by the virtual clock under
+/// simulation.
+pub use tokio::time::Instant;
 
 pub async fn sleep(dur: Duration) {
     tokio::time::sleep(dur).await;
 }
+
+/// Returns the milliseconds since unix epoch.
+///
+/// Under simulation, the wall clock starts from a fixed point and advances along with the virtual
+/// clock, so that a scenario is replayed with the same timestamps.
+pub fn timestamp_millis() -> u128 {
+    #[cfg(feature = "sim")]
+    if let Some(elapsed) = crate::sim::elapsed() {
+        return (crate::sim::WALL_CLOCK_BASE + elapsed).as_millis();
+    }
+
+    let since_the_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    since_the_epoch.as_millis()
+}
@@ -40,6 +40,20 @@ pub struct ReplaceVoters {
     pub demoting_voters: Vec<ReplicaDesc>,
 }
 
+/// Add a witness into group. The witness doesn't need to catch up any data, so it is added
+/// directly without joint consensus.
+pub struct AddWitness {
+    pub providers: Arc<GroupProviders>,
+    pub witness: ReplicaDesc,
+}
+
+/// Add a read learner into group. A read learner is a permanent learner, so it is added by a
+/// simple change like the witness, see `ReadReplicaGroup` for details.
+pub struct AddReadLearner {
+    pub providers: Arc<GroupProviders>,
+    pub learner: ReplicaDesc,
+}
+
 #[crate::async_trait]
 impl Action for AddLearners {
     async fn setup(&mut self, task_id: u64, ctx: &mut ScheduleContext<'_>) -> ActionState {
@@ -169,6 +183,79 @@ impl Action for ReplaceVoters {
     }
 }
 
+#[crate::async_trait]
+impl Action for AddWitness {
+    async fn setup(&mut self, task_id: u64, ctx: &mut ScheduleContext<'_>) -> ActionState {
+        let changes = ChangeReplicas {
+            changes: vec![replica_as_witness(&self.witness)],
+        };
+        let cc = ChangeReplicasRequest {
+            change_replicas: Some(changes),
+        };
+        let req = Request::ChangeReplicas(cc);
+        let action_state = try_execute(ctx.replica.as_ref(), task_id, &req, "adding witness").await;
+        if matches!(&action_state, ActionState::Done) {
+            self.providers.descriptor.watch(task_id);
+        }
+        action_state
+    }
+
+    async fn poll(&mut self, task_id: u64, ctx: &mut ScheduleContext<'_>) -> ActionState {
+        let replicas = self.providers.descriptor.replicas();
+        let added = replicas
+            .iter()
+            .any(|r| r.id == self.witness.id && r.role == ReplicaRole::Witness as i32);
+        if added {
+            let group_id = ctx.group_id;
+            let replica_id = ctx.replica_id;
+            info!("group {group_id} replica {replica_id} task {task_id} adding witness step done");
+
+            ActionState::Done
+        } else {
+            self.providers.descriptor.watch(task_id);
+            ActionState::Pending(None)
+        }
+    }
+}
+
+#[crate::async_trait]
+impl Action for AddReadLearner {
+    async fn setup(&mut self, task_id: u64, ctx: &mut ScheduleContext<'_>) -> ActionState {
+        let changes = ChangeReplicas {
+            changes: vec![replica_as_read_learner(&self.learner)],
+        };
+        let cc = ChangeReplicasRequest {
+            change_replicas: Some(changes),
+        };
+        let req = Request::ChangeReplicas(cc);
+        let action_state =
+            try_execute(ctx.replica.as_ref(), task_id, &req, "adding read learner").await;
+        if matches!(&action_state, ActionState::Done) {
+            self.providers.descriptor.watch(task_id);
+        }
+        action_state
+    }
+
+    async fn poll(&mut self, task_id: u64, ctx: &mut ScheduleContext<'_>) -> ActionState {
+        let replicas = self.providers.descriptor.replicas();
+        let added = replicas
+            .iter()
+            .any(|r| r.id == self.learner.id && r.role == ReplicaRole::ReadLearner as i32);
+        if added {
+            let group_id = ctx.group_id;
+            let replica_id = ctx.replica_id;
+            info!(
+                "group {group_id} replica {replica_id} task {task_id} adding read learner step done"
+            );
+
+            ActionState::Done
+        } else {
+            self.providers.descriptor.watch(task_id);
+            ActionState::Pending(None)
+        }
+    }
+}
+
 fn try_execute<'a>(
     replica: &'a Replica,
     task_id: u64,
@@ -209,6 +296,22 @@ fn replica_as_learner(r: &ReplicaDesc) -> ChangeReplica {
     }
 }
 
+fn replica_as_witness(r: &ReplicaDesc) -> ChangeReplica {
+    ChangeReplica {
+        replica_id: r.id,
+        node_id: r.node_id,
+        change_type: ChangeReplicaType::AddWitness as i32,
+    }
+}
+
+fn replica_as_read_learner(r: &ReplicaDesc) -> ChangeReplica {
+    ChangeReplica {
+        replica_id: r.id,
+        node_id: r.node_id,
+        change_type: ChangeReplicaType::AddReadLearner as i32,
+    }
+}
+
 fn replica_as_incoming_voter(r: &ReplicaDesc) -> ChangeReplica {
     ChangeReplica {
         replica_id: r.id,
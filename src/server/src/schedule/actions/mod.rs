@@ -17,7 +17,7 @@ mod act_replica;
 use std::time::Duration;
 
 pub(crate) use self::{
-    act_config_change::{AddLearners, RemoveLearners, ReplaceVoters},
+    act_config_change::{AddLearners, AddReadLearner, AddWitness, RemoveLearners, ReplaceVoters},
     act_replica::{ClearReplicaState, CreateReplicas, RemoveReplica},
 };
 use super::scheduler::ScheduleContext;
@@ -0,0 +1,34 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use lazy_static::lazy_static;
+use prometheus::*;
+
+lazy_static! {
+    pub static ref SCHEDULE_CONSISTENCY_CHECK_TOTAL: IntCounter = register_int_counter!(
+        "schedule_consistency_check_total",
+        "The total consistency checks of groups"
+    )
+    .unwrap();
+    pub static ref SCHEDULE_CONSISTENCY_CHECK_MISMATCH_TOTAL: IntCounter = register_int_counter!(
+        "schedule_consistency_check_mismatch_total",
+        "The total consistency checks which find replicas holding different data"
+    )
+    .unwrap();
+    pub static ref SCHEDULE_CONSISTENCY_CHECK_TIMEOUT_TOTAL: IntCounter = register_int_counter!(
+        "schedule_consistency_check_timeout_total",
+        "The total consistency checks which are not finished in time"
+    )
+    .unwrap();
+}
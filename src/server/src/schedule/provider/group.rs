@@ -15,7 +15,6 @@
 use std::{
     collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
-    time::Instant,
 };
 
 use engula_api::server::v1::*;
@@ -25,6 +24,7 @@ use futures::channel::oneshot;
 use crate::{
     node::Replica,
     raftgroup::RaftGroupState,
+    runtime::time::Instant,
     schedule::{
         event_source::{CommonEventSource, EventSource},
         scheduler::EventWaker,
@@ -18,7 +18,7 @@ use std::{
     pin::Pin,
     sync::{Arc, Mutex},
     task::{Context, Poll},
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use futures::Future;
@@ -29,7 +29,7 @@ use super::{
     tasks::{GroupLockTable, GENERATED_TASK_ID},
     ScheduleStateObserver,
 };
-use crate::{node::Replica, transport::TransportManager, ReplicaConfig};
+use crate::{node::Replica, runtime::time::Instant, transport::TransportManager, ReplicaConfig};
 
 #[derive(Clone)]
 pub struct EventWaker {
@@ -311,7 +311,7 @@ impl TaskTimer {
 
     async fn timeout<T: Future<Output = ()>>(&self, f: T) {
         if let Some(event) = self.timer_heap.peek() {
-            let _ = tokio::time::timeout_at(event.deadline.into(), f).await;
+            let _ = tokio::time::timeout_at(event.deadline, f).await;
         } else {
             f.await;
         }
@@ -110,6 +110,8 @@ async fn allocate_group_tasks(scheduler: &mut Scheduler, providers: Arc<GroupPro
         Box::new(PromoteGroup::new(providers.clone())),
         Box::new(DurableGroup::new(providers.clone())),
         Box::new(RemoveOrphanReplica::new(providers.clone())),
+        Box::new(ConsistencyCheck::new(providers.clone())),
+        Box::new(ReadReplicaGroup::new(providers.clone())),
         Box::new(ReplicaMigration::new(providers)),
     ];
     scheduler.install_tasks(tasks);
@@ -0,0 +1,207 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use engula_api::server::v1::ReplicaRole;
+use tracing::{error, info, warn};
+
+use crate::{
+    node::replica::ChecksumResult,
+    runtime::time::Instant,
+    schedule::{
+        metrics::*,
+        provider::GroupProviders,
+        scheduler::ScheduleContext,
+        task::{Task, TaskState},
+        tasks::CONSISTENCY_CHECK_TASK_ID,
+    },
+};
+
+/// The duration to wait for all replicas to compute the checksum of a check.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+const COLLECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Verifies that the replicas of a group hold identical data, by proposing a command to compute
+/// checksums through raft periodically and comparing the checksums of the followers with the
+/// leader's.
+pub struct ConsistencyCheck {
+    providers: Arc<GroupProviders>,
+    next_seq: u64,
+    pending_check: Option<PendingCheck>,
+}
+
+struct PendingCheck {
+    check_id: u64,
+    proposed_at: Instant,
+    leader_checksum: Option<ChecksumResult>,
+    verified_replicas: HashSet<u64>,
+}
+
+impl ConsistencyCheck {
+    pub fn new(providers: Arc<GroupProviders>) -> Self {
+        ConsistencyCheck {
+            providers,
+            next_seq: 0,
+            pending_check: None,
+        }
+    }
+
+    fn next_check_id(&mut self, term: u64) -> u64 {
+        // The check id is unique across leaders, so the stale checksums of the former leader's
+        // checks are never mistaken for the current one.
+        self.next_seq += 1;
+        (term << 32) | (self.next_seq & u32::MAX as u64)
+    }
+
+    async fn propose_check(&mut self, ctx: &mut ScheduleContext<'_>) {
+        let check_id = self.next_check_id(ctx.current_term);
+        if let Err(err) = ctx.replica.compute_checksum(check_id).await {
+            warn!(
+                "group {} replica {} propose consistency check {check_id}: {err}",
+                ctx.group_id, ctx.replica_id
+            );
+            return;
+        }
+
+        SCHEDULE_CONSISTENCY_CHECK_TOTAL.inc();
+        self.pending_check = Some(PendingCheck {
+            check_id,
+            proposed_at: Instant::now(),
+            leader_checksum: None,
+            verified_replicas: HashSet::default(),
+        });
+    }
+
+    /// Collect checksums from replicas, returns whether all replicas are verified.
+    async fn collect_checksums(
+        &mut self,
+        ctx: &mut ScheduleContext<'_>,
+        check: &mut PendingCheck,
+    ) -> bool {
+        let leader_checksum = match check.leader_checksum {
+            Some(checksum) => checksum,
+            None => match ctx.replica.checksum(check.check_id) {
+                Some(checksum) => {
+                    check.leader_checksum = Some(checksum);
+                    checksum
+                }
+                None => return false,
+            },
+        };
+
+        let desc = self.providers.descriptor.descriptor();
+        let mut finished = true;
+        for replica in &desc.replicas {
+            if replica.id == ctx.replica_id || check.verified_replicas.contains(&replica.id) {
+                continue;
+            }
+            if replica.role == ReplicaRole::Witness as i32 {
+                // A witness doesn't apply data, there is nothing to verify.
+                continue;
+            }
+
+            let client = match ctx.transport_manager.find_node_client(replica.node_id) {
+                Ok(client) => client,
+                Err(err) => {
+                    warn!(
+                        "group {} replica {} find node {} client: {err}",
+                        ctx.group_id, ctx.replica_id, replica.node_id
+                    );
+                    finished = false;
+                    continue;
+                }
+            };
+            let resp = match client.collect_checksum(ctx.group_id, check.check_id).await {
+                Ok(resp) if resp.replica_id == replica.id && resp.computed => resp,
+                Ok(_) => {
+                    finished = false;
+                    continue;
+                }
+                Err(status) => {
+                    warn!(
+                        "group {} replica {} collect checksum of check {} from replica {}: {status}",
+                        ctx.group_id, ctx.replica_id, check.check_id, replica.id
+                    );
+                    finished = false;
+                    continue;
+                }
+            };
+
+            check.verified_replicas.insert(replica.id);
+            if resp.index != leader_checksum.index || resp.checksum != leader_checksum.checksum {
+                SCHEDULE_CONSISTENCY_CHECK_MISMATCH_TOTAL.inc();
+                error!(
+                    "group {} consistency check {} found replica {} diverged: leader replica {} checksum {} at index {}, but replica {} checksum {} at index {}",
+                    ctx.group_id,
+                    check.check_id,
+                    replica.id,
+                    ctx.replica_id,
+                    leader_checksum.checksum,
+                    leader_checksum.index,
+                    replica.id,
+                    resp.checksum,
+                    resp.index,
+                );
+            }
+        }
+        finished
+    }
+}
+
+#[crate::async_trait]
+impl Task for ConsistencyCheck {
+    fn id(&self) -> u64 {
+        CONSISTENCY_CHECK_TASK_ID
+    }
+
+    async fn poll(&mut self, ctx: &mut ScheduleContext<'_>) -> TaskState {
+        let interval = ctx.cfg.consistency_check_interval_sec;
+        if interval == 0 {
+            return TaskState::Pending(None);
+        }
+        let interval = Duration::from_secs(interval);
+
+        let Some(mut check) = self.pending_check.take() else {
+            self.propose_check(ctx).await;
+            if self.pending_check.is_some() {
+                return TaskState::Pending(Some(COLLECT_INTERVAL));
+            }
+            return TaskState::Pending(Some(interval));
+        };
+
+        if self.collect_checksums(ctx, &mut check).await {
+            info!(
+                "group {} consistency check {} is finished, {} replicas are verified",
+                ctx.group_id,
+                check.check_id,
+                check.verified_replicas.len() + 1
+            );
+            return TaskState::Pending(Some(interval));
+        }
+
+        if check.proposed_at.elapsed() > CHECK_TIMEOUT {
+            SCHEDULE_CONSISTENCY_CHECK_TIMEOUT_TOTAL.inc();
+            warn!(
+                "group {} consistency check {} is timeout, only replicas {:?} are verified",
+                ctx.group_id, check.check_id, check.verified_replicas
+            );
+            return TaskState::Pending(Some(interval));
+        }
+
+        self.pending_check = Some(check);
+        TaskState::Pending(Some(COLLECT_INTERVAL))
+    }
+}
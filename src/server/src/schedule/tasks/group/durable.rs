@@ -19,7 +19,7 @@ use tracing::{debug, error, info};
 
 use super::ActionTaskWithLocks;
 use crate::schedule::{
-    actions::{AddLearners, CreateReplicas, RemoveLearners, ReplaceVoters},
+    actions::{AddLearners, AddWitness, CreateReplicas, RemoveLearners, ReplaceVoters},
     event_source::EventSource,
     provider::GroupProviders,
     scheduler::ScheduleContext,
@@ -34,6 +34,8 @@ struct ReplicaStats {
     offline_voters: HashMap<u64, ReplicaDesc>,
     online_learners: HashMap<u64, ReplicaDesc>,
     offline_learners: HashMap<u64, ReplicaDesc>,
+    online_witnesses: HashMap<u64, ReplicaDesc>,
+    offline_witnesses: HashMap<u64, ReplicaDesc>,
 }
 
 pub struct DurableGroup {
@@ -157,6 +159,45 @@ impl DurableGroup {
         ctx.delegate(Box::new(ActionTaskWithLocks::new(locks, action_task)));
     }
 
+    async fn replace_witness(
+        &mut self,
+        ctx: &mut ScheduleContext<'_>,
+        mut peers: Vec<u64>,
+        incoming_witness: ReplicaDesc,
+        outgoing_witness: ReplicaDesc,
+    ) {
+        peers.push(incoming_witness.id);
+        let task_id = ctx.next_task_id();
+        info!(
+            "group {} replica {} task {task_id} replace witness {} with {}",
+            ctx.group_id, ctx.replica_id, outgoing_witness.id, incoming_witness.id
+        );
+        let epoch = ctx.replica.epoch();
+        let incoming_witnesses = vec![incoming_witness.clone()];
+        let locks = ctx
+            .group_lock_table
+            .config_change(task_id, epoch, &peers, &incoming_witnesses, &[])
+            .expect("Check conflicts in before steps");
+        let create_replicas_action = Box::new(CreateReplicas::new(incoming_witnesses));
+        let add_witness_action = Box::new(AddWitness {
+            providers: self.providers.clone(),
+            witness: incoming_witness,
+        });
+        let remove_learners_action = Box::new(RemoveLearners {
+            providers: self.providers.clone(),
+            learners: vec![outgoing_witness],
+        });
+        let action_task = ActionTask::new(
+            task_id,
+            vec![
+                create_replicas_action,
+                add_witness_action,
+                remove_learners_action,
+            ],
+        );
+        ctx.delegate(Box::new(ActionTaskWithLocks::new(locks, action_task)));
+    }
+
     /// Alloc addition replicas from root.
     async fn alloc_addition_replicas(
         &mut self,
@@ -172,6 +213,7 @@ impl DurableGroup {
             current_term: ctx.current_term,
             leader_id: replica_id,
             num_required: num_required as u64,
+            read_learner: false,
         };
         match ctx.transport_manager.root_client().alloc_replica(req).await {
             Ok(resp) => Some(resp.replicas),
@@ -208,6 +250,25 @@ impl DurableGroup {
             return TaskState::Pending(Some(Duration::from_secs(30)));
         }
 
+        // A witness holds no data, so an offline witness is replaced by a new witness directly.
+        if let Some(outgoing_witness) = stats.offline_witnesses.values().next().cloned() {
+            let incoming_witness = self
+                .alloc_addition_replicas(ctx, "replace-witness", 1)
+                .await
+                .and_then(|mut replicas| replicas.pop());
+            if let Some(mut incoming_witness) = incoming_witness {
+                incoming_witness.role = ReplicaRole::Witness as i32;
+                self.replace_witness(ctx, stats.peers, incoming_witness, outgoing_witness)
+                    .await;
+                return TaskState::Pending(Some(Duration::from_secs(30)));
+            } else {
+                return TaskState::Pending(Some(Duration::from_secs(3)));
+            }
+        }
+
+        // The witnesses take part in voting, so they are counted towards the required replicas.
+        let num_required = num_required.saturating_sub(stats.online_witnesses.len());
+
         // The redundant replicas can be deleted, and the offline ones will be deleted first, and
         // then the online ones will be considered.
         let total_voters = stats.online_voters.len() + stats.offline_voters.len();
@@ -351,6 +412,17 @@ impl Task for DurableGroup {
                         stats.online_learners.insert(r.id, r.clone());
                     }
                 }
+                ReplicaRole::Witness => {
+                    if lost_peers.contains(&r.id) {
+                        stats.offline_witnesses.insert(r.id, r.clone());
+                    } else {
+                        stats.online_witnesses.insert(r.id, r.clone());
+                    }
+                }
+                ReplicaRole::ReadLearner => {
+                    // Read learners are kept by `ReadReplicaGroup`, they are never promoted
+                    // or removed as redundant learners here.
+                }
             }
         }
 
@@ -14,6 +14,7 @@
 
 use std::sync::Arc;
 
+use engula_api::server::v1::{ReplicaDesc, ReplicaRole};
 use tracing::debug;
 
 use super::ActionTaskWithLocks;
@@ -52,7 +53,22 @@ impl Task for ReplicaMigration {
             peers.extend(move_replicas.incoming_replicas.iter().map(|v| v.id));
             let task_id = ctx.next_task_id();
             // TODO: verify task pre-conditions.
-            if let Some(locks) = ctx.group_lock_table.config_change(
+            let moving_witness = move_replicas
+                .outgoing_replicas
+                .iter()
+                .any(|r| is_witness(&replicas, r.id));
+            if moving_witness
+                && (move_replicas.incoming_replicas.len() != 1
+                    || move_replicas.outgoing_replicas.len() != 1)
+            {
+                // Witnesses are changed one by one, see `AddWitness` for details.
+                move_replicas
+                    .sender
+                    .send(Err(Error::InvalidArgument(
+                        "only one witness could be moved at a time".to_owned(),
+                    )))
+                    .unwrap_or_default();
+            } else if let Some(locks) = ctx.group_lock_table.config_change(
                 task_id,
                 move_replicas.epoch,
                 &peers,
@@ -61,28 +77,38 @@ impl Task for ReplicaMigration {
             ) {
                 let create_replicas_action =
                     CreateReplicas::new(move_replicas.incoming_replicas.clone());
-                let add_learners_action = AddLearners {
-                    providers: self.providers.clone(),
-                    learners: move_replicas.incoming_replicas.clone(),
-                };
-                let replace_voters_action = ReplaceVoters {
-                    providers: self.providers.clone(),
-                    incoming_voters: move_replicas.incoming_replicas.clone(),
-                    demoting_voters: move_replicas.outgoing_replicas.clone(),
-                };
                 let remove_learners_action = RemoveLearners {
                     providers: self.providers.clone(),
                     learners: move_replicas.outgoing_replicas.clone(),
                 };
-                let action_task = ActionTask::new(
-                    task_id,
+                let actions: Vec<Box<dyn Action>> = if moving_witness {
+                    let add_witness_action = AddWitness {
+                        providers: self.providers.clone(),
+                        witness: move_replicas.incoming_replicas[0].clone(),
+                    };
+                    vec![
+                        Box::new(create_replicas_action),
+                        Box::new(add_witness_action),
+                        Box::new(remove_learners_action),
+                    ]
+                } else {
+                    let add_learners_action = AddLearners {
+                        providers: self.providers.clone(),
+                        learners: move_replicas.incoming_replicas.clone(),
+                    };
+                    let replace_voters_action = ReplaceVoters {
+                        providers: self.providers.clone(),
+                        incoming_voters: move_replicas.incoming_replicas.clone(),
+                        demoting_voters: move_replicas.outgoing_replicas.clone(),
+                    };
                     vec![
                         Box::new(create_replicas_action),
                         Box::new(add_learners_action),
                         Box::new(replace_voters_action),
                         Box::new(remove_learners_action),
-                    ],
-                );
+                    ]
+                };
+                let action_task = ActionTask::new(task_id, actions);
                 ctx.delegate(Box::new(ActionTaskWithLocks::new(locks, action_task)));
                 move_replicas.sender.send(Ok(())).unwrap_or_default();
             } else {
@@ -99,3 +125,9 @@ impl Task for ReplicaMigration {
         TaskState::Pending(None)
     }
 }
+
+fn is_witness(replicas: &[ReplicaDesc], replica_id: u64) -> bool {
+    replicas
+        .iter()
+        .any(|r| r.id == replica_id && r.role == ReplicaRole::Witness as i32)
+}
@@ -12,10 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod consistency;
 mod durable;
 mod migration;
 mod orphan_replica;
 mod promote;
+mod read_replica;
 mod watch_descriptor;
 mod watch_raft_state;
 mod watch_replica_states;
@@ -25,9 +27,10 @@ use std::collections::HashMap;
 use engula_api::server::v1::{ReplicaDesc, ScheduleState};
 
 pub use self::{
-    durable::DurableGroup, migration::ReplicaMigration, orphan_replica::RemoveOrphanReplica,
-    promote::PromoteGroup, watch_descriptor::WatchGroupDescriptor,
-    watch_raft_state::WatchRaftState, watch_replica_states::WatchReplicaStates,
+    consistency::ConsistencyCheck, durable::DurableGroup, migration::ReplicaMigration,
+    orphan_replica::RemoveOrphanReplica, promote::PromoteGroup, read_replica::ReadReplicaGroup,
+    watch_descriptor::WatchGroupDescriptor, watch_raft_state::WatchRaftState,
+    watch_replica_states::WatchReplicaStates,
 };
 use super::ActionTask;
 use crate::schedule::{
@@ -15,20 +15,23 @@
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use engula_api::server::v1::*;
 use tracing::info;
 
 use super::ActionTaskWithLocks;
-use crate::schedule::{
-    actions::{ClearReplicaState, RemoveReplica},
-    event_source::EventSource,
-    provider::GroupProviders,
-    scheduler::ScheduleContext,
-    task::{Task, TaskState},
-    tasks::{ActionTask, REMOVE_ORPHAN_REPLICA_TASK_ID},
+use crate::{
+    runtime::time::Instant,
+    schedule::{
+        actions::{ClearReplicaState, RemoveReplica},
+        event_source::EventSource,
+        provider::GroupProviders,
+        scheduler::ScheduleContext,
+        task::{Task, TaskState},
+        tasks::{ActionTask, REMOVE_ORPHAN_REPLICA_TASK_ID},
+    },
 };
 
 pub struct RemoveOrphanReplica {
@@ -99,6 +99,7 @@ impl PromoteGroup {
             current_term: ctx.current_term,
             leader_id: ctx.replica_id,
             num_required: num_required as u64,
+            read_learner: false,
         };
         match ctx.transport_manager.root_client().alloc_replica(req).await {
             Ok(resp) => Some(resp.replicas),
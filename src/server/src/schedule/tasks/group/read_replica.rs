@@ -0,0 +1,194 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{sync::Arc, time::Duration};
+
+use engula_api::server::v1::*;
+use tracing::{debug, error, info};
+
+use super::ActionTaskWithLocks;
+use crate::schedule::{
+    actions::{AddReadLearner, CreateReplicas, RemoveLearners},
+    provider::GroupProviders,
+    scheduler::ScheduleContext,
+    task::{Task, TaskState},
+    tasks::{ActionTask, READ_REPLICA_TASK_ID},
+};
+
+/// Keeps the permanent read learners of a group, the number of read learners is the max
+/// `read_replicas` of the collections served by this group.
+///
+/// The read learners are changed one by one, the offline and redundant ones are removed, and
+/// the missing ones are allocated on the nodes labelled for read replicas.
+pub struct ReadReplicaGroup {
+    providers: Arc<GroupProviders>,
+}
+
+impl ReadReplicaGroup {
+    pub fn new(providers: Arc<GroupProviders>) -> Self {
+        ReadReplicaGroup { providers }
+    }
+
+    fn required_read_replicas(&self, ctx: &ScheduleContext<'_>) -> usize {
+        let router = ctx.transport_manager.router();
+        self.providers
+            .descriptor
+            .descriptor()
+            .shards
+            .iter()
+            .filter_map(|shard| router.find_collection(shard.collection_id))
+            .map(|desc| desc.read_replicas as usize)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn remove_read_learner(
+        &mut self,
+        ctx: &mut ScheduleContext<'_>,
+        peers: Vec<u64>,
+        learner: ReplicaDesc,
+    ) {
+        let task_id = ctx.next_task_id();
+        info!(
+            "group {} replica {} task {task_id} remove read learner {}",
+            ctx.group_id, ctx.replica_id, learner.id
+        );
+        let epoch = ctx.replica.epoch();
+        let locks = ctx
+            .group_lock_table
+            .config_change(task_id, epoch, &peers, &[], &[])
+            .expect("Check conflicts in before steps");
+        let action_task = ActionTask::new(
+            task_id,
+            vec![Box::new(RemoveLearners {
+                providers: self.providers.clone(),
+                learners: vec![learner],
+            })],
+        );
+        ctx.delegate(Box::new(ActionTaskWithLocks::new(locks, action_task)));
+    }
+
+    fn add_read_learner(
+        &mut self,
+        ctx: &mut ScheduleContext<'_>,
+        mut peers: Vec<u64>,
+        learner: ReplicaDesc,
+    ) {
+        peers.push(learner.id);
+        let task_id = ctx.next_task_id();
+        info!(
+            "group {} replica {} task {task_id} add read learner {} on node {}",
+            ctx.group_id, ctx.replica_id, learner.id, learner.node_id
+        );
+        let epoch = ctx.replica.epoch();
+        let incoming_learners = vec![learner.clone()];
+        let locks = ctx
+            .group_lock_table
+            .config_change(task_id, epoch, &peers, &incoming_learners, &[])
+            .expect("Check conflicts in before steps");
+        let create_replicas_action = Box::new(CreateReplicas::new(incoming_learners));
+        let add_read_learner_action = Box::new(AddReadLearner {
+            providers: self.providers.clone(),
+            learner,
+        });
+        let action_task = ActionTask::new(
+            task_id,
+            vec![create_replicas_action, add_read_learner_action],
+        );
+        ctx.delegate(Box::new(ActionTaskWithLocks::new(locks, action_task)));
+    }
+
+    async fn alloc_read_learner(&self, ctx: &mut ScheduleContext<'_>) -> Option<ReplicaDesc> {
+        let group_id = ctx.group_id;
+        let replica_id = ctx.replica_id;
+        let req = AllocReplicaRequest {
+            group_id,
+            epoch: ctx.replica.epoch(),
+            current_term: ctx.current_term,
+            leader_id: replica_id,
+            num_required: 1,
+            read_learner: true,
+        };
+        match ctx.transport_manager.root_client().alloc_replica(req).await {
+            Ok(mut resp) => resp.replicas.pop(),
+            Err(
+                e @ (engula_client::Error::ResourceExhausted(_)
+                | engula_client::Error::EpochNotMatch(_)),
+            ) => {
+                debug!("group {group_id} replica {replica_id} alloc read learner: {e}");
+                None
+            }
+            Err(e) => {
+                error!("group {group_id} replica {replica_id} alloc read learner: {e}");
+                None
+            }
+        }
+    }
+}
+
+#[crate::async_trait]
+impl Task for ReadReplicaGroup {
+    fn id(&self) -> u64 {
+        READ_REPLICA_TASK_ID
+    }
+
+    async fn poll(&mut self, ctx: &mut ScheduleContext<'_>) -> TaskState {
+        let replicas = self.providers.descriptor.replicas();
+        if replicas.is_empty() || ctx.group_lock_table.has_config_change() {
+            return TaskState::Pending(Some(Duration::from_secs(1)));
+        }
+
+        let lost_peers = self.providers.raft_state.lost_peers();
+        let mut peers = vec![];
+        let mut online_learners = vec![];
+        let mut offline_learners = vec![];
+        for r in &replicas {
+            if ctx.group_lock_table.is_replica_locked(r.id) {
+                return TaskState::Pending(Some(Duration::from_secs(1)));
+            }
+            peers.push(r.id);
+            if r.role != ReplicaRole::ReadLearner as i32 {
+                continue;
+            }
+            if lost_peers.contains(&r.id) {
+                offline_learners.push(r.clone());
+            } else {
+                online_learners.push(r.clone());
+            }
+        }
+
+        // An offline read learner is replaced by a new one, just like the witness.
+        if let Some(learner) = offline_learners.pop() {
+            self.remove_read_learner(ctx, peers, learner);
+            return TaskState::Pending(Some(Duration::from_secs(10)));
+        }
+
+        let num_required = self.required_read_replicas(ctx);
+        if online_learners.len() > num_required {
+            let learner = online_learners.pop().unwrap();
+            self.remove_read_learner(ctx, peers, learner);
+            return TaskState::Pending(Some(Duration::from_secs(10)));
+        }
+
+        if online_learners.len() < num_required {
+            if let Some(mut learner) = self.alloc_read_learner(ctx).await {
+                learner.role = ReplicaRole::ReadLearner as i32;
+                self.add_read_learner(ctx, peers, learner);
+                return TaskState::Pending(Some(Duration::from_secs(10)));
+            }
+        }
+
+        TaskState::Pending(Some(Duration::from_secs(10)))
+    }
+}
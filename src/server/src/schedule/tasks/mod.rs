@@ -18,8 +18,9 @@ mod group;
 pub use self::{
     action::ActionTask,
     group::{
-        DurableGroup, GroupLockTable, PromoteGroup, RemoveOrphanReplica, ReplicaMigration,
-        WatchGroupDescriptor, WatchRaftState, WatchReplicaStates,
+        ConsistencyCheck, DurableGroup, GroupLockTable, PromoteGroup, ReadReplicaGroup,
+        RemoveOrphanReplica, ReplicaMigration, WatchGroupDescriptor, WatchRaftState,
+        WatchReplicaStates,
     },
 };
 
@@ -30,5 +31,7 @@ pub const REPLICA_MIGRATION_TASK_ID: u64 = 4;
 pub const WATCH_REPLICA_STATES_TASK_ID: u64 = 5;
 pub const WATCH_RAFT_STATE_TASK_ID: u64 = 6;
 pub const WATCH_GROUP_DESCRIPTOR_TASK_ID: u64 = 7;
+pub const CONSISTENCY_CHECK_TASK_ID: u64 = 8;
+pub const READ_REPLICA_TASK_ID: u64 = 9;
 
 pub const GENERATED_TASK_ID: u64 = 10;
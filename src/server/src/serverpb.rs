@@ -31,6 +31,14 @@ pub mod v1 {
             })
         }
 
+        #[inline]
+        pub fn remove_shard(shard_id: u64) -> Box<Self> {
+            Box::new(SyncOp {
+                remove_shard: Some(RemoveShard { shard_id }),
+                ..Default::default()
+            })
+        }
+
         #[inline]
         pub fn purge_replica(orphan_replica_id: u64) -> Box<Self> {
             Box::new(SyncOp {
@@ -63,6 +71,22 @@ pub mod v1 {
                 ..Default::default()
             })
         }
+
+        #[inline]
+        pub fn compute_checksum(check_id: u64) -> Box<Self> {
+            Box::new(SyncOp {
+                compute_checksum: Some(ComputeChecksum { check_id }),
+                ..Default::default()
+            })
+        }
+
+        #[inline]
+        pub fn replication_shipped(index: u64) -> Box<Self> {
+            Box::new(SyncOp {
+                replication_shipped: Some(ReplicationShipped { index }),
+                ..Default::default()
+            })
+        }
     }
 
     impl MigrationState {
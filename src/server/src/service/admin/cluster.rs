@@ -33,8 +33,10 @@ impl CordonHandle {
 impl super::service::HttpHandle for CordonHandle {
     async fn call(
         &self,
+        _: &http::Method,
         _: &str,
         params: &HashMap<String, String>,
+        _: &[u8],
     ) -> Result<http::Response<String>> {
         let node_id = params
             .get("node_id")
@@ -63,8 +65,10 @@ impl UncordonHandle {
 impl super::service::HttpHandle for UncordonHandle {
     async fn call(
         &self,
+        _: &http::Method,
         _: &str,
         params: &HashMap<String, String>,
+        _: &[u8],
     ) -> Result<http::Response<String>> {
         let node_id = params
             .get("node_id")
@@ -79,6 +83,50 @@ impl super::service::HttpHandle for UncordonHandle {
     }
 }
 
+pub(super) struct LabelNodeHandle {
+    server: Server,
+}
+
+impl LabelNodeHandle {
+    pub(crate) fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl super::service::HttpHandle for LabelNodeHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> Result<http::Response<String>> {
+        let node_id = params
+            .get("node_id")
+            .ok_or_else(|| crate::Error::InvalidArgument("node_id is required".into()))?
+            .parse::<u64>()
+            .map_err(|_| crate::Error::InvalidArgument("illegal node_id".into()))?;
+        // An empty `labels` clears all labels of the node.
+        let labels = params
+            .get("labels")
+            .map(|labels| {
+                labels
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        self.server.root.label_node(node_id, labels).await?;
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body("".to_owned())
+            .unwrap())
+    }
+}
+
 pub(super) struct DrainHandle {
     server: Server,
 }
@@ -93,8 +141,10 @@ impl DrainHandle {
 impl super::service::HttpHandle for DrainHandle {
     async fn call(
         &self,
+        _: &http::Method,
         _: &str,
         params: &HashMap<String, String>,
+        _: &[u8],
     ) -> Result<http::Response<String>> {
         let node_id = params
             .get("node_id")
@@ -123,8 +173,10 @@ impl StatusHandle {
 impl super::service::HttpHandle for StatusHandle {
     async fn call(
         &self,
+        _: &http::Method,
         _: &str,
         params: &HashMap<String, String>,
+        _: &[u8],
     ) -> Result<http::Response<String>> {
         let node_id = params
             .get("node_id")
@@ -138,3 +190,128 @@ impl super::service::HttpHandle for StatusHandle {
             .unwrap())
     }
 }
+
+pub(super) struct DecommissionHandle {
+    server: Server,
+}
+
+impl DecommissionHandle {
+    pub(crate) fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl super::service::HttpHandle for DecommissionHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> Result<http::Response<String>> {
+        let node_id = parse_id(params, "node_id")?;
+        self.server.root.begin_decommission(node_id).await?;
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body("".to_owned())
+            .unwrap())
+    }
+}
+
+pub(super) struct RemoveNodeHandle {
+    server: Server,
+}
+
+impl RemoveNodeHandle {
+    pub(crate) fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl super::service::HttpHandle for RemoveNodeHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> Result<http::Response<String>> {
+        let node_id = parse_id(params, "node_id")?;
+        self.server.root.remove_node(node_id).await?;
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body("".to_owned())
+            .unwrap())
+    }
+}
+
+pub(super) struct TransferLeaderHandle {
+    server: Server,
+}
+
+impl TransferLeaderHandle {
+    pub(crate) fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl super::service::HttpHandle for TransferLeaderHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> Result<http::Response<String>> {
+        let group_id = parse_id(params, "group_id")?;
+        let replica_id = parse_id(params, "replica_id")?;
+        self.server
+            .root
+            .transfer_leader(group_id, replica_id)
+            .await?;
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body("".to_owned())
+            .unwrap())
+    }
+}
+
+pub(super) struct MoveShardHandle {
+    server: Server,
+}
+
+impl MoveShardHandle {
+    pub(crate) fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl super::service::HttpHandle for MoveShardHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> Result<http::Response<String>> {
+        let shard_id = parse_id(params, "shard_id")?;
+        let dest_group_id = parse_id(params, "dest_group_id")?;
+        self.server.root.move_shard(shard_id, dest_group_id).await?;
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body("".to_owned())
+            .unwrap())
+    }
+}
+
+fn parse_id(params: &HashMap<String, String>, name: &str) -> Result<u64> {
+    params
+        .get(name)
+        .ok_or_else(|| crate::Error::InvalidArgument(format!("{name} is required")))?
+        .parse::<u64>()
+        .map_err(|_| crate::Error::InvalidArgument(format!("illegal {name}")))
+}
@@ -0,0 +1,443 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The HTTP/JSON data API. The keys and values are encoded as raw strings by default, or as
+//! base64 if `encoding=base64` is specified. The reads take the parameters from the query string,
+//! and the writes take them from a JSON object body.
+//!
+//! - `GET /data/databases`
+//! - `GET /data/database/get?name=`
+//! - `POST /data/database/create {name}`
+//! - `DELETE /data/database/delete {name}`
+//! - `GET /data/collections?database=`
+//! - `GET /data/collection/get?database=&name=`
+//! - `POST /data/collection/create {database, name[, partition: hash|range][, slots]}`
+//! - `DELETE /data/collection/delete {database, name}`
+//! - `POST /data/collection/truncate {database, name}`
+//! - `GET /data/get?database=&collection=&key=`
+//! - `PUT /data/put {database, collection, key, value}`
+//! - `DELETE /data/delete {database, collection, key}`
+//! - `DELETE /data/delete_range {database, collection[, start][, end]}`
+//! - `GET /data/scan?database=&collection=[&start=][&end=][&prefix=][&limit=][&reverse=]`
+
+use std::collections::HashMap;
+
+use engula_api::v1::{collection_desc, CollectionDesc};
+use engula_client::{AppError, AppResult, Collection, Database, EngulaClient, Partition};
+use serde_json::{json, Value};
+use tonic::codegen::*;
+
+use crate::{Error, Result};
+
+const DEFAULT_SCAN_LIMIT: usize = 100;
+const DEFAULT_NUM_SLOTS: u32 = 64;
+
+pub(super) struct DataHandle {
+    client: EngulaClient,
+}
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Raw,
+    Base64,
+}
+
+impl DataHandle {
+    pub(crate) fn new(client: EngulaClient) -> Self {
+        Self { client }
+    }
+
+    async fn execute(&self, path: &str, params: &Params<'_>) -> Result<AppResult<Value>> {
+        Ok(match path {
+            "/admin/data/databases" => self.list_databases().await,
+            "/admin/data/database/get" => {
+                let name = params.required("name")?;
+                self.open_database(name).await.map(|db| database_json(&db))
+            }
+            "/admin/data/database/create" => {
+                let name = params.required("name")?;
+                self.client
+                    .create_database(name)
+                    .await
+                    .map(|db| database_json(&db))
+            }
+            "/admin/data/database/delete" => {
+                let name = params.required("name")?;
+                self.client.delete_database(name).await.map(|_| json!({}))
+            }
+            "/admin/data/collections" => {
+                let db = params.required("database")?;
+                self.list_collections(db).await
+            }
+            "/admin/data/collection/get" => {
+                let (db, name) = (params.required("database")?, params.required("name")?);
+                self.open_collection(db, name)
+                    .await
+                    .map(|co| collection_json(&co.desc()))
+            }
+            "/admin/data/collection/create" => {
+                let (db, name) = (params.required("database")?, params.required("name")?);
+                let partition = match params.get("partition") {
+                    None | Some("hash") => Partition::Hash {
+                        slots: params.parse("slots")?.unwrap_or(DEFAULT_NUM_SLOTS),
+                    },
+                    Some("range") => Partition::Range,
+                    Some(v) => {
+                        return Err(Error::InvalidArgument(format!("unknown partition {v}")))
+                    }
+                };
+                self.create_collection(db, name, partition).await
+            }
+            "/admin/data/collection/delete" => {
+                let (db, name) = (params.required("database")?, params.required("name")?);
+                self.delete_collection(db, name).await
+            }
+            "/admin/data/collection/truncate" => {
+                let (db, name) = (params.required("database")?, params.required("name")?);
+                match self.open_collection(db, name).await {
+                    Ok(co) => co.truncate().await.map(|_| json!({})),
+                    Err(err) => Err(err),
+                }
+            }
+            "/admin/data/get"
+            | "/admin/data/put"
+            | "/admin/data/delete"
+            | "/admin/data/delete_range"
+            | "/admin/data/scan" => {
+                let request = KvRequest::parse(path, params)?;
+                let db = params.required("database")?;
+                let name = params.required("collection")?;
+                match self.open_collection(db, name).await {
+                    Ok(co) => execute_kv(&co, request, params.encoding).await,
+                    Err(err) => Err(err),
+                }
+            }
+            _ => Err(AppError::NotFound(path.to_owned())),
+        })
+    }
+
+    async fn open_database(&self, name: String) -> AppResult<Database> {
+        self.client.open_database(name).await
+    }
+
+    async fn open_collection(&self, db: String, name: String) -> AppResult<Collection> {
+        self.open_database(db).await?.open_collection(name).await
+    }
+
+    async fn list_databases(&self) -> AppResult<Value> {
+        let databases = self.client.list_database().await?;
+        Ok(Value::Array(databases.iter().map(database_json).collect()))
+    }
+
+    async fn list_collections(&self, db: String) -> AppResult<Value> {
+        let collections = self.open_database(db).await?.list_collection().await?;
+        Ok(Value::Array(
+            collections
+                .iter()
+                .map(|co| collection_json(&co.desc()))
+                .collect(),
+        ))
+    }
+
+    async fn create_collection(
+        &self,
+        db: String,
+        name: String,
+        partition: Partition,
+    ) -> AppResult<Value> {
+        let co = self
+            .open_database(db)
+            .await?
+            .create_collection(name, Some(partition))
+            .await?;
+        Ok(collection_json(&co.desc()))
+    }
+
+    async fn delete_collection(&self, db: String, name: String) -> AppResult<Value> {
+        self.open_database(db)
+            .await?
+            .delete_collection(name)
+            .await?;
+        Ok(json!({}))
+    }
+}
+
+#[crate::async_trait]
+impl super::service::HttpHandle for DataHandle {
+    async fn call(
+        &self,
+        method: &http::Method,
+        path: &str,
+        params: &HashMap<String, String>,
+        body: &[u8],
+    ) -> Result<http::Response<String>> {
+        let expect_method = request_method(path);
+        let (status, body) = if *method != expect_method {
+            (
+                http::StatusCode::METHOD_NOT_ALLOWED,
+                json!({ "error": format!("{path} requires {expect_method}") }),
+            )
+        } else {
+            let body_params;
+            let params = if *method == http::Method::GET {
+                params
+            } else {
+                body_params = parse_body(body)?;
+                &body_params
+            };
+            let params = Params::new(params)?;
+            match self.execute(path, &params).await? {
+                Ok(value) => (http::StatusCode::OK, value),
+                Err(err) => (status_code(&err), json!({ "error": err.to_string() })),
+            }
+        };
+        Ok(http::Response::builder()
+            .status(status)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .unwrap())
+    }
+}
+
+/// The method of request, the writes are not allowed to be issued by `GET`.
+fn request_method(path: &str) -> http::Method {
+    match path {
+        "/admin/data/database/create"
+        | "/admin/data/collection/create"
+        | "/admin/data/collection/truncate" => http::Method::POST,
+        "/admin/data/database/delete"
+        | "/admin/data/collection/delete"
+        | "/admin/data/delete"
+        | "/admin/data/delete_range" => http::Method::DELETE,
+        "/admin/data/put" => http::Method::PUT,
+        _ => http::Method::GET,
+    }
+}
+
+/// Parse the parameters of a write from the JSON object body, the numbers and booleans are
+/// accepted as their literals.
+fn parse_body(body: &[u8]) -> Result<HashMap<String, String>> {
+    let Ok(Value::Object(fields)) = serde_json::from_slice::<Value>(body) else {
+        return Err(Error::InvalidArgument("body must be a JSON object".into()));
+    };
+    fields
+        .into_iter()
+        .map(|(name, value)| match value {
+            Value::String(v) => Ok((name, v)),
+            Value::Number(v) => Ok((name, v.to_string())),
+            Value::Bool(v) => Ok((name, v.to_string())),
+            _ => Err(Error::InvalidArgument(format!("illegal {name}"))),
+        })
+        .collect()
+}
+
+enum KvRequest {
+    Get(Vec<u8>),
+    Put(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+    DeleteRange(Vec<u8>, Vec<u8>),
+    Scan {
+        start: Vec<u8>,
+        end: Vec<u8>,
+        prefix: Option<Vec<u8>>,
+        limit: usize,
+        reverse: bool,
+    },
+}
+
+impl KvRequest {
+    fn parse(path: &str, params: &Params<'_>) -> Result<Self> {
+        let key = || {
+            params
+                .bytes("key")?
+                .ok_or_else(|| Error::InvalidArgument("key is required".into()))
+        };
+        Ok(match path {
+            "/admin/data/get" => KvRequest::Get(key()?),
+            "/admin/data/put" => {
+                let value = params
+                    .bytes("value")?
+                    .ok_or_else(|| Error::InvalidArgument("value is required".into()))?;
+                KvRequest::Put(key()?, value)
+            }
+            "/admin/data/delete" => KvRequest::Delete(key()?),
+            "/admin/data/delete_range" => KvRequest::DeleteRange(
+                params.bytes("start")?.unwrap_or_default(),
+                params.bytes("end")?.unwrap_or_default(),
+            ),
+            _ => {
+                let prefix = params.bytes("prefix")?;
+                let reverse = params.parse("reverse")?.unwrap_or_default();
+                if prefix.is_some() && reverse {
+                    return Err(Error::InvalidArgument(
+                        "reverse prefix scan is not supported".into(),
+                    ));
+                }
+                KvRequest::Scan {
+                    start: params.bytes("start")?.unwrap_or_default(),
+                    end: params.bytes("end")?.unwrap_or_default(),
+                    prefix,
+                    limit: params.parse("limit")?.unwrap_or(DEFAULT_SCAN_LIMIT),
+                    reverse,
+                }
+            }
+        })
+    }
+}
+
+async fn execute_kv(co: &Collection, request: KvRequest, encoding: Encoding) -> AppResult<Value> {
+    let entry_json = |key: &[u8], value: &[u8]| -> AppResult<Value> {
+        Ok(json!({
+            "key": encoding.encode("key", key)?,
+            "value": encoding.encode("value", value)?,
+        }))
+    };
+    match request {
+        KvRequest::Get(key) => match co.get(key.clone()).await? {
+            Some(value) => entry_json(&key, &value),
+            None => Err(AppError::NotFound(format!(
+                "key {}",
+                encoding.encode("key", &key)?
+            ))),
+        },
+        KvRequest::Put(key, value) => {
+            co.put(key, value).await?;
+            Ok(json!({}))
+        }
+        KvRequest::Delete(key) => {
+            co.delete(key).await?;
+            Ok(json!({}))
+        }
+        KvRequest::DeleteRange(start, end) => {
+            co.delete_range(start, end).await?;
+            Ok(json!({}))
+        }
+        KvRequest::Scan {
+            start,
+            end,
+            prefix,
+            limit,
+            reverse,
+        } => {
+            let entries = match prefix {
+                Some(prefix) => co.prefix_scan(prefix, limit).await?,
+                None if reverse => co.reverse_scan(start, end, limit).await?,
+                None => co.scan(start, end, limit).await?,
+            };
+            let entries = entries
+                .iter()
+                .map(|(key, value)| entry_json(key, value))
+                .collect::<AppResult<Vec<_>>>()?;
+            Ok(json!({ "entries": entries }))
+        }
+    }
+}
+
+struct Params<'a> {
+    inner: &'a HashMap<String, String>,
+    encoding: Encoding,
+}
+
+impl<'a> Params<'a> {
+    fn new(inner: &'a HashMap<String, String>) -> Result<Self> {
+        let encoding = match inner.get("encoding").map(String::as_str) {
+            None | Some("raw") => Encoding::Raw,
+            Some("base64") => Encoding::Base64,
+            Some(v) => return Err(Error::InvalidArgument(format!("unknown encoding {v}"))),
+        };
+        Ok(Params { inner, encoding })
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.inner.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> Result<String> {
+        self.get(name)
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| Error::InvalidArgument(format!("{name} is required")))
+    }
+
+    fn parse<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.get(name)
+            .map(|v| {
+                v.parse::<T>()
+                    .map_err(|_| Error::InvalidArgument(format!("illegal {name}")))
+            })
+            .transpose()
+    }
+
+    fn bytes(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        self.get(name)
+            .map(|v| self.encoding.decode(name, v))
+            .transpose()
+    }
+}
+
+impl Encoding {
+    /// Encode the data read from collection, the raw encoding is rejected if the data isn't valid
+    /// UTF-8, instead of replacing the invalid bytes.
+    fn encode(self, name: &str, data: &[u8]) -> AppResult<String> {
+        match self {
+            Encoding::Raw => String::from_utf8(data.to_owned()).map_err(|_| {
+                AppError::InvalidArgument(format!(
+                    "{name} is not valid UTF-8, use encoding=base64 instead"
+                ))
+            }),
+            Encoding::Base64 => Ok(base64::encode(data)),
+        }
+    }
+
+    fn decode(self, name: &str, data: &str) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Raw => Ok(data.as_bytes().to_owned()),
+            Encoding::Base64 => base64::decode(data)
+                .map_err(|_| Error::InvalidArgument(format!("illegal base64 {name}"))),
+        }
+    }
+}
+
+fn status_code(err: &AppError) -> http::StatusCode {
+    match err {
+        AppError::NotFound(_) => http::StatusCode::NOT_FOUND,
+        AppError::AlreadyExists(_) => http::StatusCode::CONFLICT,
+        AppError::InvalidArgument(_) => http::StatusCode::BAD_REQUEST,
+        AppError::DeadlineExceeded(_) => http::StatusCode::GATEWAY_TIMEOUT,
+        AppError::QuotaExceeded(..) => http::StatusCode::TOO_MANY_REQUESTS,
+        AppError::Network(_) => http::StatusCode::SERVICE_UNAVAILABLE,
+        AppError::Internal(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn database_json(db: &Database) -> Value {
+    let desc = db.desc();
+    json!({ "id": desc.id, "name": desc.name })
+}
+
+fn collection_json(desc: &CollectionDesc) -> Value {
+    let partition = match desc.partition.as_ref() {
+        Some(collection_desc::Partition::Hash(hash)) => json!({
+            "type": "hash",
+            "slots": hash.slots,
+        }),
+        Some(collection_desc::Partition::Range(_)) => json!({ "type": "range" }),
+        None => Value::Null,
+    };
+    json!({
+        "id": desc.id,
+        "name": desc.name,
+        "database": desc.db,
+        "partition": partition,
+    })
+}
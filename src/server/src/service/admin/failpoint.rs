@@ -0,0 +1,53 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+
+use tonic::codegen::*;
+
+use crate::failpoint;
+
+/// Toggle failpoints of the node: `?name=<name>&action=<action>` enables a failpoint, and
+/// `?name=<name>&action=off` disables it. The enabled failpoints are returned.
+pub(super) struct FailpointHandle;
+
+#[crate::async_trait]
+impl super::service::HttpHandle for FailpointHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> crate::Result<http::Response<String>> {
+        match (params.get("name"), params.get("action")) {
+            (Some(name), None) => failpoint::disable(name),
+            (Some(name), Some(action)) if action == "off" => failpoint::disable(name),
+            (Some(name), Some(action)) => failpoint::enable(name, action.parse()?)?,
+            (None, Some(_)) => {
+                return Err(crate::Error::InvalidArgument("name is required".into()));
+            }
+            (None, None) => {}
+        }
+
+        let failpoints = failpoint::list()
+            .into_iter()
+            .map(|(name, action)| (name, action.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body(serde_json::to_string(&failpoints).unwrap())
+            .unwrap())
+    }
+}
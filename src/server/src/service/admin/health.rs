@@ -22,8 +22,10 @@ pub(super) struct HealthHandle;
 impl super::service::HttpHandle for HealthHandle {
     async fn call(
         &self,
+        _: &http::Method,
         _: &str,
         _: &HashMap<String, String>,
+        _: &[u8],
     ) -> crate::Result<http::Response<String>> {
         Ok(http::Response::builder()
             .status(http::StatusCode::OK)
@@ -32,8 +32,10 @@ impl JobHandle {
 impl super::service::HttpHandle for JobHandle {
     async fn call(
         &self,
+        _: &http::Method,
         path: &str,
         _: &HashMap<String, String>,
+        _: &[u8],
     ) -> crate::Result<http::Response<String>> {
         let info = match self.server.root.job_state().await {
             Ok(info) => info,
@@ -64,3 +66,35 @@ impl super::service::HttpHandle for JobHandle {
             .unwrap())
     }
 }
+
+pub(super) struct CancelJobHandle {
+    server: Server,
+}
+
+impl CancelJobHandle {
+    pub fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[crate::async_trait]
+impl super::service::HttpHandle for CancelJobHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> crate::Result<http::Response<String>> {
+        let job_id = params
+            .get("job_id")
+            .ok_or_else(|| crate::Error::InvalidArgument("job_id is required".into()))?
+            .parse::<u64>()
+            .map_err(|_| crate::Error::InvalidArgument("illegal job_id".into()))?;
+        self.server.root.cancel_job(job_id)?;
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body("".to_owned())
+            .unwrap())
+    }
+}
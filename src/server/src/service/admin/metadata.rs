@@ -32,8 +32,10 @@ impl MetadataHandle {
 impl super::service::HttpHandle for MetadataHandle {
     async fn call(
         &self,
+        _: &http::Method,
         path: &str,
         _: &HashMap<String, String>,
+        _: &[u8],
     ) -> crate::Result<http::Response<String>> {
         let info = match self.server.root.info().await {
             Ok(info) => serde_json::to_string(&info).unwrap(),
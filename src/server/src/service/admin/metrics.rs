@@ -55,8 +55,10 @@ impl std::ops::Drop for MetricsHandle {
 impl super::service::HttpHandle for MetricsHandle {
     async fn call(
         &self,
+        _: &http::Method,
         _: &str,
         _: &HashMap<String, String>,
+        _: &[u8],
     ) -> crate::Result<http::Response<String>> {
         METRICS_RPC_REQUESTS_TOTAL.inc();
         self.collector.try_refresh().await;
@@ -13,24 +13,34 @@
 // limitations under the License.
 
 mod cluster;
+mod data;
+#[cfg(feature = "failpoints")]
+mod failpoint;
 mod health;
 mod job;
 mod metadata;
 mod metrics;
 mod monitor;
+mod replication;
 mod service;
 
+use engula_client::EngulaClient;
+
 pub use self::service::AdminService;
 use self::service::Router;
 use crate::Server;
 
-pub fn make_admin_service(server: Server) -> AdminService {
+pub fn make_admin_service(server: Server, data_client: EngulaClient) -> AdminService {
     let router = Router::empty()
         .route(
             "/metrics",
             self::metrics::MetricsHandle::new(server.to_owned()),
         )
         .route("/job", self::job::JobHandle::new(server.to_owned()))
+        .route(
+            "/job/cancel",
+            self::job::CancelJobHandle::new(server.to_owned()),
+        )
         .route(
             "/metadata",
             self::metadata::MetadataHandle::new(server.to_owned()),
@@ -44,12 +54,64 @@ pub fn make_admin_service(server: Server) -> AdminService {
             "/uncordon",
             self::cluster::UncordonHandle::new(server.to_owned()),
         )
+        .route(
+            "/label_node",
+            self::cluster::LabelNodeHandle::new(server.to_owned()),
+        )
         .route("/drain", self::cluster::DrainHandle::new(server.to_owned()))
         .route(
             "/node_status",
             self::cluster::StatusHandle::new(server.to_owned()),
         )
+        .route(
+            "/decommission",
+            self::cluster::DecommissionHandle::new(server.to_owned()),
+        )
+        .route(
+            "/remove_node",
+            self::cluster::RemoveNodeHandle::new(server.to_owned()),
+        )
+        .route(
+            "/transfer_leader",
+            self::cluster::TransferLeaderHandle::new(server.to_owned()),
+        )
+        .route(
+            "/move_shard",
+            self::cluster::MoveShardHandle::new(server.to_owned()),
+        )
+        .route(
+            "/replication",
+            self::replication::ReplicationHandle::new(server.to_owned()),
+        )
+        .route(
+            "/replication/failover",
+            self::replication::FailoverHandle::new(server.to_owned()),
+        )
         .route("/monitor", self::monitor::MonitorHandle::new(server));
+    // Failpoints are toggled without authentication, so the endpoint is only exposed in the builds
+    // for testing.
+    #[cfg(feature = "failpoints")]
+    let router = router.route("/failpoint", self::failpoint::FailpointHandle);
+    let router = [
+        "/data/databases",
+        "/data/database/get",
+        "/data/database/create",
+        "/data/database/delete",
+        "/data/collections",
+        "/data/collection/get",
+        "/data/collection/create",
+        "/data/collection/delete",
+        "/data/collection/truncate",
+        "/data/get",
+        "/data/put",
+        "/data/delete",
+        "/data/delete_range",
+        "/data/scan",
+    ]
+    .into_iter()
+    .fold(router, |router, path| {
+        router.route(path, self::data::DataHandle::new(data_client.clone()))
+    });
     let api = Router::nest("/admin", router);
     AdminService::new(api)
 }
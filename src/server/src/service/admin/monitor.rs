@@ -43,8 +43,10 @@ impl MonitorHandle {
 impl super::service::HttpHandle for MonitorHandle {
     async fn call(
         &self,
+        _: &http::Method,
         _: &str,
         params: &HashMap<String, String>,
+        _: &[u8],
     ) -> Result<http::Response<String>> {
         let group_id = params
             .get("group_id")
@@ -0,0 +1,85 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, time::Duration};
+
+use tonic::{async_trait, codegen::http};
+
+use crate::{Error, Result, Server};
+
+/// Show the progress of replicating writes to the standby cluster.
+pub(super) struct ReplicationHandle {
+    server: Server,
+}
+
+impl ReplicationHandle {
+    pub(crate) fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl super::service::HttpHandle for ReplicationHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        _: &HashMap<String, String>,
+        _: &[u8],
+    ) -> Result<http::Response<String>> {
+        let status = self.server.node.replication().status();
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body(serde_json::to_string(&status).unwrap_or_else(|e| e.to_string()))
+            .unwrap())
+    }
+}
+
+/// Stop the replication of all nodes and wait until the pending writes are shipped to the standby
+/// cluster, so that the standby cluster could be promoted. It is served by the root leader.
+pub(super) struct FailoverHandle {
+    server: Server,
+}
+
+impl FailoverHandle {
+    pub(crate) fn new(server: Server) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl super::service::HttpHandle for FailoverHandle {
+    async fn call(
+        &self,
+        _: &http::Method,
+        _: &str,
+        params: &HashMap<String, String>,
+        _: &[u8],
+    ) -> Result<http::Response<String>> {
+        let timeout_ms = match params.get("timeout_ms") {
+            Some(timeout_ms) => timeout_ms
+                .parse::<u64>()
+                .map_err(|_| Error::InvalidArgument("illegal timeout_ms".into()))?,
+            None => 30_000,
+        };
+        self.server
+            .root
+            .failover_replication(Duration::from_millis(timeout_ms))
+            .await?;
+        Ok(http::Response::builder()
+            .status(http::StatusCode::OK)
+            .body("".to_owned())
+            .unwrap())
+    }
+}
@@ -26,10 +26,14 @@ use tonic::{
 
 #[crate::async_trait]
 pub(super) trait HttpHandle: Send + Sync {
+    /// Handle a request, the `params` are parsed from the query string, and the `body` is the
+    /// content of request.
     async fn call(
         &self,
+        method: &http::Method,
         path: &str,
         params: &HashMap<String, String>,
+        body: &[u8],
     ) -> crate::Result<http::Response<String>>;
 }
 
@@ -54,7 +58,9 @@ impl AdminService {
 
 impl<B> Service<http::Request<B>> for AdminService
 where
-    B: Send,
+    B: http_body::Body + Send + 'static,
+    B::Data: Send,
+    B::Error: std::fmt::Display,
 {
     type Response = http::Response<tonic::body::BoxBody>;
     type Error = std::convert::Infallible;
@@ -76,7 +82,19 @@ where
             })
             .unwrap_or_else(HashMap::new);
         let path = req.uri().path().to_owned();
-        Box::pin(async move { inner.call(&path, query_params).await })
+        let method = req.method().to_owned();
+        Box::pin(async move {
+            let body = match hyper::body::to_bytes(req.into_body()).await {
+                Ok(body) => body,
+                Err(err) => {
+                    return Ok(http::Response::builder()
+                        .status(http::StatusCode::BAD_REQUEST)
+                        .body(boxed(err.to_string()))
+                        .unwrap())
+                }
+            };
+            inner.call(&method, &path, query_params, &body).await
+        })
     }
 }
 
@@ -124,8 +142,10 @@ impl Router {
 
     pub async fn call(
         &self,
+        method: &http::Method,
         path: &str,
         params: HashMap<String, String>,
+        body: &[u8],
     ) -> Result<http::Response<BoxBody>, std::convert::Infallible> {
         let handle = match self.handles.get(path) {
             Some(handle) => handle,
@@ -137,10 +157,10 @@ impl Router {
             }
         };
 
-        let resp = match handle.call(path, &params).await {
+        let resp = match handle.call(method, path, &params, body).await {
             Ok(resp) => resp.map(boxed),
             Err(e) => http::Response::builder()
-                .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                .status(status_code(&e))
                 .body(boxed(e.to_string()))
                 .unwrap(),
         };
@@ -149,6 +169,18 @@ impl Router {
     }
 }
 
+fn status_code(err: &crate::Error) -> http::StatusCode {
+    use crate::Error;
+
+    match err {
+        Error::InvalidArgument(_) => http::StatusCode::BAD_REQUEST,
+        Error::DatabaseNotFound(_) => http::StatusCode::NOT_FOUND,
+        Error::AlreadyExists(_) => http::StatusCode::CONFLICT,
+        Error::DeadlineExceeded(_) => http::StatusCode::GATEWAY_TIMEOUT,
+        _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 fn boxed(body: String) -> BoxBody {
     use http_body::Body;
 
@@ -27,8 +27,12 @@ make_static_metric! {
             batch_write,
             accept_shard,
             create_shard,
+            remove_shard,
             move_replicas,
             change_replicas,
+            ingest,
+            scan,
+            delete_range,
         }
     }
     pub struct GroupRequestDuration: Histogram {
@@ -41,8 +45,12 @@ make_static_metric! {
             batch_write,
             accept_shard,
             create_shard,
+            remove_shard,
             move_replicas,
             change_replicas,
+            ingest,
+            scan,
+            delete_range,
         }
     }
 }
@@ -101,6 +109,10 @@ pub fn take_group_request_metrics(request: &GroupRequest) -> Option<&'static His
             NODE_SERVICE_GROUP_REQUEST_TOTAL.create_shard.inc();
             Some(&NODE_SERVICE_GROUP_REQUEST_DURATION_SECONDS.create_shard)
         }
+        Some(Request::RemoveShard(_)) => {
+            NODE_SERVICE_GROUP_REQUEST_TOTAL.remove_shard.inc();
+            Some(&NODE_SERVICE_GROUP_REQUEST_DURATION_SECONDS.remove_shard)
+        }
         Some(Request::ChangeReplicas(_)) => {
             NODE_SERVICE_GROUP_REQUEST_TOTAL.change_replicas.inc();
             Some(&NODE_SERVICE_GROUP_REQUEST_DURATION_SECONDS.change_replicas)
@@ -113,6 +125,18 @@ pub fn take_group_request_metrics(request: &GroupRequest) -> Option<&'static His
             NODE_SERVICE_GROUP_REQUEST_TOTAL.move_replicas.inc();
             Some(&NODE_SERVICE_GROUP_REQUEST_DURATION_SECONDS.move_replicas)
         }
+        Some(Request::Ingest(_)) => {
+            NODE_SERVICE_GROUP_REQUEST_TOTAL.ingest.inc();
+            Some(&NODE_SERVICE_GROUP_REQUEST_DURATION_SECONDS.ingest)
+        }
+        Some(Request::Scan(_)) => {
+            NODE_SERVICE_GROUP_REQUEST_TOTAL.scan.inc();
+            Some(&NODE_SERVICE_GROUP_REQUEST_DURATION_SECONDS.scan)
+        }
+        Some(Request::DeleteRange(_)) => {
+            NODE_SERVICE_GROUP_REQUEST_TOTAL.delete_range.inc();
+            Some(&NODE_SERVICE_GROUP_REQUEST_DURATION_SECONDS.delete_range)
+        }
         None => None,
     }
 }
@@ -176,6 +200,7 @@ simple_node_method!(root_heartbeat);
 simple_node_method!(migrate);
 simple_node_method!(pull);
 simple_node_method!(forward);
+simple_node_method!(collect_checksum);
 
 macro_rules! simple_root_method {
     ($name: ident) => {
@@ -215,6 +240,11 @@ lazy_static! {
         "The total msg requests of raft service",
     )
     .unwrap();
+    pub static ref RAFT_SERVICE_MSG_BATCH_TOTAL: IntCounter = register_int_counter!(
+        "raft_service_msg_batch_total",
+        "The total msg batches of raft service",
+    )
+    .unwrap();
     pub static ref RAFT_SERVICE_SNAPSHOT_REQUEST_TOTAL: IntCounter = register_int_counter!(
         "raft_service_snapshot_request_total",
         "The total snapshot requests of raft service",
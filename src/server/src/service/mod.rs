@@ -16,12 +16,14 @@ mod metrics;
 pub mod node;
 pub mod proxy;
 pub mod raft;
+pub mod resp;
 pub mod root;
 
 use std::{sync::Arc, time::Duration};
 
 use engula_client::{ClientOptions, EngulaClient};
 
+pub use self::resp::RespServer;
 use crate::{
     node::Node,
     root::Root,
@@ -126,6 +126,21 @@ impl node_server::Node for Server {
                         self.node.collect_schedule_state(&req).await,
                     )
                 }
+                piggyback_request::Info::SyncNodeLiveness(req) => {
+                    piggyback_response::Info::SyncNodeLiveness(
+                        self.node.sync_node_liveness(&req).await,
+                    )
+                }
+                piggyback_request::Info::SyncDatabaseQuota(req) => {
+                    piggyback_response::Info::SyncDatabaseQuota(
+                        self.node.sync_database_quota(&req).await,
+                    )
+                }
+                piggyback_request::Info::SyncReplicationState(req) => {
+                    piggyback_response::Info::SyncReplicationState(
+                        self.node.sync_replication_state(&req),
+                    )
+                }
             };
             piggybacks_resps.push(PiggybackResponse { info: Some(info) });
         }
@@ -167,6 +182,16 @@ impl node_server::Node for Server {
         let resp = self.node.forward(req).await?;
         Ok(Response::new(resp))
     }
+
+    async fn collect_checksum(
+        &self,
+        request: Request<CollectChecksumRequest>,
+    ) -> Result<Response<CollectChecksumResponse>, Status> {
+        record_latency!(take_collect_checksum_request_metrics());
+        let req = request.into_inner();
+        let resp = self.node.collect_checksum(&req)?;
+        Ok(Response::new(resp))
+    }
 }
 
 impl Server {
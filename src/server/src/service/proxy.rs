@@ -139,9 +139,23 @@ impl ProxyServer {
 
     async fn update_database(
         &self,
-        _req: UpdateDatabaseRequest,
+        req: UpdateDatabaseRequest,
     ) -> Result<UpdateDatabaseResponse, Status> {
-        Err(Status::unimplemented("ProxyServer::update_database"))
+        use engula_api::v1::update_database_request::Action;
+
+        let quota = match req.action {
+            Some(Action::SetQuota(set_quota)) => set_quota.quota.unwrap_or_default(),
+            None => {
+                return Err(Status::invalid_argument(
+                    "UpdateDatabaseRequest::action is required",
+                ))
+            }
+        };
+        let database = self.client.open_database(req.name).await?;
+        let database = database.set_quota(quota).await?;
+        Ok(UpdateDatabaseResponse {
+            database: Some(database.desc()),
+        })
     }
 
     async fn delete_database(
@@ -161,9 +175,11 @@ impl ProxyServer {
         })?;
         let name = req.name;
         let database = Database::new(self.client.clone(), desc, None);
-        let collection = database.open_collection(name).await?;
+        let collection = database.open_collection(name.clone()).await?;
+        let stats = database.collection_stats(name).await?;
         Ok(GetCollectionResponse {
             collection: Some(collection.desc()),
+            stats: Some(stats),
         })
     }
 
@@ -17,7 +17,10 @@ use tonic::{Request, Response, Status, Streaming};
 use tracing::{error, warn};
 
 use crate::{
-    raftgroup::snap::send::{send_snapshot, SnapshotChunkStream},
+    raftgroup::{
+        snap::send::{send_snapshot, SnapshotChunkStream},
+        unpack_message_batch,
+    },
     serverpb::v1::*,
     service::metrics::*,
     Server,
@@ -31,10 +34,22 @@ impl raft_server::Raft for Server {
         &self,
         request: Request<Streaming<RaftMessage>>,
     ) -> Result<Response<RaftDone>, Status> {
+        #[cfg(feature = "sim")]
+        let conn_info = request
+            .extensions()
+            .get::<crate::sim::SimConnectInfo>()
+            .cloned();
         let mut in_stream = request.into_inner();
         while let Some(next_msg) = in_stream.next().await {
             match next_msg {
                 Ok(msg) => {
+                    #[cfg(feature = "sim")]
+                    if let Some(conn_info) = &conn_info {
+                        if !conn_info.deliver().await {
+                            continue;
+                        }
+                    }
+
                     RAFT_SERVICE_MSG_REQUEST_TOTAL.inc();
                     RAFT_SERVICE_MSG_BATCH_SIZE.observe(msg.messages.len() as f64);
 
@@ -67,6 +82,63 @@ impl raft_server::Raft for Server {
         Ok(Response::new(RaftDone {}))
     }
 
+    async fn send_messages(
+        &self,
+        request: Request<Streaming<RaftMessageBatch>>,
+    ) -> Result<Response<RaftDone>, Status> {
+        #[cfg(feature = "sim")]
+        let conn_info = request
+            .extensions()
+            .get::<crate::sim::SimConnectInfo>()
+            .cloned();
+        let mut in_stream = request.into_inner();
+        while let Some(next_batch) = in_stream.next().await {
+            let batch = match next_batch {
+                Ok(batch) => batch,
+                Err(e) => {
+                    warn!(err = ?e, "receive message batches");
+                    break;
+                }
+            };
+
+            #[cfg(feature = "sim")]
+            if let Some(conn_info) = &conn_info {
+                if !conn_info.deliver().await {
+                    continue;
+                }
+            }
+
+            let msgs = match unpack_message_batch(batch) {
+                Ok(msgs) => msgs,
+                Err(err) => {
+                    error!(err = ?err, "unpack message batch");
+                    break;
+                }
+            };
+            RAFT_SERVICE_MSG_BATCH_TOTAL.inc();
+            for msg in msgs {
+                RAFT_SERVICE_MSG_REQUEST_TOTAL.inc();
+                RAFT_SERVICE_MSG_BATCH_SIZE.observe(msg.messages.len() as f64);
+
+                // The messages of other groups share the stream, so it continues even if the
+                // target replica doesn't exist.
+                let target_replica_id = msg.to_replica.as_ref().unwrap().id;
+                let replica = msg.from_replica.as_ref().unwrap();
+                let from_replica_id = replica.id;
+                let from_node_id = replica.node_id;
+                if let Some(mut sender) = self.node.raft_route_table().find(target_replica_id) {
+                    if sender.step(msg).is_ok() {
+                        continue;
+                    }
+                }
+                warn!(
+                    "receive message from node {from_node_id} replica {from_replica_id} to a not existed replica {target_replica_id}",
+                );
+            }
+        }
+        Ok(Response::new(RaftDone {}))
+    }
+
     async fn retrieve_snapshot(
         &self,
         request: Request<SnapshotRequest>,
@@ -76,7 +148,15 @@ impl raft_server::Raft for Server {
         let request = request.into_inner();
         let snap_mgr = self.node.raft_manager().snapshot_manager();
 
-        let stream = send_snapshot(snap_mgr, request.replica_id, request.snapshot_id).await?;
+        let stream = send_snapshot(
+            snap_mgr,
+            request.replica_id,
+            request.snapshot_id,
+            request.file_index,
+            request.offset,
+        )
+        .await?
+        .negotiate_compression(&request.accepted_compressions);
         Ok(Response::new(stream))
     }
 }
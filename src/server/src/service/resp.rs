@@ -0,0 +1,860 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A Redis protocol (RESP) compatible front end, the commands are executed on the configured
+//! database and collection through [`engula_client::Collection`].
+//!
+//! Engula doesn't support TTL, so the expiration set by `EXPIRE` or `SET ... EX` is stored with
+//! the value, see [`encode_value`]. The expired values are invisible to the commands, and they are
+//! overwritten by the following writes. Thus the collection should be dedicated to the listeners.
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use engula_client::{AppResult, ClientOptions, Collection, EngulaClient};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
+    net::{TcpListener, TcpStream},
+    sync::OnceCell,
+};
+use tracing::{debug, info, warn};
+
+use crate::{transport::TransportManager, RespConfig, Result};
+
+/// The max length of an inline command or the header lines of a multibulk command.
+const MAX_INLINE_LEN: usize = 64 * 1024;
+/// The max length of a bulk string. The values are written through gRPC, whose max decoding
+/// message size is 4MB by default, so a larger value can't be written anyway.
+const MAX_BULK_LEN: usize = 4 * 1024 * 1024;
+/// The max number of arguments of a multibulk command, the same as redis.
+const MAX_MULTIBULK_LEN: i64 = 1024 * 1024;
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+#[derive(Clone)]
+pub struct RespServer {
+    inner: Arc<RespServerInner>,
+}
+
+struct RespServerInner {
+    cfg: RespConfig,
+    client: EngulaClient,
+    collection: OnceCell<Collection>,
+    /// Serialize the read-modify-write commands of this listener.
+    rmw_lock: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Reply {
+    Simple(&'static str),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Vec<Reply>),
+}
+
+impl RespServer {
+    pub(crate) fn new(transport_manager: &TransportManager, cfg: RespConfig) -> Self {
+        let opts = ClientOptions {
+            connect_timeout: Some(Duration::from_millis(250)),
+            timeout: Some(cfg.rpc_timeout()),
+        };
+        RespServer {
+            inner: Arc::new(RespServerInner {
+                cfg,
+                client: transport_manager.build_client(opts),
+                collection: OnceCell::new(),
+                rmw_lock: tokio::sync::Mutex::default(),
+            }),
+        }
+    }
+
+    pub(crate) fn addr(&self) -> &str {
+        &self.inner.cfg.addr
+    }
+
+    /// Accept and serve the incoming RESP connections.
+    pub(crate) async fn serve(self, listener: TcpListener) -> Result<()> {
+        info!("serve RESP requests at {}", self.addr());
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = self.clone();
+            crate::runtime::current().spawn(
+                None,
+                crate::runtime::TaskPriority::Middle,
+                async move {
+                    if let Err(err) = server.serve_connection(stream).await {
+                        debug!("RESP connection {peer} is closed: {err:?}");
+                    }
+                },
+            );
+        }
+    }
+
+    async fn serve_connection(&self, stream: TcpStream) -> Result<()> {
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut writer = BufWriter::new(writer);
+        loop {
+            let args = match read_command(&mut reader).await {
+                Ok(Some(args)) => args,
+                Ok(None) => return Ok(()),
+                Err(msg) => {
+                    // The stream is broken, reply the error and close the connection.
+                    let mut buf = Vec::new();
+                    encode_reply(
+                        &Reply::Error(format!("ERR Protocol error: {msg}")),
+                        &mut buf,
+                    );
+                    writer.write_all(&buf).await?;
+                    writer.flush().await?;
+                    return Ok(());
+                }
+            };
+            if args.is_empty() {
+                continue;
+            }
+
+            let quit = args[0].eq_ignore_ascii_case(b"quit");
+            let reply = if quit {
+                Reply::Simple("OK")
+            } else {
+                self.execute(args).await
+            };
+            let mut buf = Vec::new();
+            encode_reply(&reply, &mut buf);
+            writer.write_all(&buf).await?;
+            // Flush only if there are no pipelined commands.
+            if quit || reader.buffer().is_empty() {
+                writer.flush().await?;
+            }
+            if quit {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn execute(&self, args: Vec<Vec<u8>>) -> Reply {
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_lowercase();
+        let args = &args[1..];
+        let arity_ok = match name.as_str() {
+            "ping" => args.len() <= 1,
+            "echo" | "get" | "incr" => args.len() == 1,
+            "command" => true,
+            "set" => args.len() >= 2,
+            "del" | "exists" | "mget" => !args.is_empty(),
+            "mset" => !args.is_empty() && args.len() % 2 == 0,
+            "expire" => args.len() == 2,
+            "scan" => !args.is_empty(),
+            _ => return Reply::Error(format!("ERR unknown command '{name}'")),
+        };
+        if !arity_ok {
+            return Reply::Error(format!(
+                "ERR wrong number of arguments for '{name}' command"
+            ));
+        }
+
+        match name.as_str() {
+            "ping" => match args.first() {
+                Some(msg) => Reply::Bulk(Some(msg.to_owned())),
+                None => Reply::Simple("PONG"),
+            },
+            "echo" => Reply::Bulk(Some(args[0].to_owned())),
+            // Some clients issue `COMMAND` or `COMMAND DOCS` once connected.
+            "command" => Reply::Array(vec![]),
+            _ => {
+                let collection = match self.collection().await {
+                    Ok(collection) => collection,
+                    Err(err) => return Reply::Error(format!("ERR {err}")),
+                };
+                let res = match name.as_str() {
+                    "get" => self.get(collection, &args[0]).await.map(Reply::Bulk),
+                    "set" => self.set(collection, args).await,
+                    "del" => self.del(collection, args).await,
+                    "exists" => self.exists(collection, args).await,
+                    "mget" => self.mget(collection, args).await,
+                    "mset" => self.mset(collection, args).await,
+                    "incr" => self.incr(collection, &args[0]).await,
+                    "expire" => self.expire(collection, args).await,
+                    "scan" => self.scan(collection, args).await,
+                    _ => unreachable!(),
+                };
+                res.unwrap_or_else(|err| {
+                    warn!("execute RESP command {name}: {err}");
+                    Reply::Error(format!("ERR {err}"))
+                })
+            }
+        }
+    }
+
+    async fn collection(&self) -> AppResult<&Collection> {
+        let inner = &self.inner;
+        inner
+            .collection
+            .get_or_try_init(|| async {
+                let db = inner
+                    .client
+                    .open_database(inner.cfg.database.clone())
+                    .await?;
+                db.open_collection(inner.cfg.collection.clone()).await
+            })
+            .await
+    }
+
+    async fn get(&self, collection: &Collection, key: &[u8]) -> AppResult<Option<Vec<u8>>> {
+        Ok(self
+            .get_with_expiration(collection, key)
+            .await?
+            .map(|(value, _)| value))
+    }
+
+    /// Return the value and its expiration (in unix milliseconds) of key, the expired value is
+    /// treated as not existing.
+    async fn get_with_expiration(
+        &self,
+        collection: &Collection,
+        key: &[u8],
+    ) -> AppResult<Option<(Vec<u8>, Option<u64>)>> {
+        let value = collection.get(key.to_owned()).await?;
+        Ok(value
+            .map(|value| decode_value(&value))
+            .filter(|(_, expire_at)| !is_expired(*expire_at)))
+    }
+
+    async fn put(
+        &self,
+        collection: &Collection,
+        key: &[u8],
+        value: &[u8],
+        expire_at: Option<u64>,
+    ) -> AppResult<()> {
+        collection
+            .put(key.to_owned(), encode_value(value, expire_at))
+            .await
+    }
+
+    async fn set(&self, collection: &Collection, args: &[Vec<u8>]) -> AppResult<Reply> {
+        let (key, value) = (&args[0], &args[1]);
+        let mut ttl = None;
+        let mut only_if = None;
+        let mut idx = 2;
+        while idx < args.len() {
+            let opt = String::from_utf8_lossy(&args[idx]).to_ascii_lowercase();
+            match opt.as_str() {
+                "nx" | "xx" if only_if.is_none() => only_if = Some(opt == "xx"),
+                "ex" | "px" if ttl.is_none() && idx + 1 < args.len() => {
+                    idx += 1;
+                    let Some(v) = parse_integer(&args[idx]).filter(|v| *v > 0) else {
+                        return Ok(Reply::Error("ERR invalid expire time in 'set' command".into()));
+                    };
+                    ttl = Some(if opt == "ex" {
+                        Duration::from_secs(v as u64)
+                    } else {
+                        Duration::from_millis(v as u64)
+                    });
+                }
+                _ => return Ok(Reply::Error("ERR syntax error".into())),
+            }
+            idx += 1;
+        }
+
+        let _guard = match only_if {
+            Some(exists) => {
+                let guard = self.inner.rmw_lock.lock().await;
+                if self.get(collection, key).await?.is_some() != exists {
+                    return Ok(Reply::Bulk(None));
+                }
+                Some(guard)
+            }
+            None => None,
+        };
+        let expire_at = ttl.map(|ttl| now_millis().saturating_add(ttl.as_millis() as u64));
+        self.put(collection, key, value, expire_at).await?;
+        Ok(Reply::Simple("OK"))
+    }
+
+    async fn del(&self, collection: &Collection, keys: &[Vec<u8>]) -> AppResult<Reply> {
+        let mut count = 0;
+        for key in keys {
+            if self.get(collection, key).await?.is_some() {
+                count += 1;
+            }
+            // The expired value is deleted too.
+            collection.delete(key.to_owned()).await?;
+        }
+        Ok(Reply::Integer(count))
+    }
+
+    async fn exists(&self, collection: &Collection, keys: &[Vec<u8>]) -> AppResult<Reply> {
+        let mut count = 0;
+        for key in keys {
+            if self.get(collection, key).await?.is_some() {
+                count += 1;
+            }
+        }
+        Ok(Reply::Integer(count))
+    }
+
+    async fn mget(&self, collection: &Collection, keys: &[Vec<u8>]) -> AppResult<Reply> {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(Reply::Bulk(self.get(collection, key).await?));
+        }
+        Ok(Reply::Array(values))
+    }
+
+    async fn mset(&self, collection: &Collection, args: &[Vec<u8>]) -> AppResult<Reply> {
+        for kv in args.chunks(2) {
+            self.put(collection, &kv[0], &kv[1], None).await?;
+        }
+        Ok(Reply::Simple("OK"))
+    }
+
+    /// Increase the integer value of key by one, the expiration is kept. It is a
+    /// read-modify-write, only the commands of this listener are serialized.
+    async fn incr(&self, collection: &Collection, key: &[u8]) -> AppResult<Reply> {
+        let _guard = self.inner.rmw_lock.lock().await;
+        let (value, expire_at) = match self.get_with_expiration(collection, key).await? {
+            Some((value, expire_at)) => match parse_integer(&value) {
+                Some(v) => (v, expire_at),
+                None => {
+                    return Ok(Reply::Error(
+                        "ERR value is not an integer or out of range".into(),
+                    ))
+                }
+            },
+            None => (0, None),
+        };
+        let Some(value) = value.checked_add(1) else {
+            return Ok(Reply::Error("ERR increment or decrement would overflow".into()));
+        };
+        self.put(collection, key, value.to_string().as_bytes(), expire_at)
+            .await?;
+        Ok(Reply::Integer(value))
+    }
+
+    /// Set the expiration of key. It rewrites the value, so it is a read-modify-write too.
+    async fn expire(&self, collection: &Collection, args: &[Vec<u8>]) -> AppResult<Reply> {
+        let key = &args[0];
+        let Some(seconds) = parse_integer(&args[1]) else {
+            return Ok(Reply::Error(
+                "ERR value is not an integer or out of range".into(),
+            ));
+        };
+        let _guard = self.inner.rmw_lock.lock().await;
+        let Some(value) = self.get(collection, key).await? else {
+            return Ok(Reply::Integer(0));
+        };
+        if seconds <= 0 {
+            collection.delete(key.to_owned()).await?;
+        } else {
+            let expire_at = now_millis().saturating_add((seconds as u64).saturating_mul(1000));
+            self.put(collection, key, &value, Some(expire_at)).await?;
+        }
+        Ok(Reply::Integer(1))
+    }
+
+    /// `SCAN cursor [MATCH pattern] [COUNT count]`, the cursor is the hex encoded last key
+    /// already iterated, and the iteration is resumed after it. Like redis, the entries added or
+    /// removed during the iteration might be missed.
+    async fn scan(&self, collection: &Collection, args: &[Vec<u8>]) -> AppResult<Reply> {
+        let Some(cursor) = decode_cursor(&args[0]) else {
+            return Ok(Reply::Error("ERR invalid cursor".into()));
+        };
+        let mut pattern = None;
+        let mut count = DEFAULT_SCAN_COUNT;
+        for opt in args[1..].chunks(2) {
+            if opt.len() != 2 {
+                return Ok(Reply::Error("ERR syntax error".into()));
+            }
+            match String::from_utf8_lossy(&opt[0])
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "match" => pattern = Some(opt[1].to_owned()),
+                "count" => match parse_integer(&opt[1]).filter(|v| *v > 0) {
+                    Some(v) => count = v as usize,
+                    None => return Ok(Reply::Error("ERR syntax error".into())),
+                },
+                _ => return Ok(Reply::Error("ERR syntax error".into())),
+            }
+        }
+
+        let prefix = pattern
+            .as_deref()
+            .map(literal_prefix)
+            .unwrap_or_default()
+            .to_owned();
+        let entries = collection.prefix_scan_after(prefix, cursor, count).await?;
+        let next_cursor = match entries.last() {
+            Some((key, _)) if entries.len() >= count => encode_cursor(key),
+            _ => b"0".to_vec(),
+        };
+        let keys = entries
+            .into_iter()
+            .filter(|(_, value)| !is_expired(decode_value(value).1))
+            .map(|(key, _)| key)
+            .filter(|key| pattern.as_deref().map_or(true, |p| glob_match(p, key)))
+            .map(|key| Reply::Bulk(Some(key)))
+            .collect();
+        Ok(Reply::Array(vec![
+            Reply::Bulk(Some(next_cursor)),
+            Reply::Array(keys),
+        ]))
+    }
+}
+
+/// Read a command from stream, both the multibulk and inline formats are supported. `None` is
+/// returned if the stream reaches EOF.
+async fn read_command<R>(reader: &mut R) -> std::result::Result<Option<Vec<Vec<u8>>>, String>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    let Some(line) = read_line(reader, MAX_INLINE_LEN).await? else {
+        return Ok(None);
+    };
+    if line.first() != Some(&b'*') {
+        let args = line
+            .split(|c| c.is_ascii_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .map(<[u8]>::to_vec)
+            .collect();
+        return Ok(Some(args));
+    }
+
+    let num_args = parse_integer(&line[1..])
+        .filter(|len| *len <= MAX_MULTIBULK_LEN)
+        .ok_or("invalid multibulk length")?;
+    let mut args = Vec::with_capacity(num_args.clamp(0, 1024) as usize);
+    for _ in 0..num_args {
+        let line = read_line(reader, MAX_INLINE_LEN)
+            .await?
+            .ok_or("unexpected EOF")?;
+        if line.first() != Some(&b'$') {
+            return Err(format!(
+                "expected '$', got '{}'",
+                String::from_utf8_lossy(&line)
+            ));
+        }
+        let len = parse_integer(&line[1..])
+            .filter(|len| (0..=MAX_BULK_LEN as i64).contains(len))
+            .ok_or("invalid bulk length")? as usize;
+        // The buffer grows with the received data, instead of allocating the claimed length up
+        // front.
+        let mut buf = Vec::with_capacity(len.min(MAX_INLINE_LEN) + 2);
+        (&mut *reader)
+            .take(len as u64 + 2)
+            .read_to_end(&mut buf)
+            .await
+            .map_err(|err| err.to_string())?;
+        if buf.len() != len + 2 || !buf.ends_with(b"\r\n") {
+            return Err("invalid bulk string".into());
+        }
+        buf.truncate(len);
+        args.push(buf);
+    }
+    Ok(Some(args))
+}
+
+/// Read a line terminated by `\n`, an error is returned if the line is longer than `limit`.
+async fn read_line<R>(reader: &mut R, limit: usize) -> std::result::Result<Option<Vec<u8>>, String>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    let mut line = Vec::new();
+    loop {
+        let buf = reader.fill_buf().await.map_err(|err| err.to_string())?;
+        if buf.is_empty() {
+            if line.is_empty() {
+                return Ok(None);
+            }
+            return Err("unexpected EOF".into());
+        }
+        let (consumed, finished) = match buf.iter().position(|c| *c == b'\n') {
+            Some(idx) => {
+                line.extend_from_slice(&buf[..idx]);
+                (idx + 1, true)
+            }
+            None => {
+                line.extend_from_slice(buf);
+                (buf.len(), false)
+            }
+        };
+        reader.consume(consumed);
+        if line.len() > limit {
+            return Err("too big request".into());
+        }
+        if finished {
+            break;
+        }
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(Some(line))
+}
+
+fn encode_reply(reply: &Reply, buf: &mut Vec<u8>) {
+    match reply {
+        Reply::Simple(msg) => buf.extend_from_slice(format!("+{msg}\r\n").as_bytes()),
+        Reply::Error(msg) => {
+            // Simple strings can't contain newlines.
+            let msg = msg.replace(['\r', '\n'], " ");
+            buf.extend_from_slice(format!("-{msg}\r\n").as_bytes());
+        }
+        Reply::Integer(v) => buf.extend_from_slice(format!(":{v}\r\n").as_bytes()),
+        Reply::Bulk(None) => buf.extend_from_slice(b"$-1\r\n"),
+        Reply::Bulk(Some(data)) => {
+            buf.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+            buf.extend_from_slice(data);
+            buf.extend_from_slice(b"\r\n");
+        }
+        Reply::Array(replies) => {
+            buf.extend_from_slice(format!("*{}\r\n", replies.len()).as_bytes());
+            for reply in replies {
+                encode_reply(reply, buf);
+            }
+        }
+    }
+}
+
+fn parse_integer(data: &[u8]) -> Option<i64> {
+    std::str::from_utf8(data).ok()?.parse().ok()
+}
+
+/// Encode the last iterated key as a scan cursor. The cursor `0` means the start or the end of
+/// an iteration, it never collides with the encoded keys since they have even length.
+fn encode_cursor(key: &[u8]) -> Vec<u8> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut cursor = Vec::with_capacity(key.len() * 2);
+    for c in key {
+        cursor.push(HEX[(c >> 4) as usize]);
+        cursor.push(HEX[(c & 0xf) as usize]);
+    }
+    cursor
+}
+
+/// Decode the last iterated key from a scan cursor, an empty key means the start of iteration.
+fn decode_cursor(cursor: &[u8]) -> Option<Vec<u8>> {
+    if cursor == b"0" {
+        return Some(vec![]);
+    }
+    if cursor.is_empty() || cursor.len() % 2 != 0 {
+        return None;
+    }
+    cursor
+        .chunks(2)
+        .map(|c| {
+            let hi = (c[0] as char).to_digit(16)?;
+            let lo = (c[1] as char).to_digit(16)?;
+            Some((hi << 4 | lo) as u8)
+        })
+        .collect()
+}
+
+/// The literal prefix of a glob pattern, it is used to narrow the range of scan.
+fn literal_prefix(pattern: &[u8]) -> &[u8] {
+    let end = pattern
+        .iter()
+        .position(|c| matches!(c, b'*' | b'?' | b'[' | b'\\'))
+        .unwrap_or(pattern.len());
+    &pattern[..end]
+}
+
+/// Match key with the redis style glob pattern, which supports `*`, `?`, `[...]` and `\`.
+///
+/// It is matched iteratively, only the last `*` is backtracked, so the cost is bounded by
+/// `O(pattern.len() * key.len())`.
+fn glob_match(pattern: &[u8], key: &[u8]) -> bool {
+    let (mut p, mut k) = (0, 0);
+    // The pattern position after the last `*`, and the key position matched by it.
+    let mut backtrack = None;
+    while k < key.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            backtrack = Some((p, k));
+            continue;
+        }
+        if let Some(len) = match_token(&pattern[p..], key[k]) {
+            p += len;
+            k += 1;
+            continue;
+        }
+        match backtrack {
+            Some((star_p, star_k)) => {
+                // Let the last `*` consume one more byte.
+                p = star_p;
+                k = star_k + 1;
+                backtrack = Some((star_p, k));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Match a byte with the first token of pattern, returns the length of the token if matched.
+fn match_token(pattern: &[u8], c: u8) -> Option<usize> {
+    match pattern.split_first()? {
+        (b'?', _) => Some(1),
+        (b'[', rest) => {
+            let Some(close) = rest.iter().skip(1).position(|&c| c == b']').map(|i| i + 1) else {
+                // Unclosed bracket is treated as a literal.
+                return (c == b'[').then_some(1);
+            };
+            let (class, negate) = match rest[..close].split_first() {
+                Some((b'^', class)) => (class, true),
+                _ => (&rest[..close], false),
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == b'-' {
+                    let (lo, hi) = (class[i].min(class[i + 2]), class[i].max(class[i + 2]));
+                    matched |= (lo..=hi).contains(&c);
+                    i += 3;
+                } else {
+                    matched |= class[i] == c;
+                    i += 1;
+                }
+            }
+            (matched != negate).then_some(close + 2)
+        }
+        (b'\\', [escaped, ..]) => (*escaped == c).then_some(2),
+        (literal, _) => (*literal == c).then_some(1),
+    }
+}
+
+/// Encode value with its expiration (in unix milliseconds): a flag byte, which is 1 if the
+/// expiration exists, followed by the 8 bytes big endian expiration, then the user value.
+fn encode_value(value: &[u8], expire_at: Option<u64>) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(value.len() + 9);
+    match expire_at {
+        Some(expire_at) => {
+            buf.push(1);
+            buf.extend_from_slice(&expire_at.to_be_bytes());
+        }
+        None => buf.push(0),
+    }
+    buf.extend_from_slice(value);
+    buf
+}
+
+/// Decode the value encoded by [`encode_value`], the value which isn't written by the listeners is
+/// returned as it is.
+fn decode_value(value: &[u8]) -> (Vec<u8>, Option<u64>) {
+    match value.split_first() {
+        Some((0, data)) => (data.to_owned(), None),
+        Some((1, data)) if data.len() >= 8 => {
+            let (expire_at, data) = data.split_at(8);
+            let expire_at = u64::from_be_bytes(expire_at.try_into().unwrap());
+            (data.to_owned(), Some(expire_at))
+        }
+        _ => (value.to_owned(), None),
+    }
+}
+
+fn is_expired(expire_at: Option<u64>) -> bool {
+    expire_at.map_or(false, |expire_at| expire_at <= now_millis())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::ExecutorOwner;
+
+    async fn decode(input: &[u8]) -> std::result::Result<Option<Vec<Vec<u8>>>, String> {
+        let mut reader = BufReader::new(input);
+        read_command(&mut reader).await
+    }
+
+    #[test]
+    fn read_multibulk_and_inline_command() {
+        let executor_owner = ExecutorOwner::new(1);
+        executor_owner.executor().block_on(async {
+            let args = decode(b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$5\r\nv\r\nv1\r\n")
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                args,
+                vec![b"SET".to_vec(), b"k".to_vec(), b"v\r\nv1".to_vec()]
+            );
+
+            let args = decode(b"GET  key\r\n").await.unwrap().unwrap();
+            assert_eq!(args, vec![b"GET".to_vec(), b"key".to_vec()]);
+
+            assert_eq!(decode(b"").await.unwrap(), None);
+            assert!(decode(b"*1\r\n$3\r\nGET").await.is_err());
+            assert!(decode(b"*1\r\n:3\r\n").await.is_err());
+            assert!(decode(b"*1\r\n$-2\r\n").await.is_err());
+
+            // The claimed length is limited, and it isn't allocated up front.
+            let input = format!("*1\r\n${}\r\nGET\r\n", MAX_BULK_LEN + 1);
+            assert!(decode(input.as_bytes()).await.is_err());
+            let input = format!("*1\r\n${}\r\nGET\r\n", MAX_BULK_LEN);
+            assert!(decode(input.as_bytes()).await.is_err());
+            let input = format!("*{}\r\n", MAX_MULTIBULK_LEN + 1);
+            assert!(decode(input.as_bytes()).await.is_err());
+
+            let mut input = vec![b'a'; MAX_INLINE_LEN + 1];
+            input.extend_from_slice(b"\r\n");
+            assert!(decode(&input).await.is_err());
+            let mut input = vec![b'a'; MAX_INLINE_LEN];
+            input.extend_from_slice(b"\r\n");
+            assert!(decode(&input).await.unwrap().is_some());
+        });
+    }
+
+    #[test]
+    fn value_with_expiration() {
+        assert_eq!(
+            decode_value(&encode_value(b"v", None)),
+            (b"v".to_vec(), None)
+        );
+        assert_eq!(
+            decode_value(&encode_value(b"v", Some(123))),
+            (b"v".to_vec(), Some(123))
+        );
+        assert_eq!(decode_value(&encode_value(b"", Some(1))), (vec![], Some(1)));
+        // The values aren't written by listeners.
+        assert_eq!(decode_value(b""), (vec![], None));
+        assert_eq!(decode_value(b"\x01v"), (b"\x01v".to_vec(), None));
+
+        assert!(!is_expired(None));
+        assert!(is_expired(Some(now_millis())));
+        assert!(!is_expired(Some(now_millis() + 60_000)));
+    }
+
+    #[test]
+    fn scan_cursor() {
+        assert_eq!(decode_cursor(b"0"), Some(vec![]));
+        assert_eq!(encode_cursor(b"user:1"), b"757365723a31".to_vec());
+        assert_eq!(decode_cursor(b"757365723A31"), Some(b"user:1".to_vec()));
+        let key = vec![0, 0x7f, 0x80, 0xff];
+        assert_eq!(decode_cursor(&encode_cursor(&key)), Some(key));
+
+        assert_eq!(decode_cursor(b""), None);
+        assert_eq!(decode_cursor(b"123"), None);
+        assert_eq!(decode_cursor(b"zz"), None);
+        assert_eq!(decode_cursor(b"+1"), None);
+    }
+
+    #[test]
+    fn encode_replies() {
+        let reply = Reply::Array(vec![
+            Reply::Simple("OK"),
+            Reply::Error("ERR a\nb".into()),
+            Reply::Integer(-1),
+            Reply::Bulk(None),
+            Reply::Bulk(Some(b"v".to_vec())),
+        ]);
+        let mut buf = Vec::new();
+        encode_reply(&reply, &mut buf);
+        assert_eq!(
+            buf,
+            b"*5\r\n+OK\r\n-ERR a b\r\n:-1\r\n$-1\r\n$1\r\nv\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn glob_pattern() {
+        struct Case {
+            pattern: &'static [u8],
+            key: &'static [u8],
+            matched: bool,
+        }
+        let cases = vec![
+            Case {
+                pattern: b"*",
+                key: b"",
+                matched: true,
+            },
+            Case {
+                pattern: b"user:*",
+                key: b"user:1",
+                matched: true,
+            },
+            Case {
+                pattern: b"user:*",
+                key: b"order:1",
+                matched: false,
+            },
+            Case {
+                pattern: b"h?llo",
+                key: b"hello",
+                matched: true,
+            },
+            Case {
+                pattern: b"h?llo",
+                key: b"hllo",
+                matched: false,
+            },
+            Case {
+                pattern: b"h[ae]llo",
+                key: b"hallo",
+                matched: true,
+            },
+            Case {
+                pattern: b"h[^e]llo",
+                key: b"hello",
+                matched: false,
+            },
+            Case {
+                pattern: b"h[a-c]llo",
+                key: b"hbllo",
+                matched: true,
+            },
+            Case {
+                pattern: b"h\\*llo",
+                key: b"h*llo",
+                matched: true,
+            },
+            Case {
+                pattern: b"h\\*llo",
+                key: b"hello",
+                matched: false,
+            },
+        ];
+        for Case {
+            pattern,
+            key,
+            matched,
+        } in cases
+        {
+            assert_eq!(glob_match(pattern, key), matched, "{:?} {:?}", pattern, key);
+        }
+
+        // The matching cost isn't exponential.
+        let key = vec![b'a'; 64];
+        assert!(!glob_match(b"a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b", &key));
+        assert!(glob_match(b"*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*", &key));
+        assert!(glob_match(b"a[", b"a["));
+        assert!(glob_match(b"**", b"abc"));
+        assert!(!glob_match(b"a", b"ab"));
+
+        assert_eq!(literal_prefix(b"user:*"), b"user:");
+        assert_eq!(literal_prefix(b"a?c"), b"a");
+        assert_eq!(literal_prefix(b"abc"), b"abc");
+    }
+}
@@ -79,7 +79,7 @@ impl root_server::Root for Server {
         let replicas = self
             .wrap(
                 self.root
-                    .alloc_replica(req.group_id, req.epoch, req.num_required)
+                    .alloc_replica(req.group_id, req.epoch, req.num_required, req.read_learner)
                     .await,
             )
             .await?;
@@ -106,8 +106,9 @@ impl Server {
                 let res = self.handle_create_database(req).await?;
                 admin_response_union::Response::CreateDatabase(res)
             }
-            admin_request_union::Request::UpdateDatabase(_req) => {
-                todo!()
+            admin_request_union::Request::UpdateDatabase(req) => {
+                let res = self.handle_update_database(req).await?;
+                admin_response_union::Response::UpdateDatabase(res)
             }
             admin_request_union::Request::DeleteDatabase(req) => {
                 let res = self.handle_delete_database(req).await?;
@@ -125,8 +126,9 @@ impl Server {
                 let res = self.handle_create_collection(req).await?;
                 admin_response_union::Response::CreateCollection(res)
             }
-            admin_request_union::Request::UpdateCollection(_req) => {
-                todo!()
+            admin_request_union::Request::UpdateCollection(req) => {
+                let res = self.handle_update_collection(req).await?;
+                admin_response_union::Response::UpdateCollection(res)
             }
             admin_request_union::Request::DeleteCollection(req) => {
                 let res = self.handle_delete_collection(req).await?;
@@ -156,6 +158,16 @@ impl Server {
         })
     }
 
+    async fn handle_update_database(
+        &self,
+        req: UpdateDatabaseRequest,
+    ) -> Result<UpdateDatabaseResponse> {
+        let desc = self.root.update_database(&req.name, req.action).await?;
+        Ok(UpdateDatabaseResponse {
+            database: Some(desc),
+        })
+    }
+
     async fn handle_delete_database(
         &self,
         req: DeleteDatabaseRequest,
@@ -193,6 +205,22 @@ impl Server {
         })
     }
 
+    async fn handle_update_collection(
+        &self,
+        req: UpdateCollectionRequest,
+    ) -> Result<UpdateCollectionResponse> {
+        let database = req.database.ok_or_else(|| {
+            Error::InvalidArgument("UpdateCollectionRequest::database is required".to_owned())
+        })?;
+        let collection = self
+            .root
+            .update_collection(&req.name, &database, req.action)
+            .await?;
+        Ok(UpdateCollectionResponse {
+            collection: Some(collection),
+        })
+    }
+
     async fn handle_delete_collection(
         &self,
         req: DeleteCollectionRequest,
@@ -212,7 +240,10 @@ impl Server {
             Error::InvalidArgument("GetCollectionRequest::database is required".to_owned())
         })?;
         let collection = self.root.get_collection(&req.name, &database).await?;
-        Ok(GetCollectionResponse { collection })
+        let stats = collection
+            .as_ref()
+            .map(|desc| self.root.collection_stats(desc.id));
+        Ok(GetCollectionResponse { collection, stats })
     }
 
     async fn handle_list_collection(
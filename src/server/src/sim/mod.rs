@@ -0,0 +1,190 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic simulation of a cluster.
+//!
+//! All servers of a [`Simulation`] run on a single threaded executor whose clock is paused, the
+//! clock only advances when all tasks are idle, so timers are fired in a deterministic order
+//! regardless of the speed of the host. Servers are connected through an in-memory
+//! [`SimNetwork`], which injects seeded message loss, delay and partitions.
+//!
+//! Given the same seed, a scenario is replayed with the same schedule: blocking tasks run on the
+//! simulation thread, the `select!` of servers are biased, the network faults and the election
+//! timeouts of raft are derived from the seed, and the wall clock starts from
+//! [`WALL_CLOCK_BASE`]. The executor itself is not seeded, tasks are polled in the order they are
+//! woken. The sources out of control are the threads of the storage engines, which are expected
+//! to finish an IO before the simulation observes it, and the `select!` inside dependencies, which
+//! is seeded by tokio.
+
+mod net;
+
+use std::{cell::Cell, collections::HashMap, future::Future, time::Duration};
+
+use tracing::warn;
+
+pub use self::net::{SimConnectInfo, SimEndpoint, SimIncoming, SimNetwork, SimStream};
+use crate::{
+    runtime::{time::Instant, Executor, ExecutorOwner, Shutdown, ShutdownNotifier, TaskPriority},
+    transport::Network,
+    Config,
+};
+
+/// The wall clock of a simulation at its start, 2022-01-01T00:00:00Z.
+pub const WALL_CLOCK_BASE: Duration = Duration::from_secs(1_640_995_200);
+
+thread_local! {
+    /// The virtual instant at which the simulation running on the current thread starts.
+    static EPOCH: Cell<Option<Instant>> = Cell::new(None);
+}
+
+tokio::task_local! {
+    /// The shutdown signal of the server which the current task belongs to.
+    static SERVER: Shutdown;
+}
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub seed: u64,
+    /// The probability of dropping a raft message.
+    pub loss_rate: f64,
+    /// The lower bound of delays injected into connections and raft messages.
+    pub min_delay: Duration,
+    /// The upper bound of delays injected into connections and raft messages.
+    pub max_delay: Duration,
+}
+
+pub struct Simulation {
+    cfg: SimConfig,
+    owner: ExecutorOwner,
+    network: SimNetwork,
+    servers: HashMap<String, ShutdownNotifier>,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            seed: 0,
+            loss_rate: 0.0,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+        }
+    }
+}
+
+impl Simulation {
+    pub fn new(cfg: SimConfig) -> Self {
+        let owner = ExecutorOwner::simulated();
+        let epoch = owner.executor().block_on(async { Instant::now() });
+        EPOCH.with(|v| v.set(Some(epoch)));
+        let network = SimNetwork::new(&cfg);
+        Simulation {
+            cfg,
+            owner,
+            network,
+            servers: HashMap::default(),
+        }
+    }
+
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.cfg.seed
+    }
+
+    #[inline]
+    pub fn executor(&self) -> Executor {
+        self.owner.executor()
+    }
+
+    #[inline]
+    pub fn network(&self) -> SimNetwork {
+        self.network.clone()
+    }
+
+    /// Build a [`engula_client::ConnManager`] which connects servers through the simulated
+    /// network, as a client named `name`.
+    pub fn conn_manager(&self, name: &str) -> engula_client::ConnManager {
+        Network::Sim(self.network.endpoint(name)).conn_manager()
+    }
+
+    /// Drive the simulation until the future is finished.
+    pub fn block_on<F, T>(&self, future: F) -> T
+    where
+        F: Future<Output = T> + Send,
+        T: Send + 'static,
+    {
+        self.executor().block_on(future)
+    }
+
+    /// Start a server listening on `cfg.addr` of the simulated network.
+    pub fn start_server(&mut self, mut cfg: Config) {
+        cfg.raft.testing_knobs.election_seed = Some(self.cfg.seed);
+        let addr = cfg.addr.clone();
+        let endpoint = self.network.endpoint(&addr);
+        let notifier = ShutdownNotifier::new();
+        let shutdown = notifier.subscribe();
+        let server = notifier.subscribe();
+        let cloned_addr = addr.clone();
+        let future = bind_server(server, async move {
+            if let Err(err) = crate::bootstrap::serve(cfg, Network::Sim(endpoint), shutdown).await {
+                warn!("simulated server {cloned_addr} exits: {err:?}");
+            }
+        });
+        self.executor().spawn(None, TaskPriority::High, future);
+        self.servers.insert(addr, notifier);
+    }
+
+    /// Stop the server as if its process is killed: the connections of the server are broken and
+    /// all tasks spawned by the server are cancelled.
+    pub fn kill_server(&mut self, addr: &str) {
+        self.network.shutdown(addr);
+        self.servers.remove(addr);
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        EPOCH.with(|v| v.set(None));
+    }
+}
+
+/// Returns whether the current thread is driving a simulation.
+pub(crate) fn is_simulating() -> bool {
+    EPOCH.with(|v| v.get().is_some())
+}
+
+/// Returns the virtual time elapsed since the simulation running on the current thread starts.
+pub(crate) fn elapsed() -> Option<Duration> {
+    EPOCH.with(|v| v.get()).map(|epoch| epoch.elapsed())
+}
+
+/// Bind the future to the server of the current task, if any, so that the future is cancelled
+/// once the server is killed.
+pub(crate) fn bind_current_node<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    use futures::future::Either;
+
+    match SERVER.try_with(Clone::clone) {
+        Ok(server) => Either::Left(bind_server(server, future)),
+        Err(_) => Either::Right(future),
+    }
+}
+
+fn bind_server<F: Future>(server: Shutdown, future: F) -> impl Future<Output = F::Output> {
+    SERVER.scope(server.clone(), async move {
+        crate::runtime::select! {
+            biased;
+            _ = server => futures::future::pending().await,
+            output = future => output,
+        }
+    })
+}
@@ -0,0 +1,448 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    io::{Error as IoError, ErrorKind},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use engula_client::{BoxIo, Connector};
+use futures::{channel::mpsc, future::BoxFuture, Stream, StreamExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf};
+use tonic::transport::server::Connected;
+
+use super::SimConfig;
+
+/// The buffer size of a simulated connection.
+const CONNECTION_BUFFER_SIZE: usize = 64 << 10;
+
+/// An in-memory network which connects the servers of a simulation by addresses.
+///
+/// Partitions are applied to both connections and raft messages, while message loss is only
+/// applied to raft messages, because the other RPCs are not expected to tolerate an unreliable
+/// transport.
+#[derive(Clone)]
+pub struct SimNetwork {
+    core: Arc<Mutex<Core>>,
+}
+
+struct Core {
+    rng: StdRng,
+    loss_rate: f64,
+    min_delay: Duration,
+    max_delay: Duration,
+    listeners: HashMap<String, Listener>,
+    isolated: HashSet<String>,
+    partitions: HashSet<(String, String)>,
+    /// The digest of the fates of all messages, see [`SimNetwork::trace`].
+    trace: (usize, DefaultHasher),
+}
+
+struct Listener {
+    sender: mpsc::UnboundedSender<SimStream>,
+    alive: Arc<AtomicBool>,
+}
+
+/// The network interface of a server or a client, identified by its address.
+#[derive(Clone)]
+pub struct SimEndpoint {
+    network: SimNetwork,
+    addr: String,
+    alive: Arc<AtomicBool>,
+}
+
+pub struct SimIncoming {
+    receiver: mpsc::UnboundedReceiver<SimStream>,
+}
+
+/// One side of a simulated connection. The IO fails once the connection is partitioned or the
+/// server of either side is killed.
+pub struct SimStream {
+    inner: DuplexStream,
+    info: SimConnectInfo,
+    local_alive: Arc<AtomicBool>,
+    remote_alive: Arc<AtomicBool>,
+}
+
+/// The connection info of a [`SimStream`], which is attached to the requests received from it.
+#[derive(Clone)]
+pub struct SimConnectInfo {
+    network: SimNetwork,
+    local: String,
+    remote: String,
+}
+
+impl SimNetwork {
+    pub(super) fn new(cfg: &SimConfig) -> Self {
+        SimNetwork {
+            core: Arc::new(Mutex::new(Core {
+                rng: StdRng::seed_from_u64(cfg.seed),
+                loss_rate: cfg.loss_rate,
+                min_delay: cfg.min_delay,
+                max_delay: cfg.max_delay,
+                listeners: HashMap::default(),
+                isolated: HashSet::default(),
+                partitions: HashSet::default(),
+                trace: (0, DefaultHasher::new()),
+            })),
+        }
+    }
+
+    /// Create an endpoint with the address, the previous endpoint with the same address is
+    /// replaced.
+    pub fn endpoint(&self, addr: &str) -> SimEndpoint {
+        SimEndpoint {
+            network: self.clone(),
+            addr: addr.to_owned(),
+            alive: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    pub fn set_loss_rate(&self, loss_rate: f64) {
+        self.core.lock().unwrap().loss_rate = loss_rate;
+    }
+
+    pub fn set_delay(&self, min_delay: Duration, max_delay: Duration) {
+        let mut core = self.core.lock().unwrap();
+        core.min_delay = min_delay;
+        core.max_delay = max_delay;
+    }
+
+    /// Disconnect the two addresses.
+    pub fn partition(&self, a: &str, b: &str) {
+        let mut core = self.core.lock().unwrap();
+        core.partitions.insert(ordered_pair(a, b));
+    }
+
+    /// Disconnect the address from all others.
+    pub fn isolate(&self, addr: &str) {
+        self.core.lock().unwrap().isolated.insert(addr.to_owned());
+    }
+
+    /// Remove all partitions and isolations.
+    pub fn heal(&self) {
+        let mut core = self.core.lock().unwrap();
+        core.partitions.clear();
+        core.isolated.clear();
+    }
+
+    pub fn is_connected(&self, a: &str, b: &str) -> bool {
+        self.core.lock().unwrap().is_connected(a, b)
+    }
+
+    /// Returns the number of messages sent through the network, and a digest of their fates and
+    /// the virtual time they are sent at. Two runs of a scenario are the same if their traces are
+    /// equal.
+    pub fn trace(&self) -> (usize, u64) {
+        let core = self.core.lock().unwrap();
+        (core.trace.0, core.trace.1.finish())
+    }
+
+    /// Stop listening on the address and break all connections of the endpoint.
+    pub(super) fn shutdown(&self, addr: &str) {
+        let mut core = self.core.lock().unwrap();
+        if let Some(listener) = core.listeners.remove(addr) {
+            listener.alive.store(false, Ordering::Release);
+        }
+    }
+
+    fn sample_delay(&self) -> Duration {
+        self.core.lock().unwrap().sample_delay()
+    }
+
+    /// Decide the fate of a message sent from `from` to `to`, returns the delay before delivering
+    /// it, or `None` if the message is dropped.
+    fn sample_message(&self, from: &str, to: &str) -> Option<Duration> {
+        let mut core = self.core.lock().unwrap();
+        let fate = core.sample_message(from, to);
+        let now = crate::runtime::time::timestamp_millis();
+        core.trace.0 += 1;
+        (from, to, fate, now).hash(&mut core.trace.1);
+        fate
+    }
+}
+
+impl Core {
+    fn sample_message(&mut self, from: &str, to: &str) -> Option<Duration> {
+        if !self.is_connected(from, to) {
+            return None;
+        }
+        if self.loss_rate > 0.0 && self.rng.gen_bool(self.loss_rate.min(1.0)) {
+            return None;
+        }
+        Some(self.sample_delay())
+    }
+
+    fn is_connected(&self, a: &str, b: &str) -> bool {
+        !self.isolated.contains(a)
+            && !self.isolated.contains(b)
+            && !self.partitions.contains(&ordered_pair(a, b))
+    }
+
+    fn sample_delay(&mut self) -> Duration {
+        if self.max_delay <= self.min_delay {
+            self.min_delay
+        } else {
+            self.rng.gen_range(self.min_delay..=self.max_delay)
+        }
+    }
+}
+
+impl SimEndpoint {
+    #[inline]
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Listen on the address of the endpoint.
+    pub fn bind(&self) -> SimIncoming {
+        let (sender, receiver) = mpsc::unbounded();
+        let listener = Listener {
+            sender,
+            alive: self.alive.clone(),
+        };
+        let mut core = self.network.core.lock().unwrap();
+        core.listeners.insert(self.addr.clone(), listener);
+        SimIncoming { receiver }
+    }
+
+    pub async fn connect_to(&self, target: &str) -> std::io::Result<SimStream> {
+        crate::runtime::time::sleep(self.network.sample_delay()).await;
+
+        let (sender, remote_alive) = {
+            let core = self.network.core.lock().unwrap();
+            match core.listeners.get(target) {
+                Some(listener) if core.is_connected(&self.addr, target) => {
+                    (listener.sender.clone(), listener.alive.clone())
+                }
+                _ => return Err(IoError::from(ErrorKind::ConnectionRefused)),
+            }
+        };
+
+        let (client, server) = tokio::io::duplex(CONNECTION_BUFFER_SIZE);
+        let server = SimStream {
+            inner: server,
+            info: SimConnectInfo {
+                network: self.network.clone(),
+                local: target.to_owned(),
+                remote: self.addr.clone(),
+            },
+            local_alive: remote_alive.clone(),
+            remote_alive: self.alive.clone(),
+        };
+        sender
+            .unbounded_send(server)
+            .map_err(|_| IoError::from(ErrorKind::ConnectionRefused))?;
+        Ok(SimStream {
+            inner: client,
+            info: SimConnectInfo {
+                network: self.network.clone(),
+                local: self.addr.clone(),
+                remote: target.to_owned(),
+            },
+            local_alive: self.alive.clone(),
+            remote_alive,
+        })
+    }
+}
+
+impl Connector for SimEndpoint {
+    fn connect(&self, addr: String) -> BoxFuture<'static, std::io::Result<BoxIo>> {
+        let endpoint = self.clone();
+        Box::pin(async move {
+            let stream = endpoint.connect_to(&addr).await?;
+            Ok(Box::pin(stream) as BoxIo)
+        })
+    }
+}
+
+impl Stream for SimIncoming {
+    type Item = std::io::Result<SimStream>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver
+            .poll_next_unpin(cx)
+            .map(|stream| stream.map(Ok))
+    }
+}
+
+impl SimStream {
+    fn check_available(&self) -> std::io::Result<()> {
+        if self.local_alive.load(Ordering::Acquire)
+            && self.remote_alive.load(Ordering::Acquire)
+            && self
+                .info
+                .network
+                .is_connected(&self.info.local, &self.info.remote)
+        {
+            Ok(())
+        } else {
+            Err(IoError::from(ErrorKind::ConnectionReset))
+        }
+    }
+}
+
+impl AsyncRead for SimStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        self.check_available()?;
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SimStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.check_available()?;
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.check_available()?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl Connected for SimStream {
+    type ConnectInfo = SimConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.info.clone()
+    }
+}
+
+impl SimConnectInfo {
+    /// The address of the local side.
+    #[inline]
+    pub fn local(&self) -> &str {
+        &self.local
+    }
+
+    /// The address of the remote side.
+    #[inline]
+    pub fn remote(&self) -> &str {
+        &self.remote
+    }
+
+    /// Apply the faults to a message received from the remote side, returns `false` if the
+    /// message should be dropped.
+    pub async fn deliver(&self) -> bool {
+        match self.network.sample_message(&self.remote, &self.local) {
+            Some(delay) => {
+                crate::runtime::time::sleep(delay).await;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn ordered_pair(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_owned(), b.to_owned())
+    } else {
+        (b.to_owned(), a.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::runtime::ExecutorOwner;
+
+    fn new_network(seed: u64, loss_rate: f64) -> SimNetwork {
+        SimNetwork::new(&SimConfig {
+            seed,
+            loss_rate,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn connect_and_partition() {
+        let owner = ExecutorOwner::simulated();
+        owner.executor().block_on(async move {
+            let network = new_network(0, 0.0);
+            let a = network.endpoint("a");
+            let b = network.endpoint("b");
+            let mut incoming = b.bind();
+
+            assert!(a.connect_to("c").await.is_err());
+
+            let mut client = a.connect_to("b").await.unwrap();
+            let mut server = incoming.next().await.unwrap().unwrap();
+            assert_eq!(server.connect_info().remote(), "a");
+            client.write_all(b"hello").await.unwrap();
+            let mut buf = [0u8; 5];
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+
+            network.partition("a", "b");
+            assert!(client.write_all(b"hello").await.is_err());
+            assert!(a.connect_to("b").await.is_err());
+
+            network.heal();
+            assert!(a.connect_to("b").await.is_ok());
+
+            network.shutdown("b");
+            assert!(a.connect_to("b").await.is_err());
+            assert!(server.write_all(b"hello").await.is_err());
+        });
+    }
+
+    #[test]
+    fn message_loss_is_deterministic() {
+        let owner = ExecutorOwner::simulated();
+        owner.executor().block_on(async move {
+            let mut fates = vec![];
+            for _ in 0..2 {
+                let network = new_network(42, 0.3);
+                let info = SimConnectInfo {
+                    network,
+                    local: "a".to_owned(),
+                    remote: "b".to_owned(),
+                };
+                let mut delivered = vec![];
+                for _ in 0..100 {
+                    delivered.push(info.deliver().await);
+                }
+                fates.push(delivered);
+            }
+            assert_eq!(fates[0], fates[1]);
+            assert!(fates[0].iter().any(|v| *v));
+            assert!(fates[0].iter().any(|v| !*v));
+        });
+    }
+}
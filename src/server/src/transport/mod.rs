@@ -27,6 +27,14 @@ use engula_client::{
 pub(crate) use self::{discovery::RootDiscovery, resolver::AddressResolver};
 use crate::{engine::StateEngine, Result};
 
+/// The network used by a server to accept and establish connections.
+#[derive(Clone)]
+pub(crate) enum Network {
+    Tcp,
+    #[cfg(feature = "sim")]
+    Sim(crate::sim::SimEndpoint),
+}
+
 #[derive(Clone)]
 pub(crate) struct TransportManager {
     address_resolver: Arc<AddressResolver>,
@@ -35,10 +43,27 @@ pub(crate) struct TransportManager {
     router: Router,
 }
 
+impl Network {
+    pub(crate) fn conn_manager(&self) -> ConnManager {
+        match self {
+            Network::Tcp => ConnManager::new(),
+            #[cfg(feature = "sim")]
+            Network::Sim(endpoint) => ConnManager::with_connector(Arc::new(endpoint.clone())),
+        }
+    }
+}
+
 impl TransportManager {
     pub(crate) async fn new(root_list: Vec<String>, state_engine: StateEngine) -> Self {
+        Self::with_conn_manager(root_list, state_engine, ConnManager::new()).await
+    }
+
+    pub(crate) async fn with_conn_manager(
+        root_list: Vec<String>,
+        state_engine: StateEngine,
+        conn_manager: ConnManager,
+    ) -> Self {
         let discovery = Arc::new(RootDiscovery::new(root_list, state_engine));
-        let conn_manager = ConnManager::new();
         let root_client = RootClient::new(discovery, conn_manager.clone());
         let router = Router::new(root_client.clone()).await;
         let address_resolver = Arc::new(AddressResolver::new(router.clone()));
@@ -50,7 +75,6 @@ impl TransportManager {
         }
     }
 
-    #[allow(dead_code)]
     #[inline]
     pub(crate) fn conn_manager(&self) -> &ConnManager {
         &self.conn_manager
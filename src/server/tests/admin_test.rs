@@ -18,6 +18,7 @@ use std::time::Duration;
 use engula_api::v1::{CollectionDesc, DatabaseDesc};
 use engula_client::{ClientOptions, EngulaClient, NodeClient, Partition};
 use engula_server::diagnosis;
+use serde_json::json;
 use tracing::info;
 
 use crate::helper::{context::*, init::setup_panic_hook, runtime::block_on_current};
@@ -200,6 +201,278 @@ fn admin_basic() {
     })
 }
 
+#[test]
+fn admin_reject_illegal_cluster_operations() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("admin-reject-illegal-cluster-operations");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(1).await;
+        let addrs = nodes.values().cloned().collect::<Vec<_>>();
+        let root_addr = find_root(addrs.to_owned()).await;
+        let node_id = curr_metadata(addrs.to_owned()).await.nodes[0].id;
+
+        for path in [
+            format!("remove_node?node_id={node_id}"),
+            "transfer_leader?group_id=12345&replica_id=1".to_owned(),
+            "move_shard?shard_id=12345&dest_group_id=1".to_owned(),
+            "move_shard?shard_id=1".to_owned(),
+            "decommission?node_id=12345".to_owned(),
+            format!("decommission?node_id={node_id}"),
+            "job/cancel?job_id=12345".to_owned(),
+            "job/cancel".to_owned(),
+        ] {
+            let resp = reqwest::get(format!("http://{root_addr}/admin/{path}"))
+                .await
+                .unwrap();
+            assert!(!resp.status().is_success(), "{path} should be rejected");
+        }
+
+        let m = curr_metadata(addrs).await;
+        assert!(m.nodes.iter().any(|n| n.id == node_id));
+    })
+}
+
+#[test]
+fn admin_cancel_reshard_job() {
+    use engula_api::v1::collection_desc;
+
+    block_on_current(async {
+        let mut ctx = TestContext::new("admin-cancel-reshard-job");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let addrs = nodes.values().cloned().collect::<Vec<_>>();
+        let root_addr = find_root(addrs.to_owned()).await;
+        let c = EngulaClient::new(ClientOptions::default(), addrs.to_owned())
+            .await
+            .unwrap();
+        let db = c.create_database("test_db".into()).await.unwrap();
+        let co = db
+            .create_collection("test_co".into(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        for i in 0..1000 {
+            let k = format!("key-{i}").into_bytes();
+            let v = format!("value-{i}").into_bytes();
+            co.put(k, v).await.unwrap();
+        }
+
+        let jobs = |state: &'static str| {
+            let url = format!("http://{root_addr}/admin/job");
+            async move {
+                let resp = reqwest::get(url).await.unwrap();
+                let body = resp.json::<serde_json::Value>().await.unwrap();
+                body[state].as_array().cloned().unwrap_or_default()
+            }
+        };
+
+        db.reshard_collection("test_co".into(), 6).await.unwrap();
+        let job_id = jobs("ongoing")
+            .await
+            .iter()
+            .chain(jobs("history").await.iter())
+            .find(|job| job["type"] == "reshard collection")
+            .and_then(|job| job["id"].as_u64())
+            .unwrap();
+        // The job might be switched to the target slots already, then the canceling is rejected.
+        let resp = reqwest::get(format!(
+            "http://{root_addr}/admin/job/cancel?job_id={job_id}"
+        ))
+        .await
+        .unwrap();
+        info!("cancel reshard job {job_id}: {}", resp.status());
+
+        let mut status = None;
+        for _ in 0..100 {
+            if let Some(job) = jobs("history")
+                .await
+                .into_iter()
+                .find(|job| job["id"].as_u64() == Some(job_id))
+            {
+                status = job["status"].as_str().map(ToOwned::to_owned);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let expect_slots = match status.as_deref() {
+            Some("ReshardCollectionAbort") => 3,
+            Some("ReshardCollectionFinish") => 6,
+            status => panic!("unexpected status of reshard job: {status:?}"),
+        };
+        let desc = db.open_collection("test_co".into()).await.unwrap().desc();
+        match desc.partition {
+            Some(collection_desc::Partition::Hash(hash)) => {
+                assert_eq!(hash.slots, expect_slots);
+                assert_eq!(hash.target_slots, 0);
+            }
+            _ => panic!("collection should be hash partitioned"),
+        }
+
+        // Waits for the router to receive the collection desc.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        for i in 0..1000 {
+            let k = format!("key-{i}").into_bytes();
+            let v = format!("value-{i}").into_bytes();
+            assert_eq!(co.get(k).await.unwrap(), Some(v));
+        }
+    })
+}
+
+#[test]
+fn admin_data_api() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("admin-data-api");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(1).await;
+        let addr = nodes.values().next().unwrap().to_owned();
+
+        let client = reqwest::Client::new();
+        let call = |method: reqwest::Method, path: String, body: Option<serde_json::Value>| {
+            let mut req = client.request(method, format!("http://{addr}/admin/data/{path}"));
+            if let Some(body) = body {
+                req = req.json(&body);
+            }
+            async move {
+                let resp = req.send().await.unwrap();
+                let status = resp.status().as_u16();
+                let body = resp.json::<serde_json::Value>().await.unwrap();
+                (status, body)
+            }
+        };
+        let get = |path: &str| call(reqwest::Method::GET, path.to_owned(), None);
+
+        let (status, _) = call(
+            reqwest::Method::POST,
+            "database/create".into(),
+            Some(json!({ "name": "db" })),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let (status, _) = call(
+            reqwest::Method::POST,
+            "database/create".into(),
+            Some(json!({ "name": "db" })),
+        )
+        .await;
+        assert_eq!(status, 409);
+        let (status, body) = call(
+            reqwest::Method::POST,
+            "collection/create".into(),
+            Some(json!({ "database": "db", "name": "co", "partition": "range" })),
+        )
+        .await;
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(body["partition"]["type"], "range");
+
+        // The writes are not allowed to be issued by GET.
+        let (status, _) = get("database/create?name=db1").await;
+        assert_eq!(status, 405);
+        let (status, _) = get("put?database=db&collection=co&key=k&value=v").await;
+        assert_eq!(status, 405);
+
+        for i in 0..10 {
+            let (status, _) = call(
+                reqwest::Method::PUT,
+                "put".into(),
+                Some(json!({
+                    "database": "db",
+                    "collection": "co",
+                    "key": format!("k{i}"),
+                    "value": format!("v{i}"),
+                })),
+            )
+            .await;
+            assert_eq!(status, 200);
+        }
+        let (status, body) = get("get?database=db&collection=co&key=k1").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["value"], "v1");
+
+        // base64 of "k2" and "v2".
+        let (status, body) = get("get?database=db&collection=co&key=azI%3D&encoding=base64").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["value"], "djI=");
+
+        // The value isn't valid UTF-8, so it can only be read in base64.
+        let (status, _) = call(
+            reqwest::Method::PUT,
+            "put".into(),
+            Some(json!({
+                "database": "db",
+                "collection": "co",
+                "key": "bin",
+                "value": "/w==",
+                "encoding": "base64",
+            })),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let (status, _) = get("get?database=db&collection=co&key=bin").await;
+        assert_eq!(status, 400);
+        let (status, body) = get("get?database=db&collection=co&key=Ymlu&encoding=base64").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["value"], "/w==");
+
+        let (status, _) = call(
+            reqwest::Method::DELETE,
+            "delete".into(),
+            Some(json!({ "database": "db", "collection": "co", "key": "k1" })),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let (status, _) = get("get?database=db&collection=co&key=k1").await;
+        assert_eq!(status, 404);
+
+        let (status, body) = get("scan?database=db&collection=co&start=k3&end=k6").await;
+        assert_eq!(status, 200);
+        let keys = body["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["key"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["k3", "k4", "k5"]);
+
+        let (status, _) = get("get?database=db&collection=co").await;
+        assert_eq!(status, 400);
+        let (status, _) = get("get?database=db&collection=not-exists&key=k").await;
+        assert_eq!(status, 404);
+    })
+}
+
+#[test]
+fn admin_toggle_failpoint() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("admin-toggle-failpoint");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(1).await;
+        let addr = nodes.values().next().unwrap().to_owned();
+
+        let call = |path: &str| {
+            let url = format!("http://{addr}/admin/failpoint{path}");
+            async move {
+                let resp = reqwest::get(url).await.unwrap();
+                let status = resp.status().as_u16();
+                let body = resp.json::<serde_json::Value>().await.ok();
+                (status, body)
+            }
+        };
+
+        // No migration is issued by the tests of this file, so sleeping before its steps does not
+        // affect the others.
+        let name = "migrate_next_step";
+        let (status, body) = call(&format!("?name={name}&action=sleep(10)")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body.unwrap()[name], "sleep(10)");
+
+        let (status, _) = call(&format!("?name={name}&action=unknown")).await;
+        assert_eq!(status, 400);
+
+        let (status, body) = call(&format!("?name={name}&action=off")).await;
+        assert_eq!(status, 200);
+        assert!(body.unwrap().get(name).is_none());
+    })
+}
+
 fn collection_key(database_id: u64, collection_name: &str) -> Vec<u8> {
     let mut buf = Vec::with_capacity(core::mem::size_of::<u64>() + collection_name.len());
     buf.extend_from_slice(database_id.to_le_bytes().as_slice());
@@ -15,6 +15,7 @@ mod helper;
 
 use std::time::Duration;
 
+use engula_api::v1::DatabaseQuota;
 use engula_client::{AppError, ClientOptions, Partition};
 use tracing::info;
 
@@ -182,3 +183,489 @@ fn request_to_offline_leader() {
         }
     })
 }
+
+#[test]
+fn reshard_hash_collection() {
+    use engula_api::v1::collection_desc;
+
+    block_on_current(async {
+        let mut ctx = TestContext::new("client_test__reshard_hash_collection");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes).await;
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+
+        for i in 0..100 {
+            let k = format!("key-{i}").into_bytes();
+            let v = format!("value-{i}").into_bytes();
+            co.put(k, v).await.unwrap();
+        }
+
+        db.reshard_collection("test_co".to_string(), 6)
+            .await
+            .unwrap();
+        for i in 100..200 {
+            let k = format!("key-{i}").into_bytes();
+            let v = format!("value-{i}").into_bytes();
+            co.put(k, v).await.unwrap();
+        }
+        co.delete(b"key-0".to_vec()).await.unwrap();
+
+        let mut resharded = false;
+        for _ in 0..100 {
+            let desc = db
+                .open_collection("test_co".to_string())
+                .await
+                .unwrap()
+                .desc();
+            if let Some(collection_desc::Partition::Hash(hash)) = desc.partition {
+                if hash.slots == 6 && hash.target_slots == 0 {
+                    resharded = true;
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(resharded);
+
+        // Waits for the router to receive the resharded collection desc.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(co.get(b"key-0".to_vec()).await.unwrap().is_none());
+        for i in 1..200 {
+            let k = format!("key-{i}").into_bytes();
+            let r = co.get(k).await.unwrap();
+            let r = r.map(String::from_utf8);
+            assert!(matches!(r, Some(Ok(v)) if v == format!("value-{i}")));
+        }
+    });
+}
+
+#[test]
+fn reshard_hash_collection_concurrently() {
+    use engula_api::v1::collection_desc;
+
+    block_on_current(async {
+        let mut ctx = TestContext::new("client_test__reshard_hash_collection_concurrently");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes).await;
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+
+        for i in 0..100 {
+            let k = format!("key-{i}").into_bytes();
+            let v = format!("value-{i}").into_bytes();
+            co.put(k, v).await.unwrap();
+        }
+
+        let (r1, r2) = tokio::join!(
+            db.reshard_collection("test_co".to_string(), 6),
+            db.reshard_collection("test_co".to_string(), 9),
+        );
+        let slots = match (r1, r2) {
+            (Ok(_), Err(AppError::AlreadyExists(_))) => 6,
+            (Err(AppError::AlreadyExists(_)), Ok(_)) => 9,
+            (r1, r2) => panic!("only one reshard should be accepted: {r1:?}, {r2:?}"),
+        };
+
+        let mut resharded = false;
+        for _ in 0..100 {
+            let desc = db
+                .open_collection("test_co".to_string())
+                .await
+                .unwrap()
+                .desc();
+            if let Some(collection_desc::Partition::Hash(hash)) = desc.partition {
+                if hash.target_slots == 0 && hash.slots != 3 {
+                    assert_eq!(hash.slots, slots);
+                    resharded = true;
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(resharded);
+
+        // Waits for the router to receive the resharded collection desc.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        for i in 0..100 {
+            let k = format!("key-{i}").into_bytes();
+            let r = co.get(k).await.unwrap();
+            let r = r.map(String::from_utf8);
+            assert!(matches!(r, Some(Ok(v)) if v == format!("value-{i}")));
+        }
+    });
+}
+
+#[test]
+fn reshard_hash_collection_with_conflict_writes() {
+    use engula_api::v1::collection_desc;
+
+    block_on_current(async {
+        let mut ctx = TestContext::new("client_test__reshard_hash_collection_with_conflict_writes");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes).await;
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+
+        // Two clients write the same keys while the collection is resharding, the shards of
+        // target slots should keep the values of the current shards.
+        let other = c
+            .app_client()
+            .await
+            .open_database("test_db".to_string())
+            .await
+            .unwrap()
+            .open_collection("test_co".to_string())
+            .await
+            .unwrap();
+        let write = |co: engula_client::Collection, name: &'static str| async move {
+            for round in 0..5 {
+                for i in 0..50 {
+                    let k = format!("key-{i}").into_bytes();
+                    let v = format!("{name}-{round}").into_bytes();
+                    co.put(k, v).await.unwrap();
+                }
+            }
+        };
+        let (r, _, _) = tokio::join!(
+            db.reshard_collection("test_co".to_string(), 6),
+            write(co.clone(), "a"),
+            write(other, "b"),
+        );
+        r.unwrap();
+
+        let mut values = vec![];
+        for i in 0..50 {
+            let k = format!("key-{i}").into_bytes();
+            values.push(co.get(k).await.unwrap());
+        }
+
+        let mut resharded = false;
+        for _ in 0..100 {
+            let desc = db
+                .open_collection("test_co".to_string())
+                .await
+                .unwrap()
+                .desc();
+            if let Some(collection_desc::Partition::Hash(hash)) = desc.partition {
+                if hash.slots == 6 && hash.target_slots == 0 {
+                    resharded = true;
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(resharded);
+
+        // Waits for the router to receive the resharded collection desc.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        for (i, value) in values.into_iter().enumerate() {
+            let k = format!("key-{i}").into_bytes();
+            assert_eq!(co.get(k).await.unwrap(), value, "key-{i}");
+        }
+    });
+}
+
+#[test]
+fn scan_range_and_hash_collection() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("client_test__scan_range_and_hash_collection");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes).await;
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let range_co = db
+            .create_collection("range_co".to_string(), Some(Partition::Range))
+            .await
+            .unwrap();
+        let hash_co = db
+            .create_collection("hash_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&range_co.desc()).await;
+        c.assert_collection_ready(&hash_co.desc()).await;
+
+        for co in [&range_co, &hash_co] {
+            for i in 0..100 {
+                let k = format!("key-{i:03}").into_bytes();
+                let v = format!("value-{i:03}").into_bytes();
+                co.put(k, v).await.unwrap();
+            }
+            co.delete(b"key-010".to_vec()).await.unwrap();
+        }
+
+        let entries = range_co
+            .scan(b"key-005".to_vec(), b"key-020".to_vec(), 0)
+            .await
+            .unwrap();
+        let keys = entries
+            .iter()
+            .map(|(k, _)| String::from_utf8(k.clone()).unwrap())
+            .collect::<Vec<_>>();
+        let expect = (5..20)
+            .filter(|i| *i != 10)
+            .map(|i| format!("key-{i:03}"))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, expect);
+
+        let entries = range_co.scan(vec![], vec![], 10).await.unwrap();
+        assert_eq!(entries.len(), 10);
+
+        let entries = range_co
+            .reverse_scan(b"key-005".to_vec(), b"key-020".to_vec(), 0)
+            .await
+            .unwrap();
+        let keys = entries
+            .iter()
+            .map(|(k, _)| String::from_utf8(k.clone()).unwrap())
+            .collect::<Vec<_>>();
+        let expect = (5..20)
+            .rev()
+            .filter(|i| *i != 10)
+            .map(|i| format!("key-{i:03}"))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, expect);
+
+        // The limit keeps the largest keys.
+        let entries = range_co.reverse_scan(vec![], vec![], 3).await.unwrap();
+        let keys = entries
+            .iter()
+            .map(|(k, _)| String::from_utf8(k.clone()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["key-099", "key-098", "key-097"]);
+
+        let mut entries = hash_co.prefix_scan(b"key-01".to_vec(), 0).await.unwrap();
+        entries.sort();
+        let keys = entries
+            .iter()
+            .map(|(k, _)| String::from_utf8(k.clone()).unwrap())
+            .collect::<Vec<_>>();
+        let expect = (11..20).map(|i| format!("key-{i:03}")).collect::<Vec<_>>();
+        assert_eq!(keys, expect);
+
+        // Each page resumes after the last key of the previous page, the keys are visited once.
+        for co in [&range_co, &hash_co] {
+            let mut keys = Vec::new();
+            let mut after = vec![];
+            loop {
+                let entries = co
+                    .prefix_scan_after(b"key-".to_vec(), after, 7)
+                    .await
+                    .unwrap();
+                keys.extend(entries.iter().map(|(k, _)| k.clone()));
+                match entries.last() {
+                    Some((key, _)) if entries.len() == 7 => after = key.clone(),
+                    _ => break,
+                }
+            }
+            let total = keys.len();
+            keys.sort();
+            keys.dedup();
+            assert_eq!(keys.len(), total);
+            assert_eq!(keys.len(), 99);
+        }
+    });
+}
+
+/// The follower reads are served by the read learner on the labelled node, and they always see
+/// the acknowledged writes.
+#[test]
+fn follower_read_sees_acknowledged_writes() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("client_test__follower_read_sees_acknowledged_writes");
+        ctx.disable_all_balance();
+        let mut nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes.clone()).await;
+        c.assert_root_group_has_promoted().await;
+        let addr = ctx.add_server(nodes.values().cloned().collect(), 3).await;
+        nodes.insert(3, addr);
+        let c = ClusterClient::new(nodes).await;
+        c.label_node(3, "analytics").await;
+
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Range))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+        db.set_read_replicas("test_co".to_string(), 1)
+            .await
+            .unwrap();
+        let group_id = c
+            .find_router_group_state_by_key(&co.desc(), b"key")
+            .await
+            .unwrap()
+            .id;
+        let learner = c.must_group_read_learner(group_id, &[]).await;
+        assert_eq!(learner.node_id, 3);
+
+        for i in 0..100 {
+            let k = format!("key-{i:03}").into_bytes();
+            let v = format!("value-{i:03}").into_bytes();
+            co.put(k.clone(), v.clone()).await.unwrap();
+            assert_eq!(co.follower_get(k).await.unwrap(), Some(v));
+        }
+        co.delete(b"key-010".to_vec()).await.unwrap();
+        assert!(co
+            .follower_get(b"key-010".to_vec())
+            .await
+            .unwrap()
+            .is_none());
+
+        let entries = co.follower_scan(vec![], vec![], 0).await.unwrap();
+        let keys = entries
+            .iter()
+            .map(|(k, _)| String::from_utf8(k.clone()).unwrap())
+            .collect::<Vec<_>>();
+        let expect = (0..100)
+            .filter(|i| *i != 10)
+            .map(|i| format!("key-{i:03}"))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, expect);
+    });
+}
+
+#[test]
+fn delete_range_and_truncate_collection() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("client_test__delete_range_and_truncate_collection");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes).await;
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let range_co = db
+            .create_collection("range_co".to_string(), Some(Partition::Range))
+            .await
+            .unwrap();
+        let hash_co = db
+            .create_collection("hash_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&range_co.desc()).await;
+        c.assert_collection_ready(&hash_co.desc()).await;
+
+        for co in [&range_co, &hash_co] {
+            for i in 0..100 {
+                let k = format!("key-{i:03}").into_bytes();
+                let v = format!("value-{i:03}").into_bytes();
+                co.put(k, v).await.unwrap();
+            }
+        }
+
+        range_co
+            .delete_range(b"key-010".to_vec(), b"key-090".to_vec())
+            .await
+            .unwrap();
+        let entries = range_co.scan(vec![], vec![], 0).await.unwrap();
+        let keys = entries
+            .iter()
+            .map(|(k, _)| String::from_utf8(k.clone()).unwrap())
+            .collect::<Vec<_>>();
+        let expect = (0..10)
+            .chain(90..100)
+            .map(|i| format!("key-{i:03}"))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, expect);
+
+        assert!(matches!(
+            hash_co
+                .delete_range(b"key-010".to_vec(), b"key-090".to_vec())
+                .await,
+            Err(AppError::InvalidArgument(_))
+        ));
+
+        for co in [&range_co, &hash_co] {
+            co.truncate().await.unwrap();
+            assert!(co.scan(vec![], vec![], 0).await.unwrap().is_empty());
+            assert!(co.get(b"key-000".to_vec()).await.unwrap().is_none());
+
+            // The collection is still writable after truncating.
+            co.put(b"key".to_vec(), b"value".to_vec()).await.unwrap();
+            assert_eq!(
+                co.get(b"key".to_vec()).await.unwrap(),
+                Some(b"value".to_vec())
+            );
+        }
+    });
+}
+
+#[test]
+fn database_write_qps_quota() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("client_test__database_write_qps_quota");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes).await;
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Hash { slots: 1 }))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+        co.put(b"key".to_vec(), b"value".to_vec()).await.unwrap();
+
+        let db = db
+            .set_quota(DatabaseQuota {
+                write_qps: 1,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(db.desc().quota.unwrap().write_qps, 1);
+
+        // The quota takes effect after it is synced to nodes by heartbeat.
+        let mut exceeded = false;
+        for _ in 0..1000 {
+            match co.put(b"key".to_vec(), b"value".to_vec()).await {
+                Ok(()) => {}
+                Err(AppError::QuotaExceeded(database_id, resource)) => {
+                    assert_eq!(database_id, db.desc().id);
+                    assert_eq!(resource, "write_qps");
+                    exceeded = true;
+                    break;
+                }
+                Err(err) => panic!("put: {err:?}"),
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(exceeded);
+
+        // The reads are not limited.
+        assert!(co.get(b"key".to_vec()).await.unwrap().is_some());
+
+        db.set_quota(DatabaseQuota::default()).await.unwrap();
+        let mut recovered = false;
+        for _ in 0..1000 {
+            if co.put(b"key".to_vec(), b"value".to_vec()).await.is_ok()
+                && co.put(b"key".to_vec(), b"value".to_vec()).await.is_ok()
+            {
+                recovered = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(recovered);
+    });
+}
@@ -0,0 +1,110 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+mod helper;
+
+use std::time::{Duration, Instant};
+
+use engula_client::Partition;
+use engula_server::failpoint::{self, Action};
+
+use crate::helper::{client::*, context::*, init::setup_panic_hook, runtime::*};
+
+#[ctor::ctor]
+fn init() {
+    setup_panic_hook();
+    tracing_subscriber::fmt::init();
+}
+
+/// The replicas crash after the applied entries are committed into the group engine, but before
+/// the applied index of raft is advanced, they should recover from the persisted apply state.
+#[test]
+fn recover_from_crash_after_commit() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("failpoint_test__recover_from_crash_after_commit");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes.clone()).await;
+        let app = c.app_client().await;
+        let db = app.create_database("db".into()).await.unwrap();
+        let co = db
+            .create_collection("co".into(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        co.put(b"k1".to_vec(), b"v1".to_vec()).await.unwrap();
+
+        failpoint::enable("fsm_after_commit", Action::Return).unwrap();
+        let put = co.put(b"k2".to_vec(), b"v2".to_vec());
+        let res = tokio::time::timeout(Duration::from_secs(3), put).await;
+        assert!(
+            !matches!(res, Ok(Ok(()))),
+            "the write is acked by crashed replicas"
+        );
+        ctx.shutdown();
+        failpoint::disable("fsm_after_commit");
+
+        let nodes = ctx.start_servers(nodes).await;
+        let c = ClusterClient::new(nodes).await;
+        let app = c.app_client().await;
+        let db = app.open_database("db".into()).await.unwrap();
+        let co = db.open_collection("co".into()).await.unwrap();
+        assert_eq!(co.get(b"k1".to_vec()).await.unwrap(), Some(b"v1".to_vec()));
+        co.put(b"k3".to_vec(), b"v3".to_vec()).await.unwrap();
+        assert_eq!(co.get(b"k3".to_vec()).await.unwrap(), Some(b"v3".to_vec()));
+        let value = co.get(b"k2".to_vec()).await.unwrap();
+        assert!(value.is_none() || value == Some(b"v2".to_vec()));
+    });
+}
+
+/// The read index of a follower read is lost, the read learner gives up after the timeout and the
+/// client falls back to the leader.
+#[test]
+fn follower_read_falls_back_to_leader() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("failpoint_test__follower_read_falls_back_to_leader");
+        ctx.disable_all_balance();
+        let mut nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes.clone()).await;
+        c.assert_root_group_has_promoted().await;
+        let addr = ctx.add_server(nodes.values().cloned().collect(), 3).await;
+        nodes.insert(3, addr);
+        let c = ClusterClient::new(nodes).await;
+        c.label_node(3, "analytics").await;
+
+        let app = c.app_client().await;
+        let db = app.create_database("db".into()).await.unwrap();
+        let co = db
+            .create_collection("co".into(), Some(Partition::Range))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+        db.set_read_replicas("co".into(), 1).await.unwrap();
+        let group_id = c
+            .find_router_group_state_by_key(&co.desc(), b"k1")
+            .await
+            .unwrap()
+            .id;
+        c.must_group_read_learner(group_id, &[]).await;
+        // Waits for the router of the app client to receive the read learner.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        co.put(b"k1".to_vec(), b"v1".to_vec()).await.unwrap();
+
+        failpoint::enable("follower_read_index", Action::Pause).unwrap();
+        let start = Instant::now();
+        let value = co.follower_get(b"k1".to_vec()).await;
+        let elapsed = start.elapsed();
+        failpoint::disable("follower_read_index");
+        assert_eq!(value.unwrap(), Some(b"v1".to_vec()));
+        assert!(elapsed >= Duration::from_secs(3), "elapsed {elapsed:?}");
+    });
+}
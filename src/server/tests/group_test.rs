@@ -25,6 +25,14 @@ fn init() {
     tracing_subscriber::fmt::init();
 }
 
+fn counter_value(name: &str) -> u64 {
+    prometheus::gather()
+        .iter()
+        .find(|mf| mf.get_name() == name)
+        .and_then(|mf| mf.get_metric().first().map(|m| m.get_counter().get_value()))
+        .unwrap_or_default() as u64
+}
+
 async fn create_group(c: &ClusterClient, group_id: u64, nodes: Vec<u64>) {
     let replicas = nodes
         .iter()
@@ -145,6 +153,180 @@ fn create_group_with_multi_replicas() {
     });
 }
 
+/// An idle group hibernates, and it is woken up by the next proposal.
+#[test]
+fn hibernate_idle_group() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("hibernate-idle-group");
+        ctx.disable_all_balance();
+        ctx.disable_all_node_scheduler();
+        ctx.enable_hibernation();
+        let nodes = ctx.bootstrap_servers(4).await;
+        let c = ClusterClient::new(nodes).await;
+
+        let group_id = 100000000;
+        create_group(&c, group_id, vec![0, 1, 2]).await;
+        c.assert_group_leader(group_id).await;
+
+        info!("wait group {group_id} hibernating");
+        let hibernated = counter_value("raftgroup_hibernate_total");
+        ctx.wait_election_timeout().await;
+        ctx.wait_election_timeout().await;
+        c.assert_group_leader(group_id).await;
+        assert!(counter_value("raftgroup_hibernate_total") > hibernated);
+
+        let empty_desc = GroupDesc {
+            id: group_id,
+            ..Default::default()
+        };
+        let new_replica_id = group_id * 10 + 3;
+        c.create_replica(3, new_replica_id, empty_desc).await;
+
+        info!("add replica {new_replica_id} to the hibernated group {group_id}");
+        let mut group_client = c.group(group_id);
+        group_client.add_replica(new_replica_id, 3).await.unwrap();
+        ctx.wait_election_timeout().await;
+
+        c.assert_group_contains_member(group_id, new_replica_id)
+            .await;
+    });
+}
+
+/// The followers of a hibernated group wake up and elect a new leader, once the leader is dead.
+#[test]
+fn wake_hibernated_group_if_leader_is_dead() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("wake-hibernated-group-if-leader-is-dead");
+        ctx.disable_all_balance();
+        ctx.disable_all_node_scheduler();
+        ctx.enable_hibernation();
+        let nodes = ctx.bootstrap_servers(4).await;
+        let c = ClusterClient::new(nodes).await;
+
+        // The root group is located in node 0, it never hibernates.
+        let group_id = 100000000;
+        create_group(&c, group_id, vec![1, 2, 3]).await;
+        c.assert_group_leader(group_id).await;
+
+        info!("wait group {group_id} hibernating");
+        let hibernated = counter_value("raftgroup_hibernate_total");
+        ctx.wait_election_timeout().await;
+        ctx.wait_election_timeout().await;
+        assert!(counter_value("raftgroup_hibernate_total") > hibernated);
+
+        let woken = counter_value("raftgroup_wake_up_total");
+        let leader_node_id = c.get_group_leader_node_id(group_id).await.unwrap();
+        info!("stop the node {leader_node_id} of the hibernated group {group_id} leader");
+        ctx.stop_server(leader_node_id).await;
+
+        let mut new_leader_node_id = None;
+        for _ in 0..20 {
+            ctx.wait_election_timeout().await;
+            match c.get_group_leader_node_id(group_id).await {
+                Some(node_id) if node_id != leader_node_id => {
+                    new_leader_node_id = Some(node_id);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert!(new_leader_node_id.is_some());
+        assert!(counter_value("raftgroup_wake_up_total") > woken);
+    });
+}
+
+/// A group with two data replicas and a witness tolerates the failure of a data replica, and the
+/// witness never becomes leader.
+#[test]
+fn witness_never_become_leader() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("witness-never-become-leader");
+        ctx.disable_all_balance();
+        ctx.disable_all_node_scheduler();
+        let nodes = ctx.bootstrap_servers(4).await;
+        let c = ClusterClient::new(nodes).await;
+
+        let group_id = 100000000;
+        create_group(&c, group_id, vec![1, 2]).await;
+        c.assert_group_leader(group_id).await;
+
+        let empty_desc = GroupDesc {
+            id: group_id,
+            ..Default::default()
+        };
+        let witness_id = group_id * 10 + 3;
+        c.create_replica(3, witness_id, empty_desc).await;
+
+        info!("add witness {witness_id} to group {group_id}");
+        let mut group_client = c.group(group_id);
+        group_client.add_witness(witness_id, 3).await.unwrap();
+        c.assert_group_contains_member(group_id, witness_id).await;
+        ctx.wait_election_timeout().await;
+
+        info!("transfer leadership of group {group_id} to witness {witness_id}");
+        group_client.transfer_leader(witness_id).await.unwrap();
+        assert_witness_never_become_leader(&ctx, &c, group_id, witness_id).await;
+
+        let leader_node_id = c.get_group_leader_node_id(group_id).await.unwrap();
+        info!("stop the node {leader_node_id} of group {group_id} leader");
+        ctx.stop_server(leader_node_id).await;
+        ctx.wait_election_timeout().await;
+        assert_witness_never_become_leader(&ctx, &c, group_id, witness_id).await;
+    });
+}
+
+async fn assert_witness_never_become_leader(
+    ctx: &TestContext,
+    c: &ClusterClient,
+    group_id: u64,
+    witness_id: u64,
+) {
+    for _ in 0..5 {
+        let leader = c.assert_group_leader(group_id).await;
+        assert_ne!(leader, witness_id);
+        ctx.wait_election_timeout().await;
+    }
+}
+
+/// A read learner is a permanent learner, it can't be promoted to a voter.
+#[test]
+fn read_learner_is_not_promoted() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("read-learner-is-not-promoted");
+        ctx.disable_all_balance();
+        ctx.disable_all_node_scheduler();
+        let nodes = ctx.bootstrap_servers(4).await;
+        let c = ClusterClient::new(nodes).await;
+
+        let group_id = 100000000;
+        create_group(&c, group_id, vec![0, 1, 2]).await;
+        c.assert_group_leader(group_id).await;
+
+        let empty_desc = GroupDesc {
+            id: group_id,
+            ..Default::default()
+        };
+        let read_learner_id = group_id * 10 + 3;
+        c.create_replica(3, read_learner_id, empty_desc).await;
+
+        info!("add read learner {read_learner_id} to group {group_id}");
+        let mut group_client = c.group(group_id);
+        group_client
+            .add_read_learner(read_learner_id, 3)
+            .await
+            .unwrap();
+        c.assert_group_contains_member(group_id, read_learner_id)
+            .await;
+
+        info!("promote read learner {read_learner_id} of group {group_id}");
+        let mut group_client = c.group(group_id);
+        assert!(group_client.add_replica(read_learner_id, 3).await.is_err());
+
+        let members = c.group_members(group_id).await;
+        assert!(members.contains(&(read_learner_id, ReplicaRole::ReadLearner as i32)));
+    });
+}
+
 /// The root group can be promoted to cluster mode as long as enough nodes are added to the cluster.
 #[test]
 fn promote_to_cluster_from_single_node() {
@@ -306,7 +306,10 @@ impl ClusterClient {
                 piggyback_response::Info::SyncRoot(_)
                 | piggyback_response::Info::CollectStats(_)
                 | piggyback_response::Info::CollectScheduleState(_)
-                | piggyback_response::Info::CollectGroupDetail(_) => {}
+                | piggyback_response::Info::CollectGroupDetail(_)
+                | piggyback_response::Info::SyncNodeLiveness(_)
+                | piggyback_response::Info::SyncDatabaseQuota(_)
+                | piggyback_response::Info::SyncReplicationState(_) => {}
                 piggyback_response::Info::CollectMigrationState(resp) => {
                     return Ok(resp.clone());
                 }
@@ -340,7 +343,10 @@ impl ClusterClient {
                 piggyback_response::Info::SyncRoot(_)
                 | piggyback_response::Info::CollectStats(_)
                 | piggyback_response::Info::CollectScheduleState(_)
-                | piggyback_response::Info::CollectMigrationState(_) => {}
+                | piggyback_response::Info::CollectMigrationState(_)
+                | piggyback_response::Info::SyncNodeLiveness(_)
+                | piggyback_response::Info::SyncDatabaseQuota(_)
+                | piggyback_response::Info::SyncReplicationState(_) => {}
                 piggyback_response::Info::CollectGroupDetail(resp) => {
                     for state in &resp.replica_states {
                         if state.group_id == group_id {
@@ -391,6 +397,69 @@ impl ClusterClient {
         }
     }
 
+    /// Replace the labels of the node, the request is sent to every node until the root leader
+    /// accepts it.
+    pub async fn label_node(&self, node_id: u64, labels: &str) {
+        for _ in 0..100 {
+            for addr in self.nodes.values() {
+                let url =
+                    format!("http://{addr}/admin/label_node?node_id={node_id}&labels={labels}");
+                if let Ok(resp) = reqwest::get(url).await {
+                    if resp.status().is_success() {
+                        return;
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("label node {node_id} timeout");
+    }
+
+    /// Stop the replication to the standby cluster, the request is sent to every node until the
+    /// root leader accepts it.
+    pub async fn failover_replication(&self) {
+        for _ in 0..100 {
+            for addr in self.nodes.values() {
+                let url = format!("http://{addr}/admin/replication/failover?timeout_ms=1000");
+                if let Ok(resp) = reqwest::get(url).await {
+                    if resp.status().is_success() {
+                        return;
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("failover replication timeout");
+    }
+
+    pub async fn group_read_learners(&self, group_id: u64) -> Vec<ReplicaDesc> {
+        match self.router.find_group(group_id) {
+            Ok(state) => state
+                .replicas
+                .into_values()
+                .filter(|r| r.role == ReplicaRole::ReadLearner as i32)
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Wait until the group has exactly one read learner, which isn't placed on the excluded
+    /// nodes.
+    pub async fn must_group_read_learner(
+        &self,
+        group_id: u64,
+        excluded_nodes: &[u64],
+    ) -> ReplicaDesc {
+        for _ in 0..1000 {
+            let mut learners = self.group_read_learners(group_id).await;
+            if learners.len() == 1 && !excluded_nodes.contains(&learners[0].node_id) {
+                return learners.pop().unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("group {group_id} has no read learner");
+    }
+
     /// Some tests may shut down a server, if root happens to be on that server, and there is only
     /// one replica in root group, then the test will not continue because root group is lost.
     pub async fn assert_root_group_has_promoted(&self) {
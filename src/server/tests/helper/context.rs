@@ -11,7 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::{collections::HashMap, thread, time::Duration};
+use std::{collections::HashMap, path::PathBuf, thread, time::Duration};
 
 use engula_server::{
     runtime::{ExecutorOwner, ShutdownNotifier},
@@ -31,6 +31,8 @@ pub struct TestContext {
     replica_knobs: ReplicaTestingKnobs,
     raft_knobs: RaftTestingKnobs,
     disable_group_promoting: bool,
+    enable_hibernation: bool,
+    replication: Option<ReplicationConfig>,
 
     tick_interval_ms: u64,
 
@@ -46,6 +48,8 @@ impl TestContext {
             name: prefix.to_owned(),
             root_dir,
             disable_group_promoting: false,
+            enable_hibernation: false,
+            replication: None,
             replica_knobs: ReplicaTestingKnobs::default(),
             raft_knobs: RaftTestingKnobs::default(),
             root_cfg: RootConfig::default(),
@@ -106,12 +110,31 @@ impl TestContext {
         self.disable_group_balance();
     }
 
+    pub fn enable_hibernation(&mut self) {
+        self.enable_hibernation = true;
+    }
+
+    /// Replicate the user writes to the standby cluster, which has the same databases and
+    /// collections.
+    pub fn set_standby_cluster(&mut self, standby_addrs: Vec<String>) {
+        self.replication = Some(ReplicationConfig {
+            standby_addrs,
+            retry_interval_ms: 100,
+            ..Default::default()
+        });
+    }
+
     pub fn disable_all_node_scheduler(&mut self) {
         self.replica_knobs.disable_scheduler_durable_task = true;
         self.replica_knobs
             .disable_scheduler_remove_orphan_replica_task = true;
     }
 
+    /// The data directory of the server.
+    pub fn node_root_dir(&self, idx: u64) -> PathBuf {
+        self.root_dir.path().join(idx.to_string())
+    }
+
     #[allow(dead_code)]
     pub fn spawn_server(&mut self, idx: usize, addr: &str, init: bool, join_list: Vec<String>) {
         self.spawn_server_with_cfg(idx, addr, 2, init, join_list, self.root_cfg.clone());
@@ -128,14 +151,15 @@ impl TestContext {
         root: RootConfig,
     ) {
         let addr = addr.to_owned();
-        let name = idx.to_string();
-        let root_dir = self.root_dir.path().join(name);
+        let root_dir = self.node_root_dir(idx as u64);
         let cfg = Config {
             root_dir,
             addr,
             cpu_nums,
             init,
             enable_proxy_service: false,
+            resp: None,
+            replication: self.replication.clone(),
             join_list,
             node: NodeConfig {
                 replica: ReplicaConfig {
@@ -146,6 +170,8 @@ impl TestContext {
             },
             raft: RaftConfig {
                 tick_interval_ms: self.tick_interval_ms,
+                enable_hibernation: self.enable_hibernation,
+                hibernate_idle_ticks: 4,
                 testing_knobs: self.raft_knobs.clone(),
                 ..Default::default()
             },
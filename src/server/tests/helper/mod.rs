@@ -16,4 +16,5 @@ pub mod client;
 pub mod context;
 pub mod init;
 pub mod runtime;
+pub mod sim;
 pub mod socket;
@@ -0,0 +1,149 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use engula_client::{
+    ClientOptions, ConnManager, EngulaClient, RootClient, Router, StaticServiceDiscovery,
+};
+use engula_server::{
+    runtime,
+    sim::{SimConfig, SimNetwork, Simulation},
+    Config, DbConfig, NodeConfig, RaftConfig, RootConfig, *,
+};
+use tempdir::TempDir;
+use tracing::info;
+
+/// A cluster running in a deterministic [`Simulation`], which mirrors the
+/// [`super::context::TestContext`].
+#[allow(dead_code)]
+pub struct SimContext {
+    name: String,
+    root_dir: TempDir,
+    root_cfg: RootConfig,
+    tick_interval_ms: u64,
+    sim: Simulation,
+    nodes: HashMap<u64, String>,
+}
+
+#[allow(dead_code)]
+impl SimContext {
+    pub fn new(prefix: &str, cfg: SimConfig) -> Self {
+        info!("{prefix} run simulation with seed {}", cfg.seed);
+        SimContext {
+            name: prefix.to_owned(),
+            root_dir: TempDir::new(prefix).unwrap(),
+            root_cfg: RootConfig::default(),
+            tick_interval_ms: 500,
+            sim: Simulation::new(cfg),
+            nodes: HashMap::default(),
+        }
+    }
+
+    pub fn network(&self) -> SimNetwork {
+        self.sim.network()
+    }
+
+    pub fn block_on<F, T>(&self, future: F) -> T
+    where
+        F: std::future::Future<Output = T> + Send,
+        T: Send + 'static,
+    {
+        self.sim.block_on(future)
+    }
+
+    pub fn disable_all_balance(&mut self) {
+        self.root_cfg.enable_replica_balance = false;
+        self.root_cfg.enable_leader_balance = false;
+        self.root_cfg.enable_shard_balance = false;
+        self.root_cfg.enable_group_balance = false;
+    }
+
+    pub fn node_addr(&self, idx: u64) -> String {
+        format!("10.0.0.{idx}:21805")
+    }
+
+    pub fn spawn_server(&mut self, idx: u64, init: bool, join_list: Vec<String>) {
+        let addr = self.node_addr(idx);
+        let cfg = Config {
+            root_dir: self.root_dir.path().join(idx.to_string()),
+            addr: addr.clone(),
+            cpu_nums: 2,
+            init,
+            enable_proxy_service: false,
+            resp: None,
+            replication: None,
+            join_list,
+            node: NodeConfig::default(),
+            raft: RaftConfig {
+                tick_interval_ms: self.tick_interval_ms,
+                ..Default::default()
+            },
+            root: self.root_cfg.clone(),
+            executor: ExecutorConfig::default(),
+            db: DbConfig::default(),
+        };
+        self.sim.start_server(cfg);
+        self.nodes.insert(idx, addr.clone());
+
+        let conn_manager = self.conn_manager();
+        self.block_on(async move {
+            while conn_manager.connect(&addr).await.is_err() {
+                runtime::time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+    }
+
+    /// Create a set of servers and bootstrap all of them, one by one.
+    pub fn bootstrap_servers(&mut self, num_server: u64) -> HashMap<u64, String> {
+        let root_addr = self.node_addr(0);
+        for idx in 0..num_server {
+            info!("{} start server {idx}", self.name);
+            if idx == 0 {
+                self.spawn_server(idx, true, vec![]);
+            } else {
+                self.spawn_server(idx, false, vec![root_addr.clone()]);
+            }
+        }
+        self.nodes.clone()
+    }
+
+    pub fn kill_server(&mut self, idx: u64) {
+        info!("{} kill server {idx}", self.name);
+        if let Some(addr) = self.nodes.remove(&idx) {
+            self.sim.kill_server(&addr);
+        }
+    }
+
+    pub fn conn_manager(&self) -> ConnManager {
+        let sim = &self.sim;
+        self.block_on(async { sim.conn_manager("client") })
+    }
+
+    pub fn app_client(&self, opts: ClientOptions) -> EngulaClient {
+        let conn_manager = self.conn_manager();
+        let addrs = self.nodes.values().cloned().collect::<Vec<_>>();
+        self.block_on(async move {
+            let discovery = Arc::new(StaticServiceDiscovery::new(addrs));
+            let root_client = RootClient::new(discovery, conn_manager.clone());
+            let router = Router::new(root_client.clone()).await;
+            EngulaClient::build(opts, router, root_client, conn_manager)
+        })
+    }
+
+    pub fn wait_election_timeout(&self) {
+        let timeout = Duration::from_millis(self.tick_interval_ms * 6);
+        self.block_on(runtime::time::sleep(timeout));
+    }
+}
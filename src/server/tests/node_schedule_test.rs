@@ -304,3 +304,69 @@ fn cure_group() {
             .await;
     });
 }
+
+/// The read learners are only placed on the labelled nodes, an offline one is replaced by a new
+/// one on another labelled node, and they are removed once the read replicas are not required.
+#[test]
+fn keep_read_learners_on_labelled_nodes() {
+    use engula_client::Partition;
+
+    block_on_current(async {
+        let mut ctx = TestContext::new("node-schedule-test--keep-read-learners-on-labelled-nodes");
+        ctx.disable_all_balance();
+        let mut nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes.clone()).await;
+        c.assert_root_group_has_promoted().await;
+        let root_addrs = nodes.values().cloned().collect::<Vec<_>>();
+        for node_id in [3, 4] {
+            let addr = ctx.add_server(root_addrs.clone(), node_id).await;
+            nodes.insert(node_id, addr);
+        }
+        let c = ClusterClient::new(nodes).await;
+        c.label_node(3, "analytics").await;
+        c.label_node(4, "analytics").await;
+
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Range))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+        let group_id = c
+            .find_router_group_state_by_key(&co.desc(), b"key")
+            .await
+            .unwrap()
+            .id;
+
+        info!("add read learner to group {group_id}");
+        db.set_read_replicas("test_co".to_string(), 1)
+            .await
+            .unwrap();
+        let learner = c.must_group_read_learner(group_id, &[]).await;
+        assert!([3, 4].contains(&learner.node_id));
+        let state = c.get_router_group_state(group_id).await.unwrap();
+        assert!(state
+            .replicas
+            .values()
+            .filter(|r| r.role != ReplicaRole::ReadLearner as i32)
+            .all(|r| r.node_id < 3));
+
+        info!("replace offline read learner {}", learner.id);
+        ctx.stop_server(learner.node_id).await;
+        let new_learner = c
+            .must_group_read_learner(group_id, &[learner.node_id])
+            .await;
+        assert!([3, 4].contains(&new_learner.node_id));
+        c.assert_group_not_contains_member(group_id, learner.id)
+            .await;
+        c.assert_num_group_voters(group_id, 3).await;
+
+        info!("remove read learner {}", new_learner.id);
+        db.set_read_replicas("test_co".to_string(), 0)
+            .await
+            .unwrap();
+        c.assert_group_not_contains_member(group_id, new_learner.id)
+            .await;
+    });
+}
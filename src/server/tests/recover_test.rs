@@ -0,0 +1,214 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+mod helper;
+
+use std::collections::HashMap;
+
+use engula_api::server::v1::{GroupDesc, ReplicaDesc, ReplicaRole};
+use engula_client::Partition;
+use engula_server::{DbConfig, Error};
+use tracing::info;
+
+use crate::helper::{client::*, context::*, init::setup_panic_hook, runtime::*};
+
+const ROOT_GROUP_ID: u64 = 0;
+
+#[ctor::ctor]
+fn init() {
+    setup_panic_hook();
+    tracing_subscriber::fmt::init();
+}
+
+/// Stop the servers and remove their data directories, so that they never come back.
+async fn destroy_servers(ctx: &mut TestContext, ids: &[u64]) {
+    for &id in ids {
+        ctx.stop_server(id).await;
+        std::fs::remove_dir_all(ctx.node_root_dir(id)).unwrap();
+    }
+}
+
+/// Run unsafe recover on the stopped server, then restart it and add new servers to replace the
+/// destroyed ones.
+async fn recover_server(
+    ctx: &mut TestContext,
+    id: u64,
+    addr: String,
+    groups: &[u64],
+    new_servers: &[u64],
+) -> HashMap<u64, String> {
+    ctx.stop_server(id).await;
+    for &group_id in groups {
+        info!("unsafe recover group {group_id} on server {id}");
+        let desc = engula_server::debug::unsafe_recover(
+            &ctx.node_root_dir(id),
+            &DbConfig::default(),
+            group_id,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(desc.replicas.len(), 1);
+    }
+
+    let mut nodes = ctx.start_servers(HashMap::from([(id, addr.clone())])).await;
+    for &new_id in new_servers {
+        let new_addr = ctx.add_server(vec![addr.clone()], new_id).await;
+        nodes.insert(new_id, new_addr);
+    }
+    nodes
+}
+
+#[test]
+fn unsafe_recover_root_group() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("recover_test__unsafe_recover_root_group");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes.clone()).await;
+        c.assert_root_group_has_promoted().await;
+        let client = c.app_client().await;
+        client.create_database("test_db".to_string()).await.unwrap();
+
+        info!("destroy servers 1 and 2");
+        destroy_servers(&mut ctx, &[1, 2]).await;
+        let addr = nodes.get(&0).cloned().unwrap();
+        let nodes = recover_server(&mut ctx, 0, addr, &[ROOT_GROUP_ID], &[3, 4]).await;
+
+        let c = ClusterClient::new(nodes).await;
+        c.assert_group_leader(ROOT_GROUP_ID).await;
+        let client = c.app_client().await;
+        client.open_database("test_db".to_string()).await.unwrap();
+        client.create_database("new_db".to_string()).await.unwrap();
+        client.open_database("new_db".to_string()).await.unwrap();
+
+        info!("wait root group to be re-replicated");
+        c.assert_root_group_has_promoted().await;
+    });
+}
+
+#[test]
+fn unsafe_recover_user_group() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("recover_test__unsafe_recover_user_group");
+        ctx.disable_all_balance();
+        let nodes = ctx.bootstrap_servers(3).await;
+        let c = ClusterClient::new(nodes.clone()).await;
+        c.assert_root_group_has_promoted().await;
+        let client = c.app_client().await;
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Range))
+            .await
+            .unwrap();
+        c.assert_collection_ready(&co.desc()).await;
+        for i in 0..100 {
+            let k = format!("key-{i}").into_bytes();
+            let v = format!("value-{i}").into_bytes();
+            co.put(k, v).await.unwrap();
+        }
+        let group_id = c
+            .find_router_group_state_by_key(&co.desc(), b"key")
+            .await
+            .unwrap()
+            .id;
+        c.assert_num_group_voters(group_id, 3).await;
+
+        // All groups of a three nodes cluster lost the majority, including the root group.
+        info!("destroy servers 1 and 2");
+        destroy_servers(&mut ctx, &[1, 2]).await;
+        let addr = nodes.get(&0).cloned().unwrap();
+        let groups = [ROOT_GROUP_ID, group_id];
+        let nodes = recover_server(&mut ctx, 0, addr, &groups, &[3, 4]).await;
+
+        let c = ClusterClient::new(nodes).await;
+        c.assert_group_leader(group_id).await;
+        let client = c.app_client().await;
+        let db = client.open_database("test_db".to_string()).await.unwrap();
+        let co = db.open_collection("test_co".to_string()).await.unwrap();
+        for i in 0..100 {
+            let k = format!("key-{i}").into_bytes();
+            let r = co.get(k).await.unwrap();
+            let r = r.map(String::from_utf8);
+            assert!(matches!(r, Some(Ok(v)) if v == format!("value-{i}")));
+        }
+        co.put(b"key".to_vec(), b"value".to_vec()).await.unwrap();
+        assert_eq!(
+            co.get(b"key".to_vec()).await.unwrap(),
+            Some(b"value".to_vec())
+        );
+
+        info!("wait group {group_id} to be re-replicated");
+        c.assert_num_group_voters(group_id, 3).await;
+        c.assert_root_group_has_promoted().await;
+    });
+}
+
+/// A witness has no data, so it is only recovered if it is forced.
+#[test]
+fn unsafe_recover_rejects_witness() {
+    block_on_current(async {
+        let mut ctx = TestContext::new("recover_test__unsafe_recover_rejects_witness");
+        ctx.disable_all_balance();
+        ctx.disable_all_node_scheduler();
+        let nodes = ctx.bootstrap_servers(4).await;
+        let c = ClusterClient::new(nodes).await;
+
+        let group_id = 100000000;
+        let replicas = [1, 2]
+            .into_iter()
+            .map(|node_id| ReplicaDesc {
+                id: group_id * 10 + node_id,
+                node_id,
+                role: ReplicaRole::Voter as i32,
+            })
+            .collect::<Vec<_>>();
+        let group_desc = GroupDesc {
+            id: group_id,
+            replicas: replicas.clone(),
+            ..Default::default()
+        };
+        for replica in replicas {
+            c.create_replica(replica.node_id, replica.id, group_desc.clone())
+                .await;
+        }
+        c.assert_group_leader(group_id).await;
+
+        let empty_desc = GroupDesc {
+            id: group_id,
+            ..Default::default()
+        };
+        let witness_id = group_id * 10 + 3;
+        c.create_replica(3, witness_id, empty_desc).await;
+        info!("add witness {witness_id} to group {group_id}");
+        c.group(group_id).add_witness(witness_id, 3).await.unwrap();
+        c.assert_group_contains_member(group_id, witness_id).await;
+        // Wait for the witness to be initialized by the snapshot from leader.
+        ctx.wait_election_timeout().await;
+
+        ctx.stop_server(3).await;
+        let root_dir = ctx.node_root_dir(3);
+        let err =
+            engula_server::debug::unsafe_recover(&root_dir, &DbConfig::default(), group_id, false)
+                .await
+                .unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)), "{err:?}");
+
+        let desc =
+            engula_server::debug::unsafe_recover(&root_dir, &DbConfig::default(), group_id, true)
+                .await
+                .unwrap();
+        assert_eq!(desc.replicas.len(), 1);
+        assert_eq!(desc.replicas[0].id, witness_id);
+    });
+}
@@ -0,0 +1,147 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+mod helper;
+
+use std::time::Duration;
+
+use engula_client::{Collection, Partition};
+use tracing::info;
+
+use crate::helper::{client::*, context::*, init::setup_panic_hook, runtime::*};
+
+#[ctor::ctor]
+fn init() {
+    setup_panic_hook();
+    tracing_subscriber::fmt::init();
+}
+
+async fn create_collections(c: &ClusterClient) -> (Collection, Collection) {
+    let client = c.app_client().await;
+    let db = client.create_database("test_db".to_string()).await.unwrap();
+    let range_co = db
+        .create_collection("range_co".to_string(), Some(Partition::Range))
+        .await
+        .unwrap();
+    let hash_co = db
+        .create_collection("hash_co".to_string(), Some(Partition::Hash { slots: 3 }))
+        .await
+        .unwrap();
+    (range_co, hash_co)
+}
+
+async fn scan_keys(co: &Collection) -> Vec<Vec<u8>> {
+    let mut keys = co
+        .scan(vec![], vec![], 0)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys
+}
+
+/// Wait until the keys of the standby collection are the same as the primary collection.
+async fn assert_converged(primary: &Collection, standby: &Collection) {
+    let expect = scan_keys(primary).await;
+    for _ in 0..300 {
+        if scan_keys(standby).await == expect {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!(
+        "standby collection {} is not converged",
+        standby.desc().name
+    );
+}
+
+#[test]
+fn replicate_to_standby_cluster_and_failover() {
+    block_on_current(async {
+        let mut standby_ctx = TestContext::new("replication_test__standby");
+        standby_ctx.disable_all_balance();
+        let standby_nodes = standby_ctx.bootstrap_servers(1).await;
+        let standby = ClusterClient::new(standby_nodes.clone()).await;
+
+        let mut primary_ctx = TestContext::new("replication_test__primary");
+        primary_ctx.disable_all_balance();
+        primary_ctx.set_standby_cluster(standby_nodes.values().cloned().collect());
+        let primary_nodes = primary_ctx.bootstrap_servers(3).await;
+        let primary = ClusterClient::new(primary_nodes).await;
+
+        // The standby cluster has the same databases and collections.
+        let (standby_range, standby_hash) = create_collections(&standby).await;
+        let (primary_range, primary_hash) = create_collections(&primary).await;
+        primary.assert_collection_ready(&primary_range.desc()).await;
+        primary.assert_collection_ready(&primary_hash.desc()).await;
+
+        for co in [&primary_range, &primary_hash] {
+            for i in 0..100 {
+                let k = format!("key-{i:03}").into_bytes();
+                let v = format!("value-{i:03}").into_bytes();
+                co.put(k, v).await.unwrap();
+            }
+            co.delete(b"key-000".to_vec()).await.unwrap();
+        }
+        primary_range
+            .delete_range(b"key-010".to_vec(), b"key-020".to_vec())
+            .await
+            .unwrap();
+        info!("wait standby cluster to converge");
+        assert_converged(&primary_range, &standby_range).await;
+        assert_converged(&primary_hash, &standby_hash).await;
+        assert_eq!(
+            standby_range.get(b"key-050".to_vec()).await.unwrap(),
+            Some(b"value-050".to_vec())
+        );
+
+        // Truncating a hash collection is replayed as deleting the shards of the same slots.
+        primary_hash.truncate().await.unwrap();
+        primary_hash
+            .put(b"key".to_vec(), b"value".to_vec())
+            .await
+            .unwrap();
+        assert_converged(&primary_hash, &standby_hash).await;
+        assert_eq!(
+            standby_hash.get(b"key".to_vec()).await.unwrap(),
+            Some(b"value".to_vec())
+        );
+
+        info!("failover to standby cluster");
+        primary.failover_replication().await;
+
+        // The writes to the old primary cluster are not replicated any more.
+        primary_range
+            .put(b"after-failover".to_vec(), b"primary".to_vec())
+            .await
+            .unwrap();
+
+        // The promoted cluster serves the writes of users.
+        for co in [&standby_range, &standby_hash] {
+            co.put(b"after-failover".to_vec(), b"standby".to_vec())
+                .await
+                .unwrap();
+            assert_eq!(
+                co.get(b"after-failover".to_vec()).await.unwrap(),
+                Some(b"standby".to_vec())
+            );
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(
+            standby_range.get(b"after-failover".to_vec()).await.unwrap(),
+            Some(b"standby".to_vec())
+        );
+    });
+}
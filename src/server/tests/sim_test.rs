@@ -0,0 +1,158 @@
+// Copyright 2022 The Engula Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+mod helper;
+
+use std::time::Duration;
+
+use engula_client::{ClientOptions, Partition};
+use engula_server::sim::SimConfig;
+
+use crate::helper::{init::setup_panic_hook, sim::*};
+
+#[ctor::ctor]
+fn init() {
+    setup_panic_hook();
+    tracing_subscriber::fmt::init();
+}
+
+fn client_options() -> ClientOptions {
+    ClientOptions {
+        connect_timeout: Some(Duration::from_millis(200)),
+        timeout: Some(Duration::from_secs(1)),
+    }
+}
+
+#[test]
+fn serve_requests_with_message_loss() {
+    let cfg = SimConfig {
+        seed: 1,
+        loss_rate: 0.1,
+        ..Default::default()
+    };
+    let mut ctx = SimContext::new("sim_test__serve_requests_with_message_loss", cfg);
+    ctx.disable_all_balance();
+    ctx.bootstrap_servers(3);
+    let client = ctx.app_client(ClientOptions::default());
+    ctx.block_on(async move {
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        for i in 0..100u64 {
+            let k = format!("key-{i}").as_bytes().to_vec();
+            let v = format!("value-{i}").as_bytes().to_vec();
+            co.put(k.clone(), v.clone()).await.unwrap();
+            assert_eq!(co.get(k).await.unwrap(), Some(v));
+        }
+    });
+}
+
+#[test]
+fn serve_requests_after_partition_healed() {
+    let cfg = SimConfig {
+        seed: 2,
+        ..Default::default()
+    };
+    let mut ctx = SimContext::new("sim_test__serve_requests_after_partition_healed", cfg);
+    ctx.bootstrap_servers(3);
+    let client = ctx.app_client(client_options());
+    let network = ctx.network();
+    let isolated_addr = ctx.node_addr(2);
+    ctx.block_on(async move {
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        let co = db
+            .create_collection("test_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap();
+        co.put(b"key".to_vec(), b"value".to_vec()).await.unwrap();
+
+        network.isolate(&isolated_addr);
+        engula_server::runtime::time::sleep(Duration::from_secs(10)).await;
+        network.heal();
+
+        loop {
+            match co.get(b"key".to_vec()).await {
+                Ok(value) => {
+                    assert_eq!(value, Some(b"value".to_vec()));
+                    break;
+                }
+                Err(_) => {
+                    engula_server::runtime::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn serve_requests_after_server_killed() {
+    let cfg = SimConfig {
+        seed: 3,
+        ..Default::default()
+    };
+    let mut ctx = SimContext::new("sim_test__serve_requests_after_server_killed", cfg);
+    ctx.disable_all_balance();
+    ctx.bootstrap_servers(3);
+    let client = ctx.app_client(ClientOptions::default());
+    let co = ctx.block_on(async move {
+        let db = client.create_database("test_db".to_string()).await.unwrap();
+        db.create_collection("test_co".to_string(), Some(Partition::Hash { slots: 3 }))
+            .await
+            .unwrap()
+    });
+
+    ctx.kill_server(2);
+    ctx.wait_election_timeout();
+    ctx.block_on(async move {
+        for i in 0..10u64 {
+            let k = format!("key-{i}").as_bytes().to_vec();
+            let v = format!("value-{i}").as_bytes().to_vec();
+            co.put(k.clone(), v.clone()).await.unwrap();
+            assert_eq!(co.get(k).await.unwrap(), Some(v));
+        }
+    });
+}
+
+#[test]
+fn replay_with_same_seed() {
+    fn run(seed: u64) -> (usize, u64) {
+        let cfg = SimConfig {
+            seed,
+            loss_rate: 0.05,
+            ..Default::default()
+        };
+        let mut ctx = SimContext::new("sim_test__replay_with_same_seed", cfg);
+        ctx.disable_all_balance();
+        ctx.bootstrap_servers(3);
+        let client = ctx.app_client(ClientOptions::default());
+        ctx.block_on(async move {
+            let db = client.create_database("test_db".to_string()).await.unwrap();
+            let co = db
+                .create_collection("test_co".to_string(), Some(Partition::Hash { slots: 3 }))
+                .await
+                .unwrap();
+            for i in 0..20u64 {
+                let k = format!("key-{i}").as_bytes().to_vec();
+                let v = format!("value-{i}").as_bytes().to_vec();
+                co.put(k, v).await.unwrap();
+            }
+        });
+        ctx.network().trace()
+    }
+
+    let trace = run(4);
+    assert!(trace.0 > 0);
+    assert_eq!(trace, run(4));
+}